@@ -0,0 +1,377 @@
+//! End-to-end patient flow against a localnet running the Arcium test cluster.
+//!
+//! Stores a record, mints and issues a doctor's credential NFT, grants a caregiver consent to
+//! share, shares the record with the doctor, runs the cardiovascular risk circuit, revokes
+//! every grant and dumps the record's audit trail:
+//!
+//! ```text
+//! arcium localnet   # or leave `arcium test` running
+//! ARCIUM_CLUSTER_PUBKEY=<cluster> cargo run -p amoca-client --example full_flow -- record.json
+//! ```
+//!
+//! The program must be deployed with the `share_patient_data` and `compute_cardio_risk`
+//! computation definitions initialized, as `arcium test` leaves it. The wallet at
+//! `ANCHOR_WALLET` (default `~/.config/solana/id.json`) acts as config authority and
+//! registrar, like in the integration suite.
+//!
+//! `record.json` holds the record encrypted for the MXE, i.e. `PatientRecord::to_plaintexts`
+//! encrypted with `RescueCipher`, and the receiver key the doctor decrypts with:
+//!
+//! ```text
+//! { "sender_pub_key": [32 bytes], "nonce": "<u128>", "ciphertexts": [[32 bytes], ...],
+//!   "receiver": [32 bytes] }
+//! ```
+
+use std::error::Error;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use amoca_client::pda::{self, ArciumAccounts};
+use amoca_client::{rpc, SharePatientDataBuilder, StorePatientDataBuilder};
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{
+    system_program, AccountDeserialize, Discriminator, InstructionData, ToAccountMetas,
+};
+use anchor_spl::associated_token::{get_associated_token_address, spl_associated_token_account};
+use anchor_spl::token::spl_token;
+use serde::Deserialize;
+use share_medical_records::{
+    accounts, instruction, AccessLogEntry, ShareStatus, ALL_SECTIONS, DELEGATION_SCOPE_SHARE, ID,
+    PURPOSE_TREATMENT, ROLE_DOCTOR, SHARE_STATUS_QUEUED,
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+/// Validity of the caregiver's delegation and the doctor's credential.
+const GRANT_SECONDS: i64 = 86_400;
+
+/// Record encrypted for the MXE by the patient's client.
+#[derive(Deserialize)]
+struct EncryptedRecord {
+    sender_pub_key: [u8; 32],
+    nonce: String,
+    ciphertexts: Vec<[u8; 32]>,
+    receiver: [u8; 32],
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let path = std::env::args()
+        .nth(1)
+        .ok_or("usage: full_flow <encrypted record json>")?;
+    let record: EncryptedRecord = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let nonce: u128 = record.nonce.parse()?;
+    let cluster: Pubkey = std::env::var("ARCIUM_CLUSTER_PUBKEY")?.parse()?;
+    let wallet = std::env::var("ANCHOR_WALLET").unwrap_or_else(|_| {
+        format!(
+            "{}/.config/solana/id.json",
+            std::env::var("HOME").unwrap_or_default()
+        )
+    });
+    let owner = read_keypair_file(wallet)?;
+
+    let client = RpcClient::new_with_commitment(
+        "http://127.0.0.1:8899".to_string(),
+        CommitmentConfig::confirmed(),
+    );
+    let patient = Keypair::new();
+    let caregiver = Keypair::new();
+    let doctor = Keypair::new();
+    for wallet in [&patient, &caregiver] {
+        let signature = client
+            .request_airdrop(&wallet.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .await?;
+        client.poll_for_signature(&signature).await?;
+    }
+
+    // 1. The patient stores their record
+    let store = StorePatientDataBuilder::new(patient.pubkey()).ciphertexts(record.ciphertexts);
+    rpc::store_patient_data(&client, &patient, &store).await?;
+    let patient_data = store.patient_data();
+    println!("stored record {patient_data}");
+
+    // 2. The registrar mints the doctor's credential NFT and issues its validity record
+    let credential_mint = Keypair::new();
+    let credential_account =
+        issue_doctor_credential(&client, &owner, &credential_mint, &doctor.pubkey()).await?;
+    println!(
+        "issued doctor credential {} held in {credential_account}",
+        credential_mint.pubkey()
+    );
+
+    // 3. The patient grants a caregiver consent to share on their behalf
+    let delegation = pda::delegation(&patient_data, &caregiver.pubkey());
+    let delegate = build(
+        accounts::DelegateAuthority {
+            payer: patient.pubkey(),
+            system_program: system_program::ID,
+            patient_data,
+            delegation,
+            grant_expiry_index: pda::grant_expiry_index(&patient_data),
+            patient_inbox: pda::patient_inbox(&patient_data),
+        },
+        instruction::DelegateAuthority {
+            delegate: caregiver.pubkey(),
+            scope: DELEGATION_SCOPE_SHARE,
+            expires_at: unix_now()? + GRANT_SECONDS,
+        },
+    );
+    rpc::send(&client, &patient, &[delegate]).await?;
+    println!("delegated sharing to {}", caregiver.pubkey());
+
+    // 4. The caregiver shares the record with the doctor
+    let share_offset = fresh_offset()?;
+    let share = SharePatientDataBuilder::new(
+        caregiver.pubkey(),
+        patient_data,
+        record.receiver,
+        share_offset,
+    )
+    .sender(record.sender_pub_key, nonce)
+    .cluster(cluster)
+    .as_delegate();
+    rpc::share_patient_data(&client, &caregiver, share).await?;
+    let status = await_share(&client, &patient_data, share_offset).await?;
+    println!("share {share_offset} resolved with status {status}");
+
+    // 5. The doctor gets a cardiovascular risk band instead of the raw record
+    let risk_offset = fresh_offset()?;
+    let arcium = ArciumAccounts::new("compute_cardio_risk", risk_offset, cluster);
+    let audit_index = rpc::ensure_audit_index_shard(&client, &patient, patient_data, 0).await?;
+    let risk = build(
+        accounts::ComputeCardioRisk {
+            payer: patient.pubkey(),
+            sign_pda_account: arcium.sign_pda_account,
+            mxe_account: arcium.mxe_account,
+            mempool_account: arcium.mempool_account,
+            executing_pool: arcium.executing_pool,
+            computation_account: arcium.computation_account,
+            comp_def_account: arcium.comp_def_account,
+            cluster_account: arcium.cluster_account,
+            approved_clusters: pda::approved_clusters(),
+            pool_account: arcium.pool_account,
+            clock_account: arcium.clock_account,
+            system_program: system_program::ID,
+            arcium_program: arcium.arcium_program,
+            patient_data,
+            section_locks: pda::section_locks(&patient_data),
+            receiver_revocation: pda::receiver_revocation(&patient_data, &record.receiver),
+            audit_index,
+            access_log_entry: pda::access_log(&patient_data, risk_offset),
+            share_nonce: pda::share_nonce(&patient_data, &record.receiver),
+            delegation: None,
+            program_config: pda::program_config(),
+        },
+        instruction::ComputeCardioRisk {
+            computation_offset: risk_offset,
+            receiver: record.receiver,
+            sender_pub_key: record.sender_pub_key,
+            nonce,
+            purpose: PURPOSE_TREATMENT,
+            cluster_offset: None,
+        },
+    );
+    let signature = rpc::send(&client, &patient, &[risk]).await?;
+    // The callback emits the band, encrypted for the receiver, in `CardioRiskComputedEvent`
+    println!("queued cardio risk {risk_offset} in {signature}");
+
+    // 6. The patient revokes every grant
+    let mut revoke = build(
+        accounts::RevokeAllAccess {
+            payer: patient.pubkey(),
+            patient_data,
+            grant_expiry_index: Some(pda::grant_expiry_index(&patient_data)),
+        },
+        instruction::RevokeAllAccess,
+    );
+    revoke.accounts.push(AccountMeta::new(delegation, false));
+    rpc::send(&client, &patient, &[revoke]).await?;
+    println!("revoked all access");
+
+    // 7. The audit trail holds one entry per disclosure
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, AccessLogEntry::DISCRIMINATOR)),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(8, patient_data.as_ref())),
+        ]),
+        ..RpcProgramAccountsConfig::default()
+    };
+    for (address, account) in client.get_program_accounts_with_config(&ID, config).await? {
+        let entry = AccessLogEntry::try_deserialize(&mut account.data.as_slice())?;
+        println!(
+            "{address}: accessor {} role {} purpose {} offset {} at {}",
+            entry.accessor, entry.role, entry.purpose, entry.computation_offset, entry.timestamp
+        );
+    }
+    Ok(())
+}
+
+/// Mints a credential NFT into `doctor`'s associated token account, registers it as a
+/// doctor credential and issues its validity record, creating the program config, provider
+/// registry and doctor role config first if needed. Returns the doctor's token account.
+async fn issue_doctor_credential(
+    client: &RpcClient,
+    owner: &Keypair,
+    credential_mint: &Keypair,
+    doctor: &Pubkey,
+) -> Result<Pubkey, Box<dyn Error>> {
+    let mint = credential_mint.pubkey();
+    let token_account = get_associated_token_address(doctor, &mint);
+    let rent = client
+        .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)
+        .await?;
+    let mint_nft = [
+        system_instruction::create_account(
+            &owner.pubkey(),
+            &mint,
+            rent,
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(&spl_token::ID, &mint, &owner.pubkey(), None, 0)?,
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &owner.pubkey(),
+            doctor,
+            &mint,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::mint_to(
+            &spl_token::ID,
+            &mint,
+            &token_account,
+            &owner.pubkey(),
+            &[],
+            1,
+        )?,
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &mint_nft,
+        Some(&owner.pubkey()),
+        &[owner, credential_mint],
+        client.get_latest_blockhash().await?,
+    );
+    client.send_and_confirm_transaction(&transaction).await?;
+
+    let program_config = pda::program_config();
+    let provider_registry = pda::provider_registry();
+    let role_config = pda::role_config(ROLE_DOCTOR);
+    let mut instructions = Vec::new();
+    if !exists(client, &program_config).await? {
+        instructions.push(build(
+            accounts::InitProgramConfig {
+                payer: owner.pubkey(),
+                program_config,
+                subsidy_pool: pda::subsidy_pool(),
+                system_program: system_program::ID,
+            },
+            instruction::InitProgramConfig,
+        ));
+    }
+    if !exists(client, &provider_registry).await? {
+        instructions.push(build(
+            accounts::SetRegistrar {
+                authority: owner.pubkey(),
+                system_program: system_program::ID,
+                program_config,
+                provider_registry,
+            },
+            instruction::SetRegistrar {
+                registrar: owner.pubkey(),
+            },
+        ));
+    }
+    if !exists(client, &role_config).await? {
+        instructions.push(build(
+            accounts::SetRoleConfig {
+                authority: owner.pubkey(),
+                system_program: system_program::ID,
+                program_config,
+                role_config,
+            },
+            instruction::SetRoleConfig {
+                role: ROLE_DOCTOR,
+                collection: Pubkey::default(),
+                credential_mint: Pubkey::default(),
+                section_mask: ALL_SECTIONS,
+            },
+        ));
+    }
+    instructions.push(build(
+        accounts::RegisterProvider {
+            registrar: owner.pubkey(),
+            system_program: system_program::ID,
+            provider_registry,
+            provider_record: pda::provider_record(&mint),
+            role_config,
+        },
+        instruction::RegisterProvider {
+            mint,
+            role: ROLE_DOCTOR,
+            license_hash: mint.to_bytes(),
+        },
+    ));
+    instructions.push(build(
+        accounts::IssueCredential {
+            registrar: owner.pubkey(),
+            system_program: system_program::ID,
+            provider_registry,
+            credential_record: pda::credential_record(&mint),
+        },
+        instruction::IssueCredential {
+            mint,
+            expires_at: unix_now()? + GRANT_SECONDS,
+        },
+    ));
+    rpc::send(client, owner, &instructions).await?;
+    Ok(token_account)
+}
+
+/// Polls the share's `ShareStatus` until the callback or a timeout resolves it.
+async fn await_share(
+    client: &RpcClient,
+    patient_data: &Pubkey,
+    computation_offset: u64,
+) -> Result<u8, Box<dyn Error>> {
+    let address = pda::share_status(patient_data, computation_offset);
+    for _ in 0..120 {
+        let data = client.get_account_data(&address).await?;
+        let status = ShareStatus::try_deserialize(&mut data.as_slice())?;
+        if status.status != SHARE_STATUS_QUEUED {
+            return Ok(status.status);
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    Err("share computation did not finalize".into())
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+async fn exists(client: &RpcClient, address: &Pubkey) -> Result<bool, Box<dyn Error>> {
+    Ok(client
+        .get_account_with_commitment(address, client.commitment())
+        .await?
+        .value
+        .is_some())
+}
+
+fn unix_now() -> Result<i64, Box<dyn Error>> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64)
+}
+
+/// Computation offset unlikely to collide with an earlier run.
+fn fresh_offset() -> Result<u64, Box<dyn Error>> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64)
+}
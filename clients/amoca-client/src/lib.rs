@@ -0,0 +1,34 @@
+//! Typed instruction builders for `share_medical_records`.
+//!
+//! The builders derive every program and Arcium PDA an instruction needs and return a
+//! ready-to-sign `Instruction`; the `rpc` helpers send them over a nonblocking `RpcClient`:
+//!
+//! ```ignore
+//! let store = StorePatientDataBuilder::new(patient.pubkey()).ciphertext_bytes(&encrypted)?;
+//! rpc::store_patient_data(&client, &patient, &store).await?;
+//!
+//! let share = SharePatientDataBuilder::new(patient.pubkey(), patient_data, receiver, offset)
+//!     .sender(sender_pub_key, nonce)
+//!     .cluster(cluster);
+//! rpc::share_patient_data(&client, &patient, share).await?;
+//! ```
+
+pub mod pda;
+pub mod rpc;
+mod share;
+mod store;
+
+pub use share::*;
+pub use store::*;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0} must be set before building the instruction")]
+    MissingField(&'static str),
+    #[error("expected 152, 153, 178 or 206 ciphertexts, got {0}")]
+    InvalidCiphertextCount(usize),
+    #[error("{0} ciphertext bytes do not split into 32-byte ciphertexts")]
+    InvalidCiphertextLength(usize),
+    #[error(transparent)]
+    Rpc(#[from] solana_client::client_error::ClientError),
+}
@@ -0,0 +1,184 @@
+//! Program-derived addresses of `share_medical_records` and the Arcium accounts its queued
+//! computations use.
+
+use anchor_lang::prelude::Pubkey;
+use arcium_anchor::prelude::*;
+// The derive_*_pda! macros resolve the program ID by name
+#[allow(unused_imports)]
+use share_medical_records::{ID, ID_CONST};
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+/// Record created by `store_patient_data` for `patient`.
+pub fn patient_data(patient: &Pubkey) -> Pubkey {
+    find(&[b"patient_data", patient.as_ref()])
+}
+
+pub fn record_metadata(patient_data: &Pubkey) -> Pubkey {
+    find(&[b"record_metadata", patient_data.as_ref()])
+}
+
+pub fn section_locks(patient_data: &Pubkey) -> Pubkey {
+    find(&[b"section_locks", patient_data.as_ref()])
+}
+
+pub fn program_config() -> Pubkey {
+    find(&[b"program_config"])
+}
+
+pub fn subsidy_pool() -> Pubkey {
+    find(&[b"subsidy_pool"])
+}
+
+pub fn provider_registry() -> Pubkey {
+    find(&[b"provider_registry"])
+}
+
+pub fn role_config(role: u8) -> Pubkey {
+    find(&[b"role_config", &[role]])
+}
+
+pub fn provider_record(credential_mint: &Pubkey) -> Pubkey {
+    find(&[b"provider_record", credential_mint.as_ref()])
+}
+
+pub fn credential_record(credential_mint: &Pubkey) -> Pubkey {
+    find(&[b"credential_record", credential_mint.as_ref()])
+}
+
+pub fn extension_registry() -> Pubkey {
+    find(&[b"extension_registry"])
+}
+
+pub fn cpi_allowlist() -> Pubkey {
+    find(&[b"cpi_allowlist"])
+}
+
+pub fn approved_clusters() -> Pubkey {
+    find(&[b"approved_clusters"])
+}
+
+pub fn comp_def_registry() -> Pubkey {
+    find(&[b"comp_def_registry"])
+}
+
+pub fn jurisdiction_rules() -> Pubkey {
+    find(&[b"jurisdiction_rules"])
+}
+
+pub fn audit_index(patient_data: &Pubkey, epoch: u64, shard: u8) -> Pubkey {
+    find(&[
+        b"audit_index",
+        patient_data.as_ref(),
+        &epoch.to_le_bytes(),
+        &[shard],
+    ])
+}
+
+pub fn delegation(patient_data: &Pubkey, delegate: &Pubkey) -> Pubkey {
+    find(&[b"delegation", patient_data.as_ref(), delegate.as_ref()])
+}
+
+pub fn grant_expiry_index(patient_data: &Pubkey) -> Pubkey {
+    find(&[b"grant_expiry_index", patient_data.as_ref()])
+}
+
+pub fn patient_inbox(patient_data: &Pubkey) -> Pubkey {
+    find(&[b"patient_inbox", patient_data.as_ref()])
+}
+
+pub fn receiver_revocation(patient_data: &Pubkey, receiver: &[u8; 32]) -> Pubkey {
+    find(&[b"receiver_revocation", patient_data.as_ref(), receiver])
+}
+
+pub fn receiver_allowlist(patient_data: &Pubkey) -> Pubkey {
+    find(&[b"receiver_allowlist", patient_data.as_ref()])
+}
+
+pub fn receiver_jurisdiction(receiver: &[u8; 32]) -> Pubkey {
+    find(&[b"receiver_jurisdiction", receiver])
+}
+
+pub fn share_nonce(patient_data: &Pubkey, receiver: &[u8; 32]) -> Pubkey {
+    find(&[b"share_nonce", patient_data.as_ref(), receiver])
+}
+
+pub fn access_log(patient_data: &Pubkey, computation_offset: u64) -> Pubkey {
+    find(&[
+        b"access_log",
+        patient_data.as_ref(),
+        &computation_offset.to_le_bytes(),
+    ])
+}
+
+pub fn shared_record(
+    patient_data: &Pubkey,
+    receiver: &[u8; 32],
+    computation_offset: u64,
+) -> Pubkey {
+    find(&[
+        b"shared_record",
+        patient_data.as_ref(),
+        receiver,
+        &computation_offset.to_le_bytes(),
+    ])
+}
+
+/// Progress of share computation `computation_offset` of `patient_data`.
+pub fn share_status(patient_data: &Pubkey, computation_offset: u64) -> Pubkey {
+    find(&[
+        b"share_status",
+        patient_data.as_ref(),
+        &computation_offset.to_le_bytes(),
+    ])
+}
+
+pub fn inbox_entry(receiver: &[u8; 32], computation_offset: u64) -> Pubkey {
+    find(&[b"inbox_entry", receiver, &computation_offset.to_le_bytes()])
+}
+
+/// Arcium cluster account at `cluster_offset`.
+pub fn cluster(cluster_offset: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[CLUSTER_PDA_SEED, &cluster_offset.to_le_bytes()],
+        &ARCIUM_PROG_ID,
+    )
+    .0
+}
+
+/// Arcium accounts of a queued computation, in the order every `queue_computation_accounts`
+/// context of the program declares them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArciumAccounts {
+    pub sign_pda_account: Pubkey,
+    pub mxe_account: Pubkey,
+    pub mempool_account: Pubkey,
+    pub executing_pool: Pubkey,
+    pub computation_account: Pubkey,
+    pub comp_def_account: Pubkey,
+    pub cluster_account: Pubkey,
+    pub pool_account: Pubkey,
+    pub clock_account: Pubkey,
+    pub arcium_program: Pubkey,
+}
+
+impl ArciumAccounts {
+    /// Derives the accounts for computation `computation_offset` of the circuit named
+    /// `circuit` (e.g. `"share_patient_data"`), queued on `cluster`.
+    pub fn new(circuit: &str, computation_offset: u64, cluster: Pubkey) -> Self {
+        ArciumAccounts {
+            sign_pda_account: derive_sign_pda!(),
+            mxe_account: derive_mxe_pda!(),
+            mempool_account: derive_mempool_pda!(),
+            executing_pool: derive_execpool_pda!(),
+            computation_account: derive_comp_pda!(computation_offset),
+            comp_def_account: derive_comp_def_pda!(comp_def_offset(circuit)),
+            cluster_account: cluster,
+            pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+            clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+            arcium_program: ARCIUM_PROG_ID,
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Async helpers sending the builders' instructions over a nonblocking `RpcClient`.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+
+use crate::{init_audit_index_shard, pda, Error, SharePatientDataBuilder, StorePatientDataBuilder};
+
+/// Signs `instructions` with `payer` and sends them in one transaction, waiting for
+/// confirmation at the client's commitment.
+pub async fn send(
+    client: &RpcClient,
+    payer: &Keypair,
+    instructions: &[Instruction],
+) -> Result<Signature, Error> {
+    let blockhash = client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        blockhash,
+    );
+    Ok(client.send_and_confirm_transaction(&transaction).await?)
+}
+
+/// Returns audit index shard `shard` of `patient_data` for the current epoch, opening it
+/// first if no share has used it yet.
+pub async fn ensure_audit_index_shard(
+    client: &RpcClient,
+    payer: &Keypair,
+    patient_data: Pubkey,
+    shard: u8,
+) -> Result<Pubkey, Error> {
+    let epoch = client.get_epoch_info().await?.epoch;
+    let audit_index = pda::audit_index(&patient_data, epoch, shard);
+    let existing = client
+        .get_account_with_commitment(&audit_index, client.commitment())
+        .await?
+        .value;
+    if existing.is_none() {
+        let instruction = init_audit_index_shard(payer.pubkey(), patient_data, epoch, shard);
+        send(client, payer, &[instruction]).await?;
+    }
+    Ok(audit_index)
+}
+
+pub async fn store_patient_data(
+    client: &RpcClient,
+    payer: &Keypair,
+    builder: &StorePatientDataBuilder,
+) -> Result<Signature, Error> {
+    send(client, payer, &[builder.build()?]).await
+}
+
+/// Sends a share, using shard 0 of the current epoch when no audit index shard was set.
+pub async fn share_patient_data(
+    client: &RpcClient,
+    payer: &Keypair,
+    mut builder: SharePatientDataBuilder,
+) -> Result<Signature, Error> {
+    if builder.audit_index.is_none() {
+        let patient_data = builder.patient_data();
+        let audit_index = ensure_audit_index_shard(client, payer, patient_data, 0).await?;
+        builder = builder.audit_index(audit_index);
+    }
+    send(client, payer, &[builder.build()?]).await
+}
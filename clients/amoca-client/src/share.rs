@@ -0,0 +1,198 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use share_medical_records::{accounts, instruction, ID, PURPOSE_TREATMENT};
+
+use crate::pda::{self, ArciumAccounts};
+use crate::Error;
+
+/// Builds `share_patient_data`, re-encrypting `patient_data` for `receiver`.
+///
+/// The sender key and nonce, the Arcium cluster and the audit index shard must be set;
+/// everything else defaults to a share by the patient themselves for treatment, closable
+/// by the payer.
+#[derive(Clone, Debug)]
+pub struct SharePatientDataBuilder {
+    payer: Pubkey,
+    patient_data: Pubkey,
+    receiver: [u8; 32],
+    computation_offset: u64,
+    sender: Option<([u8; 32], u128)>,
+    purpose: u8,
+    cluster: Option<Pubkey>,
+    cluster_offset: Option<u32>,
+    pub(crate) audit_index: Option<Pubkey>,
+    receiver_authority: Option<Pubkey>,
+    delegation: Option<Pubkey>,
+    organization: Option<Pubkey>,
+    remaining_accounts: Vec<AccountMeta>,
+}
+
+impl SharePatientDataBuilder {
+    pub fn new(
+        payer: Pubkey,
+        patient_data: Pubkey,
+        receiver: [u8; 32],
+        computation_offset: u64,
+    ) -> Self {
+        SharePatientDataBuilder {
+            payer,
+            patient_data,
+            receiver,
+            computation_offset,
+            sender: None,
+            purpose: PURPOSE_TREATMENT,
+            cluster: None,
+            cluster_offset: None,
+            audit_index: None,
+            receiver_authority: None,
+            delegation: None,
+            organization: None,
+            remaining_accounts: Vec::new(),
+        }
+    }
+
+    /// x25519 public key and nonce the stored record was encrypted with.
+    pub fn sender(mut self, sender_pub_key: [u8; 32], nonce: u128) -> Self {
+        self.sender = Some((sender_pub_key, nonce));
+        self
+    }
+
+    /// Purpose-of-use code, one of the program's `PURPOSE_*` constants.
+    pub fn purpose(mut self, purpose: u8) -> Self {
+        self.purpose = purpose;
+        self
+    }
+
+    /// Arcium cluster account of the MXE, which the computation is queued on by default.
+    pub fn cluster(mut self, cluster: Pubkey) -> Self {
+        self.cluster = Some(cluster);
+        self
+    }
+
+    /// Pins the computation to the cluster at `cluster_offset` instead of the MXE's own; the
+    /// offset must have been approved with `approve_cluster`.
+    pub fn cluster_offset(mut self, cluster_offset: u32) -> Self {
+        self.cluster = Some(pda::cluster(cluster_offset));
+        self.cluster_offset = Some(cluster_offset);
+        self
+    }
+
+    /// Audit index shard of the record for the current epoch; see `init_audit_index_shard`.
+    pub fn audit_index(mut self, audit_index: Pubkey) -> Self {
+        self.audit_index = Some(audit_index);
+        self
+    }
+
+    /// Wallet allowed to close the shared record. Defaults to the payer.
+    pub fn receiver_authority(mut self, receiver_authority: Pubkey) -> Self {
+        self.receiver_authority = Some(receiver_authority);
+        self
+    }
+
+    /// Shares as a delegate, passing the payer's `DelegationAccount` on the record.
+    pub fn as_delegate(mut self) -> Self {
+        self.delegation = Some(pda::delegation(&self.patient_data, &self.payer));
+        self
+    }
+
+    /// Shares as a member of `organization`, passing the organization's delegation.
+    pub fn as_organization_member(mut self, organization: Pubkey) -> Self {
+        self.delegation = Some(pda::delegation(&self.patient_data, &organization));
+        self.organization = Some(organization);
+        self
+    }
+
+    /// Accounts of registered share extensions, as described in `register_extension`.
+    pub fn remaining_accounts(mut self, accounts: Vec<AccountMeta>) -> Self {
+        self.remaining_accounts = accounts;
+        self
+    }
+
+    /// Record being shared.
+    pub fn patient_data(&self) -> Pubkey {
+        self.patient_data
+    }
+
+    pub fn build(&self) -> Result<Instruction, Error> {
+        let (sender_pub_key, nonce) = self.sender.ok_or(Error::MissingField("sender"))?;
+        let cluster = self.cluster.ok_or(Error::MissingField("cluster"))?;
+        let audit_index = self.audit_index.ok_or(Error::MissingField("audit_index"))?;
+        let arcium = ArciumAccounts::new("share_patient_data", self.computation_offset, cluster);
+        let (patient_data, receiver, offset) =
+            (&self.patient_data, &self.receiver, self.computation_offset);
+
+        let mut metas = accounts::SharePatientData {
+            payer: self.payer,
+            sign_pda_account: arcium.sign_pda_account,
+            mxe_account: arcium.mxe_account,
+            mempool_account: arcium.mempool_account,
+            executing_pool: arcium.executing_pool,
+            computation_account: arcium.computation_account,
+            comp_def_account: arcium.comp_def_account,
+            cluster_account: arcium.cluster_account,
+            approved_clusters: pda::approved_clusters(),
+            comp_def_registry: pda::comp_def_registry(),
+            pool_account: arcium.pool_account,
+            clock_account: arcium.clock_account,
+            system_program: system_program::ID,
+            arcium_program: arcium.arcium_program,
+            patient_data: *patient_data,
+            section_locks: pda::section_locks(patient_data),
+            receiver_revocation: pda::receiver_revocation(patient_data, receiver),
+            audit_index,
+            access_log_entry: pda::access_log(patient_data, offset),
+            share_nonce: pda::share_nonce(patient_data, receiver),
+            delegation: self.delegation,
+            receiver_authority: self.receiver_authority.unwrap_or(self.payer),
+            shared_record: pda::shared_record(patient_data, receiver, offset),
+            program_config: pda::program_config(),
+            extension_registry: pda::extension_registry(),
+            organization: self.organization,
+            receiver_allowlist: pda::receiver_allowlist(patient_data),
+            inbox_entry: pda::inbox_entry(receiver, offset),
+            jurisdiction_rules: pda::jurisdiction_rules(),
+            receiver_jurisdiction: pda::receiver_jurisdiction(receiver),
+            cpi_allowlist: pda::cpi_allowlist(),
+            instructions_sysvar: sysvar::instructions::ID,
+            share_status: pda::share_status(patient_data, offset),
+        }
+        .to_account_metas(None);
+        metas.extend(self.remaining_accounts.iter().cloned());
+
+        Ok(Instruction {
+            program_id: ID,
+            accounts: metas,
+            data: instruction::SharePatientData {
+                computation_offset: offset,
+                receiver: *receiver,
+                sender_pub_key,
+                nonce,
+                purpose: self.purpose,
+                cluster_offset: self.cluster_offset,
+            }
+            .data(),
+        })
+    }
+}
+
+/// Builds `init_audit_index_shard`, opening shard `shard` of `patient_data` for `epoch`.
+pub fn init_audit_index_shard(
+    payer: Pubkey,
+    patient_data: Pubkey,
+    epoch: u64,
+    shard: u8,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::InitAuditIndexShard {
+            payer,
+            system_program: system_program::ID,
+            patient_data,
+            audit_index: pda::audit_index(&patient_data, epoch, shard),
+        }
+        .to_account_metas(None),
+        data: instruction::InitAuditIndexShard { epoch, shard }.data(),
+    }
+}
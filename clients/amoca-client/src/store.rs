@@ -0,0 +1,80 @@
+use amoca_fhir::{BASE_FIELDS, CANARY_FIELDS, IMMUNIZATION_FIELDS, TERMINOLOGY_FIELDS};
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use share_medical_records::{accounts, instruction, ID};
+
+use crate::{pda, Error};
+
+/// Builds `store_patient_data` for the payer's own record.
+#[derive(Clone, Debug)]
+pub struct StorePatientDataBuilder {
+    payer: Pubkey,
+    ciphertexts: Vec<[u8; 32]>,
+}
+
+impl StorePatientDataBuilder {
+    pub fn new(payer: Pubkey) -> Self {
+        StorePatientDataBuilder {
+            payer,
+            ciphertexts: Vec::new(),
+        }
+    }
+
+    /// Ciphertexts in the field order of `store_patient_data`.
+    pub fn ciphertexts(mut self, ciphertexts: Vec<[u8; 32]>) -> Self {
+        self.ciphertexts = ciphertexts;
+        self
+    }
+
+    /// Ciphertexts as the flat byte buffer a cipher produces, split with `chunk_ciphertexts`.
+    pub fn ciphertext_bytes(self, bytes: &[u8]) -> Result<Self, Error> {
+        Ok(self.ciphertexts(chunk_ciphertexts(bytes)?))
+    }
+
+    /// Address of the record the instruction creates.
+    pub fn patient_data(&self) -> Pubkey {
+        pda::patient_data(&self.payer)
+    }
+
+    pub fn build(&self) -> Result<Instruction, Error> {
+        let count = self.ciphertexts.len();
+        if ![
+            BASE_FIELDS,
+            CANARY_FIELDS,
+            IMMUNIZATION_FIELDS,
+            TERMINOLOGY_FIELDS,
+        ]
+        .contains(&count)
+        {
+            return Err(Error::InvalidCiphertextCount(count));
+        }
+        let patient_data = self.patient_data();
+        Ok(Instruction {
+            program_id: ID,
+            accounts: accounts::StorePatientData {
+                payer: self.payer,
+                system_program: system_program::ID,
+                patient_data,
+                record_metadata: pda::record_metadata(&patient_data),
+                program_config: pda::program_config(),
+            }
+            .to_account_metas(None),
+            data: instruction::StorePatientData {
+                ciphertexts: self.ciphertexts.clone(),
+            }
+            .data(),
+        })
+    }
+}
+
+/// Splits a flat buffer of ciphertexts into the 32-byte ciphertexts instructions take.
+pub fn chunk_ciphertexts(bytes: &[u8]) -> Result<Vec<[u8; 32]>, Error> {
+    if bytes.len() % 32 != 0 {
+        return Err(Error::InvalidCiphertextLength(bytes.len()));
+    }
+    Ok(bytes
+        .chunks_exact(32)
+        .map(|chunk| chunk.try_into().unwrap())
+        .collect())
+}
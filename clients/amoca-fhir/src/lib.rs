@@ -0,0 +1,44 @@
+//! FHIR R4 mapping for `share_medical_records`.
+//!
+//! Converts `Patient`, `Observation`, `MedicationStatement` and `Condition` resources into the
+//! plaintext `PatientData` layout a client encrypts for `store_patient_data`, and converts a
+//! decrypted record back into resources:
+//!
+//! ```ignore
+//! let bundle: Bundle = serde_json::from_str(&json)?;
+//! let record = PatientRecord::from_bundle(&bundle, today)?;
+//! let plaintexts = record.to_plaintexts(); // encrypt with RescueCipher, then store
+//!
+//! let shared = PatientRecord::from_plaintexts(&decrypted)?;
+//! let bundle = shared.to_bundle();
+//! ```
+//!
+//! Fields without a counterpart in these four resources (allergies, procedures, family
+//! history, genomics, imaging, immunizations and the canary) are left for the caller to set.
+
+mod mapping;
+mod record;
+pub mod resources;
+
+pub use mapping::*;
+pub use record::*;
+pub use resources::{Bundle, Resource};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("expected 152, 153, 178 or 206 fields, got {0}")]
+    InvalidFieldCount(usize),
+    #[error("field {field} holds {value}, which does not fit its type")]
+    FieldOutOfRange { field: usize, value: u128 },
+    #[error("code {0:?} is not an ASCII code of 1 to 8 bytes")]
+    InvalidCode(String),
+    #[error("{element} has unsupported value {value:?}")]
+    InvalidValue {
+        element: &'static str,
+        value: String,
+    },
+    #[error("more than {max} {field} entries")]
+    Capacity { field: &'static str, max: usize },
+    #[error("more than one Patient resource")]
+    MultiplePatients,
+}
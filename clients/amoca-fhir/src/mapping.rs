@@ -0,0 +1,773 @@
+use crate::resources::{
+    Bundle, BundleEntry, CodeableConcept, Condition, Identifier, MedicationStatement, Observation,
+    Patient, Quantity, Resource,
+};
+use crate::{pack_code, unpack_code, Error, PatientRecord};
+
+pub const LOINC: &str = "http://loinc.org";
+pub const SNOMED: &str = "http://snomed.info/sct";
+pub const ICD10: &str = "http://hl7.org/fhir/sid/icd-10-cm";
+pub const RXNORM: &str = "http://www.nlm.nih.gov/research/umls/rxnorm";
+pub const UCUM: &str = "http://unitsofmeasure.org";
+pub const OBSERVATION_CATEGORY: &str = "http://terminology.hl7.org/CodeSystem/observation-category";
+pub const OBSERVATION_INTERPRETATION: &str =
+    "http://terminology.hl7.org/CodeSystem/v3-ObservationInterpretation";
+pub const CONDITION_CLINICAL: &str = "http://terminology.hl7.org/CodeSystem/condition-clinical";
+/// Identifier system carrying the record's numeric `patient_id`.
+pub const PATIENT_ID_SYSTEM: &str = "urn:amoca:patient-id";
+
+const LOINC_AGE: &str = "30525-0";
+const LOINC_BLOOD_GROUP: &str = "882-1";
+const LOINC_BODY_WEIGHT: &str = "29463-7";
+const LOINC_BODY_HEIGHT: &str = "8302-2";
+
+/// SNOMED CT blood group codes, indexed by `blood_type` (O-, O+, A-, A+, B-, B+, AB-, AB+).
+const BLOOD_GROUPS: [&str; 8] = [
+    "278148006",
+    "278147001",
+    "278152006",
+    "278149003",
+    "278153001",
+    "278150003",
+    "278154007",
+    "278151004",
+];
+
+/// Chapter letter and first and last category number of an ICD-10 range, e.g. `('E', 8, 13)`
+/// for E08-E13.
+type CategoryRange = (char, u8, u8);
+
+/// ICD-10 category ranges of each `medical_history` flag, with the code written back when
+/// the record carries no `icd10_codes` entry for a set flag.
+const HISTORY_CATEGORIES: [(&[CategoryRange], &str); 10] = [
+    (&[('E', 8, 13)], "E11"),                 // diabetes
+    (&[('I', 10, 16)], "I10"),                // hypertension
+    (&[('I', 20, 25), ('I', 30, 52)], "I25"), // heart_disease
+    (&[('C', 0, 96)], "C80"),                 // cancer
+    (&[('I', 60, 64)], "I63"),                // stroke
+    (&[('J', 45, 45)], "J45"),                // asthma
+    (&[('J', 41, 44)], "J44"),                // copd
+    (&[('M', 5, 19)], "M19"),                 // arthritis
+    (&[('M', 80, 81)], "M81"),                // osteoporosis
+    (&[('F', 32, 33)], "F32"),                // depression
+];
+
+/// Panel LOINC codes of each `lab_test_types` value (cbc, lipid_panel, metabolic_panel,
+/// liver_function, kidney_function, thyroid, hba1c, psa); the first is written back when the
+/// record carries no `loinc_codes` entry.
+const LAB_TYPES: [&[&str]; 8] = [
+    &["58410-2", "57021-8"],
+    &["57698-3", "24331-1"],
+    &["24323-8", "51990-0"],
+    &["24325-3"],
+    &["24362-6"],
+    &["3016-3"],
+    &["4548-4"],
+    &["2857-1"],
+];
+
+impl PatientRecord {
+    /// Maps the resources of a bundle; see `from_resources`.
+    pub fn from_bundle(bundle: &Bundle, today: u32) -> Result<Self, Error> {
+        let resources: Vec<Resource> = bundle.entry.iter().map(|e| e.resource.clone()).collect();
+        Self::from_resources(&resources, today)
+    }
+
+    /// Maps one patient's resources onto a record. `today` (days since epoch) turns
+    /// `Patient.birthDate` into `age`.
+    ///
+    /// Observations and conditions whose codes have no slot in the record are skipped, as are
+    /// entered-in-error observations, inactive medications and resolved conditions.
+    pub fn from_resources(resources: &[Resource], today: u32) -> Result<Self, Error> {
+        let mut record = PatientRecord::default();
+        let mut patient = None;
+        for resource in resources {
+            match resource {
+                Resource::Patient(p) => {
+                    if patient.replace(p).is_some() {
+                        return Err(Error::MultiplePatients);
+                    }
+                }
+                Resource::Observation(o) => record.apply_observation(o)?,
+                Resource::MedicationStatement(m) => record.apply_medication(m)?,
+                Resource::Condition(c) => record.apply_condition(c)?,
+                Resource::Other => {}
+            }
+        }
+        if let Some(patient) = patient {
+            record.apply_patient(patient, today)?;
+        }
+        Ok(record)
+    }
+
+    /// Converts the record back into resources: the patient, its age, blood group, weight
+    /// and height, one condition per set `medical_history` flag, and the medications and lab
+    /// tests within their counts.
+    pub fn to_resources(&self) -> Vec<Resource> {
+        let mut out = vec![Resource::Patient(Patient {
+            identifier: vec![Identifier {
+                system: Some(PATIENT_ID_SYSTEM.to_string()),
+                value: Some(self.patient_id.to_string()),
+            }],
+            gender: Some(if self.gender { "female" } else { "male" }.to_string()),
+            birth_date: None,
+        })];
+
+        out.push(Resource::Observation(Observation {
+            value_quantity: quantity(self.age.into(), "a"),
+            ..observation("vital-signs", LOINC_AGE)
+        }));
+        if let Some(group) = BLOOD_GROUPS.get(self.blood_type as usize) {
+            out.push(Resource::Observation(Observation {
+                value_codeable_concept: Some(CodeableConcept::new(SNOMED, group)),
+                ..observation("laboratory", LOINC_BLOOD_GROUP)
+            }));
+        }
+        out.push(Resource::Observation(Observation {
+            value_quantity: quantity(self.weight.into(), "kg"),
+            ..observation("vital-signs", LOINC_BODY_WEIGHT)
+        }));
+        out.push(Resource::Observation(Observation {
+            value_quantity: quantity(self.height.into(), "cm"),
+            ..observation("vital-signs", LOINC_BODY_HEIGHT)
+        }));
+
+        for (i, (_, fallback)) in HISTORY_CATEGORIES.iter().enumerate() {
+            if !self.medical_history[i] {
+                continue;
+            }
+            let code = unpack_code(self.icd10_codes[i]).unwrap_or_else(|| fallback.to_string());
+            out.push(Resource::Condition(Condition {
+                clinical_status: Some(CodeableConcept::new(CONDITION_CLINICAL, "active")),
+                code: CodeableConcept::new(ICD10, &code),
+            }));
+        }
+
+        for i in 0..(self.medication_count as usize).min(self.medications.len()) {
+            let cui = match self.rxnorm_codes[i] {
+                0 => self.medications[i],
+                cui => cui,
+            };
+            out.push(Resource::MedicationStatement(MedicationStatement {
+                status: "active".to_string(),
+                medication_codeable_concept: CodeableConcept::new(RXNORM, &cui.to_string()),
+            }));
+        }
+
+        for i in 0..(self.lab_test_count as usize).min(self.lab_test_types.len()) {
+            let Some(codes) = LAB_TYPES.get(self.lab_test_types[i] as usize) else {
+                continue;
+            };
+            let code = unpack_code(self.loinc_codes[i]).unwrap_or_else(|| codes[0].to_string());
+            let interpretation = ["L", "N", "H"]
+                .get(self.lab_test_flags[i] as usize)
+                .map(|flag| CodeableConcept::new(OBSERVATION_INTERPRETATION, flag));
+            out.push(Resource::Observation(Observation {
+                effective_date_time: Some(format_date(self.lab_test_dates[i])),
+                value_quantity: Some(Quantity {
+                    value: self.lab_test_values[i].into(),
+                    ..Default::default()
+                }),
+                interpretation: interpretation.into_iter().collect(),
+                ..observation("laboratory", &code)
+            }));
+        }
+        out
+    }
+
+    /// `to_resources` wrapped in a collection bundle.
+    pub fn to_bundle(&self) -> Bundle {
+        Bundle {
+            bundle_type: "collection".to_string(),
+            entry: self
+                .to_resources()
+                .into_iter()
+                .map(|resource| BundleEntry { resource })
+                .collect(),
+        }
+    }
+
+    fn apply_patient(&mut self, patient: &Patient, today: u32) -> Result<(), Error> {
+        if let Some(id) = patient
+            .identifier
+            .iter()
+            .find(|i| i.system.as_deref() == Some(PATIENT_ID_SYSTEM))
+            .and_then(|i| i.value.as_deref())
+        {
+            self.patient_id = id.parse().map_err(|_| invalid("Patient.identifier", id))?;
+        }
+        match patient.gender.as_deref() {
+            Some("female") => self.gender = true,
+            Some("male") | None => self.gender = false,
+            Some(other) => return Err(invalid("Patient.gender", other)),
+        }
+        if let Some(birth_date) = patient.birth_date.as_deref() {
+            let (by, bm, bd) = parse_date(birth_date)?;
+            let (ty, tm, td) = civil_from_days(today.into());
+            let age = ty - by - i64::from((tm, td) < (bm, bd));
+            self.age = u8::try_from(age).map_err(|_| invalid("Patient.birthDate", birth_date))?;
+        }
+        Ok(())
+    }
+
+    fn apply_observation(&mut self, observation: &Observation) -> Result<(), Error> {
+        if observation.status == "entered-in-error" || observation.status == "cancelled" {
+            return Ok(());
+        }
+        let Some(code) = observation.code.code_in(LOINC) else {
+            return Ok(());
+        };
+        match code {
+            LOINC_AGE => self.age = to_int(observation, &[("a", 1.0)])?,
+            LOINC_BODY_WEIGHT => {
+                self.weight = to_int(
+                    observation,
+                    &[("kg", 1.0), ("g", 0.001), ("[lb_av]", 0.45359237)],
+                )?
+            }
+            LOINC_BODY_HEIGHT => {
+                self.height = to_int(observation, &[("cm", 1.0), ("m", 100.0), ("[in_i]", 2.54)])?
+            }
+            LOINC_BLOOD_GROUP => {
+                let group = observation
+                    .value_codeable_concept
+                    .as_ref()
+                    .and_then(|v| v.code_in(SNOMED))
+                    .unwrap_or_default();
+                self.blood_type = BLOOD_GROUPS
+                    .iter()
+                    .position(|g| *g == group)
+                    .ok_or_else(|| invalid("Observation.valueCodeableConcept", group))?
+                    as u8;
+            }
+            _ => self.apply_lab_test(observation, code)?,
+        }
+        Ok(())
+    }
+
+    fn apply_lab_test(&mut self, observation: &Observation, code: &str) -> Result<(), Error> {
+        let is_lab = observation
+            .category
+            .iter()
+            .any(|c| c.code_in(OBSERVATION_CATEGORY) == Some("laboratory"));
+        let Some(lab_type) = LAB_TYPES.iter().position(|codes| codes.contains(&code)) else {
+            return Ok(());
+        };
+        if !is_lab {
+            return Ok(());
+        }
+        let slot = self.lab_test_count as usize;
+        if slot == self.lab_test_types.len() {
+            return Err(Error::Capacity {
+                field: "lab_test_types",
+                max: self.lab_test_types.len(),
+            });
+        }
+        self.lab_test_types[slot] = lab_type as u8;
+        self.loinc_codes[slot] = pack_code(code)?;
+        if let Some(date) = observation.effective_date_time.as_deref() {
+            self.lab_test_dates[slot] = date_to_days(date)?;
+        }
+        if observation.value_quantity.is_some() {
+            self.lab_test_values[slot] = to_int(observation, &[])?;
+        }
+        let interpretation = observation
+            .interpretation
+            .iter()
+            .find_map(|i| i.code_in(OBSERVATION_INTERPRETATION));
+        // Results without an interpretation are recorded as within range
+        self.lab_test_flags[slot] = match interpretation {
+            Some("L") | Some("LL") => 0,
+            Some("H") | Some("HH") => 2,
+            _ => 1,
+        };
+        self.lab_test_count += 1;
+        Ok(())
+    }
+
+    fn apply_medication(&mut self, medication: &MedicationStatement) -> Result<(), Error> {
+        if medication.status != "active" {
+            return Ok(());
+        }
+        let Some(cui) = medication.medication_codeable_concept.code_in(RXNORM) else {
+            return Ok(());
+        };
+        let cui: u64 = cui
+            .parse()
+            .map_err(|_| invalid("MedicationStatement.medicationCodeableConcept", cui))?;
+        let slot = self.medication_count as usize;
+        if slot == self.medications.len() {
+            return Err(Error::Capacity {
+                field: "medications",
+                max: self.medications.len(),
+            });
+        }
+        self.medications[slot] = cui;
+        self.rxnorm_codes[slot] = cui;
+        self.medication_count += 1;
+        Ok(())
+    }
+
+    fn apply_condition(&mut self, condition: &Condition) -> Result<(), Error> {
+        let status = condition
+            .clinical_status
+            .as_ref()
+            .and_then(|s| s.code_in(CONDITION_CLINICAL));
+        if !matches!(status, None | Some("active" | "recurrence" | "relapse")) {
+            return Ok(());
+        }
+        let Some(code) = condition.code.code_in(ICD10) else {
+            return Ok(());
+        };
+        let Some(flag) = history_flag(code) else {
+            return Ok(());
+        };
+        self.medical_history[flag] = true;
+        if self.icd10_codes[flag] == 0 {
+            self.icd10_codes[flag] = pack_code(code)?;
+        }
+        Ok(())
+    }
+}
+
+/// The `medical_history` flag an ICD-10 code falls under, by its three-character category.
+fn history_flag(code: &str) -> Option<usize> {
+    let mut chars = code.chars();
+    let letter = chars.next()?.to_ascii_uppercase();
+    let number: u8 = code.get(1..3)?.parse().ok()?;
+    HISTORY_CATEGORIES.iter().position(|(ranges, _)| {
+        ranges
+            .iter()
+            .any(|(l, lo, hi)| *l == letter && (*lo..=*hi).contains(&number))
+    })
+}
+
+/// Rounds an observation's quantity to an integer field after scaling it by the factor of
+/// its UCUM unit. An empty `units` list accepts any unit unscaled.
+fn to_int<T: TryFrom<i64>>(observation: &Observation, units: &[(&str, f64)]) -> Result<T, Error> {
+    let quantity = observation
+        .value_quantity
+        .as_ref()
+        .ok_or_else(|| invalid("Observation.valueQuantity", "missing"))?;
+    let unit = quantity.code.as_deref().or(quantity.unit.as_deref());
+    let factor = match (units.is_empty(), unit) {
+        (true, _) => 1.0,
+        (false, None) => units[0].1,
+        (false, Some(unit)) => units
+            .iter()
+            .find(|(u, _)| *u == unit)
+            .map(|(_, f)| *f)
+            .ok_or_else(|| invalid("Observation.valueQuantity.unit", unit))?,
+    };
+    let value = (quantity.value * factor).round();
+    if !value.is_finite() || value < 0.0 || value > i64::MAX as f64 {
+        return Err(invalid(
+            "Observation.valueQuantity",
+            &quantity.value.to_string(),
+        ));
+    }
+    T::try_from(value as i64)
+        .map_err(|_| invalid("Observation.valueQuantity", &quantity.value.to_string()))
+}
+
+fn observation(category: &str, code: &str) -> Observation {
+    Observation {
+        status: "final".to_string(),
+        category: vec![CodeableConcept::new(OBSERVATION_CATEGORY, category)],
+        code: CodeableConcept::new(LOINC, code),
+        ..Default::default()
+    }
+}
+
+fn quantity(value: f64, unit: &str) -> Option<Quantity> {
+    Some(Quantity {
+        value,
+        unit: Some(unit.to_string()),
+        system: Some(UCUM.to_string()),
+        code: Some(unit.to_string()),
+    })
+}
+
+fn invalid(element: &'static str, value: &str) -> Error {
+    Error::InvalidValue {
+        element,
+        value: value.to_string(),
+    }
+}
+
+/// Converts a FHIR date or dateTime to days since epoch; the time of day is dropped.
+pub fn date_to_days(date: &str) -> Result<u32, Error> {
+    let (y, m, d) = parse_date(date)?;
+    u32::try_from(days_from_civil(y, m, d)).map_err(|_| invalid("date", date))
+}
+
+/// Formats days since epoch as a FHIR date.
+pub fn format_date(days: u32) -> String {
+    let (y, m, d) = civil_from_days(days.into());
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+fn parse_date(date: &str) -> Result<(i64, u32, u32), Error> {
+    let err = || invalid("date", date);
+    let day = date.get(..10).ok_or_else(err)?;
+    let mut parts = day.split('-');
+    let (Some(y), Some(m), Some(d), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(err());
+    };
+    let (y, m, d) = (
+        y.parse().map_err(|_| err())?,
+        m.parse().map_err(|_| err())?,
+        d.parse().map_err(|_| err())?,
+    );
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return Err(err());
+    }
+    Ok((y, m, d))
+}
+
+// Proleptic Gregorian conversions, after Howard Hinnant's `days_from_civil` and
+// `civil_from_days`
+
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = i64::from((m + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(d) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-03-15
+    const TODAY: u32 = 19797;
+
+    fn round_trip(record: &PatientRecord) -> PatientRecord {
+        PatientRecord::from_resources(&record.to_resources(), TODAY).unwrap()
+    }
+
+    fn lab_observation(code: &str, value: f64, interpretation: &str) -> Resource {
+        Resource::Observation(Observation {
+            effective_date_time: Some("2024-01-02T08:30:00Z".to_string()),
+            value_quantity: Some(Quantity {
+                value,
+                ..Default::default()
+            }),
+            interpretation: vec![CodeableConcept::new(
+                OBSERVATION_INTERPRETATION,
+                interpretation,
+            )],
+            ..observation("laboratory", code)
+        })
+    }
+
+    fn condition(system: &str, code: &str, status: &str) -> Resource {
+        Resource::Condition(Condition {
+            clinical_status: Some(CodeableConcept::new(CONDITION_CLINICAL, status)),
+            code: CodeableConcept::new(system, code),
+        })
+    }
+
+    fn medication(system: &str, code: &str) -> Resource {
+        Resource::MedicationStatement(MedicationStatement {
+            status: "active".to_string(),
+            medication_codeable_concept: CodeableConcept::new(system, code),
+        })
+    }
+
+    #[test]
+    fn patient_round_trip() {
+        let record = PatientRecord {
+            patient_id: 420,
+            age: 69,
+            gender: true,
+            blood_type: 3,
+            weight: 70,
+            height: 170,
+            ..Default::default()
+        };
+        assert_eq!(round_trip(&record), record);
+    }
+
+    #[test]
+    fn patient_age_from_birth_date() {
+        let patient = Resource::Patient(Patient {
+            gender: Some("male".to_string()),
+            birth_date: Some("1955-03-16".to_string()),
+            ..Default::default()
+        });
+        let record = PatientRecord::from_resources(&[patient], TODAY).unwrap();
+        assert_eq!(record.age, 68);
+        assert!(!record.gender);
+    }
+
+    #[test]
+    fn observation_units_are_converted() {
+        let weight = Resource::Observation(Observation {
+            value_quantity: quantity(154.0, "[lb_av]"),
+            ..observation("vital-signs", LOINC_BODY_WEIGHT)
+        });
+        let height = Resource::Observation(Observation {
+            value_quantity: quantity(1.8, "m"),
+            ..observation("vital-signs", LOINC_BODY_HEIGHT)
+        });
+        let record = PatientRecord::from_resources(&[weight, height], TODAY).unwrap();
+        assert_eq!((record.weight, record.height), (70, 180));
+    }
+
+    #[test]
+    fn condition_round_trip() {
+        let mut record = PatientRecord::default();
+        record.medical_history[0] = true;
+        record.icd10_codes[0] = pack_code("E11.9").unwrap();
+        record.medical_history[2] = true;
+        record.icd10_codes[2] = pack_code("I48").unwrap();
+        assert_eq!(round_trip(&record), record);
+    }
+
+    #[test]
+    fn condition_without_code_uses_category_fallback() {
+        let mut record = PatientRecord::default();
+        record.medical_history[9] = true;
+
+        let mapped = round_trip(&record);
+        assert!(mapped.medical_history[9]);
+        assert_eq!(unpack_code(mapped.icd10_codes[9]).as_deref(), Some("F32"));
+    }
+
+    #[test]
+    fn medication_round_trip() {
+        let mut record = PatientRecord {
+            medication_count: 2,
+            ..Default::default()
+        };
+        record.medications[..2].copy_from_slice(&[197361, 860975]);
+        record.rxnorm_codes[..2].copy_from_slice(&[197361, 860975]);
+        assert_eq!(round_trip(&record), record);
+    }
+
+    #[test]
+    fn medication_without_rxnorm_code_uses_medication_id() {
+        let mut record = PatientRecord {
+            medication_count: 1,
+            ..Default::default()
+        };
+        record.medications[0] = 197361;
+
+        let mapped = round_trip(&record);
+        assert_eq!(mapped.medications[0], 197361);
+        assert_eq!(mapped.rxnorm_codes[0], 197361);
+    }
+
+    #[test]
+    fn lab_test_round_trip() {
+        let mut record = PatientRecord {
+            lab_test_count: 2,
+            ..Default::default()
+        };
+        record.lab_test_types[..2].copy_from_slice(&[6, 1]);
+        record.lab_test_dates[..2].copy_from_slice(&[19700, 19723]);
+        record.lab_test_values[..2].copy_from_slice(&[64, 210]);
+        record.lab_test_flags[..2].copy_from_slice(&[2, 0]);
+        record.loinc_codes[0] = pack_code("4548-4").unwrap();
+        record.loinc_codes[1] = pack_code("24331-1").unwrap();
+        assert_eq!(round_trip(&record), record);
+    }
+
+    #[test]
+    fn lab_test_without_code_uses_panel_code() {
+        let mut record = PatientRecord {
+            lab_test_count: 1,
+            ..Default::default()
+        };
+        record.lab_test_flags[0] = 1;
+
+        let mapped = round_trip(&record);
+        assert_eq!(mapped.lab_test_types[0], 0);
+        assert_eq!(
+            unpack_code(mapped.loinc_codes[0]).as_deref(),
+            Some("58410-2")
+        );
+    }
+
+    #[test]
+    fn lab_test_interpretation_defaults_to_normal() {
+        let mut lab = lab_observation("2857-1", 4.0, "H");
+        if let Resource::Observation(o) = &mut lab {
+            o.interpretation.clear();
+        }
+        let record = PatientRecord::from_resources(&[lab], TODAY).unwrap();
+        assert_eq!(record.lab_test_count, 1);
+        assert_eq!(record.lab_test_types[0], 7);
+        assert_eq!(
+            record.lab_test_dates[0],
+            date_to_days("2024-01-02").unwrap()
+        );
+        assert_eq!(record.lab_test_flags[0], 1);
+    }
+
+    #[test]
+    fn bundle_round_trip_through_json() {
+        let mut record = PatientRecord {
+            patient_id: 7,
+            age: 41,
+            blood_type: 7,
+            weight: 82,
+            height: 188,
+            medication_count: 1,
+            lab_test_count: 1,
+            ..Default::default()
+        };
+        record.medical_history[1] = true;
+        record.icd10_codes[1] = pack_code("I10").unwrap();
+        record.medications[0] = 314076;
+        record.rxnorm_codes[0] = 314076;
+        record.lab_test_types[0] = 2;
+        record.lab_test_dates[0] = 19790;
+        record.lab_test_values[0] = 95;
+        record.lab_test_flags[0] = 1;
+        record.loinc_codes[0] = pack_code("24323-8").unwrap();
+
+        let json = serde_json::to_string(&record.to_bundle()).unwrap();
+        let bundle: Bundle = serde_json::from_str(&json).unwrap();
+        assert_eq!(PatientRecord::from_bundle(&bundle, TODAY).unwrap(), record);
+    }
+
+    #[test]
+    fn unknown_codes_are_skipped() {
+        let resources = [
+            // ICD-10 category without a medical history flag
+            condition(ICD10, "Z99.2", "active"),
+            // Condition coded in another system only
+            condition(SNOMED, "44054006", "active"),
+            // LOINC code outside every lab panel
+            lab_observation("1234-5", 1.0, "N"),
+            // Lab panel code not categorized as laboratory
+            Resource::Observation(Observation {
+                value_quantity: quantity(5.0, "%"),
+                ..observation("vital-signs", "4548-4")
+            }),
+            // Medication coded in another system only
+            medication(SNOMED, "387458008"),
+            Resource::Other,
+        ];
+        let record = PatientRecord::from_resources(&resources, TODAY).unwrap();
+        assert_eq!(record, PatientRecord::default());
+    }
+
+    #[test]
+    fn inactive_resources_are_skipped() {
+        let mut lab = lab_observation("4548-4", 6.0, "N");
+        if let Resource::Observation(o) = &mut lab {
+            o.status = "entered-in-error".to_string();
+        }
+        let resources = [
+            condition(ICD10, "E11", "resolved"),
+            Resource::MedicationStatement(MedicationStatement {
+                status: "stopped".to_string(),
+                medication_codeable_concept: CodeableConcept::new(RXNORM, "197361"),
+            }),
+            lab,
+        ];
+        let record = PatientRecord::from_resources(&resources, TODAY).unwrap();
+        assert_eq!(record, PatientRecord::default());
+    }
+
+    #[test]
+    fn invalid_codes_are_rejected() {
+        let blood_group = Resource::Observation(Observation {
+            value_codeable_concept: Some(CodeableConcept::new(SNOMED, "112144000")),
+            ..observation("laboratory", LOINC_BLOOD_GROUP)
+        });
+        let cases = [
+            (blood_group, "Observation.valueCodeableConcept"),
+            (
+                medication(RXNORM, "not-a-cui"),
+                "MedicationStatement.medicationCodeableConcept",
+            ),
+            (
+                Resource::Patient(Patient {
+                    gender: Some("unknown".to_string()),
+                    ..Default::default()
+                }),
+                "Patient.gender",
+            ),
+            (
+                Resource::Observation(Observation {
+                    value_quantity: quantity(70.0, "stone"),
+                    ..observation("vital-signs", LOINC_BODY_WEIGHT)
+                }),
+                "Observation.valueQuantity.unit",
+            ),
+            (
+                Resource::Observation(observation("vital-signs", LOINC_AGE)),
+                "Observation.valueQuantity",
+            ),
+        ];
+        for (resource, expected) in cases {
+            match PatientRecord::from_resources(&[resource], TODAY) {
+                Err(Error::InvalidValue { element, .. }) => assert_eq!(element, expected),
+                other => panic!("expected an invalid {expected}, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn overlong_icd10_code_is_rejected() {
+        let resources = [condition(ICD10, "E11.65123", "active")];
+        assert!(matches!(
+            PatientRecord::from_resources(&resources, TODAY),
+            Err(Error::InvalidCode(_))
+        ));
+    }
+
+    #[test]
+    fn multiple_patients_are_rejected() {
+        let patient = Resource::Patient(Patient::default());
+        assert!(matches!(
+            PatientRecord::from_resources(&[patient.clone(), patient], TODAY),
+            Err(Error::MultiplePatients)
+        ));
+    }
+
+    #[test]
+    fn medications_beyond_capacity_are_rejected() {
+        let resources: Vec<Resource> = (0..9)
+            .map(|i| medication(RXNORM, &(100 + i).to_string()))
+            .collect();
+        assert!(matches!(
+            PatientRecord::from_resources(&resources, TODAY),
+            Err(Error::Capacity {
+                field: "medications",
+                max: 8
+            })
+        ));
+    }
+
+    #[test]
+    fn dates_round_trip() {
+        assert_eq!(date_to_days("1970-01-01").unwrap(), 0);
+        assert_eq!(format_date(TODAY), "2024-03-15");
+        assert_eq!(date_to_days(&format_date(TODAY)).unwrap(), TODAY);
+        assert!(date_to_days("2024-13-01").is_err());
+        assert!(date_to_days("2024-01").is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use crate::Error;
+
+/// Number of plaintext fields in a record without the optional trailing sections.
+pub const BASE_FIELDS: usize = 152;
+/// Number of plaintext fields in a record up to and including the canary.
+pub const CANARY_FIELDS: usize = BASE_FIELDS + 1;
+/// Number of plaintext fields in a record up to and including the immunizations.
+pub const IMMUNIZATION_FIELDS: usize = CANARY_FIELDS + 25;
+/// Number of plaintext fields in a full record, terminology codes included.
+pub const TERMINOLOGY_FIELDS: usize = IMMUNIZATION_FIELDS + 28;
+
+/// Plaintext patient record, laid out field for field as the `PatientData` circuit input.
+///
+/// `to_plaintexts` flattens it into the scalars a client encrypts with `RescueCipher` before
+/// calling `store_patient_data`; `from_plaintexts` rebuilds it from a decrypted share.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PatientRecord {
+    // Basic demographics
+    pub patient_id: u64,
+    pub age: u8,
+    /// `true` for female, `false` for male
+    pub gender: bool,
+    /// ABO group * 2 + Rh: O-, O+, A-, A+, B-, B+, AB-, AB+ = 0..7
+    pub blood_type: u8,
+    /// Body weight in kg
+    pub weight: u16,
+    /// Body height in cm
+    pub height: u16,
+    /// [peanuts, latex, bees, wasps, cats]
+    pub allergies: [bool; 5],
+
+    // Advanced healthcare data
+    /// [diabetes, hypertension, heart_disease, cancer, stroke, asthma, copd, arthritis,
+    ///  osteoporosis, depression]
+    pub medical_history: [bool; 10],
+    pub medication_count: u8,
+    pub medications: [u64; 8],
+    pub procedure_count: u8,
+    /// Days since epoch
+    pub procedure_dates: [u32; 8],
+    /// [diabetes, heart_disease, cancer, stroke, hypertension]
+    pub family_history: [bool; 5],
+
+    // Genomic analysis data
+    pub variant_count: u16,
+    pub genetic_markers: [u64; 15],
+    pub variant_significance: [u8; 15],
+    /// [cystic_fibrosis, sickle_cell, tay_sachs, hemophilia, huntington]
+    pub carrier_status: [bool; 5],
+    /// [warfarin_sensitivity, clopidogrel_resistance, statin_response]
+    pub pharmacogenomic_markers: [bool; 3],
+    pub ancestry_components: [u8; 7],
+
+    // Lab test results
+    pub lab_test_count: u8,
+    /// [cbc, lipid_panel, metabolic_panel, liver_function, kidney_function, thyroid, hba1c, psa]
+    pub lab_test_types: [u8; 10],
+    /// Days since epoch
+    pub lab_test_dates: [u32; 10],
+    pub lab_test_values: [u16; 10],
+    /// 0=low, 1=normal, 2=high
+    pub lab_test_flags: [u8; 10],
+    pub imaging_count: u8,
+    /// [xray, ct, mri, ultrasound, mammography]
+    pub imaging_types: [u8; 10],
+    /// Days since epoch
+    pub imaging_dates: [u32; 10],
+
+    /// Honeytoken canary, unique per patient
+    pub canary: u64,
+
+    // Immunizations
+    pub immunization_count: u8,
+    pub vaccine_codes: [u64; 8],
+    /// Days since epoch
+    pub vaccination_dates: [u32; 8],
+    pub vaccine_doses: [u8; 8],
+
+    // Terminology codes, parallel to the fields they code
+    /// ICD-10 code of each `medical_history` flag, packed with `pack_code`
+    pub icd10_codes: [u64; 10],
+    /// RxNorm CUI of each `medications` entry
+    pub rxnorm_codes: [u64; 8],
+    /// LOINC code of each `lab_test_types` entry, packed with `pack_code`
+    pub loinc_codes: [u64; 10],
+}
+
+impl PatientRecord {
+    /// Flattens the record into its `TERMINOLOGY_FIELDS` plaintext scalars, in the order
+    /// `store_patient_data` expects its ciphertexts.
+    pub fn to_plaintexts(&self) -> Vec<u128> {
+        let mut out = Vec::with_capacity(TERMINOLOGY_FIELDS);
+        out.push(self.patient_id.into());
+        out.push(self.age.into());
+        out.push(self.gender.into());
+        out.push(self.blood_type.into());
+        out.push(self.weight.into());
+        out.push(self.height.into());
+        extend(&mut out, &self.allergies);
+
+        extend(&mut out, &self.medical_history);
+        out.push(self.medication_count.into());
+        extend(&mut out, &self.medications);
+        out.push(self.procedure_count.into());
+        extend(&mut out, &self.procedure_dates);
+        extend(&mut out, &self.family_history);
+
+        out.push(self.variant_count.into());
+        extend(&mut out, &self.genetic_markers);
+        extend(&mut out, &self.variant_significance);
+        extend(&mut out, &self.carrier_status);
+        extend(&mut out, &self.pharmacogenomic_markers);
+        extend(&mut out, &self.ancestry_components);
+
+        out.push(self.lab_test_count.into());
+        extend(&mut out, &self.lab_test_types);
+        extend(&mut out, &self.lab_test_dates);
+        extend(&mut out, &self.lab_test_values);
+        extend(&mut out, &self.lab_test_flags);
+        out.push(self.imaging_count.into());
+        extend(&mut out, &self.imaging_types);
+        extend(&mut out, &self.imaging_dates);
+
+        out.push(self.canary.into());
+
+        out.push(self.immunization_count.into());
+        extend(&mut out, &self.vaccine_codes);
+        extend(&mut out, &self.vaccination_dates);
+        extend(&mut out, &self.vaccine_doses);
+
+        extend(&mut out, &self.icd10_codes);
+        extend(&mut out, &self.rxnorm_codes);
+        extend(&mut out, &self.loinc_codes);
+        debug_assert_eq!(out.len(), TERMINOLOGY_FIELDS);
+        out
+    }
+
+    /// Rebuilds a record from decrypted plaintexts. Accepts the same lengths as
+    /// `store_patient_data` (152, 153, 178 or 206 fields); missing trailing sections stay
+    /// zeroed.
+    pub fn from_plaintexts(values: &[u128]) -> Result<Self, Error> {
+        if ![
+            BASE_FIELDS,
+            CANARY_FIELDS,
+            IMMUNIZATION_FIELDS,
+            TERMINOLOGY_FIELDS,
+        ]
+        .contains(&values.len())
+        {
+            return Err(Error::InvalidFieldCount(values.len()));
+        }
+        let mut r = Reader { values, index: 0 };
+        let mut record = PatientRecord {
+            patient_id: r.scalar()?,
+            age: r.scalar()?,
+            gender: r.flag()?,
+            blood_type: r.scalar()?,
+            weight: r.scalar()?,
+            height: r.scalar()?,
+            allergies: r.flags()?,
+
+            medical_history: r.flags()?,
+            medication_count: r.scalar()?,
+            medications: r.scalars()?,
+            procedure_count: r.scalar()?,
+            procedure_dates: r.scalars()?,
+            family_history: r.flags()?,
+
+            variant_count: r.scalar()?,
+            genetic_markers: r.scalars()?,
+            variant_significance: r.scalars()?,
+            carrier_status: r.flags()?,
+            pharmacogenomic_markers: r.flags()?,
+            ancestry_components: r.scalars()?,
+
+            lab_test_count: r.scalar()?,
+            lab_test_types: r.scalars()?,
+            lab_test_dates: r.scalars()?,
+            lab_test_values: r.scalars()?,
+            lab_test_flags: r.scalars()?,
+            imaging_count: r.scalar()?,
+            imaging_types: r.scalars()?,
+            imaging_dates: r.scalars()?,
+            ..Default::default()
+        };
+        if values.len() >= CANARY_FIELDS {
+            record.canary = r.scalar()?;
+        }
+        if values.len() >= IMMUNIZATION_FIELDS {
+            record.immunization_count = r.scalar()?;
+            record.vaccine_codes = r.scalars()?;
+            record.vaccination_dates = r.scalars()?;
+            record.vaccine_doses = r.scalars()?;
+        }
+        if values.len() >= TERMINOLOGY_FIELDS {
+            record.icd10_codes = r.scalars()?;
+            record.rxnorm_codes = r.scalars()?;
+            record.loinc_codes = r.scalars()?;
+        }
+        Ok(record)
+    }
+}
+
+/// Packs an ASCII code of up to 8 bytes (e.g. `E11.9` or `4548-4`) into a u64, first byte
+/// lowest, as stored in `icd10_codes` and `loinc_codes`.
+pub fn pack_code(code: &str) -> Result<u64, Error> {
+    let bytes = code.as_bytes();
+    if bytes.is_empty() || bytes.len() > 8 || !code.is_ascii() {
+        return Err(Error::InvalidCode(code.to_string()));
+    }
+    let mut packed = [0u8; 8];
+    packed[..bytes.len()].copy_from_slice(bytes);
+    Ok(u64::from_le_bytes(packed))
+}
+
+/// Reverses `pack_code`. Returns `None` for an empty slot.
+pub fn unpack_code(packed: u64) -> Option<String> {
+    let bytes = packed.to_le_bytes();
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    if len == 0 {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes[..len]).into_owned())
+}
+
+fn extend<T: Copy + Into<u128>>(out: &mut Vec<u128>, values: &[T]) {
+    out.extend(values.iter().map(|v| (*v).into()));
+}
+
+struct Reader<'a> {
+    values: &'a [u128],
+    index: usize,
+}
+
+impl Reader<'_> {
+    fn scalar<T: TryFrom<u128>>(&mut self) -> Result<T, Error> {
+        let value = self.values[self.index];
+        let field = self.index;
+        self.index += 1;
+        T::try_from(value).map_err(|_| Error::FieldOutOfRange { field, value })
+    }
+
+    fn flag(&mut self) -> Result<bool, Error> {
+        match self.scalar::<u8>()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(Error::FieldOutOfRange {
+                field: self.index - 1,
+                value: value.into(),
+            }),
+        }
+    }
+
+    fn scalars<T: TryFrom<u128> + Default + Copy, const N: usize>(
+        &mut self,
+    ) -> Result<[T; N], Error> {
+        let mut out = [T::default(); N];
+        for slot in out.iter_mut() {
+            *slot = self.scalar()?;
+        }
+        Ok(out)
+    }
+
+    fn flags<const N: usize>(&mut self) -> Result<[bool; N], Error> {
+        let mut out = [false; N];
+        for slot in out.iter_mut() {
+            *slot = self.flag()?;
+        }
+        Ok(out)
+    }
+}
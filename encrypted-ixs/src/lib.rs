@@ -59,8 +59,171 @@ mod circuits {
         pub imaging_types: [u8; 10],
         // Imaging dates (days since epoch, up to 10)
         pub imaging_dates: [u32; 10],
+
+        // Honeytoken canary, unique per patient and never read by any computation
+        pub canary: u64,
+
+        // Immunizations
+        // Immunization count (up to 8 vaccines tracked)
+        pub immunization_count: u8,
+        // Vaccine codes (CVX-style u64 identifiers, up to 8)
+        pub vaccine_codes: [u64; 8],
+        // Date of the latest dose (days since epoch, up to 8)
+        pub vaccination_dates: [u32; 8],
+        // Doses received of each vaccine (up to 8)
+        pub vaccine_doses: [u8; 8],
+
+        // Terminology codes, parallel to the fields they code
+        // ICD-10 code of each medical history flag (ASCII packed into a u64)
+        pub icd10_codes: [u64; 10],
+        // RxNorm CUI of each medication
+        pub rxnorm_codes: [u64; 8],
+        // LOINC code of each lab test (ASCII packed into a u64)
+        pub loinc_codes: [u64; 10],
+    }
+
+    // Record sections, laid out exactly as the matching byte ranges of PatientData
+
+    pub struct Demographics {
+        pub patient_id: u64,
+        pub age: u8,
+        pub gender: bool,
+        pub blood_type: u8,
+        pub weight: u16,
+        pub height: u16,
+        pub allergies: [bool; 5],
+    }
+
+    pub struct HealthcareData {
+        pub medical_history: [bool; 10],
+        pub medication_count: u8,
+        pub medications: [u64; 8],
+        pub procedure_count: u8,
+        pub procedure_dates: [u32; 8],
+        pub family_history: [bool; 5],
+    }
+
+    pub struct GenomicData {
+        pub variant_count: u16,
+        pub genetic_markers: [u64; 15],
+        pub variant_significance: [u8; 15],
+        pub carrier_status: [bool; 5],
+        pub pharmacogenomic_markers: [bool; 3],
+        pub ancestry_components: [u8; 7],
+    }
+
+    pub struct LabTestData {
+        pub lab_test_count: u8,
+        pub lab_test_types: [u8; 10],
+        pub lab_test_dates: [u32; 10],
+        pub lab_test_values: [u16; 10],
+        pub lab_test_flags: [u8; 10],
+        pub imaging_count: u8,
+        pub imaging_types: [u8; 10],
+        pub imaging_dates: [u32; 10],
+    }
+
+    pub struct ImmunizationData {
+        pub immunization_count: u8,
+        pub vaccine_codes: [u64; 8],
+        pub vaccination_dates: [u32; 8],
+        pub vaccine_doses: [u8; 8],
+    }
+
+    // Fields of a single appended record entry (lab result, medication, visit note)
+    pub struct EntryFields {
+        pub fields: [u64; 16],
+    }
+
+    // An amended entry disclosed together with the entry it superseded
+    pub struct EntryHistory {
+        pub current: [u64; 16],
+        pub previous: [u64; 16],
+    }
+
+    // Mental health section, stored and shared apart from the patient record
+    pub struct MentalHealthData {
+        pub diagnoses: [u64; 4],
+        pub therapy_notes: [u64; 4],
+        pub medication_flags: [bool; 8],
+    }
+
+    // Reproductive and obstetric record, stored and shared apart from the patient record
+    pub struct ObstetricData {
+        // Pregnancies, births and pregnancy losses
+        pub gravida: u8,
+        pub para: u8,
+        pub abortus: u8,
+        // Estimated due date (days since epoch) and gestational age in weeks
+        pub estimated_due_date: u32,
+        pub gestational_weeks: u8,
+        // Complications: [gestational diabetes, preeclampsia, placenta previa, preterm labor,
+        // hyperemesis, ectopic, postpartum hemorrhage, other]
+        pub complications: [bool; 8],
+        pub delivery_method: u8,
+    }
+
+    // Secrets of a file attachment: its symmetric content key and off-chain storage URI,
+    // 16 bytes per element
+    pub struct AttachmentSecrets {
+        pub content_key: [u128; 2],
+        pub storage_uri: [u128; 4],
+    }
+
+    // One lab result of the compressed lab history
+    pub struct LabResult {
+        pub test_type: u8,
+        pub date: u32,
+        pub value: u16,
+        pub flag: u8,
+    }
+
+    // Chunk of expanded record capacity: 8 medications, or 2 lab tests as type, date, value
+    // and flag
+    pub struct CapacityChunk {
+        pub values: [u64; 8],
+    }
+
+    // Patient annotation attached to a flagged entry
+    pub struct EntryAnnotation {
+        pub fields: [u64; 4],
+    }
+
+    // A flagged entry disclosed together with the patient's annotation
+    pub struct FlaggedEntry {
+        pub fields: [u64; 16],
+        pub annotation: [u64; 4],
+    }
+
+    // Restricted view released under break-glass emergency access
+    pub struct EmergencyData {
+        pub age: u8,
+        pub gender: bool,
+        pub blood_type: u8,
+        pub weight: u16,
+        pub height: u16,
+        pub allergies: [bool; 5],
+        pub medical_history: [bool; 10],
+        pub medication_count: u8,
+        pub medications: [u64; 8],
+    }
+
+    // Minimal view released to emergency responders: only the facts needed to treat
+    pub struct EmergencySummary {
+        pub blood_type: u8,
+        pub allergies: [bool; 5],
+        // medical_history with every flag outside CRITICAL_CONDITIONS cleared
+        pub critical_conditions: [bool; 10],
+        pub medication_count: u8,
+        pub medications: [u64; 8],
     }
 
+    // medical_history flags an emergency responder needs: diabetes, hypertension, heart
+    // disease, stroke, asthma and COPD
+    const CRITICAL_CONDITIONS: [bool; 10] = [
+        true, true, true, false, true, true, true, false, false, false,
+    ];
+
     #[instruction]
     pub fn share_patient_data(
         receiver: Shared,
@@ -69,4 +232,1297 @@ mod circuits {
         let input = input_ctxt.to_arcis();
         receiver.from_arcis(input)
     }
+
+    // Record sections a role may be shown, as bits of the `sections` mask
+    const SECTION_DEMOGRAPHICS: u8 = 1 << 0;
+    const SECTION_ALLERGIES: u8 = 1 << 1;
+    const SECTION_MEDICATIONS: u8 = 1 << 2;
+    const SECTION_HISTORY: u8 = 1 << 3;
+    const SECTION_GENOMICS: u8 = 1 << 4;
+    const SECTION_LAB_TESTS: u8 = 1 << 5;
+    const SECTION_IMAGING: u8 = 1 << 6;
+    const SECTION_IMMUNIZATIONS: u8 = 1 << 7;
+
+    /// Re-encrypts the record with every section outside `sections` zeroed, so a role only
+    /// receives the slices it is permitted to see.
+    #[instruction]
+    pub fn share_patient_data_scoped(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        sections: u8,
+    ) -> Enc<Shared, PatientData> {
+        let mut data = input_ctxt.to_arcis();
+
+        if sections & SECTION_DEMOGRAPHICS == 0 {
+            data.patient_id = 0;
+            data.age = 0;
+            data.gender = false;
+            data.blood_type = 0;
+            data.weight = 0;
+            data.height = 0;
+        }
+        if sections & SECTION_ALLERGIES == 0 {
+            data.allergies = [false; 5];
+        }
+        if sections & SECTION_MEDICATIONS == 0 {
+            data.medication_count = 0;
+            data.medications = [0; 8];
+            data.rxnorm_codes = [0; 8];
+        }
+        if sections & SECTION_HISTORY == 0 {
+            data.medical_history = [false; 10];
+            data.icd10_codes = [0; 10];
+            data.procedure_count = 0;
+            data.procedure_dates = [0; 8];
+            data.family_history = [false; 5];
+        }
+        if sections & SECTION_GENOMICS == 0 {
+            data.variant_count = 0;
+            data.genetic_markers = [0; 15];
+            data.variant_significance = [0; 15];
+            data.carrier_status = [false; 5];
+            data.pharmacogenomic_markers = [false; 3];
+            data.ancestry_components = [0; 7];
+        }
+        if sections & SECTION_LAB_TESTS == 0 {
+            data.lab_test_count = 0;
+            data.lab_test_types = [0; 10];
+            data.lab_test_dates = [0; 10];
+            data.lab_test_values = [0; 10];
+            data.lab_test_flags = [0; 10];
+            data.loinc_codes = [0; 10];
+        }
+        if sections & SECTION_IMAGING == 0 {
+            data.imaging_count = 0;
+            data.imaging_types = [0; 10];
+            data.imaging_dates = [0; 10];
+        }
+        if sections & SECTION_IMMUNIZATIONS == 0 {
+            data.immunization_count = 0;
+            data.vaccine_codes = [0; 8];
+            data.vaccination_dates = [0; 8];
+            data.vaccine_doses = [0; 8];
+        }
+
+        receiver.from_arcis(data)
+    }
+
+    /// Re-encrypts only the fields selected by `mask` for the receiver; every other field
+    /// arrives as an encrypted zero. Bit i selects the i-th field of PatientData in
+    /// declaration order, arrays counting as one field and the canary skipped, so bits 0-26
+    /// cover the 152 base fields and bits 27-33 the immunization and terminology sections.
+    #[instruction]
+    pub fn share_selected_fields(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        mask: u128,
+    ) -> Enc<Shared, PatientData> {
+        let mut data = input_ctxt.to_arcis();
+
+        if mask & 1 == 0 {
+            data.patient_id = 0;
+        }
+        if mask & (1 << 1) == 0 {
+            data.age = 0;
+        }
+        if mask & (1 << 2) == 0 {
+            data.gender = false;
+        }
+        if mask & (1 << 3) == 0 {
+            data.blood_type = 0;
+        }
+        if mask & (1 << 4) == 0 {
+            data.weight = 0;
+        }
+        if mask & (1 << 5) == 0 {
+            data.height = 0;
+        }
+        if mask & (1 << 6) == 0 {
+            data.allergies = [false; 5];
+        }
+        if mask & (1 << 7) == 0 {
+            data.medical_history = [false; 10];
+        }
+        if mask & (1 << 8) == 0 {
+            data.medication_count = 0;
+        }
+        if mask & (1 << 9) == 0 {
+            data.medications = [0; 8];
+        }
+        if mask & (1 << 10) == 0 {
+            data.procedure_count = 0;
+        }
+        if mask & (1 << 11) == 0 {
+            data.procedure_dates = [0; 8];
+        }
+        if mask & (1 << 12) == 0 {
+            data.family_history = [false; 5];
+        }
+        if mask & (1 << 13) == 0 {
+            data.variant_count = 0;
+        }
+        if mask & (1 << 14) == 0 {
+            data.genetic_markers = [0; 15];
+        }
+        if mask & (1 << 15) == 0 {
+            data.variant_significance = [0; 15];
+        }
+        if mask & (1 << 16) == 0 {
+            data.carrier_status = [false; 5];
+        }
+        if mask & (1 << 17) == 0 {
+            data.pharmacogenomic_markers = [false; 3];
+        }
+        if mask & (1 << 18) == 0 {
+            data.ancestry_components = [0; 7];
+        }
+        if mask & (1 << 19) == 0 {
+            data.lab_test_count = 0;
+        }
+        if mask & (1 << 20) == 0 {
+            data.lab_test_types = [0; 10];
+        }
+        if mask & (1 << 21) == 0 {
+            data.lab_test_dates = [0; 10];
+        }
+        if mask & (1 << 22) == 0 {
+            data.lab_test_values = [0; 10];
+        }
+        if mask & (1 << 23) == 0 {
+            data.lab_test_flags = [0; 10];
+        }
+        if mask & (1 << 24) == 0 {
+            data.imaging_count = 0;
+        }
+        if mask & (1 << 25) == 0 {
+            data.imaging_types = [0; 10];
+        }
+        if mask & (1 << 26) == 0 {
+            data.imaging_dates = [0; 10];
+        }
+        if mask & (1 << 27) == 0 {
+            data.immunization_count = 0;
+        }
+        if mask & (1 << 28) == 0 {
+            data.vaccine_codes = [0; 8];
+        }
+        if mask & (1 << 29) == 0 {
+            data.vaccination_dates = [0; 8];
+        }
+        if mask & (1 << 30) == 0 {
+            data.vaccine_doses = [0; 8];
+        }
+        if mask & (1 << 31) == 0 {
+            data.icd10_codes = [0; 10];
+        }
+        if mask & (1 << 32) == 0 {
+            data.rxnorm_codes = [0; 8];
+        }
+        if mask & (1 << 33) == 0 {
+            data.loinc_codes = [0; 10];
+        }
+
+        receiver.from_arcis(data)
+    }
+
+    // Age band width and Gregorian month length (146097 days per 4800 months) used when
+    // pseudonymizing records for research
+    const AGE_BAND_YEARS: u8 = 5;
+    const DAYS_PER_400_YEARS: u64 = 146_097;
+    const MONTHS_PER_400_YEARS: u64 = 4_800;
+
+    // Rounds a date (days since epoch) down to the start of its month, months taken at their
+    // average Gregorian length so the result is within a day of the calendar month start
+    fn month_start(date: u32) -> u32 {
+        let month = date as u64 * MONTHS_PER_400_YEARS / DAYS_PER_400_YEARS;
+        let days = month * DAYS_PER_400_YEARS;
+        let start = days / MONTHS_PER_400_YEARS;
+        // First day whose month index is `month`
+        if start * MONTHS_PER_400_YEARS < days {
+            (start + 1) as u32
+        } else {
+            start as u32
+        }
+    }
+
+    /// Re-encrypts a de-identified copy of the record for a researcher: the patient id is
+    /// zeroed, age is bucketed into 5-year bands and every procedure, lab test, imaging and
+    /// vaccination date is coarsened to the start of its month.
+    #[instruction]
+    pub fn pseudonymize_for_research(
+        researcher: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+    ) -> Enc<Shared, PatientData> {
+        let mut data = input_ctxt.to_arcis();
+
+        data.patient_id = 0;
+        data.age = data.age / AGE_BAND_YEARS * AGE_BAND_YEARS;
+        for i in 0..8 {
+            data.procedure_dates[i] = month_start(data.procedure_dates[i]);
+            data.vaccination_dates[i] = month_start(data.vaccination_dates[i]);
+        }
+        for i in 0..10 {
+            data.lab_test_dates[i] = month_start(data.lab_test_dates[i]);
+            data.imaging_dates[i] = month_start(data.imaging_dates[i]);
+        }
+
+        researcher.from_arcis(data)
+    }
+
+    #[instruction]
+    pub fn share_emergency_data(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+    ) -> Enc<Shared, EmergencyData> {
+        let input = input_ctxt.to_arcis();
+        let emergency = EmergencyData {
+            age: input.age,
+            gender: input.gender,
+            blood_type: input.blood_type,
+            weight: input.weight,
+            height: input.height,
+            allergies: input.allergies,
+            medical_history: input.medical_history,
+            medication_count: input.medication_count,
+            medications: input.medications,
+        };
+        receiver.from_arcis(emergency)
+    }
+
+    #[instruction]
+    pub fn emergency_summary(
+        responder: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+    ) -> Enc<Shared, EmergencySummary> {
+        let input = input_ctxt.to_arcis();
+        let mut critical_conditions = [false; 10];
+        for i in 0..10 {
+            critical_conditions[i] = input.medical_history[i] && CRITICAL_CONDITIONS[i];
+        }
+        let summary = EmergencySummary {
+            blood_type: input.blood_type,
+            allergies: input.allergies,
+            critical_conditions,
+            medication_count: input.medication_count,
+            medications: input.medications,
+        };
+        responder.from_arcis(summary)
+    }
+
+    // Re-encrypts the patient's advance directive flags (DNR and similar) for a hospital
+    // responder; nothing from the record itself is read.
+    #[instruction]
+    pub fn share_advance_directive(
+        receiver: Shared,
+        directive_ctxt: Enc<Shared, u8>,
+    ) -> Enc<Shared, u8> {
+        receiver.from_arcis(directive_ctxt.to_arcis())
+    }
+
+    #[instruction]
+    pub fn share_demographics(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, Demographics>,
+    ) -> Enc<Shared, Demographics> {
+        let input = input_ctxt.to_arcis();
+        receiver.from_arcis(input)
+    }
+
+    #[instruction]
+    pub fn share_healthcare(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, HealthcareData>,
+    ) -> Enc<Shared, HealthcareData> {
+        let input = input_ctxt.to_arcis();
+        receiver.from_arcis(input)
+    }
+
+    #[instruction]
+    pub fn share_genomics(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, GenomicData>,
+    ) -> Enc<Shared, GenomicData> {
+        let input = input_ctxt.to_arcis();
+        receiver.from_arcis(input)
+    }
+
+    #[instruction]
+    pub fn share_lab_tests(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, LabTestData>,
+    ) -> Enc<Shared, LabTestData> {
+        let input = input_ctxt.to_arcis();
+        receiver.from_arcis(input)
+    }
+
+    #[instruction]
+    pub fn share_entry(
+        receiver: Shared,
+        entry_ctxt: Enc<Shared, EntryFields>,
+    ) -> Enc<Shared, EntryFields> {
+        let entry = entry_ctxt.to_arcis();
+        receiver.from_arcis(entry)
+    }
+
+    #[instruction]
+    pub fn share_entry_history(
+        receiver: Shared,
+        current_ctxt: Enc<Shared, EntryFields>,
+        previous_ctxt: Enc<Shared, EntryFields>,
+    ) -> Enc<Shared, EntryHistory> {
+        let current = current_ctxt.to_arcis();
+        let previous = previous_ctxt.to_arcis();
+        receiver.from_arcis(EntryHistory {
+            current: current.fields,
+            previous: previous.fields,
+        })
+    }
+
+    #[instruction]
+    pub fn share_flagged_entry(
+        receiver: Shared,
+        entry_ctxt: Enc<Shared, EntryFields>,
+        annotation_ctxt: Enc<Shared, EntryAnnotation>,
+    ) -> Enc<Shared, FlaggedEntry> {
+        let entry = entry_ctxt.to_arcis();
+        let annotation = annotation_ctxt.to_arcis();
+        receiver.from_arcis(FlaggedEntry {
+            fields: entry.fields,
+            annotation: annotation.fields,
+        })
+    }
+
+    #[instruction]
+    pub fn share_mental_health(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, MentalHealthData>,
+    ) -> Enc<Shared, MentalHealthData> {
+        let input = input_ctxt.to_arcis();
+        receiver.from_arcis(input)
+    }
+
+    #[instruction]
+    pub fn share_obstetric_data(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, ObstetricData>,
+    ) -> Enc<Shared, ObstetricData> {
+        let input = input_ctxt.to_arcis();
+        receiver.from_arcis(input)
+    }
+
+    #[instruction]
+    pub fn share_attachment_key(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, AttachmentSecrets>,
+    ) -> Enc<Shared, AttachmentSecrets> {
+        let input = input_ctxt.to_arcis();
+        receiver.from_arcis(input)
+    }
+
+    #[instruction]
+    pub fn share_lab_leaf(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, LabResult>,
+    ) -> Enc<Shared, LabResult> {
+        let input = input_ctxt.to_arcis();
+        receiver.from_arcis(input)
+    }
+
+    #[instruction]
+    pub fn share_capacity_chunk(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, CapacityChunk>,
+    ) -> Enc<Shared, CapacityChunk> {
+        let input = input_ctxt.to_arcis();
+        receiver.from_arcis(input)
+    }
+
+    // Cardiovascular risk bands returned by compute_cardio_risk
+    const CARDIO_RISK_LOW: u8 = 0;
+    const CARDIO_RISK_MODERATE: u8 = 1;
+    const CARDIO_RISK_HIGH: u8 = 2;
+    const CARDIO_RISK_VERY_HIGH: u8 = 3;
+
+    // Lab test type and range flag marking an out-of-range lipid panel
+    const LAB_TEST_LIPID_PANEL: u8 = 1;
+    const LAB_TEST_FLAG_HIGH: u8 = 2;
+
+    #[instruction]
+    pub fn compute_cardio_risk(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+    ) -> Enc<Shared, u8> {
+        let input = input_ctxt.to_arcis();
+        let mut score: u8 = 0;
+
+        if input.age >= 65 {
+            score += 3;
+        } else if input.age >= 55 {
+            score += 2;
+        } else if input.age >= 45 {
+            score += 1;
+        }
+        // gender is true for male patients
+        if input.gender {
+            score += 1;
+        }
+
+        // diabetes, hypertension, heart_disease, stroke
+        if input.medical_history[0] {
+            score += 2;
+        }
+        if input.medical_history[1] {
+            score += 2;
+        }
+        if input.medical_history[2] {
+            score += 3;
+        }
+        if input.medical_history[4] {
+            score += 2;
+        }
+        // family heart_disease
+        if input.family_history[1] {
+            score += 1;
+        }
+
+        let mut high_lipids = false;
+        for i in 0..10 {
+            if (i as u8) < input.lab_test_count
+                && input.lab_test_types[i] == LAB_TEST_LIPID_PANEL
+                && input.lab_test_flags[i] == LAB_TEST_FLAG_HIGH
+            {
+                high_lipids = true;
+            }
+        }
+        if high_lipids {
+            score += 2;
+        }
+
+        let band = if score >= 8 {
+            CARDIO_RISK_VERY_HIGH
+        } else if score >= 5 {
+            CARDIO_RISK_HIGH
+        } else if score >= 3 {
+            CARDIO_RISK_MODERATE
+        } else {
+            CARDIO_RISK_LOW
+        };
+        receiver.from_arcis(band)
+    }
+
+    // Medication ids known to the interaction check
+    const MED_WARFARIN: u64 = 1001;
+    const MED_CLOPIDOGREL: u64 = 1002;
+    const MED_SIMVASTATIN: u64 = 1003;
+    const MED_ASPIRIN: u64 = 1004;
+    const MED_AMIODARONE: u64 = 1005;
+    const MED_CLARITHROMYCIN: u64 = 1006;
+    const MED_OMEPRAZOLE: u64 = 1007;
+
+    // Pairs of medications that must not be combined, as two parallel columns
+    const INTERACTION_PAIRS: usize = 4;
+    const INTERACTS_WITH_A: [u64; INTERACTION_PAIRS] =
+        [MED_WARFARIN, MED_WARFARIN, MED_SIMVASTATIN, MED_CLOPIDOGREL];
+    const INTERACTS_WITH_B: [u64; INTERACTION_PAIRS] =
+        [MED_ASPIRIN, MED_AMIODARONE, MED_CLARITHROMYCIN, MED_OMEPRAZOLE];
+
+    #[instruction]
+    pub fn check_drug_interaction(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        candidate_ctxt: Enc<Shared, u64>,
+    ) -> Enc<Shared, bool> {
+        let input = input_ctxt.to_arcis();
+        let candidate = candidate_ctxt.to_arcis();
+        let mut interacts = false;
+
+        // Duplicate therapy or a known interacting pair with a current medication
+        for i in 0..8 {
+            let med = input.medications[i];
+            let mut conflict = med == candidate;
+            for j in 0..INTERACTION_PAIRS {
+                if (med == INTERACTS_WITH_A[j] && candidate == INTERACTS_WITH_B[j])
+                    || (med == INTERACTS_WITH_B[j] && candidate == INTERACTS_WITH_A[j])
+                {
+                    conflict = true;
+                }
+            }
+            if (i as u8) < input.medication_count && conflict {
+                interacts = true;
+            }
+        }
+
+        // Pharmacogenomic markers flag variants that make the matching drug unsafe
+        let markers = input.pharmacogenomic_markers;
+        if (candidate == MED_WARFARIN && markers[0])
+            || (candidate == MED_CLOPIDOGREL && markers[1])
+            || (candidate == MED_SIMVASTATIN && markers[2])
+        {
+            interacts = true;
+        }
+
+        receiver.from_arcis(interacts)
+    }
+
+    #[instruction]
+    pub fn check_trial_eligibility(
+        sponsor: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        min_age: u8,
+        max_age: u8,
+        required_history: u16,
+        excluded_med_0: u64,
+        excluded_med_1: u64,
+        excluded_med_2: u64,
+        excluded_med_3: u64,
+    ) -> Enc<Shared, bool> {
+        let input = input_ctxt.to_arcis();
+        let mut eligible = input.age >= min_age && input.age <= max_age;
+
+        // Bit i of required_history requires medical_history[i]
+        for i in 0..10 {
+            if (required_history >> i) & 1 == 1 && !input.medical_history[i] {
+                eligible = false;
+            }
+        }
+
+        // Id 0 marks an unused exclusion slot
+        let excluded = [excluded_med_0, excluded_med_1, excluded_med_2, excluded_med_3];
+        for i in 0..8 {
+            for j in 0..4 {
+                if excluded[j] != 0
+                    && (i as u8) < input.medication_count
+                    && input.medications[i] == excluded[j]
+                {
+                    eligible = false;
+                }
+            }
+        }
+
+        sponsor.from_arcis(eligible)
+    }
+
+    // Prescription codes by the allergy class that rules them out. Allergies are recorded as
+    // [penicillin, sulfonamide, nsaid, opioid, cephalosporin].
+    const PENICILLIN_DRUGS: [u64; 2] = [2001, 2002]; // amoxicillin, penicillin V
+    const SULFONAMIDE_DRUGS: [u64; 2] = [2011, 2012]; // sulfamethoxazole, sulfasalazine
+    const NSAID_DRUGS: [u64; 2] = [1004, 2021]; // aspirin, ibuprofen
+    const OPIOID_DRUGS: [u64; 2] = [2031, 2032]; // morphine, codeine
+    const CEPHALOSPORIN_DRUGS: [u64; 2] = [2041, 2042]; // cephalexin, ceftriaxone
+
+    #[instruction]
+    pub fn check_allergy_conflict(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        prescription_code: u64,
+    ) -> Enc<Shared, bool> {
+        let input = input_ctxt.to_arcis();
+        let classes = [
+            PENICILLIN_DRUGS,
+            SULFONAMIDE_DRUGS,
+            NSAID_DRUGS,
+            OPIOID_DRUGS,
+            CEPHALOSPORIN_DRUGS,
+        ];
+        let mut in_class = [false; 5];
+        for c in 0..5 {
+            for d in 0..2 {
+                if classes[c][d] == prescription_code {
+                    in_class[c] = true;
+                }
+            }
+        }
+
+        let mut conflict = false;
+        for c in 0..5 {
+            if in_class[c] && input.allergies[c] {
+                conflict = true;
+            }
+        }
+        // Penicillin allergies cross-react with cephalosporins
+        if in_class[4] && input.allergies[0] {
+            conflict = true;
+        }
+        // NSAIDs can trigger bronchospasm in asthma and worsen heart disease
+        if in_class[2] && (input.medical_history[5] || input.medical_history[2]) {
+            conflict = true;
+        }
+        // Opioids depress breathing in COPD
+        if in_class[3] && input.medical_history[6] {
+            conflict = true;
+        }
+
+        receiver.from_arcis(conflict)
+    }
+
+    // Blood types are encoded as ABO group * 2 + Rh: O-, O+, A-, A+, B-, B+, AB-, AB+ = 0..7.
+    // Entry [donor][recipient] is true if the donor's blood can be given to the recipient.
+    const BLOOD_COMPATIBLE: [[bool; 8]; 8] = [
+        [true, true, true, true, true, true, true, true],
+        [false, true, false, true, false, true, false, true],
+        [false, false, true, true, false, false, true, true],
+        [false, false, false, true, false, false, false, true],
+        [false, false, false, false, true, true, true, true],
+        [false, false, false, false, false, true, false, true],
+        [false, false, false, false, false, false, true, true],
+        [false, false, false, false, false, false, false, true],
+    ];
+
+    #[instruction]
+    pub fn check_blood_compatibility(
+        receiver: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        recipient_ctxt: Enc<Shared, u8>,
+    ) -> Enc<Shared, bool> {
+        let donor = input_ctxt.to_arcis().blood_type;
+        let recipient = recipient_ctxt.to_arcis();
+        let mut compatible = false;
+        for d in 0..8 {
+            for r in 0..8 {
+                if BLOOD_COMPATIBLE[d][r] && donor == d as u8 && recipient == r as u8 {
+                    compatible = true;
+                }
+            }
+        }
+        receiver.from_arcis(compatible)
+    }
+
+    // HLA alleles typed for transplant matching: two each of HLA-A, HLA-B and HLA-DR
+    const HLA_MARKERS: usize = 6;
+
+    pub struct DonorProfile {
+        pub donor_consent: bool,
+        pub hla_markers: [u16; HLA_MARKERS],
+    }
+
+    pub struct RecipientCriteria {
+        pub blood_type: u8,
+        pub hla_markers: [u16; HLA_MARKERS],
+        pub min_matches: u8,
+    }
+
+    // Checks a donor for a transplant coordinator's recipient: whether the donor consented,
+    // is blood compatible and carries at least min_matches of the recipient's HLA alleles at
+    // the same locus. Only that flag is revealed, not the number of matching alleles.
+    #[instruction]
+    pub fn check_donor_match(
+        coordinator: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        donor_ctxt: Enc<Shared, DonorProfile>,
+        criteria_ctxt: Enc<Shared, RecipientCriteria>,
+    ) -> Enc<Shared, bool> {
+        let donor_blood_type = input_ctxt.to_arcis().blood_type;
+        let donor = donor_ctxt.to_arcis();
+        let criteria = criteria_ctxt.to_arcis();
+
+        let mut compatible = false;
+        for d in 0..8 {
+            for r in 0..8 {
+                if BLOOD_COMPATIBLE[d][r]
+                    && donor_blood_type == d as u8
+                    && criteria.blood_type == r as u8
+                {
+                    compatible = true;
+                }
+            }
+        }
+
+        let mut matches = 0u8;
+        for i in 0..HLA_MARKERS {
+            let locus = i / 2 * 2;
+            if criteria.hla_markers[i] == donor.hla_markers[locus]
+                || criteria.hla_markers[i] == donor.hla_markers[locus + 1]
+            {
+                matches += 1;
+            }
+        }
+
+        let eligible = donor.donor_consent && compatible && matches >= criteria.min_matches;
+        coordinator.from_arcis(eligible)
+    }
+
+    #[instruction]
+    pub fn rotate_patient_data(
+        owner: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+    ) -> Enc<Shared, PatientData> {
+        let input = input_ctxt.to_arcis();
+        owner.from_arcis(input)
+    }
+
+    #[instruction]
+    pub fn rekey_patient_data(
+        new_owner: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+    ) -> Enc<Shared, PatientData> {
+        let input = input_ctxt.to_arcis();
+        new_owner.from_arcis(input)
+    }
+
+    // Dose buckets returned by recommend_dose are quarters of the base dose
+    const DOSE_BUCKETS_STANDARD: u64 = 4;
+    const DOSE_BUCKETS_REDUCED: u64 = 2;
+    const DOSE_BUCKETS_AVOID: u64 = 0;
+
+    #[instruction]
+    pub fn recommend_dose(
+        prescriber: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        drug_id: u64,
+        base_dose: u64,
+    ) -> Enc<Shared, u64> {
+        let input = input_ctxt.to_arcis();
+        let markers = input.pharmacogenomic_markers;
+
+        // Warfarin sensitivity and poor statin response call for half the dose, while
+        // clopidogrel resistance means the drug will not work and an alternative is needed
+        let buckets = if drug_id == MED_CLOPIDOGREL && markers[1] {
+            DOSE_BUCKETS_AVOID
+        } else if (drug_id == MED_WARFARIN && markers[0])
+            || (drug_id == MED_SIMVASTATIN && markers[2])
+        {
+            DOSE_BUCKETS_REDUCED
+        } else {
+            DOSE_BUCKETS_STANDARD
+        };
+
+        prescriber.from_arcis(base_dose / DOSE_BUCKETS_STANDARD * buckets)
+    }
+
+    // Attributes prove_attribute_threshold can compare
+    const ATTRIBUTE_AGE: u8 = 0;
+    const ATTRIBUTE_WEIGHT: u8 = 1;
+    const ATTRIBUTE_HEIGHT: u8 = 2;
+    const ATTRIBUTE_BMI: u8 = 3;
+
+    #[instruction]
+    pub fn prove_attribute_threshold(
+        verifier: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        attribute_selector: u8,
+        threshold: u16,
+    ) -> Enc<Shared, bool> {
+        let input = input_ctxt.to_arcis();
+        let threshold = threshold as u64;
+
+        // BMI = kg / m^2, compared without division as weight * 10000 >= threshold * cm^2
+        let height = input.height as u64;
+        let bmi_at_least = (input.weight as u64) * 10000 >= threshold * height * height;
+
+        let at_least = if attribute_selector == ATTRIBUTE_AGE {
+            (input.age as u64) >= threshold
+        } else if attribute_selector == ATTRIBUTE_WEIGHT {
+            (input.weight as u64) >= threshold
+        } else if attribute_selector == ATTRIBUTE_HEIGHT {
+            height >= threshold
+        } else if attribute_selector == ATTRIBUTE_BMI {
+            bmi_at_least
+        } else {
+            false
+        };
+
+        verifier.from_arcis(at_least)
+    }
+
+    #[instruction]
+    pub fn prove_vaccination(
+        verifier: Shared,
+        input_ctxt: Enc<Shared, ImmunizationData>,
+        vaccine_code: u64,
+    ) -> Enc<Shared, bool> {
+        let input = input_ctxt.to_arcis();
+        let mut vaccinated = false;
+        for i in 0..8 {
+            if (i as u8) < input.immunization_count
+                && input.vaccine_codes[i] == vaccine_code
+                && input.vaccine_doses[i] > 0
+            {
+                vaccinated = true;
+            }
+        }
+        verifier.from_arcis(vaccinated)
+    }
+
+    // Number of patients aggregated by aggregate_cohort
+    const COHORT_SIZE: usize = 5;
+    // Query condition matching every member regardless of medical history
+    const ANY_CONDITION: u8 = u8::MAX;
+
+    pub struct CohortStats {
+        // Number of matching members with each medical_history flag set
+        pub history_counts: [u8; 10],
+        // Mean age of the matching members, rounded down
+        pub mean_age: u8,
+        // Set when fewer than min_cohort members matched; the statistics are then all zero
+        pub insufficient_cohort: bool,
+    }
+
+    // Members match when they have medical_history flag `condition` (or any history with
+    // ANY_CONDITION) and an age in min_age..=max_age. Statistics are only released when at
+    // least min_cohort members matched, so a query cannot single out one patient.
+    #[instruction]
+    pub fn aggregate_cohort(
+        researcher: Shared,
+        member_0: Enc<Shared, PatientData>,
+        member_1: Enc<Shared, PatientData>,
+        member_2: Enc<Shared, PatientData>,
+        member_3: Enc<Shared, PatientData>,
+        member_4: Enc<Shared, PatientData>,
+        condition: u8,
+        min_age: u8,
+        max_age: u8,
+        min_cohort: u8,
+    ) -> Enc<Shared, CohortStats> {
+        let members = [
+            member_0.to_arcis(),
+            member_1.to_arcis(),
+            member_2.to_arcis(),
+            member_3.to_arcis(),
+            member_4.to_arcis(),
+        ];
+
+        let mut history_counts = [0u8; 10];
+        let mut age_total: u16 = 0;
+        let mut matched: u8 = 0;
+        for m in 0..COHORT_SIZE {
+            let mut has_condition = condition == ANY_CONDITION;
+            for i in 0..10 {
+                if condition as usize == i && members[m].medical_history[i] {
+                    has_condition = true;
+                }
+            }
+            let age = members[m].age;
+            if has_condition && age >= min_age && age <= max_age {
+                for i in 0..10 {
+                    if members[m].medical_history[i] {
+                        history_counts[i] += 1;
+                    }
+                }
+                age_total += age as u16;
+                matched += 1;
+            }
+        }
+
+        let insufficient_cohort = matched < min_cohort || matched == 0;
+        let divisor = if matched == 0 { 1 } else { matched as u16 };
+        let mut mean_age = (age_total / divisor) as u8;
+        if insufficient_cohort {
+            history_counts = [0; 10];
+            mean_age = 0;
+        }
+
+        researcher.from_arcis(CohortStats {
+            history_counts,
+            mean_age,
+            insufficient_cohort,
+        })
+    }
+
+    // Upper bound on the coin flips behind each noisy count of aggregate_cohort_dp
+    const MAX_NOISE_TRIALS: usize = 64;
+
+    pub struct NoisyCohortCounts {
+        // Number of cohort members with each medical_history flag set, plus noise
+        pub history_counts: [u8; 10],
+    }
+
+    #[instruction]
+    pub fn aggregate_cohort_dp(
+        researcher: Shared,
+        member_0: Enc<Shared, PatientData>,
+        member_1: Enc<Shared, PatientData>,
+        member_2: Enc<Shared, PatientData>,
+        member_3: Enc<Shared, PatientData>,
+        member_4: Enc<Shared, PatientData>,
+        noise_trials: u8,
+    ) -> Enc<Shared, NoisyCohortCounts> {
+        let members = [
+            member_0.to_arcis(),
+            member_1.to_arcis(),
+            member_2.to_arcis(),
+            member_3.to_arcis(),
+            member_4.to_arcis(),
+        ];
+
+        // Binomial mechanism: add Bin(noise_trials, 1/2) - noise_trials / 2 to each count,
+        // drawn inside MPC so no node learns the noise
+        let mut history_counts = [0u8; 10];
+        for i in 0..10 {
+            // Twice (count + heads), so the expected noise can be removed without fractions
+            let mut doubled: u16 = 0;
+            for m in 0..COHORT_SIZE {
+                if members[m].medical_history[i] {
+                    doubled += 2;
+                }
+            }
+            for t in 0..MAX_NOISE_TRIALS {
+                if (t as u8) < noise_trials && ArcisRNG::bool() {
+                    doubled += 2;
+                }
+            }
+            let trials = noise_trials as u16;
+            let centered = if doubled > trials { (doubled - trials) / 2 } else { 0 };
+            history_counts[i] = if centered > 255 { 255 } else { centered as u8 };
+        }
+
+        researcher.from_arcis(NoisyCohortCounts { history_counts })
+    }
+
+    // Number of markers in a researcher's match_genetic_markers query
+    const MARKER_QUERY_SIZE: usize = 8;
+
+    pub struct MarkerQuery {
+        // Marker ids to look for; 0 marks an unused slot
+        pub markers: [u64; MARKER_QUERY_SIZE],
+    }
+
+    #[instruction]
+    pub fn match_genetic_markers(
+        researcher: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        query_ctxt: Enc<Shared, MarkerQuery>,
+    ) -> Enc<Shared, u8> {
+        let input = input_ctxt.to_arcis();
+        let query = query_ctxt.to_arcis();
+
+        // Every pair is compared, so neither side learns which markers matched
+        let mut count: u8 = 0;
+        for q in 0..MARKER_QUERY_SIZE {
+            let mut found = false;
+            for i in 0..15 {
+                if (i as u16) < input.variant_count
+                    && input.genetic_markers[i] == query.markers[q]
+                {
+                    found = true;
+                }
+            }
+            if found && query.markers[q] != 0 {
+                count += 1;
+            }
+        }
+
+        researcher.from_arcis(count)
+    }
+
+    // Trend directions returned by analyze_lab_trend
+    const TREND_STABLE: u8 = 0;
+    const TREND_RISING: u8 = 1;
+    const TREND_FALLING: u8 = 2;
+    // Lab flag values
+    const LAB_FLAG_LOW: u8 = 0;
+    const LAB_FLAG_HIGH: u8 = 2;
+    // Consecutive out-of-range results of one test type that count as an anomaly
+    const LAB_ANOMALY_STREAK: u8 = 3;
+
+    pub struct LabTrend {
+        pub anomaly: bool,
+        pub direction: u8,
+    }
+
+    #[instruction]
+    pub fn analyze_lab_trend(
+        physician: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        test_type: u8,
+    ) -> Enc<Shared, LabTrend> {
+        let input = input_ctxt.to_arcis();
+
+        let mut seen = false;
+        let mut first_date: u32 = 0;
+        let mut first_value: u16 = 0;
+        let mut last_date: u32 = 0;
+        let mut last_value: u16 = 0;
+        let mut last_flag: u8 = 1;
+        let mut streak: u8 = 0;
+        let mut longest_streak: u8 = 0;
+
+        // Results are stored in the order they were taken
+        for i in 0..10 {
+            let matches = (i as u8) < input.lab_test_count && input.lab_test_types[i] == test_type;
+            let date = input.lab_test_dates[i];
+            let value = input.lab_test_values[i];
+            let flag = input.lab_test_flags[i];
+
+            if matches && (!seen || date < first_date) {
+                first_date = date;
+                first_value = value;
+            }
+            if matches && (!seen || date >= last_date) {
+                last_date = date;
+                last_value = value;
+                last_flag = flag;
+            }
+            if matches {
+                seen = true;
+                streak = if flag == LAB_FLAG_LOW || flag == LAB_FLAG_HIGH { streak + 1 } else { 0 };
+                if streak > longest_streak {
+                    longest_streak = streak;
+                }
+            }
+        }
+
+        // Changes within 1/20 (5%) of the first value count as stable
+        let first = first_value as u32;
+        let last = last_value as u32;
+        let tolerance = first / 20;
+        let direction = if last > first + tolerance {
+            TREND_RISING
+        } else if last + tolerance < first {
+            TREND_FALLING
+        } else {
+            TREND_STABLE
+        };
+
+        // A persistent streak, or a latest result moving further out of range
+        let anomaly = longest_streak >= LAB_ANOMALY_STREAK
+            || (last_flag == LAB_FLAG_HIGH && direction == TREND_RISING)
+            || (last_flag == LAB_FLAG_LOW && direction == TREND_FALLING);
+
+        physician.from_arcis(LabTrend { anomaly, direction })
+    }
+
+    // One batch of vitals samples streamed by a remote-monitoring device
+    pub struct VitalsSamples {
+        pub timestamps: [u64; 4],
+        pub systolic: [u16; 4],
+        pub diastolic: [u16; 4],
+        pub heart_rate: [u8; 4],
+        pub spo2: [u8; 4],
+        pub temperature: [u16; 4],
+    }
+
+    #[instruction]
+    pub fn share_vitals_window(
+        receiver: Shared,
+        batch_ctxt: Enc<Shared, VitalsSamples>,
+        start: u64,
+        end: u64,
+    ) -> Enc<Shared, VitalsSamples> {
+        let mut batch = batch_ctxt.to_arcis();
+
+        // Samples outside the window are zeroed rather than dropped, keeping the output size
+        // independent of how many samples matched
+        for i in 0..4 {
+            let inside = batch.timestamps[i] >= start && batch.timestamps[i] <= end;
+            if !inside {
+                batch.timestamps[i] = 0;
+                batch.systolic[i] = 0;
+                batch.diastolic[i] = 0;
+                batch.heart_rate[i] = 0;
+                batch.spo2[i] = 0;
+                batch.temperature[i] = 0;
+            }
+        }
+
+        receiver.from_arcis(batch)
+    }
+
+    // Consensus time-in-range target for continuous glucose monitoring, in mg/dL
+    const GLUCOSE_RANGE_LOW: u16 = 70;
+    const GLUCOSE_RANGE_HIGH: u16 = 180;
+
+    // One batch of continuous glucose monitoring readings in mg/dL, 0 for a missed reading
+    pub struct GlucoseReadings {
+        pub readings: [u16; 24],
+    }
+
+    pub struct GlucoseMetrics {
+        pub time_in_range: u8,
+        pub estimated_a1c: u16,
+    }
+
+    #[instruction]
+    pub fn compute_glucose_metrics(
+        endocrinologist: Shared,
+        first_ctxt: Enc<Shared, GlucoseReadings>,
+        second_ctxt: Enc<Shared, GlucoseReadings>,
+        third_ctxt: Enc<Shared, GlucoseReadings>,
+    ) -> Enc<Shared, GlucoseMetrics> {
+        let first = first_ctxt.to_arcis();
+        let second = second_ctxt.to_arcis();
+        let third = third_ctxt.to_arcis();
+
+        let mut readings = [0u16; 72];
+        for i in 0..24 {
+            readings[i] = first.readings[i];
+            readings[24 + i] = second.readings[i];
+            readings[48 + i] = third.readings[i];
+        }
+
+        let mut count: u32 = 0;
+        let mut in_range: u32 = 0;
+        let mut total: u32 = 0;
+        for i in 0..72 {
+            let reading = readings[i];
+            if reading > 0 {
+                count += 1;
+                total += reading as u32;
+                if reading >= GLUCOSE_RANGE_LOW && reading <= GLUCOSE_RANGE_HIGH {
+                    in_range += 1;
+                }
+            }
+        }
+
+        // Time in range in percent; the glucose management indicator estimates A1c as
+        // 3.31 + 0.02392 * mean glucose, returned in tenths of a percent
+        let divisor = if count == 0 { 1 } else { count };
+        let time_in_range = (in_range * 100 / divisor) as u8;
+        let mean = total / divisor;
+        let estimated_a1c = if count == 0 {
+            0
+        } else {
+            ((331_000 + 2_392 * mean) / 10_000) as u16
+        };
+
+        endocrinologist.from_arcis(GlucoseMetrics {
+            time_in_range,
+            estimated_a1c,
+        })
+    }
+
+    // Billed lines of an insurance claim
+    const CLAIM_LINES: usize = 4;
+    // CPT code ranges of surgical procedures, radiology and lab/pathology services
+    const CPT_SURGERY: (u32, u32) = (10_000, 69_999);
+    const CPT_RADIOLOGY: (u32, u32) = (70_000, 79_999);
+    const CPT_LAB: (u32, u32) = (80_000, 89_999);
+
+    pub struct ClaimData {
+        // Billed CPT codes (Category I, numeric); 0 marks an unused line
+        pub cpt_codes: [u32; CLAIM_LINES],
+        // Billed amount in cents
+        pub amount: u64,
+        // Primary diagnosis as an ICD-10 code (ASCII packed into a u64)
+        pub diagnosis: u64,
+    }
+
+    // Scores a claim against the record as the percentage of its checks that pass: the
+    // diagnosis must be coded on a medical history flag the patient has, and the surgical,
+    // radiology and lab lines billed must each be covered by as many recorded procedures,
+    // imaging results and lab tests. Only the score is encrypted for the insurer.
+    #[instruction]
+    pub fn verify_claim_consistency(
+        insurer: Shared,
+        claim_ctxt: Enc<Shared, ClaimData>,
+        input_ctxt: Enc<Shared, PatientData>,
+    ) -> Enc<Shared, u8> {
+        let claim = claim_ctxt.to_arcis();
+        let input = input_ctxt.to_arcis();
+
+        let mut diagnosed = false;
+        for i in 0..10 {
+            if input.medical_history[i] && input.icd10_codes[i] == claim.diagnosis {
+                diagnosed = true;
+            }
+        }
+
+        let mut checks: u16 = 1;
+        let mut passed: u16 = if diagnosed { 1 } else { 0 };
+        let (mut surgery, mut radiology, mut lab) = (0u8, 0u8, 0u8);
+        for i in 0..CLAIM_LINES {
+            let code = claim.cpt_codes[i];
+            let supported = if code >= CPT_SURGERY.0 && code <= CPT_SURGERY.1 {
+                surgery += 1;
+                surgery <= input.procedure_count
+            } else if code >= CPT_RADIOLOGY.0 && code <= CPT_RADIOLOGY.1 {
+                radiology += 1;
+                radiology <= input.imaging_count
+            } else if code >= CPT_LAB.0 && code <= CPT_LAB.1 {
+                lab += 1;
+                lab <= input.lab_test_count
+            } else {
+                // Evaluation and management, anesthesia and medicine codes leave no trace
+                // in the record to check against
+                true
+            };
+            if code != 0 {
+                checks += 1;
+                if supported {
+                    passed += 1;
+                }
+            }
+        }
+
+        insurer.from_arcis((passed * 100 / checks) as u8)
+    }
+
+    // Pre-authorization decisions of check_coverage
+    const COVERAGE_APPROVE: u8 = 0;
+    const COVERAGE_DENY: u8 = 1;
+    const COVERAGE_REVIEW: u8 = 2;
+
+    // Decides a pre-authorization against the insurer's plaintext policy: denied when the
+    // patient has an excluded condition or lacks the required one (ANY_CONDITION requires
+    // none), sent to review when they have a review condition or more recorded procedures
+    // than max_procedures, approved otherwise. Only the decision is encrypted, once for the
+    // insurer and once for the patient.
+    #[instruction]
+    pub fn check_coverage(
+        insurer: Shared,
+        patient: Shared,
+        input_ctxt: Enc<Shared, PatientData>,
+        required_condition: u8,
+        excluded_conditions: u16,
+        review_conditions: u16,
+        max_procedures: u8,
+    ) -> (Enc<Shared, u8>, Enc<Shared, u8>) {
+        let input = input_ctxt.to_arcis();
+
+        let mut has_required = required_condition == ANY_CONDITION;
+        let mut excluded = false;
+        let mut review = input.procedure_count > max_procedures;
+        for i in 0..10 {
+            if input.medical_history[i] {
+                if required_condition as usize == i {
+                    has_required = true;
+                }
+                if excluded_conditions & (1 << i) != 0 {
+                    excluded = true;
+                }
+                if review_conditions & (1 << i) != 0 {
+                    review = true;
+                }
+            }
+        }
+
+        let decision = if excluded || !has_required {
+            COVERAGE_DENY
+        } else if review {
+            COVERAGE_REVIEW
+        } else {
+            COVERAGE_APPROVE
+        };
+        (insurer.from_arcis(decision), patient.from_arcis(decision))
+    }
+
+    // Compares the verdict codes two specialists encrypted for a second opinion and reveals
+    // to the patient only whether they agree, never the verdicts themselves.
+    #[instruction]
+    pub fn compare_opinions(
+        patient: Shared,
+        first_ctxt: Enc<Shared, u8>,
+        second_ctxt: Enc<Shared, u8>,
+    ) -> Enc<Shared, bool> {
+        patient.from_arcis(first_ctxt.to_arcis() == second_ctxt.to_arcis())
+    }
+
+    // Re-encrypts a record under the MXE's own key for a care team, so no team member can
+    // decrypt it until release_team_record is approved on-chain.
+    #[instruction]
+    pub fn share_to_care_team(
+        mxe: Mxe,
+        input_ctxt: Enc<Shared, PatientData>,
+    ) -> Enc<Mxe, PatientData> {
+        mxe.from_arcis(input_ctxt.to_arcis())
+    }
+
+    // Re-encrypts a care team's copy of a record for the member whose access reached the
+    // team's approval threshold.
+    #[instruction]
+    pub fn release_team_record(
+        member: Shared,
+        team_ctxt: Enc<Mxe, PatientData>,
+    ) -> Enc<Shared, PatientData> {
+        member.from_arcis(team_ctxt.to_arcis())
+    }
 }
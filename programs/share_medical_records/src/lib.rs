@@ -1,7 +1,443 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::address_lookup_table::{
+    self, instruction as alt_instruction,
+};
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::instruction::{
+    get_stack_height, AccountMeta, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT,
+};
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::sysvar::instructions::get_instruction_relative;
 use arcium_anchor::prelude::*;
+use core::mem::offset_of;
 
 const COMP_DEF_OFFSET_SHARE_PATIENT_DATA: u32 = comp_def_offset("share_patient_data");
+const COMP_DEF_OFFSET_SHARE_EMERGENCY_DATA: u32 = comp_def_offset("share_emergency_data");
+const COMP_DEF_OFFSET_EMERGENCY_SUMMARY: u32 = comp_def_offset("emergency_summary");
+const COMP_DEF_OFFSET_SHARE_ADVANCE_DIRECTIVE: u32 = comp_def_offset("share_advance_directive");
+const COMP_DEF_OFFSET_CHECK_DONOR_MATCH: u32 = comp_def_offset("check_donor_match");
+const COMP_DEF_OFFSET_SHARE_DEMOGRAPHICS: u32 = comp_def_offset("share_demographics");
+const COMP_DEF_OFFSET_SHARE_HEALTHCARE: u32 = comp_def_offset("share_healthcare");
+const COMP_DEF_OFFSET_SHARE_GENOMICS: u32 = comp_def_offset("share_genomics");
+const COMP_DEF_OFFSET_SHARE_LAB_TESTS: u32 = comp_def_offset("share_lab_tests");
+const COMP_DEF_OFFSET_SHARE_ENTRY: u32 = comp_def_offset("share_entry");
+const COMP_DEF_OFFSET_SHARE_ENTRY_HISTORY: u32 = comp_def_offset("share_entry_history");
+const COMP_DEF_OFFSET_SHARE_FLAGGED_ENTRY: u32 = comp_def_offset("share_flagged_entry");
+const COMP_DEF_OFFSET_COMPUTE_CARDIO_RISK: u32 = comp_def_offset("compute_cardio_risk");
+const COMP_DEF_OFFSET_CHECK_DRUG_INTERACTION: u32 = comp_def_offset("check_drug_interaction");
+const COMP_DEF_OFFSET_CHECK_TRIAL_ELIGIBILITY: u32 = comp_def_offset("check_trial_eligibility");
+const COMP_DEF_OFFSET_CHECK_ALLERGY_CONFLICT: u32 = comp_def_offset("check_allergy_conflict");
+const COMP_DEF_OFFSET_CHECK_BLOOD_COMPATIBILITY: u32 =
+    comp_def_offset("check_blood_compatibility");
+const COMP_DEF_OFFSET_ROTATE_PATIENT_DATA: u32 = comp_def_offset("rotate_patient_data");
+const COMP_DEF_OFFSET_REKEY_PATIENT_DATA: u32 = comp_def_offset("rekey_patient_data");
+const COMP_DEF_OFFSET_RECOMMEND_DOSE: u32 = comp_def_offset("recommend_dose");
+const COMP_DEF_OFFSET_PROVE_ATTRIBUTE_THRESHOLD: u32 =
+    comp_def_offset("prove_attribute_threshold");
+const COMP_DEF_OFFSET_PROVE_VACCINATION: u32 = comp_def_offset("prove_vaccination");
+const COMP_DEF_OFFSET_AGGREGATE_COHORT: u32 = comp_def_offset("aggregate_cohort");
+const COMP_DEF_OFFSET_AGGREGATE_COHORT_DP: u32 = comp_def_offset("aggregate_cohort_dp");
+const COMP_DEF_OFFSET_MATCH_GENETIC_MARKERS: u32 = comp_def_offset("match_genetic_markers");
+const COMP_DEF_OFFSET_ANALYZE_LAB_TREND: u32 = comp_def_offset("analyze_lab_trend");
+const COMP_DEF_OFFSET_SHARE_PATIENT_DATA_SCOPED: u32 =
+    comp_def_offset("share_patient_data_scoped");
+const COMP_DEF_OFFSET_SHARE_VITALS_WINDOW: u32 = comp_def_offset("share_vitals_window");
+const COMP_DEF_OFFSET_COMPUTE_GLUCOSE_METRICS: u32 = comp_def_offset("compute_glucose_metrics");
+const COMP_DEF_OFFSET_SHARE_MENTAL_HEALTH: u32 = comp_def_offset("share_mental_health");
+const COMP_DEF_OFFSET_SHARE_OBSTETRIC_DATA: u32 = comp_def_offset("share_obstetric_data");
+const COMP_DEF_OFFSET_SHARE_ATTACHMENT_KEY: u32 = comp_def_offset("share_attachment_key");
+const COMP_DEF_OFFSET_SHARE_LAB_LEAF: u32 = comp_def_offset("share_lab_leaf");
+const COMP_DEF_OFFSET_SHARE_CAPACITY_CHUNK: u32 = comp_def_offset("share_capacity_chunk");
+const COMP_DEF_OFFSET_SHARE_SELECTED_FIELDS: u32 = comp_def_offset("share_selected_fields");
+const COMP_DEF_OFFSET_PSEUDONYMIZE_FOR_RESEARCH: u32 = comp_def_offset("pseudonymize_for_research");
+const COMP_DEF_OFFSET_VERIFY_CLAIM_CONSISTENCY: u32 = comp_def_offset("verify_claim_consistency");
+const COMP_DEF_OFFSET_CHECK_COVERAGE: u32 = comp_def_offset("check_coverage");
+const COMP_DEF_OFFSET_COMPARE_OPINIONS: u32 = comp_def_offset("compare_opinions");
+const COMP_DEF_OFFSET_SHARE_TO_CARE_TEAM: u32 = comp_def_offset("share_to_care_team");
+const COMP_DEF_OFFSET_RELEASE_TEAM_RECORD: u32 = comp_def_offset("release_team_record");
+
+/// Maximum number of guardians a patient can register in an emergency policy.
+const MAX_EMERGENCY_GUARDIANS: usize = 8;
+
+/// Number of audit index shards available per patient per epoch.
+const AUDIT_INDEX_SHARDS: u8 = 8;
+
+/// Delegation scope bit allowing the delegate to call `share_patient_data`.
+pub const DELEGATION_SCOPE_SHARE: u8 = 1 << 0;
+/// All delegation scope bits currently understood by the program.
+const DELEGATION_SCOPE_ALL: u8 = DELEGATION_SCOPE_SHARE;
+
+/// Maximum number of receivers in a single batch share.
+const MAX_BATCH_RECEIVERS: usize = 5;
+
+/// Number of `remaining_accounts` each receiver of a batch share passes.
+const BATCH_RECEIVER_ACCOUNTS: usize = 8;
+
+/// Maximum number of expiring grants tracked per patient in the expiry index.
+const MAX_TRACKED_GRANTS: usize = 16;
+/// Maximum number of notifications retained in a patient's inbox.
+const MAX_INBOX_NOTIFICATIONS: usize = 16;
+/// Grants expiring within this many days trigger a reminder.
+const GRANT_REMINDER_WINDOW_DAYS: i64 = 7;
+/// Grant kind of a caregiver `DelegationAccount`.
+const GRANT_KIND_DELEGATION: u8 = 0;
+/// Grant kind of an appointment's `VisitGrant`.
+const GRANT_KIND_VISIT: u8 = 1;
+/// Notification kind for a grant that is about to expire.
+const NOTIFICATION_GRANT_EXPIRING: u8 = 0;
+/// Notification kind: the patient's honeytoken canary was seen outside the program.
+const NOTIFICATION_CANARY_HIT: u8 = 1;
+
+/// Role of the patient writing to their own record.
+pub const ROLE_PATIENT: u8 = 0;
+/// Role of a credentialed doctor.
+pub const ROLE_DOCTOR: u8 = 1;
+/// Role of a credentialed nurse.
+pub const ROLE_NURSE: u8 = 2;
+/// Role of a credentialed pharmacist.
+pub const ROLE_PHARMACIST: u8 = 3;
+/// Role of a credentialed lab technician.
+pub const ROLE_LAB_TECHNICIAN: u8 = 4;
+/// Role of a credentialed provider whose specific role is not asserted.
+pub const ROLE_PROVIDER: u8 = 5;
+/// Role of a caregiver acting under a `DelegationAccount`.
+pub const ROLE_CAREGIVER: u8 = 6;
+/// Role of a credentialed endocrinologist, configured with `set_role_config`.
+pub const ROLE_ENDOCRINOLOGIST: u8 = 7;
+/// Role of a credentialed obstetrician/gynecologist, configured with `set_role_config`.
+pub const ROLE_OBGYN: u8 = 8;
+/// Role of a credentialed insurer, which adjudicates claims instead of receiving records.
+pub const ROLE_INSURER: u8 = 9;
+/// Role of a credentialed transplant coordinator, who scores organ donors for recipients.
+pub const ROLE_TRANSPLANT_COORDINATOR: u8 = 10;
+/// Attachment type: clinical report, e.g. a discharge summary or lab report PDF.
+pub const ATTACHMENT_TYPE_REPORT: u8 = 0;
+/// Attachment type: scanned document, e.g. a referral letter or consent form.
+pub const ATTACHMENT_TYPE_SCAN: u8 = 1;
+/// Attachment type: any other file.
+pub const ATTACHMENT_TYPE_OTHER: u8 = 2;
+/// Number of role ids a `PatientPolicy` can express, one bit each.
+const MAX_ROLES: u8 = 16;
+
+/// Data category: basic demographics.
+pub const CATEGORY_DEMOGRAPHICS: u8 = 0;
+/// Data category: medical history, medications, procedures and family history.
+pub const CATEGORY_HEALTHCARE: u8 = 1;
+/// Data category: genomic analysis.
+pub const CATEGORY_GENOMICS: u8 = 2;
+/// Data category: lab tests and imaging.
+pub const CATEGORY_LAB_TESTS: u8 = 3;
+/// Number of data categories a `PatientPolicy` restricts.
+const DATA_CATEGORIES: usize = 4;
+/// Bit set of every data category, as touched by a full-record share.
+const ALL_DATA_CATEGORIES: u8 = (1 << DATA_CATEGORIES) - 1;
+
+/// Record section: patient id, age, gender, blood type, weight and height.
+pub const SECTION_DEMOGRAPHICS: u8 = 1 << 0;
+/// Record section: allergies.
+pub const SECTION_ALLERGIES: u8 = 1 << 1;
+/// Record section: current medications.
+pub const SECTION_MEDICATIONS: u8 = 1 << 2;
+/// Record section: medical history, procedures and family history.
+pub const SECTION_HISTORY: u8 = 1 << 3;
+/// Record section: genomic analysis.
+pub const SECTION_GENOMICS: u8 = 1 << 4;
+/// Record section: lab test results.
+pub const SECTION_LAB_TESTS: u8 = 1 << 5;
+/// Record section: imaging results.
+pub const SECTION_IMAGING: u8 = 1 << 6;
+/// Record section: immunizations.
+pub const SECTION_IMMUNIZATIONS: u8 = 1 << 7;
+/// Every record section.
+pub const ALL_SECTIONS: u8 = u8::MAX;
+
+/// Number of record fields `share_selected_fields` can select, one mask bit each in the
+/// field order of `PatientData`.
+pub const SELECTABLE_FIELDS: u32 = 34;
+/// Field mask selecting every field `share_selected_fields` can disclose.
+pub const ALL_SELECTABLE_FIELDS: u128 = (1 << SELECTABLE_FIELDS) - 1;
+/// Record section of each field `share_selected_fields` can select, by mask bit.
+const SELECTABLE_FIELD_SECTIONS: [u8; SELECTABLE_FIELDS as usize] = [
+    SECTION_DEMOGRAPHICS,
+    SECTION_DEMOGRAPHICS,
+    SECTION_DEMOGRAPHICS,
+    SECTION_DEMOGRAPHICS,
+    SECTION_DEMOGRAPHICS,
+    SECTION_DEMOGRAPHICS,
+    SECTION_ALLERGIES,
+    SECTION_HISTORY,
+    SECTION_MEDICATIONS,
+    SECTION_MEDICATIONS,
+    SECTION_HISTORY,
+    SECTION_HISTORY,
+    SECTION_HISTORY,
+    SECTION_GENOMICS,
+    SECTION_GENOMICS,
+    SECTION_GENOMICS,
+    SECTION_GENOMICS,
+    SECTION_GENOMICS,
+    SECTION_GENOMICS,
+    SECTION_LAB_TESTS,
+    SECTION_LAB_TESTS,
+    SECTION_LAB_TESTS,
+    SECTION_LAB_TESTS,
+    SECTION_LAB_TESTS,
+    SECTION_IMAGING,
+    SECTION_IMAGING,
+    SECTION_IMAGING,
+    SECTION_IMMUNIZATIONS,
+    SECTION_IMMUNIZATIONS,
+    SECTION_IMMUNIZATIONS,
+    SECTION_IMMUNIZATIONS,
+    SECTION_HISTORY,
+    SECTION_MEDICATIONS,
+    SECTION_LAB_TESTS,
+];
+
+/// Purpose of use: treatment of the patient.
+pub const PURPOSE_TREATMENT: u8 = 0;
+/// Purpose of use: payment and insurance.
+pub const PURPOSE_PAYMENT: u8 = 1;
+/// Purpose of use: healthcare operations.
+pub const PURPOSE_OPERATIONS: u8 = 2;
+/// Purpose of use: research.
+pub const PURPOSE_RESEARCH: u8 = 3;
+/// Purpose of use: emergency (break-glass) access.
+pub const PURPOSE_EMERGENCY: u8 = 4;
+/// Purpose of use: disclosure requested by the patient.
+pub const PURPOSE_PATIENT_REQUEST: u8 = 5;
+/// Share status: the computation is queued and its callback has not run yet.
+pub const SHARE_STATUS_QUEUED: u8 = 0;
+/// Share status: the callback wrote the receiver's ciphertexts.
+pub const SHARE_STATUS_COMPLETED: u8 = 1;
+/// Share status: the computation aborted.
+pub const SHARE_STATUS_FAILED: u8 = 2;
+/// Share status: no callback arrived within `SHARE_TIMEOUT_SLOTS`.
+pub const SHARE_STATUS_TIMED_OUT: u8 = 3;
+/// Share status: the patient abandoned the share with `cancel_share`.
+pub const SHARE_STATUS_CANCELLED: u8 = 4;
+/// Share status: the share was re-queued under a new offset with `retry_share`.
+pub const SHARE_STATUS_RETRIED: u8 = 5;
+/// Slots after queueing after which a share still awaiting its callback may be marked timed
+/// out, about ten minutes.
+const SHARE_TIMEOUT_SLOTS: u64 = 1_500;
+
+/// Entry kind of an appended lab result.
+pub const ENTRY_KIND_LAB_RESULT: u8 = 0;
+/// Entry kind of an added medication.
+pub const ENTRY_KIND_MEDICATION: u8 = 1;
+/// Entry kind of a visit note.
+pub const ENTRY_KIND_VISIT_NOTE: u8 = 2;
+/// Number of ciphertexts held by a single record entry.
+const ENTRY_FIELDS: usize = 16;
+/// Byte offset of `RecordEntry::ciphertexts`, including the account discriminator.
+const RECORD_ENTRY_CIPHERTEXTS_OFFSET: u32 = 8 + 32 + 32 + 16;
+/// Byte offset of `Encounter::ciphertexts`; matches `RecordEntry` so `share_entry` can read it.
+const ENCOUNTER_CIPHERTEXTS_OFFSET: u32 = 8 + 32 + 32 + 16;
+/// Number of encrypted fields in a patient annotation on an entry.
+const ANNOTATION_FIELDS: usize = 4;
+/// Byte offset of `EntryFlag::ciphertexts`, after the discriminator, patient, key and nonce.
+const ENTRY_FLAG_CIPHERTEXTS_OFFSET: u32 = 8 + 32 + 32 + 16;
+/// Number of encrypted fields in a mental health section: diagnoses, therapy note
+/// references and medication flags.
+const MENTAL_HEALTH_FIELDS: usize = 16;
+/// Byte offset of `MentalHealthData::ciphertexts`, after the discriminator, patient, key and
+/// nonce.
+const MENTAL_HEALTH_CIPHERTEXTS_OFFSET: u32 = 8 + 32 + 32 + 16;
+/// Number of encrypted fields in an obstetric record: pregnancy history, estimated due date,
+/// gestational age, complications and delivery method.
+const OBSTETRIC_FIELDS: usize = 14;
+/// Byte offset of `ObstetricData::ciphertexts`, after the discriminator, patient, key and
+/// nonce.
+const OBSTETRIC_CIPHERTEXTS_OFFSET: u32 = 8 + 32 + 32 + 16;
+/// Number of imaging studies a record tracks, matching `PatientData::imaging_types`.
+const MAX_IMAGING_STUDIES: usize = 10;
+/// Number of ciphertexts holding an off-chain storage URI, e.g. an IPFS CID or Arweave
+/// transaction id, packed 16 bytes per ciphertext.
+const STORAGE_URI_CIPHERTEXTS: usize = 4;
+/// Number of excluded medications a trial eligibility check can carry.
+const MAX_EXCLUDED_MEDICATIONS: usize = 4;
+/// Number of ciphertexts holding an attachment's 256-bit symmetric content key, packed 16
+/// bytes per ciphertext.
+const ATTACHMENT_KEY_CIPHERTEXTS: usize = 2;
+/// Number of encrypted fields of an attachment: its content key followed by its storage URI.
+const ATTACHMENT_CIPHERTEXTS: usize = ATTACHMENT_KEY_CIPHERTEXTS + STORAGE_URI_CIPHERTEXTS;
+/// Byte offset of `Attachment::ciphertexts`, after the discriminator, patient, key and nonce.
+const ATTACHMENT_CIPHERTEXTS_OFFSET: u32 = 8 + 32 + 32 + 16;
+/// Number of ciphertexts in a `DemographicsAccount`, matching `patient_id` through `allergies` of
+/// `PatientData`.
+const DEMOGRAPHICS_FIELDS: usize = 11;
+/// Number of ciphertexts in a `HealthcareAccount`, matching `medical_history` through
+/// `family_history` of `PatientData`.
+const HEALTHCARE_FIELDS: usize = 33;
+/// Number of ciphertexts in a `GenomicAccount`, matching `variant_count` through
+/// `ancestry_components` of `PatientData`.
+const GENOMIC_FIELDS: usize = 46;
+/// Number of ciphertexts in a `LabAccount`, matching `lab_test_count` through `imaging_dates` of
+/// `PatientData`.
+const LAB_FIELDS: usize = 62;
+/// Number of ciphertexts in a chunk of expanded record capacity: 8 medications or 2 lab tests.
+const CAPACITY_CHUNK_FIELDS: usize = 8;
+/// Byte length of a `CapacityChunk`: encryption key, nonce and ciphertexts.
+const CAPACITY_CHUNK_LEN: usize = 32 + 16 + CAPACITY_CHUNK_FIELDS * 32;
+/// Byte length of the expanded capacity header: medication and lab chunk counts.
+const CAPACITY_HEADER_LEN: usize = 4;
+/// Byte offset of the expanded capacity header, right after the fixed `PatientData` layout.
+const RECORD_TAIL_OFFSET: usize = 8 + core::mem::size_of::<PatientData>();
+/// Depth of a patient's lab-history Merkle tree, bounding it to 65,536 results.
+const LAB_HISTORY_DEPTH: usize = 16;
+/// Number of encrypted fields of a lab-history leaf: test type, date, value and range flag.
+const LAB_LEAF_FIELDS: usize = 4;
+/// Number of past re-encryptions kept in a record's rotation history.
+const MAX_ROTATION_HISTORY: usize = 8;
+/// Number of ciphertexts in the immunization section of a record.
+const IMMUNIZATION_FIELDS: usize = 25;
+/// Number of ciphertexts in the terminology code section of a record: 10 ICD-10, 8 RxNorm
+/// and 10 LOINC codes.
+const TERMINOLOGY_FIELDS: usize = 28;
+/// Maximum number of circuits a receiver profile can list.
+const MAX_PROFILE_CIRCUITS: usize = 16;
+/// Number of patient records aggregated by `aggregate_cohort`.
+const COHORT_SIZE: usize = 5;
+/// Billed lines of an insurance claim.
+const CLAIM_LINES: usize = 4;
+/// Claim status: submitted by the provider, awaiting its consistency check.
+pub const CLAIM_STATUS_SUBMITTED: u8 = 0;
+/// Claim status: consistency score computed, awaiting adjudication.
+pub const CLAIM_STATUS_VERIFIED: u8 = 1;
+/// Claim status: approved by the insurer.
+pub const CLAIM_STATUS_APPROVED: u8 = 2;
+/// Claim status: denied by the insurer.
+pub const CLAIM_STATUS_DENIED: u8 = 3;
+/// Pre-authorization decision of `check_coverage`: covered.
+pub const COVERAGE_APPROVE: u8 = 0;
+/// Pre-authorization decision of `check_coverage`: not covered.
+pub const COVERAGE_DENY: u8 = 1;
+/// Pre-authorization decision of `check_coverage`: the insurer must review the request.
+pub const COVERAGE_REVIEW: u8 = 2;
+/// Maximum number of specialists asked in one second-opinion request.
+const MAX_OPINION_SPECIALISTS: usize = 4;
+/// Maximum number of members of a care team; approvals are tracked in a `u8` bitmap.
+const MAX_CARE_TEAM_MEMBERS: usize = 8;
+/// Offset of the ciphertexts in a `TeamRecord` account, after the discriminator.
+const TEAM_RECORD_CIPHERTEXTS_OFFSET: u32 = 8 + offset_of!(TeamRecord, ciphertexts) as u32;
+/// Advance directive flag: do not resuscitate.
+pub const DIRECTIVE_DNR: u8 = 1 << 0;
+/// Advance directive flag: do not intubate.
+pub const DIRECTIVE_DNI: u8 = 1 << 1;
+/// Advance directive flag: no artificial life support.
+pub const DIRECTIVE_NO_LIFE_SUPPORT: u8 = 1 << 2;
+/// HLA alleles typed for transplant matching: two each of HLA-A, HLA-B and HLA-DR.
+const HLA_MARKERS: usize = 6;
+/// Number of ciphertexts in a `DonorProfile`: donor consent, then the HLA alleles.
+const DONOR_PROFILE_FIELDS: usize = 1 + HLA_MARKERS;
+/// Number of ciphertexts of `check_donor_match` recipient criteria: blood type, HLA
+/// alleles and the minimum number of matching alleles.
+const RECIPIENT_CRITERIA_FIELDS: usize = 2 + HLA_MARKERS;
+
+/// Cohort query condition matching members regardless of their medical history.
+pub const ANY_CONDITION: u8 = u8::MAX;
+/// Members that must match a cohort query before its statistics are released, until the
+/// config authority sets its own with `set_min_cohort_size`.
+const DEFAULT_MIN_COHORT_SIZE: u8 = 3;
+
+/// Research consent scope: genomic data, read by `match_genetic_markers`.
+pub const RESEARCH_SCOPE_GENOMIC: u8 = 1 << 0;
+/// Research consent scope: clinical data, read by the cohort aggregations.
+pub const RESEARCH_SCOPE_CLINICAL: u8 = 1 << 1;
+/// Research consent scope: imaging results.
+pub const RESEARCH_SCOPE_IMAGING: u8 = 1 << 2;
+/// Every research consent scope, required by `pseudonymize_for_research`.
+pub const RESEARCH_SCOPE_ALL: u8 =
+    RESEARCH_SCOPE_GENOMIC | RESEARCH_SCOPE_CLINICAL | RESEARCH_SCOPE_IMAGING;
+/// Maximum number of extension programs in the registry.
+const MAX_EXTENSIONS: usize = 4;
+/// Maximum number of programs approved to invoke `share_patient_data` via CPI.
+const MAX_CPI_CALLERS: usize = 16;
+/// Maximum number of Arcium clusters computations may be pinned to.
+const MAX_APPROVED_CLUSTERS: usize = 8;
+/// Maximum number of instructions with versioned circuits in the `CompDefRegistry`.
+const MAX_VERSIONED_CIRCUITS: usize = 48;
+/// Maximum length of an instruction name in the `CompDefRegistry`.
+const MAX_CIRCUIT_NAME_LEN: usize = 32;
+/// Version of the layout of the record and share lifecycle events, bumped whenever a field
+/// is added, removed or changes meaning, so indexers can pick a decoder.
+pub const EVENT_SCHEMA_VERSION: u8 = 2;
+/// Total privacy loss (epsilon * 100) a record may contribute to differentially private
+/// queries over its lifetime.
+const PRIVACY_BUDGET_LIMIT: u64 = 1_000;
+/// Coin flips per noisy count at epsilon = 1, i.e. `epsilon_scaled = 100`.
+const NOISE_TRIALS_AT_UNIT_EPSILON: u64 = 16;
+/// Upper bound on the coin flips behind each noisy count.
+const MAX_NOISE_TRIALS: u64 = 64;
+/// Smallest privacy loss (epsilon * 100) a differentially private query may spend: below it
+/// the noise stops growing at `MAX_NOISE_TRIALS`, so cheaper queries would be under-noised.
+const MIN_EPSILON_SCALED: u64 = NOISE_TRIALS_AT_UNIT_EPSILON * 100 / MAX_NOISE_TRIALS;
+/// Number of markers in a `match_genetic_markers` query.
+const MARKER_QUERY_SIZE: usize = 8;
+/// Telemedicine session status: open for notes.
+pub const SESSION_OPEN: u8 = 0;
+/// Telemedicine session status: closed, no further notes.
+pub const SESSION_CLOSED: u8 = 1;
+/// Appointment status: booked by the patient, awaiting the provider's confirmation.
+pub const APPOINTMENT_BOOKED: u8 = 0;
+/// Appointment status: confirmed by both parties, with an active visit grant.
+pub const APPOINTMENT_CONFIRMED: u8 = 1;
+/// Appointment status: cancelled by either party.
+pub const APPOINTMENT_CANCELLED: u8 = 2;
+/// Maximum number of visit notes a telemedicine session can link.
+const MAX_SESSION_NOTES: usize = 16;
+/// Maximum number of member providers of an organization.
+const MAX_ORGANIZATION_MEMBERS: usize = 32;
+/// Maximum number of receiver keys on a patient's allowlist.
+const MAX_ALLOWLISTED_RECEIVERS: usize = 16;
+/// Seconds after approval before an uncompleted paid share can be refunded.
+const PAID_ACCESS_TIMEOUT: i64 = 24 * 60 * 60;
+/// Basis points making up 100%.
+const MAX_BPS: u16 = 10_000;
+/// Maximum number of members of the admin governance.
+const MAX_GOVERNANCE_MEMBERS: usize = 10;
+/// Receiver input nonces remembered per (patient, receiver) pair to reject replays.
+const RECENT_INPUT_NONCES: usize = 8;
+/// Maximum number of recovery guardians of a patient record.
+const MAX_RECOVERY_GUARDIANS: usize = 8;
+/// Seconds shared records are kept by default before `expire_shared_record` may close them.
+const DEFAULT_SHARE_RETENTION: i64 = 90 * 24 * 60 * 60;
+/// Maximum number of record jurisdictions with a data-residency rule.
+const MAX_JURISDICTION_RULES: usize = 16;
+/// Maximum number of receiver jurisdictions a data-residency rule allows.
+const MAX_ALLOWED_JURISDICTIONS: usize = 8;
+/// Jurisdiction of records and receivers that have not been tagged.
+pub const JURISDICTION_UNTAGGED: u16 = 0;
+/// Length of the encrypted part of `PatientData` read by the MPC circuits; the plaintext
+/// fields after it are not passed to computations.
+const PATIENT_CIPHERTEXTS_LEN: u32 = offset_of!(PatientData, jurisdiction) as u32;
+/// Samples in one encrypted vitals batch.
+const VITALS_SAMPLES_PER_BATCH: usize = 4;
+/// Ciphertexts in one vitals batch: timestamp, systolic and diastolic pressure, heart rate,
+/// SpO2 and temperature of each sample.
+const VITALS_BATCH_FIELDS: usize = 6 * VITALS_SAMPLES_PER_BATCH;
+/// Batches kept in a `VitalsSeries` ring buffer before the oldest is overwritten.
+const VITALS_RING_BATCHES: usize = 12;
+/// Encrypted readings in one continuous glucose monitoring batch.
+const GLUCOSE_READINGS_PER_BATCH: usize = 24;
+/// Batches kept in a `GlucoseSeries` ring buffer before the oldest is overwritten.
+const GLUCOSE_RING_BATCHES: usize = 12;
+/// Consecutive batches `compute_glucose_metrics` summarizes.
+const GLUCOSE_METRICS_BATCHES: u64 = 3;
+/// Extension hook called before a share is queued.
+pub const HOOK_PRE_SHARE: u8 = 1 << 0;
+/// Extension hook called after a share is queued.
+pub const HOOK_POST_SHARE: u8 = 1 << 1;
+/// Attribute selector for the patient's age in years.
+pub const ATTRIBUTE_AGE: u8 = 0;
+/// Attribute selector for the patient's weight in kilograms.
+pub const ATTRIBUTE_WEIGHT: u8 = 1;
+/// Attribute selector for the patient's height in centimetres.
+pub const ATTRIBUTE_HEIGHT: u8 = 2;
+/// Attribute selector for the patient's body mass index, derived from weight and height.
+pub const ATTRIBUTE_BMI: u8 = 3;
 
 declare_id!("NEnkfYAYz9epwXkXChP3hz2y1L8wUgf2xkrUKAmfxBD");
 
@@ -25,52 +461,92 @@ pub mod share_medical_records {
     ///                   pharmacogenomic_markers, ancestry_components
     /// Lab test results: lab_test_count, lab_test_types, lab_test_dates, lab_test_values,
     ///                   lab_test_flags, imaging_count, imaging_types, imaging_dates
+    /// Honeytoken (optional): canary
+    /// Immunizations (optional, after the canary): immunization_count, vaccine_codes,
+    ///                                             vaccination_dates, vaccine_doses
+    /// Terminology codes (optional, after immunizations): icd10_codes, rxnorm_codes,
+    ///                                                     loinc_codes
     pub fn store_patient_data(
         ctx: Context<StorePatientData>,
         ciphertexts: Vec<[u8; 32]>,
     ) -> Result<()> {
-        // Expect 152 fields, indexed exactly as emitted in the callback
-        if ciphertexts.len() != 152 {
-            return Err(ErrorCode::InvalidInputLength.into());
-        }
+        check_not_paused(&ctx.accounts.program_config)?;
+        write_patient_ciphertexts(&mut *ctx.accounts.patient_data.load_init()?, &ciphertexts)?;
 
-        let mut data = ctx.accounts.patient_data.load_init()?;
+        let author = EntryAuthor {
+            provider: ctx.accounts.payer.key(),
+            role: ROLE_PATIENT,
+            credential_mint: Pubkey::default(),
+        };
+        ctx.accounts.record_metadata.set_inner(new_record_metadata(
+            ctx.accounts.patient_data.key(),
+            author.clone(),
+            ctx.bumps.record_metadata,
+        )?);
 
-        // Basic demographics
-        data.patient_id = ciphertexts[0];
-        data.age = ciphertexts[1];
-        data.gender = ciphertexts[2];
-        data.blood_type = ciphertexts[3];
-        data.weight = ciphertexts[4];
-        data.height = ciphertexts[5];
-        for i in 0..5 { data.allergies[i] = ciphertexts[6 + i]; }
+        emit!(PatientDataStoredEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            patient_data: ctx.accounts.patient_data.key(),
+            author,
+        });
+        Ok(())
+    }
 
-        // Advanced healthcare
-        for i in 0..10 { data.medical_history[i] = ciphertexts[11 + i]; }
-        data.medication_count = ciphertexts[21];
-        for i in 0..8 { data.medications[i] = ciphertexts[22 + i]; }
-        data.procedure_count = ciphertexts[30];
-        for i in 0..8 { data.procedure_dates[i] = ciphertexts[31 + i]; }
-        for i in 0..5 { data.family_history[i] = ciphertexts[39 + i]; }
+    /// Stores a minor's record, created by a guardian on their behalf.
+    ///
+    /// The record is seeded by the guardian and `child_id` rather than the patient's wallet,
+    /// and the guardian receives a `DelegationAccount` on it, so every instruction accepting
+    /// delegates accepts the guardian. A `Custody` account remembers when the patient comes
+    /// of age for `transfer_custody_to_patient`. Ciphertexts are laid out as in
+    /// `store_patient_data`.
+    ///
+    /// # Arguments
+    /// * `child_id` - Guardian-chosen identifier of the child, unique per guardian
+    /// * `adult_at` - Unix timestamp from which custody can be transferred to the patient
+    /// * `ciphertexts` - Encrypted record fields, as in `store_patient_data`
+    pub fn store_pediatric_patient_data(
+        ctx: Context<StorePediatricPatientData>,
+        child_id: u64,
+        adult_at: i64,
+        ciphertexts: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        check_not_paused(&ctx.accounts.program_config)?;
+        write_patient_ciphertexts(&mut *ctx.accounts.patient_data.load_init()?, &ciphertexts)?;
 
-        // Genomic analysis
-        data.variant_count = ciphertexts[44];
-        for i in 0..15 { data.genetic_markers[i] = ciphertexts[45 + i]; }
-        for i in 0..15 { data.variant_significance[i] = ciphertexts[60 + i]; }
-        for i in 0..5 { data.carrier_status[i] = ciphertexts[75 + i]; }
-        for i in 0..3 { data.pharmacogenomic_markers[i] = ciphertexts[80 + i]; }
-        for i in 0..7 { data.ancestry_components[i] = ciphertexts[83 + i]; }
+        let patient_data = ctx.accounts.patient_data.key();
+        let guardian = ctx.accounts.payer.key();
+        let author = EntryAuthor {
+            provider: guardian,
+            role: ROLE_CAREGIVER,
+            credential_mint: Pubkey::default(),
+        };
+        ctx.accounts.record_metadata.set_inner(new_record_metadata(
+            patient_data,
+            author.clone(),
+            ctx.bumps.record_metadata,
+        )?);
 
-        // Lab test results
-        data.lab_test_count = ciphertexts[90];
-        for i in 0..10 { data.lab_test_types[i] = ciphertexts[91 + i]; }
-        for i in 0..10 { data.lab_test_dates[i] = ciphertexts[101 + i]; }
-        for i in 0..10 { data.lab_test_values[i] = ciphertexts[111 + i]; }
-        for i in 0..10 { data.lab_test_flags[i] = ciphertexts[121 + i]; }
-        data.imaging_count = ciphertexts[131];
-        for i in 0..10 { data.imaging_types[i] = ciphertexts[132 + i]; }
-        for i in 0..10 { data.imaging_dates[i] = ciphertexts[142 + i]; }
+        ctx.accounts.custody.set_inner(Custody {
+            patient_data,
+            guardian,
+            child_id,
+            adult_at,
+            bump: ctx.bumps.custody,
+        });
+        ctx.accounts.delegation.set_inner(DelegationAccount {
+            patient_data,
+            delegate: guardian,
+            scope: DELEGATION_SCOPE_ALL,
+            expires_at: i64::MAX,
+            jurisdiction: JURISDICTION_UNTAGGED,
+            bump: ctx.bumps.delegation,
+        });
 
+        emit!(PatientDataStoredEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            patient_data,
+            author,
+        });
         Ok(())
     }
 
@@ -90,145 +566,21700 @@ pub mod share_medical_records {
     ///
     /// # Arguments
     /// * `receiver` - Public key of the authorized recipient
-    /// * `receiver_nonce` - Cryptographic nonce for the receiver's encryption
     /// * `sender_pub_key` - Sender's public key for the operation
     /// * `nonce` - Cryptographic nonce for the sender's encryption
-    pub fn share_patient_data(
-        ctx: Context<SharePatientData>,
+    ///
+    /// The receiver nonce is issued on-chain by the `ShareNonce` PDA of the (patient, receiver)
+    /// pair and emitted in `ShareNonceEvent`, so clients never choose it themselves.
+    ///
+    /// The signer must be the patient or a caregiver holding a `DelegationAccount` with the
+    /// share scope, passed as the optional `delegation` account. A member of an
+    /// `Organization` the patient delegated to may share by also passing the organization.
+    ///
+    /// If the patient has a `ReceiverAllowlist`, `receiver` must be on it. If the record is
+    /// tagged with a jurisdiction that has a data-residency rule, `receiver` must be
+    /// registered in one of the jurisdictions the rule allows.
+    ///
+    /// Fails with `ProgramPaused` while the program is paused.
+    ///
+    /// Extensions registered for the pre-share and post-share hooks are called before and
+    /// after the computation is queued; `remaining_accounts` carries their accounts as
+    /// described in `register_extension`.
+    ///
+    /// Any `Encounter` accounts of the record passed after the extension accounts are
+    /// included in the share: they are listed in `EncountersIncludedEvent` so the receiver
+    /// knows which visit summaries to fetch with `share_encounter`.
+    ///
+    /// Programs approved with `add_cpi_caller` may invoke it via CPI; other programs fail
+    /// with `CpiCallerNotAllowed`.
+    ///
+    /// The share's progress is tracked in a `ShareStatus` account seeded by the record and
+    /// `computation_offset`, created as queued and resolved by the callback or
+    /// `time_out_share`.
+    pub fn share_patient_data<'info>(
+        ctx: Context<'_, '_, '_, 'info, SharePatientData<'info>>,
         computation_offset: u64,
         receiver: [u8; 32],
-        receiver_nonce: u128,
         sender_pub_key: [u8; 32],
         nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
     ) -> Result<()> {
+        check_cpi_caller(
+            &ctx.accounts.cpi_allowlist,
+            &ctx.accounts.instructions_sysvar,
+        )?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, ALL_SECTIONS)?;
+        check_comp_def(
+            &ctx.accounts.comp_def_registry,
+            "share_patient_data",
+            COMP_DEF_OFFSET_SHARE_PATIENT_DATA,
+        )?;
+        let role = match ctx.accounts.organization.as_deref() {
+            Some(organization) => authorize_organization_member(
+                &ctx.accounts.payer.key(),
+                &ctx.accounts.patient_data.key(),
+                ctx.accounts.delegation.as_deref(),
+                organization,
+                DELEGATION_SCOPE_SHARE,
+            )?,
+            None => authorize_patient_or_delegate(
+                &ctx.accounts.payer.key(),
+                &ctx.accounts.patient_data.key(),
+                ctx.accounts.delegation.as_deref(),
+                DELEGATION_SCOPE_SHARE,
+            )?,
+        };
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_receiver_allowlist(&ctx.accounts.receiver_allowlist, &receiver)?;
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+
+        let extensions =
+            extension_hooks(&ctx.accounts.extension_registry, ctx.remaining_accounts)?;
+        let extension_accounts: usize = extensions.iter().map(|(_, accounts)| accounts.len()).sum();
+        let encounters = included_encounters(
+            &ctx.accounts.patient_data.key(),
+            &ctx.remaining_accounts[extension_accounts..],
+        )?;
+        let hook_args = ShareHookArgs {
+            patient_data: ctx.accounts.patient_data.key(),
+            receiver,
+            accessor: ctx.accounts.payer.key(),
+            purpose,
+            computation_offset,
+        };
+        let hook_accounts = [
+            ctx.accounts.patient_data.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+        ];
+        call_extension_hooks(&extensions, HOOK_PRE_SHARE, &hook_args, &hook_accounts)?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
         let args = vec![
             Argument::ArcisPubkey(receiver),
             Argument::PlaintextU128(receiver_nonce),
             Argument::ArcisPubkey(sender_pub_key),
             Argument::PlaintextU128(nonce),
-            Argument::Account(
-                ctx.accounts.patient_data.key(),
-                8,
-                core::mem::size_of::<PatientData>() as u32,
-            ),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
         ];
 
+        if !encounters.is_empty() {
+            emit!(EncountersIncludedEvent {
+                patient_data: ctx.accounts.patient_data.key(),
+                computation_offset,
+                receiver,
+                encounters,
+            });
+        }
+
+        init_shared_record(
+            &ctx.accounts.shared_record,
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.receiver_authority.key(),
+            ctx.accounts.payer.key(),
+            computation_offset,
+        )?;
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
+        let entry = new_inbox_entry(
+            receiver,
+            ctx.accounts.receiver_authority.key(),
+            ctx.accounts.payer.key(),
+            ctx.accounts.patient_data.key(),
+            computation_offset,
+            ALL_SECTIONS,
+            ctx.bumps.inbox_entry,
+        )?;
+        ctx.accounts.inbox_entry.set_inner(entry);
+
+        ctx.accounts.share_status.set_inner(ShareStatus {
+            patient_data: ctx.accounts.patient_data.key(),
+            computation_offset,
+            status: SHARE_STATUS_QUEUED,
+            queued_slot: Clock::get()?.slot,
+            resolved_slot: 0,
+            failure_reason: 0,
+            bump: ctx.bumps.share_status,
+        });
+
+        emit!(ShareQueuedEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            patient_data: ctx.accounts.patient_data.key(),
+            computation_offset,
+            receiver,
+            purpose,
+            accessor: ctx.accounts.payer.key(),
+        });
+
+        let shared_record = ctx.accounts.shared_record.key();
+        let inbox_entry = ctx.accounts.inbox_entry.key();
+        let share_status = ctx.accounts.share_status.key();
         queue_computation(
             ctx.accounts,
             computation_offset,
             args,
             None,
-            vec![],
+            vec![SharePatientDataCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: shared_record,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: inbox_entry,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: share_status,
+                    is_writable: true,
+                },
+            ])],
         )?;
+
+        call_extension_hooks(&extensions, HOOK_POST_SHARE, &hook_args, &hook_accounts)?;
         Ok(())
     }
 
     /// AMOCA Telemedicine: Role-gated share using a certificate NFT (SPL token with 0 decimals).
+    ///
+    /// Shares to the same receiver are limited to the configured number per window (see
+    /// `set_share_rate_limit`), so a leaked credential cannot spam computations on the
+    /// patient's record.
+    ///
+    /// `role` is looked up in its `RoleConfig`, so new roles (radiologist, researcher, ...)
+    /// only need a `set_role_config` call. The credential mint must be registered in the
+    /// provider registry for `role`, and `role` is recorded in the access log. Only the
+    /// record sections in the role's `section_mask` are re-encrypted; the others arrive
+    /// zeroed. The receiver allowlist and data-residency rules are enforced as in
+    /// `share_patient_data`, and the share is tracked in a `ShareStatus` the same way.
     pub fn share_patient_data_with_role(
         ctx: Context<SharePatientDataWithRole>,
+        role: u8,
         computation_offset: u64,
         receiver: [u8; 32],
-        receiver_nonce: u128,
         sender_pub_key: [u8; 32],
         nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
     ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_comp_def(
+            &ctx.accounts.comp_def_registry,
+            "share_patient_data_scoped",
+            COMP_DEF_OFFSET_SHARE_PATIENT_DATA_SCOPED,
+        )?;
         // Verify credential token account belongs to signer, matches mint, and holds at least 1 token
         require_keys_eq!(ctx.accounts.credential_token_account.owner, ctx.accounts.payer.key(), ErrorCode::Unauthorized);
         require_keys_eq!(ctx.accounts.credential_token_account.mint, ctx.accounts.credential_mint.key(), ErrorCode::Unauthorized);
         require!(ctx.accounts.credential_mint.decimals == 0, ErrorCode::InvalidCredentialMint);
         require!(ctx.accounts.credential_token_account.amount >= 1, ErrorCode::MissingCredential);
+        check_credential_record(&ctx.accounts.credential_record)?;
+        require!(!ctx.accounts.program_config.paused, ErrorCode::ProgramPaused);
+        if ctx.accounts.program_config.require_soulbound_credentials {
+            require!(
+                is_non_transferable(&ctx.accounts.credential_mint.to_account_info())?,
+                ErrorCode::TransferableCredential
+            );
+        }
+        check_credential_collection(
+            &ctx.accounts.role_config,
+            ctx.accounts.credential_metadata.as_deref(),
+            &ctx.accounts.credential_mint.key(),
+        )?;
+        if ctx.accounts.role_config.credential_mint != Pubkey::default() {
+            require_keys_eq!(
+                ctx.accounts.role_config.credential_mint,
+                ctx.accounts.credential_mint.key(),
+                ErrorCode::InvalidCredentialMint
+            );
+        }
+        // The mint must be registered by the registrar for this role
+        require!(ctx.accounts.provider_record.active, ErrorCode::ProviderNotRegistered);
+        require!(ctx.accounts.provider_record.role == role, ErrorCode::CredentialRoleMismatch);
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_receiver_allowlist(&ctx.accounts.receiver_allowlist, &receiver)?;
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+        let sections = ctx.accounts.role_config.section_mask;
+        check_sections_unlocked(&ctx.accounts.section_locks, sections)?;
+        check_patient_policy(&ctx.accounts.patient_policy, section_categories(sections), role)?;
+        ctx.accounts.rate_limit.record_share(
+            &ctx.accounts.program_config,
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.rate_limit,
+        )?;
+
+        // Proceed with the share, scoped to the role's sections
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
 
-        // Proceed with regular share
         let args = vec![
             Argument::ArcisPubkey(receiver),
             Argument::PlaintextU128(receiver_nonce),
             Argument::ArcisPubkey(sender_pub_key),
             Argument::PlaintextU128(nonce),
-            Argument::Account(
-                ctx.accounts.patient_data.key(),
-                8,
-                core::mem::size_of::<PatientData>() as u32,
-            ),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::PlaintextU8(sections),
         ];
 
+        init_shared_record(
+            &ctx.accounts.shared_record,
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.receiver_authority.key(),
+            ctx.accounts.payer.key(),
+            computation_offset,
+        )?;
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
+        let entry = new_inbox_entry(
+            receiver,
+            ctx.accounts.receiver_authority.key(),
+            ctx.accounts.payer.key(),
+            ctx.accounts.patient_data.key(),
+            computation_offset,
+            sections,
+            ctx.bumps.inbox_entry,
+        )?;
+        ctx.accounts.inbox_entry.set_inner(entry);
+
+        ctx.accounts.share_status.set_inner(ShareStatus {
+            patient_data: ctx.accounts.patient_data.key(),
+            computation_offset,
+            status: SHARE_STATUS_QUEUED,
+            queued_slot: Clock::get()?.slot,
+            resolved_slot: 0,
+            failure_reason: 0,
+            bump: ctx.bumps.share_status,
+        });
+
+        emit!(ShareQueuedEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            patient_data: ctx.accounts.patient_data.key(),
+            computation_offset,
+            receiver,
+            purpose,
+            accessor: ctx.accounts.payer.key(),
+        });
+
+        let shared_record = ctx.accounts.shared_record.key();
+        let inbox_entry = ctx.accounts.inbox_entry.key();
+        let share_status = ctx.accounts.share_status.key();
         queue_computation(
             ctx.accounts,
             computation_offset,
             args,
             None,
-            vec![],
+            vec![SharePatientDataScopedCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: shared_record,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: inbox_entry,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: share_status,
+                    is_writable: true,
+                },
+            ])],
         )?;
         Ok(())
     }
 
-    /// Convenience: doctor role (uses provided credential mint/token account)
-    pub fn share_patient_data_doctor(
-        ctx: Context<SharePatientDataWithRole>,
+    /// Revokes a receiver's access to future shares of the caller's patient data.
+    ///
+    /// Creates a revocation marker PDA keyed by the patient record and the receiver's x25519
+    /// public key. While the marker exists, `share_patient_data` and `share_patient_data_with_role`
+    /// fail with `ReceiverRevoked` before any computation is queued, so a compromised provider
+    /// key cannot keep requesting re-encryptions.
+    ///
+    /// # Arguments
+    /// * `receiver` - Public key of the recipient whose access is being revoked
+    pub fn revoke_receiver(ctx: Context<RevokeReceiver>, receiver: [u8; 32]) -> Result<()> {
+        let revocation = &mut ctx.accounts.receiver_revocation;
+        revocation.patient_data = ctx.accounts.patient_data.key();
+        revocation.receiver = receiver;
+        revocation.revoked_at = Clock::get()?.unix_timestamp;
+        revocation.bump = ctx.bumps.receiver_revocation;
+        Ok(())
+    }
+
+    /// Lifts a previous revocation by closing the receiver's revocation marker and
+    /// returning its rent to the patient.
+    ///
+    /// # Arguments
+    /// * `receiver` - Public key of the recipient being reinstated
+    pub fn reinstate_receiver(_ctx: Context<ReinstateReceiver>, _receiver: [u8; 32]) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn init_share_patient_data_scoped_comp_def(
+        ctx: Context<InitSharePatientDataScopedCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_share_emergency_data_comp_def(
+        ctx: Context<InitShareEmergencyDataCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_emergency_summary_comp_def(
+        ctx: Context<InitEmergencySummaryCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_share_advance_directive_comp_def(
+        ctx: Context<InitShareAdvanceDirectiveCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Registers (or replaces) the patient's break-glass emergency policy.
+    ///
+    /// Guardians are trusted keys (family members, a primary physician) that can co-sign an
+    /// emergency access request while the patient is unable to sign. A request becomes
+    /// executable once `threshold` guardians approve it, or once `delay_seconds` have elapsed
+    /// since it was opened. A delay of 0 disables the time-based fallback.
+    ///
+    /// # Arguments
+    /// * `guardians` - Guardian public keys (up to 8)
+    /// * `threshold` - Number of guardian approvals required
+    /// * `delay_seconds` - Time after which a request is executable without approvals (0 = never)
+    pub fn set_emergency_policy(
+        ctx: Context<SetEmergencyPolicy>,
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+        delay_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            !guardians.is_empty() && guardians.len() <= MAX_EMERGENCY_GUARDIANS,
+            ErrorCode::InvalidEmergencyPolicy
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= guardians.len(),
+            ErrorCode::InvalidEmergencyPolicy
+        );
+        require!(delay_seconds >= 0, ErrorCode::InvalidEmergencyPolicy);
+
+        let policy = &mut ctx.accounts.emergency_policy;
+        policy.patient_data = ctx.accounts.patient_data.key();
+        policy.guardians = guardians;
+        policy.threshold = threshold;
+        policy.delay_seconds = delay_seconds;
+        policy.bump = ctx.bumps.emergency_policy;
+        Ok(())
+    }
+
+    /// Opens a break-glass request for emergency access to a patient's record.
+    ///
+    /// Only credentialed providers (certificate NFT, same rules as `share_patient_data_with_role`)
+    /// may open a request. The request records the receiver key the restricted record will be
+    /// re-encrypted for once the request becomes executable.
+    ///
+    /// # Arguments
+    /// * `receiver` - Public key the emergency data will be encrypted for
+    pub fn open_break_glass(ctx: Context<OpenBreakGlass>, receiver: [u8; 32]) -> Result<()> {
+        require!(ctx.accounts.credential_mint.decimals == 0, ErrorCode::InvalidCredentialMint);
+        require!(ctx.accounts.credential_token_account.amount >= 1, ErrorCode::MissingCredential);
+        check_credential_record(&ctx.accounts.credential_record)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let request = &mut ctx.accounts.break_glass_request;
+        request.patient_data = ctx.accounts.patient_data.key();
+        request.requester = ctx.accounts.payer.key();
+        request.receiver = receiver;
+        request.opened_at = now;
+        request.approval_mask = 0;
+        request.bump = ctx.bumps.break_glass_request;
+
+        emit!(BreakGlassOpenedEvent {
+            patient_data: request.patient_data,
+            requester: request.requester,
+            receiver,
+            opened_at: now,
+        });
+        Ok(())
+    }
+
+    /// Records a guardian's approval of an open break-glass request.
+    pub fn approve_break_glass(ctx: Context<ApproveBreakGlass>) -> Result<()> {
+        let guardian = ctx.accounts.guardian.key();
+        let index = ctx
+            .accounts
+            .emergency_policy
+            .guardians
+            .iter()
+            .position(|g| *g == guardian)
+            .ok_or(ErrorCode::NotAGuardian)?;
+
+        let request = &mut ctx.accounts.break_glass_request;
+        let bit = 1u8 << index;
+        require!(request.approval_mask & bit == 0, ErrorCode::GuardianAlreadyApproved);
+        request.approval_mask |= bit;
+        Ok(())
+    }
+
+    /// Executes an approved break-glass request by queueing the restricted emergency share.
+    ///
+    /// The `share_emergency_data` circuit re-encrypts only the fields an emergency room needs
+    /// (demographics, allergies, medical history and current medications) for the receiver
+    /// recorded in the request; the callback delivers them in an `EmergencyDataEvent`. The
+    /// request is closed and a `BreakGlassExecutedEvent` is emitted so that every emergency
+    /// access is visible on-chain.
+    ///
+    /// # Arguments
+    /// * `sender_pub_key` - Sender's public key for the operation
+    /// * `nonce` - Cryptographic nonce for the sender's encryption
+    pub fn execute_break_glass(
+        ctx: Context<ExecuteBreakGlass>,
         computation_offset: u64,
-        receiver: [u8; 32],
-        receiver_nonce: u128,
         sender_pub_key: [u8; 32],
         nonce: u128,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        let request = &ctx.accounts.break_glass_request;
+        let (approvals, approved) =
+            check_break_glass_ready(&ctx.accounts.emergency_policy, request, now)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver = request.receiver;
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            ROLE_PROVIDER,
+            PURPOSE_EMERGENCY,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ShareEmergencyDataCallback::callback_ix(&[])],
+        )?;
+
+        emit!(BreakGlassExecutedEvent {
+            patient_data: ctx.accounts.patient_data.key(),
+            requester: ctx.accounts.payer.key(),
+            receiver,
+            approvals,
+            via_time_delay: !approved,
+            executed_at: now,
+        });
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "share_emergency_data")]
+    pub fn share_emergency_data_callback(
+        ctx: Context<ShareEmergencyDataCallback>,
+        output: ComputationOutputs<ShareEmergencyDataOutput>,
     ) -> Result<()> {
-        share_patient_data_with_role(ctx, computation_offset, receiver, receiver_nonce, sender_pub_key, nonce)
+        let emergency = match output {
+            ComputationOutputs::Success(ShareEmergencyDataOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let c = &emergency.ciphertexts;
+        emit!(EmergencyDataEvent {
+            nonce: emergency.nonce.to_le_bytes(),
+            age: c[0],
+            gender: c[1],
+            blood_type: c[2],
+            weight: c[3],
+            height: c[4],
+            allergies: ciphertext_array(c, 5),
+            medical_history: ciphertext_array(c, 10),
+            medication_count: c[20],
+            medications: ciphertext_array(c, 21),
+        });
+        Ok(())
     }
 
-    /// Convenience: nurse role (uses provided credential mint/token account)
-    pub fn share_patient_data_nurse(
-        ctx: Context<SharePatientDataWithRole>,
+    /// Executes an approved break-glass request with the minimal `emergency_summary` view.
+    ///
+    /// Same rules as `execute_break_glass`, but the circuit re-encrypts only the blood type,
+    /// allergies, critical medical history flags and current medications for the responder,
+    /// delivered in an `EmergencySummaryEvent`. Like every break-glass path it deliberately
+    /// skips the pause flag and the patient's receiver allowlist, since responders are not
+    /// known in advance; revocations and jurisdiction rules still apply.
+    ///
+    /// # Arguments
+    /// * `sender_pub_key` - Sender's public key for the operation
+    /// * `nonce` - Cryptographic nonce for the sender's encryption
+    pub fn execute_emergency_summary(
+        ctx: Context<ExecuteEmergencySummary>,
         computation_offset: u64,
-        receiver: [u8; 32],
-        receiver_nonce: u128,
         sender_pub_key: [u8; 32],
         nonce: u128,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        let request = &ctx.accounts.break_glass_request;
+        let (approvals, approved) =
+            check_break_glass_ready(&ctx.accounts.emergency_policy, request, now)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver = request.receiver;
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            ROLE_PROVIDER,
+            PURPOSE_EMERGENCY,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![EmergencySummaryCallback::callback_ix(&[])],
+        )?;
+
+        emit!(BreakGlassExecutedEvent {
+            patient_data: ctx.accounts.patient_data.key(),
+            requester: ctx.accounts.payer.key(),
+            receiver,
+            approvals,
+            via_time_delay: !approved,
+            executed_at: now,
+        });
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "emergency_summary")]
+    pub fn emergency_summary_callback(
+        ctx: Context<EmergencySummaryCallback>,
+        output: ComputationOutputs<EmergencySummaryOutput>,
     ) -> Result<()> {
-        share_patient_data_with_role(ctx, computation_offset, receiver, receiver_nonce, sender_pub_key, nonce)
+        let summary = match output {
+            ComputationOutputs::Success(EmergencySummaryOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let c = &summary.ciphertexts;
+        emit!(EmergencySummaryEvent {
+            nonce: summary.nonce.to_le_bytes(),
+            blood_type: c[0],
+            allergies: ciphertext_array(c, 1),
+            critical_conditions: ciphertext_array(c, 6),
+            medication_count: c[16],
+            medications: ciphertext_array(c, 17),
+        });
+        Ok(())
     }
 
-    /// Convenience: pharmacist role (uses provided credential mint/token account)
-    pub fn share_patient_data_pharmacist(
-        ctx: Context<SharePatientDataWithRole>,
-        computation_offset: u64,
-        receiver: [u8; 32],
-        receiver_nonce: u128,
-        sender_pub_key: [u8; 32],
+    /// Stores or replaces the caller's advance directive. Every change must be co-signed by
+    /// a witness other than the patient, who is recorded with the directive.
+    ///
+    /// # Arguments
+    /// * `encryption_key` - x25519 public key the flags are encrypted with
+    /// * `nonce` - Nonce the flags are encrypted with
+    /// * `directive_flags` - Encrypted `DIRECTIVE_*` bits
+    /// * `document_hash` - Hash of the signed directive document kept off-chain
+    pub fn set_advance_directive(
+        ctx: Context<SetAdvanceDirective>,
+        encryption_key: [u8; 32],
         nonce: u128,
+        directive_flags: [u8; 32],
+        document_hash: [u8; 32],
     ) -> Result<()> {
-        share_patient_data_with_role(ctx, computation_offset, receiver, receiver_nonce, sender_pub_key, nonce)
+        require_keys_neq!(
+            ctx.accounts.witness.key(),
+            ctx.accounts.payer.key(),
+            ErrorCode::InvalidWitness
+        );
+
+        ctx.accounts.advance_directive.set_inner(AdvanceDirective {
+            patient_data: ctx.accounts.patient_data.key(),
+            encryption_key,
+            nonce,
+            directive_flags,
+            document_hash,
+            witness: ctx.accounts.witness.key(),
+            updated_at: Clock::get()?.unix_timestamp,
+            bump: ctx.bumps.advance_directive,
+        });
+
+        emit!(AdvanceDirectiveUpdatedEvent {
+            patient_data: ctx.accounts.patient_data.key(),
+            witness: ctx.accounts.witness.key(),
+            document_hash,
+        });
+        Ok(())
     }
 
-    // Callback removed to minimize stack usage
-}
+    /// Re-encrypts a patient's advance directive for the receiver of the caller's break-glass
+    /// request, without the patient's signature or guardian approvals so that resuscitation
+    /// decisions are never delayed.
+    ///
+    /// Only doctors and nurses holding a valid registered credential may call it, and the
+    /// access is logged for `PURPOSE_EMERGENCY`. The flags are delivered in an
+    /// `AdvanceDirectiveSharedEvent`. As a break-glass path it deliberately skips the pause
+    /// flag and the patient's receiver allowlist; a revoked receiver is still rejected.
+    pub fn share_advance_directive(
+        ctx: Context<ShareAdvanceDirective>,
+        computation_offset: u64,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_credential_record(&ctx.accounts.credential_record)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
 
-#[derive(Accounts)]
-pub struct StorePatientData<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + core::mem::size_of::<PatientData>(),
-        seeds = [b"patient_data", payer.key().as_ref()],
-        bump,
-    )]
-    pub patient_data: AccountLoader<'info, PatientData>,
-}
+        let receiver = ctx.accounts.break_glass_request.receiver;
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
 
-#[queue_computation_accounts("share_patient_data", payer)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct SharePatientData<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = payer,
+        let directive = &ctx.accounts.advance_directive;
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(directive.encryption_key),
+            Argument::PlaintextU128(directive.nonce),
+            Argument::EncryptedU8(directive.directive_flags),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            ctx.accounts.provider_record.role,
+            PURPOSE_EMERGENCY,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let advance_directive = ctx.accounts.advance_directive.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ShareAdvanceDirectiveCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: advance_directive,
+                    is_writable: false,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "share_advance_directive")]
+    pub fn share_advance_directive_callback(
+        ctx: Context<ShareAdvanceDirectiveCallback>,
+        output: ComputationOutputs<ShareAdvanceDirectiveOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(ShareAdvanceDirectiveOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let directive = &ctx.accounts.advance_directive;
+        emit!(AdvanceDirectiveSharedEvent {
+            patient_data: directive.patient_data,
+            nonce: result.nonce.to_le_bytes(),
+            directive_flags: result.ciphertexts[0],
+            document_hash: directive.document_hash,
+        });
+        Ok(())
+    }
+
+    /// Stores or replaces the caller's organ donor profile: whether they consent to donate
+    /// and their HLA typing. Withdrawing consent is done by storing a false consent flag.
+    ///
+    /// # Arguments
+    /// * `encryption_key` - x25519 public key the profile is encrypted with
+    /// * `nonce` - Nonce the profile is encrypted with
+    /// * `ciphertexts` - Encrypted donor consent followed by the HLA allele codes
+    pub fn set_donor_profile(
+        ctx: Context<SetDonorProfile>,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        ciphertexts: [[u8; 32]; DONOR_PROFILE_FIELDS],
+    ) -> Result<()> {
+        ctx.accounts.donor_profile.set_inner(DonorProfile {
+            ciphertexts,
+            patient_data: ctx.accounts.patient_data.key(),
+            encryption_key,
+            nonce,
+            bump: ctx.bumps.donor_profile,
+        });
+        Ok(())
+    }
+
+    pub fn init_check_donor_match_comp_def(ctx: Context<InitCheckDonorMatchCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Checks a registered donor against a transplant coordinator's recipient.
+    ///
+    /// The MPC network combines the record's blood type and the donor profile with the
+    /// coordinator's encrypted recipient criteria, and only whether the donor is eligible
+    /// (consented, blood compatible and at least the minimum number of matching HLA alleles)
+    /// is encrypted for the coordinator and emitted in `DonorMatchCheckedEvent`. Only holders
+    /// of a valid `ROLE_TRANSPLANT_COORDINATOR` credential may call it. Every query is written
+    /// to the donor's access log and counted against the share rate limit of the
+    /// coordinator's credential, and fails while the program is paused or once the patient
+    /// has revoked `coordinator_key`.
+    ///
+    /// # Arguments
+    /// * `coordinator_key` - x25519 public key of the coordinator, used for the criteria and
+    ///   the result
+    /// * `coordinator_nonce` - Nonce for the result
+    /// * `criteria_nonce` - Nonce the criteria are encrypted with
+    /// * `criteria` - Encrypted recipient blood type, HLA alleles and minimum matches
+    /// * `sender_pub_key` - x25519 public key the stored record is encrypted with
+    /// * `nonce` - Nonce the stored record is encrypted with
+    #[allow(clippy::too_many_arguments)]
+    pub fn check_donor_match(
+        ctx: Context<CheckDonorMatch>,
+        computation_offset: u64,
+        coordinator_key: [u8; 32],
+        coordinator_nonce: u128,
+        criteria_nonce: u128,
+        criteria: [[u8; 32]; RECIPIENT_CRITERIA_FIELDS],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_credential_record(&ctx.accounts.credential_record)?;
+        require!(!ctx.accounts.program_config.paused, ErrorCode::ProgramPaused);
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        // Counted per credential rather than per x25519 key, which the coordinator can rotate
+        ctx.accounts.rate_limit.record_share(
+            &ctx.accounts.program_config,
+            ctx.accounts.patient_data.key(),
+            ctx.accounts.credential_mint.key().to_bytes(),
+            ctx.bumps.rate_limit,
+        )?;
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            coordinator_key,
+            ctx.accounts.payer.key(),
+            ROLE_TRANSPLANT_COORDINATOR,
+            PURPOSE_TREATMENT,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+
+        let donor_profile = &ctx.accounts.donor_profile;
+        let mut args = vec![
+            Argument::ArcisPubkey(coordinator_key),
+            Argument::PlaintextU128(coordinator_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::ArcisPubkey(donor_profile.encryption_key),
+            Argument::PlaintextU128(donor_profile.nonce),
+            Argument::Account(donor_profile.key(), 8, (DONOR_PROFILE_FIELDS * 32) as u32),
+            Argument::ArcisPubkey(coordinator_key),
+            Argument::PlaintextU128(criteria_nonce),
+            Argument::EncryptedU8(criteria[0]),
+        ];
+        args.extend(
+            criteria[1..=HLA_MARKERS]
+                .iter()
+                .map(|m| Argument::EncryptedU16(*m)),
+        );
+        args.push(Argument::EncryptedU8(criteria[HLA_MARKERS + 1]));
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let patient_data = ctx.accounts.patient_data.key();
+        let coordinator = ctx.accounts.payer.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CheckDonorMatchCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: patient_data,
+                    is_writable: false,
+                },
+                CallbackAccount {
+                    pubkey: coordinator,
+                    is_writable: false,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_donor_match")]
+    pub fn check_donor_match_callback(
+        ctx: Context<CheckDonorMatchCallback>,
+        output: ComputationOutputs<CheckDonorMatchOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(CheckDonorMatchOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(DonorMatchCheckedEvent {
+            patient_data: ctx.accounts.patient_data.key(),
+            coordinator: ctx.accounts.coordinator.key(),
+            nonce: result.nonce.to_le_bytes(),
+            eligible: result.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    /// Creates an audit index shard for a patient record in the current epoch.
+    ///
+    /// Share instructions write to an audit index shard instead of a single per-patient
+    /// account, so concurrent shares for the same patient can pick different shards and
+    /// avoid contending on one writable account. Shards are seeded with the epoch number
+    /// and a shard index; anyone may pay to open one.
+    ///
+    /// # Arguments
+    /// * `epoch` - Current epoch number, used as a seed of the shard
+    /// * `shard` - Shard index within the epoch (0..8)
+    pub fn init_audit_index_shard(
+        ctx: Context<InitAuditIndexShard>,
+        epoch: u64,
+        shard: u8,
+    ) -> Result<()> {
+        require!(epoch == Clock::get()?.epoch, ErrorCode::StaleAuditShard);
+        require!(shard < AUDIT_INDEX_SHARDS, ErrorCode::InvalidAuditShard);
+
+        let index = &mut ctx.accounts.audit_index;
+        index.patient_data = ctx.accounts.patient_data.key();
+        index.epoch = epoch;
+        index.shard = shard;
+        index.share_count = 0;
+        index.last_share_at = 0;
+        index.bump = ctx.bumps.audit_index;
+        Ok(())
+    }
+
+    /// Aggregates audit index shards for a patient record.
+    ///
+    /// Shards are passed as remaining accounts; each must belong to the given patient record.
+    /// The totals are emitted as an `AuditIndexAggregateEvent` and also returned as
+    /// instruction return data so clients can read them through simulation.
+    pub fn aggregate_audit_index(ctx: Context<AggregateAuditIndex>) -> Result<()> {
+        let patient_data = ctx.accounts.patient_data.key();
+        let mut aggregate = AuditIndexAggregate {
+            patient_data,
+            shards: 0,
+            total_shares: 0,
+            first_epoch: u64::MAX,
+            last_epoch: 0,
+            last_share_at: 0,
+        };
+
+        for info in ctx.remaining_accounts.iter() {
+            let shard = Account::<AuditIndexShard>::try_from(info)?;
+            require_keys_eq!(shard.patient_data, patient_data, ErrorCode::InvalidAuditShard);
+            aggregate.shards += 1;
+            aggregate.total_shares = aggregate.total_shares.saturating_add(shard.share_count);
+            aggregate.first_epoch = aggregate.first_epoch.min(shard.epoch);
+            aggregate.last_epoch = aggregate.last_epoch.max(shard.epoch);
+            aggregate.last_share_at = aggregate.last_share_at.max(shard.last_share_at);
+        }
+        if aggregate.shards == 0 {
+            aggregate.first_epoch = 0;
+        }
+
+        anchor_lang::solana_program::program::set_return_data(&aggregate.try_to_vec()?);
+        emit!(AuditIndexAggregateEvent {
+            patient_data,
+            shards: aggregate.shards,
+            total_shares: aggregate.total_shares,
+            first_epoch: aggregate.first_epoch,
+            last_epoch: aggregate.last_epoch,
+            last_share_at: aggregate.last_share_at,
+        });
+        Ok(())
+    }
+
+    /// Creates the program-managed address lookup table for share transactions.
+    ///
+    /// Share contexts carry many static Arcium accounts. This instruction creates an address
+    /// lookup table owned by a program PDA, seeds it with those accounts (MXE, mempool,
+    /// executing pool, fee pool, clock, sign PDA and programs), and records its address in the
+    /// `LookupTableConfig` PDA so clients can always find it when building v0 transactions.
+    ///
+    /// # Arguments
+    /// * `recent_slot` - Recent slot used by the lookup table program to derive the table address
+    pub fn create_share_lookup_table(
+        ctx: Context<CreateShareLookupTable>,
+        recent_slot: u64,
+    ) -> Result<()> {
+        let authority = ctx.accounts.lookup_table_authority.key();
+        let (create_ix, table_address) =
+            alt_instruction::create_lookup_table(authority, ctx.accounts.payer.key(), recent_slot);
+        require_keys_eq!(
+            table_address,
+            ctx.accounts.lookup_table.key(),
+            ErrorCode::InvalidLookupTable
+        );
+
+        invoke_signed(
+            &create_ix,
+            &[
+                ctx.accounts.lookup_table.to_account_info(),
+                ctx.accounts.lookup_table_authority.to_account_info(),
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[&[b"lookup_table_authority", &[ctx.bumps.lookup_table_authority]]],
+        )?;
+
+        let config = &mut ctx.accounts.lookup_table_config;
+        config.authority = ctx.accounts.payer.key();
+        config.lookup_table = table_address;
+        config.address_count = 0;
+        config.bump = ctx.bumps.lookup_table_config;
+
+        let addresses = vec![
+            derive_mxe_pda!(),
+            derive_mempool_pda!(),
+            derive_execpool_pda!(),
+            ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+            ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+            derive_sign_pda!(),
+            Arcium::id(),
+            System::id(),
+        ];
+        let bump = ctx.bumps.lookup_table_authority;
+        extend_share_lookup_table_inner(ctx.accounts.to_lookup_table_accounts(), bump, addresses)
+    }
+
+    /// Appends addresses (e.g. computation definition or cluster accounts) to the share lookup table.
+    ///
+    /// Only the authority recorded in `LookupTableConfig` may extend the table.
+    ///
+    /// # Arguments
+    /// * `addresses` - Addresses to append to the table
+    pub fn extend_share_lookup_table(
+        ctx: Context<ExtendShareLookupTable>,
+        addresses: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(!addresses.is_empty(), ErrorCode::InvalidLookupTable);
+        let bump = ctx.bumps.lookup_table_authority;
+        extend_share_lookup_table_inner(ctx.accounts.to_lookup_table_accounts(), bump, addresses)
+    }
+
+    /// Grants a caregiver authority to act on the caller's patient record.
+    ///
+    /// Creates (or updates) a `DelegationAccount` for the delegate. Within the granted scope
+    /// and until `expires_at`, the delegate may sign `share_patient_data` on the patient's
+    /// behalf, e.g. a family member managing sharing for an elderly patient. Delegating to an
+    /// `Organization` account lets any of its current members act instead.
+    ///
+    /// # Arguments
+    /// * `delegate` - Public key of the caregiver receiving authority
+    /// * `scope` - Bit flags of granted actions (`DELEGATION_SCOPE_SHARE`)
+    /// * `expires_at` - Unix timestamp after which the delegation is no longer valid
+    pub fn delegate_authority(
+        ctx: Context<DelegateAuthority>,
+        delegate: Pubkey,
+        scope: u8,
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(
+            scope != 0 && scope & !DELEGATION_SCOPE_ALL == 0,
+            ErrorCode::InvalidDelegation
+        );
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidDelegation
+        );
+
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.patient_data = ctx.accounts.patient_data.key();
+        delegation.delegate = delegate;
+        delegation.scope = scope;
+        delegation.expires_at = expires_at;
+        delegation.jurisdiction = ctx.accounts.patient_data.load()?.jurisdiction;
+        delegation.bump = ctx.bumps.delegation;
+
+        let patient_data = ctx.accounts.patient_data.key();
+        let index = &mut ctx.accounts.grant_expiry_index;
+        index.patient_data = patient_data;
+        index.bump = ctx.bumps.grant_expiry_index;
+        index.upsert(ctx.accounts.delegation.key(), GRANT_KIND_DELEGATION, expires_at)?;
+
+        let inbox = &mut ctx.accounts.patient_inbox;
+        inbox.patient_data = patient_data;
+        inbox.bump = ctx.bumps.patient_inbox;
+        Ok(())
+    }
+
+    /// Revokes a caregiver delegation, closing the account and returning rent to the patient.
+    ///
+    /// # Arguments
+    /// * `delegate` - Public key of the caregiver whose authority is revoked
+    pub fn revoke_delegation(ctx: Context<RevokeDelegation>, _delegate: Pubkey) -> Result<()> {
+        let delegation = ctx.accounts.delegation.key();
+        ctx.accounts.grant_expiry_index.remove(&delegation);
+        Ok(())
+    }
+
+    /// Revokes every grant on the caller's record in one go, e.g. after a lost or stolen phone.
+    ///
+    /// Remaining accounts are the patient's `DelegationAccount`s and `VisitGrant`s, all of
+    /// which are closed with their rent returned to the patient; each `VisitGrant` must be
+    /// followed by its `Appointment`, which is cancelled. Every grant tracked by the
+    /// `GrantExpiryIndex` must be passed, so a single call cannot leave an active grant
+    /// behind. Emits one `AllAccessRevokedEvent` summarizing what was revoked.
+    pub fn revoke_all_access<'info>(
+        ctx: Context<'_, '_, '_, 'info, RevokeAllAccess<'info>>,
+    ) -> Result<()> {
+        let patient_data = ctx.accounts.patient_data.key();
+        let payer = ctx.accounts.payer.to_account_info();
+
+        let mut revoked = Vec::with_capacity(ctx.remaining_accounts.len());
+        let mut delegations: u8 = 0;
+        let mut accounts = ctx.remaining_accounts.iter();
+        while let Some(account) = accounts.next() {
+            if is_visit_grant(account)? {
+                revoke_visit_grant(account, &mut accounts, patient_data, &payer)?;
+            } else {
+                let delegation = Account::<DelegationAccount>::try_from(account)?;
+                require_keys_eq!(
+                    delegation.patient_data,
+                    patient_data,
+                    ErrorCode::InvalidDelegation
+                );
+                delegation.close(payer.clone())?;
+                delegations += 1;
+            }
+            revoked.push(account.key());
+        }
+
+        if let Some(index) = ctx.accounts.grant_expiry_index.as_deref_mut() {
+            index.entries.retain(|entry| !revoked.contains(&entry.grant));
+            require!(index.entries.is_empty(), ErrorCode::GrantsRemaining);
+        }
+
+        emit!(AllAccessRevokedEvent {
+            patient_data,
+            delegations_revoked: delegations,
+            revoked_at: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Erases the caller's record, GDPR-style.
+    ///
+    /// The record is zeroed and closed together with its metadata, and every downstream
+    /// grant and shared copy passed in `remaining_accounts` is closed: `DelegationAccount`s
+    /// return their rent to the patient, each `VisitGrant` is handled as in
+    /// `revoke_all_access`, and each `SharedRecord` must be followed by its rent payer, which
+    /// gets its rent back. As in `revoke_all_access`, every grant tracked by the
+    /// `GrantExpiryIndex` must be passed. A `Tombstone` recording only the erasure
+    /// time is left behind.
+    pub fn erase_patient_data<'info>(
+        ctx: Context<'_, '_, '_, 'info, ErasePatientData<'info>>,
+    ) -> Result<()> {
+        let patient_data = ctx.accounts.patient_data.key();
+        let payer = ctx.accounts.payer.to_account_info();
+
+        let mut delegations = Vec::new();
+        let mut grants = Vec::new();
+        let mut shared_records: u16 = 0;
+        let mut accounts = ctx.remaining_accounts.iter();
+        while let Some(account) = accounts.next() {
+            let is_shared_record = account.owner == &ID
+                && account.try_borrow_data()?.starts_with(SharedRecord::DISCRIMINATOR);
+            if is_visit_grant(account)? {
+                revoke_visit_grant(account, &mut accounts, patient_data, &payer)?;
+                grants.push(account.key());
+            } else if is_shared_record {
+                let shared = AccountLoader::<SharedRecord>::try_from(account)?;
+                let rent_payer = accounts.next().ok_or(ErrorCode::InvalidInputLength)?;
+                {
+                    let record = shared.load()?;
+                    require_keys_eq!(record.patient_data, patient_data, ErrorCode::Unauthorized);
+                    require_keys_eq!(rent_payer.key(), record.rent_payer, ErrorCode::Unauthorized);
+                }
+                shared.close(rent_payer.clone())?;
+                shared_records += 1;
+            } else {
+                let delegation = Account::<DelegationAccount>::try_from(account)?;
+                require_keys_eq!(
+                    delegation.patient_data,
+                    patient_data,
+                    ErrorCode::InvalidDelegation
+                );
+                delegations.push(account.key());
+                delegation.close(payer.clone())?;
+            }
+        }
+
+        if let Some(index) = ctx.accounts.grant_expiry_index.as_deref_mut() {
+            index.entries.retain(|entry| {
+                !delegations.contains(&entry.grant) && !grants.contains(&entry.grant)
+            });
+            require!(index.entries.is_empty(), ErrorCode::GrantsRemaining);
+        }
+
+        ctx.accounts.patient_data.to_account_info().try_borrow_mut_data()?[8..].fill(0);
+
+        let erased_at = Clock::get()?.unix_timestamp;
+        ctx.accounts.tombstone.set_inner(Tombstone {
+            erased_at,
+            bump: ctx.bumps.tombstone,
+        });
+
+        emit!(PatientDataErasedEvent {
+            patient_data,
+            delegations_closed: delegations.len() as u16,
+            shared_records_closed: shared_records,
+            erased_at,
+        });
+        Ok(())
+    }
+
+    pub fn init_share_demographics_comp_def(
+        ctx: Context<InitShareDemographicsCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares only the basic demographics (identifier, age, gender, blood type, weight, height, allergies) section of the patient record with a receiver.
+    ///
+    /// Works like `share_patient_data` (same authorization, revocation, nonce and audit rules),
+    /// but passes only this section's byte range of the `PatientData` account to the
+    /// `share_demographics` circuit.
+    pub fn share_demographics(
+        ctx: Context<ShareDemographics>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_DEMOGRAPHICS | SECTION_ALLERGIES,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let (offset, length) = section_range(offset_of!(PatientData, patient_id), Some(offset_of!(PatientData, medical_history)));
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), offset, length),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    pub fn init_share_healthcare_comp_def(
+        ctx: Context<InitShareHealthcareCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares only the advanced healthcare data (medical history, medications, procedures, family history) section of the patient record with a receiver.
+    ///
+    /// Works like `share_patient_data` (same authorization, revocation, nonce and audit rules),
+    /// but passes only this section's byte range of the `PatientData` account to the
+    /// `share_healthcare` circuit.
+    pub fn share_healthcare(
+        ctx: Context<ShareHealthcare>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_MEDICATIONS | SECTION_HISTORY,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let (offset, length) = section_range(offset_of!(PatientData, medical_history), Some(offset_of!(PatientData, variant_count)));
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), offset, length),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    pub fn init_share_genomics_comp_def(
+        ctx: Context<InitShareGenomicsCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares only the genomic analysis data (variants, markers, carrier status, pharmacogenomics, ancestry) section of the patient record with a receiver.
+    ///
+    /// Works like `share_patient_data` (same authorization, revocation, nonce and audit rules),
+    /// but passes only this section's byte range of the `PatientData` account to the
+    /// `share_genomics` circuit.
+    pub fn share_genomics(
+        ctx: Context<ShareGenomics>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_GENOMICS)?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let (offset, length) = section_range(offset_of!(PatientData, variant_count), Some(offset_of!(PatientData, lab_test_count)));
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), offset, length),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    pub fn init_share_lab_tests_comp_def(
+        ctx: Context<InitShareLabTestsCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares only the lab test results and imaging data section of the patient record with a receiver.
+    ///
+    /// Works like `share_patient_data` (same authorization, revocation, nonce and audit rules),
+    /// but passes only this section's byte range of the `PatientData` account to the
+    /// `share_lab_tests` circuit.
+    pub fn share_lab_tests(
+        ctx: Context<ShareLabTests>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_LAB_TESTS | SECTION_IMAGING,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let (offset, length) = section_range(
+            offset_of!(PatientData, lab_test_count),
+            Some(offset_of!(PatientData, canary)),
+        );
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), offset, length),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    /// Creates the caller's split record: an index tying together four per-section accounts that
+    /// replace the monolithic `PatientData` account.
+    ///
+    /// The split record is keyed by the caller's `patient_data` PDA address, so delegations,
+    /// revocations, share nonces and the audit trail apply to it as to a monolithic record, but no
+    /// `PatientData` account is created. Sections are stored with `store_demographics_section`,
+    /// `store_healthcare_section`, `store_genomics_section` and `store_lab_tests_section`, and each
+    /// is rented and shared on its own.
+    pub fn init_record_index(ctx: Context<InitRecordIndex>) -> Result<()> {
+        ctx.accounts.record_index.set_inner(RecordIndex {
+            patient_data: ctx.accounts.patient_data.key(),
+            demographics: Pubkey::default(),
+            healthcare: Pubkey::default(),
+            genomics: Pubkey::default(),
+            lab_tests: Pubkey::default(),
+            sections: 0,
+            updated_at: Clock::get()?.unix_timestamp,
+            bump: ctx.bumps.record_index,
+        });
+        Ok(())
+    }
+
+    /// Stores or replaces the basic demographics and allergies section of the caller's split
+    /// record.
+    ///
+    /// Ciphertexts are laid out as `patient_id` through `allergies` in `store_patient_data`. The
+    /// section's rent is only paid once it is first stored.
+    pub fn store_demographics_section(
+        ctx: Context<StoreDemographicsSection>,
+        ciphertexts: [[u8; 32]; DEMOGRAPHICS_FIELDS],
+    ) -> Result<()> {
+        check_not_paused(&ctx.accounts.program_config)?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_DEMOGRAPHICS | SECTION_ALLERGIES,
+        )?;
+        let section = &mut ctx.accounts.section;
+        section.ciphertexts = ciphertexts;
+        section.bump = ctx.bumps.section;
+
+        let index = &mut ctx.accounts.record_index;
+        index.demographics = section.key();
+        index_record_section(index, SECTION_DEMOGRAPHICS | SECTION_ALLERGIES)
+    }
+
+    /// Shares the basic demographics and allergies section of a split record with a receiver.
+    ///
+    /// Works like `share_demographics` (same authorization, revocation, nonce and audit rules), but
+    /// passes the whole `DemographicsAccount` to the `share_demographics` circuit.
+    pub fn share_demographics_section(
+        ctx: Context<ShareDemographicsSection>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_DEMOGRAPHICS | SECTION_ALLERGIES,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.section.key(), 8, (DEMOGRAPHICS_FIELDS * 32) as u32),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    /// Stores or replaces the advanced healthcare (history, medications, procedures, family
+    /// history) section of the caller's split record.
+    ///
+    /// Ciphertexts are laid out as `medical_history` through `family_history` in
+    /// `store_patient_data`. The section's rent is only paid once it is first stored.
+    pub fn store_healthcare_section(
+        ctx: Context<StoreHealthcareSection>,
+        ciphertexts: [[u8; 32]; HEALTHCARE_FIELDS],
+    ) -> Result<()> {
+        check_not_paused(&ctx.accounts.program_config)?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_MEDICATIONS | SECTION_HISTORY,
+        )?;
+        let section = &mut ctx.accounts.section;
+        section.ciphertexts = ciphertexts;
+        section.bump = ctx.bumps.section;
+
+        let index = &mut ctx.accounts.record_index;
+        index.healthcare = section.key();
+        index_record_section(index, SECTION_MEDICATIONS | SECTION_HISTORY)
+    }
+
+    /// Shares the advanced healthcare (history, medications, procedures, family history) section of
+    /// a split record with a receiver.
+    ///
+    /// Works like `share_healthcare` (same authorization, revocation, nonce and audit rules), but
+    /// passes the whole `HealthcareAccount` to the `share_healthcare` circuit.
+    pub fn share_healthcare_section(
+        ctx: Context<ShareHealthcareSection>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_MEDICATIONS | SECTION_HISTORY,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.section.key(), 8, (HEALTHCARE_FIELDS * 32) as u32),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    /// Stores or replaces the genomic analysis section of the caller's split record.
+    ///
+    /// Ciphertexts are laid out as `variant_count` through `ancestry_components` in
+    /// `store_patient_data`. The section's rent is only paid once it is first stored.
+    pub fn store_genomics_section(
+        ctx: Context<StoreGenomicsSection>,
+        ciphertexts: [[u8; 32]; GENOMIC_FIELDS],
+    ) -> Result<()> {
+        check_not_paused(&ctx.accounts.program_config)?;
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_GENOMICS)?;
+        let section = &mut ctx.accounts.section;
+        section.ciphertexts = ciphertexts;
+        section.bump = ctx.bumps.section;
+
+        let index = &mut ctx.accounts.record_index;
+        index.genomics = section.key();
+        index_record_section(index, SECTION_GENOMICS)
+    }
+
+    /// Shares the genomic analysis section of a split record with a receiver.
+    ///
+    /// Works like `share_genomics` (same authorization, revocation, nonce and audit rules), but
+    /// passes the whole `GenomicAccount` to the `share_genomics` circuit.
+    pub fn share_genomics_section(
+        ctx: Context<ShareGenomicsSection>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_GENOMICS)?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.section.key(), 8, (GENOMIC_FIELDS * 32) as u32),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    /// Stores or replaces the lab test and imaging section of the caller's split record.
+    ///
+    /// Ciphertexts are laid out as `lab_test_count` through `imaging_dates` in
+    /// `store_patient_data`. The section's rent is only paid once it is first stored.
+    pub fn store_lab_tests_section(
+        ctx: Context<StoreLabTestsSection>,
+        ciphertexts: [[u8; 32]; LAB_FIELDS],
+    ) -> Result<()> {
+        check_not_paused(&ctx.accounts.program_config)?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_LAB_TESTS | SECTION_IMAGING,
+        )?;
+        let section = &mut ctx.accounts.section;
+        section.ciphertexts = ciphertexts;
+        section.bump = ctx.bumps.section;
+
+        let index = &mut ctx.accounts.record_index;
+        index.lab_tests = section.key();
+        index_record_section(index, SECTION_LAB_TESTS | SECTION_IMAGING)
+    }
+
+    /// Shares the lab test and imaging section of a split record with a receiver.
+    ///
+    /// Works like `share_lab_tests` (same authorization, revocation, nonce and audit rules), but
+    /// passes the whole `LabAccount` to the `share_lab_tests` circuit.
+    pub fn share_lab_tests_section(
+        ctx: Context<ShareLabTestsSection>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_LAB_TESTS | SECTION_IMAGING,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.section.key(), 8, (LAB_FIELDS * 32) as u32),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    /// Shares the patient record with several receivers in one instruction.
+    ///
+    /// Queues one `share_patient_data` computation per receiver and records all of them in a
+    /// `ShareBatch` account keyed by `batch_id`, so a care team can be onboarded in a single
+    /// transaction and tracked as a unit. Authorization, revocation, receiver allowlist,
+    /// data-residency and audit rules are the same as for `share_patient_data`, checked for
+    /// every receiver.
+    ///
+    /// For every receiver, `remaining_accounts` must contain, in order, its computation account
+    /// (derived from the receiver's `computation_offset`), its receiver revocation marker, the
+    /// (not yet created) `SharedRecord` the callback will write its output into, the (not
+    /// yet created) `AccessLogEntry` of the share, its `ShareNonce`, which is created on
+    /// first use and issues the receiver nonce as in `share_patient_data`, its
+    /// `ReceiverJurisdiction` registration, checked against the record's data-residency rule,
+    /// and the (not yet created) `InboxEntry` and `ShareStatus` of the share. Each share is
+    /// announced with `ShareQueuedEvent` and resolved by the callback like a single share.
+    ///
+    /// # Arguments
+    /// * `batch_id` - Caller-chosen identifier, unique per patient record
+    /// * `receivers` - Receivers and the computation offset to use for each (up to 5)
+    /// * `sender_pub_key` - Sender's public key for the operation
+    /// * `nonce` - Cryptographic nonce for the sender's encryption
+    pub fn share_patient_data_multi<'info>(
+        ctx: Context<'_, '_, '_, 'info, SharePatientDataMulti<'info>>,
+        batch_id: u64,
+        receivers: Vec<BatchShareReceiver>,
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, ALL_SECTIONS)?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(
+            !receivers.is_empty() && receivers.len() <= MAX_BATCH_RECEIVERS,
+            ErrorCode::InvalidBatch
+        );
+        require!(
+            ctx.remaining_accounts.len() == receivers.len() * BATCH_RECEIVER_ACCOUNTS,
+            ErrorCode::InvalidBatch
+        );
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+
+        let patient_data = ctx.accounts.patient_data.key();
+        let accessor = ctx.accounts.payer.key();
+        let clock = Clock::get()?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let receiver_accounts = ctx.remaining_accounts.chunks_exact(BATCH_RECEIVER_ACCOUNTS);
+        for (entry, accounts) in receivers.iter().zip(receiver_accounts) {
+            let computation_account = &accounts[0];
+            let receiver_revocation = &accounts[1];
+            let shared_record = &accounts[2];
+            let access_log_entry = &accounts[3];
+            let share_nonce = &accounts[4];
+            let receiver_jurisdiction = &accounts[5];
+            let inbox_entry = &accounts[6];
+            let share_status = &accounts[7];
+
+            require_keys_eq!(
+                computation_account.key(),
+                derive_comp_pda!(entry.computation_offset),
+                ErrorCode::InvalidBatch
+            );
+            let (revocation_address, _) = Pubkey::find_program_address(
+                &[b"receiver_revocation", patient_data.as_ref(), entry.receiver.as_ref()],
+                &ID,
+            );
+            require_keys_eq!(
+                receiver_revocation.key(),
+                revocation_address,
+                ErrorCode::InvalidBatch
+            );
+            require!(receiver_revocation.data_is_empty(), ErrorCode::ReceiverRevoked);
+            check_receiver_allowlist(&ctx.accounts.receiver_allowlist, &entry.receiver)?;
+            let (jurisdiction_address, _) = Pubkey::find_program_address(
+                &[b"receiver_jurisdiction", entry.receiver.as_ref()],
+                &ID,
+            );
+            require_keys_eq!(
+                receiver_jurisdiction.key(),
+                jurisdiction_address,
+                ErrorCode::InvalidBatch
+            );
+            check_jurisdiction(
+                &ctx.accounts.patient_data,
+                &ctx.accounts.jurisdiction_rules,
+                &UncheckedAccount::try_from(receiver_jurisdiction),
+            )?;
+
+            create_shared_record(
+                shared_record,
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+                &patient_data,
+                entry,
+            )?;
+
+            let receiver_nonce = advance_batch_share_nonce(
+                share_nonce,
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+                patient_data,
+                entry.receiver,
+            )?;
+            let args = vec![
+                Argument::ArcisPubkey(entry.receiver),
+                Argument::PlaintextU128(receiver_nonce),
+                Argument::ArcisPubkey(sender_pub_key),
+                Argument::PlaintextU128(nonce),
+                Argument::Account(patient_data, 8, PATIENT_CIPHERTEXTS_LEN),
+            ];
+
+            let offset_bytes = entry.computation_offset.to_le_bytes();
+            let access_log_bump = create_program_account(
+                access_log_entry,
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+                &[b"access_log", patient_data.as_ref(), &offset_bytes],
+                8 + AccessLogEntry::INIT_SPACE,
+            )?;
+            let access_log = log_access(
+                patient_data,
+                entry.receiver,
+                accessor,
+                role,
+                purpose,
+                entry.computation_offset,
+                access_log_bump,
+            )?;
+            access_log.try_serialize(&mut &mut access_log_entry.try_borrow_mut_data()?[..])?;
+
+            let inbox_bump = create_program_account(
+                inbox_entry,
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+                &[b"inbox_entry", entry.receiver.as_ref(), &offset_bytes],
+                8 + InboxEntry::INIT_SPACE,
+            )?;
+            let inbox = new_inbox_entry(
+                entry.receiver,
+                entry.receiver_authority,
+                accessor,
+                patient_data,
+                entry.computation_offset,
+                ALL_SECTIONS,
+                inbox_bump,
+            )?;
+            inbox.try_serialize(&mut &mut inbox_entry.try_borrow_mut_data()?[..])?;
+
+            let share_status_bump = create_program_account(
+                share_status,
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+                &[b"share_status", patient_data.as_ref(), &offset_bytes],
+                8 + ShareStatus::INIT_SPACE,
+            )?;
+            let status = ShareStatus {
+                patient_data,
+                computation_offset: entry.computation_offset,
+                status: SHARE_STATUS_QUEUED,
+                queued_slot: clock.slot,
+                resolved_slot: 0,
+                failure_reason: 0,
+                bump: share_status_bump,
+            };
+            status.try_serialize(&mut &mut share_status.try_borrow_mut_data()?[..])?;
+
+            emit!(ShareQueuedEvent {
+                schema_version: EVENT_SCHEMA_VERSION,
+                patient_data,
+                computation_offset: entry.computation_offset,
+                receiver: entry.receiver,
+                purpose,
+                accessor,
+            });
+
+            ctx.accounts.audit_index.record_share(&clock)?;
+            ctx.accounts.computation_account = UncheckedAccount::try_from(computation_account);
+
+            queue_computation(
+                ctx.accounts,
+                entry.computation_offset,
+                args,
+                None,
+                vec![SharePatientDataCallback::callback_ix(&[
+                    CallbackAccount {
+                        pubkey: shared_record.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: inbox_entry.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: share_status.key(),
+                        is_writable: true,
+                    },
+                ])],
+            )?;
+        }
+
+        let batch = &mut ctx.accounts.share_batch;
+        batch.patient_data = patient_data;
+        batch.batch_id = batch_id;
+        batch.created_at = clock.unix_timestamp;
+        batch.receivers = receivers.iter().map(|r| r.receiver).collect();
+        batch.computation_offsets = receivers.iter().map(|r| r.computation_offset).collect();
+        batch.bump = ctx.bumps.share_batch;
+
+        emit!(ShareBatchQueuedEvent {
+            patient_data,
+            batch_id,
+            receivers: batch.receivers.clone(),
+            computation_offsets: batch.computation_offsets.clone(),
+        });
+        Ok(())
+    }
+
+    /// Permissionless crank emitting reminders for grants that are about to expire.
+    ///
+    /// Walks the patient's expiry-ordered `GrantExpiryIndex` from the earliest expiry, drops
+    /// grants that have already lapsed, and for every grant expiring within
+    /// `GRANT_REMINDER_WINDOW_DAYS` that has not been reminded yet, appends a notification to
+    /// the patient's inbox and emits a `GrantExpiryReminderEvent`. The walk stops at the first
+    /// grant outside the window, so the crank stays cheap regardless of how many grants exist.
+    pub fn crank_grant_expiry_reminders(ctx: Context<CrankGrantExpiryReminders>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let window_end = now.saturating_add(GRANT_REMINDER_WINDOW_DAYS * 24 * 60 * 60);
+        let patient_data = ctx.accounts.grant_expiry_index.patient_data;
+
+        let index = &mut ctx.accounts.grant_expiry_index;
+        index.entries.retain(|entry| entry.expires_at > now);
+
+        let inbox = &mut ctx.accounts.patient_inbox;
+        for entry in index.entries.iter_mut() {
+            if entry.expires_at > window_end {
+                break;
+            }
+            if entry.reminded {
+                continue;
+            }
+            entry.reminded = true;
+            inbox.push(Notification {
+                kind: NOTIFICATION_GRANT_EXPIRING,
+                subject: entry.grant,
+                due_at: entry.expires_at,
+                created_at: now,
+            });
+            emit!(GrantExpiryReminderEvent {
+                patient_data,
+                grant: entry.grant,
+                grant_kind: entry.kind,
+                expires_at: entry.expires_at,
+            });
+        }
+        Ok(())
+    }
+
+    /// Appends an encrypted entry (lab result, medication or visit note) to a patient record.
+    ///
+    /// Every entry records its author: the signer's key, the role they wrote under and the
+    /// credential mint backing that role. The patient may write to their own record as
+    /// `ROLE_PATIENT`; any other author must present a credential NFT as in
+    /// `share_patient_data_with_role`. Entries are stored in their own PDAs numbered by the
+    /// record's entry counter, so writes never overwrite existing clinical data.
+    ///
+    /// # Arguments
+    /// * `kind` - Entry kind (`ENTRY_KIND_*`)
+    /// * `role` - Role the author writes under (`ROLE_*`)
+    /// * `payload` - Encrypted entry fields with the key and nonce they were encrypted with
+    pub fn append_entry(
+        ctx: Context<AppendEntry>,
+        kind: u8,
+        role: u8,
+        payload: EncryptedEntry,
+    ) -> Result<()> {
+        require!(kind <= ENTRY_KIND_VISIT_NOTE, ErrorCode::InvalidEntry);
+        check_sections_unlocked(&ctx.accounts.section_locks, entry_section(kind))?;
+
+        let author = resolve_author(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            role,
+            ctx.accounts.credential_mint.as_deref(),
+            ctx.accounts.credential_token_account.as_deref(),
+        )?;
+
+        let entry_id = write_entry(
+            &ctx.accounts.program_config,
+            &mut ctx.accounts.record_metadata,
+            &mut ctx.accounts.record_entry,
+            author.clone(),
+            kind,
+            &payload,
+            None,
+        )?;
+        let entry = &mut ctx.accounts.record_entry;
+        entry.patient_data = ctx.accounts.patient_data.key();
+        entry.bump = ctx.bumps.record_entry;
+
+        emit!(RecordEntryAppendedEvent {
+            patient_data: entry.patient_data,
+            entry: entry.key(),
+            entry_id,
+            kind,
+            author,
+        });
+        Ok(())
+    }
+
+    /// Corrects an entry without overwriting it.
+    ///
+    /// Appends the corrected ciphertexts as a new entry that links back to the original via
+    /// `supersedes`, and marks the original as superseded. Only the latest version of an entry
+    /// can be amended. Providers may amend any entry; the patient may only amend entries they
+    /// authored themselves, so clinical data cannot be silently altered.
+    ///
+    /// # Arguments
+    /// * `original_entry_id` - Id of the entry being corrected
+    /// * `role` - Role the amending author writes under (`ROLE_*`)
+    /// * `payload` - Corrected encrypted entry fields
+    pub fn amend_entry(
+        ctx: Context<AmendEntry>,
+        original_entry_id: u64,
+        role: u8,
+        payload: EncryptedEntry,
+    ) -> Result<()> {
+        let author = resolve_author(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            role,
+            ctx.accounts.credential_mint.as_deref(),
+            ctx.accounts.credential_token_account.as_deref(),
+        )?;
+
+        let original = &ctx.accounts.original_entry;
+        require!(original.superseded_by.is_none(), ErrorCode::EntrySuperseded);
+        require!(
+            author.role != ROLE_PATIENT || original.author.role == ROLE_PATIENT,
+            ErrorCode::Unauthorized
+        );
+        let kind = original.kind;
+        check_sections_unlocked(&ctx.accounts.section_locks, entry_section(kind))?;
+
+        let amended_entry_id = write_entry(
+            &ctx.accounts.program_config,
+            &mut ctx.accounts.record_metadata,
+            &mut ctx.accounts.amended_entry,
+            author.clone(),
+            kind,
+            &payload,
+            Some(original_entry_id),
+        )?;
+        let amended = &mut ctx.accounts.amended_entry;
+        amended.patient_data = ctx.accounts.patient_data.key();
+        amended.bump = ctx.bumps.amended_entry;
+        ctx.accounts.original_entry.superseded_by = Some(amended_entry_id);
+
+        emit!(RecordEntryAmendedEvent {
+            patient_data: ctx.accounts.patient_data.key(),
+            original_entry_id,
+            amended_entry_id,
+            kind,
+            author,
+        });
+        Ok(())
+    }
+
+    /// Writes a provider's encrypted visit summary back to a patient record.
+    ///
+    /// The signer must hold an active provider credential and the active `VisitGrant` of a
+    /// confirmed appointment on the record, before its visit window closes. Caregiver
+    /// delegations do not authorize write-back. Summaries are stored in their own
+    /// `Encounter` PDAs and are never overwritten.
+    ///
+    /// # Arguments
+    /// * `encounter_id` - Client-chosen id of the encounter, unique within the record
+    /// * `payload` - Encrypted summary fields with the key and nonce they were encrypted with
+    pub fn append_visit_summary(
+        ctx: Context<AppendVisitSummary>,
+        encounter_id: u64,
+        payload: EncryptedEntry,
+    ) -> Result<()> {
+        check_credential_record(&ctx.accounts.credential_record)?;
+        let grant = &ctx.accounts.visit_grant;
+        require_keys_eq!(
+            grant.patient_data,
+            ctx.accounts.patient_data.key(),
+            ErrorCode::Unauthorized
+        );
+        require_keys_eq!(
+            grant.provider,
+            ctx.accounts.payer.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(
+            grant.active && Clock::get()?.unix_timestamp < grant.expires_at,
+            ErrorCode::InvalidAppointment
+        );
+        require!(
+            payload.ciphertexts.len() == ENTRY_FIELDS,
+            ErrorCode::InvalidInputLength
+        );
+
+        let encounter = &mut ctx.accounts.encounter;
+        encounter.patient_data = ctx.accounts.patient_data.key();
+        encounter.encryption_key = payload.encryption_key;
+        encounter.nonce = payload.nonce;
+        encounter.ciphertexts.copy_from_slice(&payload.ciphertexts);
+        encounter.encounter_id = encounter_id;
+        encounter.provider = ctx.accounts.payer.key();
+        encounter.credential_mint = ctx.accounts.credential_mint.key();
+        encounter.created_at = Clock::get()?.unix_timestamp;
+        encounter.bump = ctx.bumps.encounter;
+
+        emit!(VisitSummaryAppendedEvent {
+            patient_data: encounter.patient_data,
+            encounter: encounter.key(),
+            encounter_id,
+            provider: encounter.provider,
+        });
+        Ok(())
+    }
+
+    /// Proposes replacing one section of a record, to be applied once the other party approves.
+    ///
+    /// Corrections such as a changed blood type need both the patient and a clinician: whoever
+    /// proposes commits to the SHA-256 of the new ciphertexts in a `PendingUpdate`, and the
+    /// other party applies them with `approve_update`. A section has at most one pending
+    /// update at a time.
+    ///
+    /// # Arguments
+    /// * `section` - A single `SECTION_*` bit
+    /// * `ciphertexts_hash` - SHA-256 of the new section ciphertexts, concatenated
+    /// * `role` - Role the proposer signs under (`ROLE_*`), as in `append_entry`
+    pub fn propose_update(
+        ctx: Context<ProposeUpdate>,
+        section: u8,
+        ciphertexts_hash: [u8; 32],
+        role: u8,
+    ) -> Result<()> {
+        section_byte_ranges(section)?;
+        check_sections_unlocked(&ctx.accounts.section_locks, section)?;
+        let author = resolve_author(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            role,
+            ctx.accounts.credential_mint.as_ref(),
+            ctx.accounts.credential_token_account.as_ref(),
+        )?;
+
+        ctx.accounts.pending_update.set_inner(PendingUpdate {
+            patient_data: ctx.accounts.patient_data.key(),
+            section,
+            ciphertexts_hash,
+            proposer: author.clone(),
+            proposed_at: Clock::get()?.unix_timestamp,
+            bump: ctx.bumps.pending_update,
+        });
+
+        emit!(UpdateProposedEvent {
+            patient_data: ctx.accounts.patient_data.key(),
+            section,
+            ciphertexts_hash,
+            proposer: author,
+        });
+        Ok(())
+    }
+
+    /// Approves a pending update and writes its ciphertexts into the record.
+    ///
+    /// The approver must be the patient if a clinician proposed the update and a credentialed
+    /// clinician if the patient did. `ciphertexts` must hash to the proposed
+    /// `ciphertexts_hash` and cover the whole section in `PatientData` field order, encrypted
+    /// like the rest of the record. The `PendingUpdate` is closed to the proposer.
+    ///
+    /// # Arguments
+    /// * `ciphertexts` - New section ciphertexts
+    /// * `role` - Role the approver signs under (`ROLE_*`)
+    pub fn approve_update(
+        ctx: Context<ApproveUpdate>,
+        ciphertexts: Vec<[u8; 32]>,
+        role: u8,
+    ) -> Result<()> {
+        check_not_paused(&ctx.accounts.program_config)?;
+        let author = resolve_author(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            role,
+            ctx.accounts.credential_mint.as_ref(),
+            ctx.accounts.credential_token_account.as_ref(),
+        )?;
+        let pending = &ctx.accounts.pending_update;
+        require!(
+            (pending.proposer.role == ROLE_PATIENT) != (author.role == ROLE_PATIENT),
+            ErrorCode::CoSignerRequired
+        );
+
+        check_sections_unlocked(&ctx.accounts.section_locks, pending.section)?;
+        let input: &[u8] = bytemuck::cast_slice(&ciphertexts);
+        require!(
+            hashv(&[input]).to_bytes() == pending.ciphertexts_hash,
+            ErrorCode::UpdateHashMismatch
+        );
+        let ranges = section_byte_ranges(pending.section)?;
+        require!(
+            input.len() == ranges.iter().map(|range| range.len()).sum::<usize>(),
+            ErrorCode::InvalidInputLength
+        );
+        {
+            let mut data = ctx.accounts.patient_data.load_mut()?;
+            let record = bytemuck::bytes_of_mut(&mut *data);
+            let mut cursor = 0;
+            for range in ranges {
+                let len = range.len();
+                record[range].copy_from_slice(&input[cursor..cursor + len]);
+                cursor += len;
+            }
+        }
+
+        let metadata = &mut ctx.accounts.record_metadata;
+        metadata.last_written_by = author.clone();
+        metadata.last_written_at = Clock::get()?.unix_timestamp;
+
+        emit!(UpdateAppliedEvent {
+            patient_data: ctx.accounts.patient_data.key(),
+            section: pending.section,
+            proposer: pending.proposer.clone(),
+            approver: author,
+        });
+        Ok(())
+    }
+
+    /// Withdraws a pending update. The proposer or the patient may call it, so a proposal the
+    /// patient disagrees with does not block the section.
+    pub fn cancel_update(ctx: Context<CancelUpdate>, _section: u8) -> Result<()> {
+        let signer = ctx.accounts.payer.key();
+        let patient_data = ctx.accounts.patient_data.key();
+        let (owned, _) = Pubkey::find_program_address(&[b"patient_data", signer.as_ref()], &ID);
+        require!(
+            signer == ctx.accounts.proposer.key() || owned == patient_data,
+            ErrorCode::Unauthorized
+        );
+
+        emit!(UpdateCancelledEvent {
+            patient_data,
+            section: ctx.accounts.pending_update.section,
+            cancelled_by: signer,
+        });
+        Ok(())
+    }
+
+    pub fn init_share_entry_comp_def(ctx: Context<InitShareEntryCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares the current (amended) view of a record entry with a receiver.
+    ///
+    /// Only the latest version of an entry can be shared this way; use
+    /// `share_entry_history` to disclose a correction together with the entry it replaced, and
+    /// `share_flagged_entry` for entries the patient has flagged. Authorization, revocation, nonce and audit rules match `share_patient_data`.
+    pub fn share_entry(
+        ctx: Context<ShareEntry>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            entry_section(ctx.accounts.record_entry.kind),
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+        let entry = &ctx.accounts.record_entry;
+        require!(entry.superseded_by.is_none(), ErrorCode::EntrySuperseded);
+        require!(!entry.flagged, ErrorCode::EntryFlagged);
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(entry.encryption_key),
+            Argument::PlaintextU128(entry.nonce),
+            Argument::Account(
+                entry.key(),
+                RECORD_ENTRY_CIPHERTEXTS_OFFSET,
+                (ENTRY_FIELDS * 32) as u32,
+            ),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    /// Shares a visit summary written with `append_visit_summary` with a receiver.
+    ///
+    /// Encounters use the `share_entry` circuit. Authorization, revocation, nonce and audit
+    /// rules match `share_patient_data`.
+    pub fn share_encounter(
+        ctx: Context<ShareEncounter>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_HISTORY)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+        let encounter = &ctx.accounts.encounter;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(encounter.encryption_key),
+            Argument::PlaintextU128(encounter.nonce),
+            Argument::Account(
+                encounter.key(),
+                ENCOUNTER_CIPHERTEXTS_OFFSET,
+                (ENTRY_FIELDS * 32) as u32,
+            ),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    pub fn init_share_entry_history_comp_def(
+        ctx: Context<InitShareEntryHistoryCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares an amended entry together with the entry it superseded.
+    ///
+    /// Gives the receiver the correction and the original side by side; clients can walk
+    /// `supersedes` links to disclose a longer history. Neither entry may be flagged by the
+    /// patient. Authorization, revocation, nonce and audit rules match `share_patient_data`.
+    pub fn share_entry_history(
+        ctx: Context<ShareEntryHistory>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            entry_section(ctx.accounts.record_entry.kind),
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+        let entry = &ctx.accounts.record_entry;
+        let previous = &ctx.accounts.previous_entry;
+        require!(!entry.flagged && !previous.flagged, ErrorCode::EntryFlagged);
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(entry.encryption_key),
+            Argument::PlaintextU128(entry.nonce),
+            Argument::Account(
+                entry.key(),
+                RECORD_ENTRY_CIPHERTEXTS_OFFSET,
+                (ENTRY_FIELDS * 32) as u32,
+            ),
+            Argument::ArcisPubkey(previous.encryption_key),
+            Argument::PlaintextU128(previous.nonce),
+            Argument::Account(
+                previous.key(),
+                RECORD_ENTRY_CIPHERTEXTS_OFFSET,
+                (ENTRY_FIELDS * 32) as u32,
+            ),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    /// Closes a `SharedRecord` once the receiver has downloaded its ciphertexts.
+    ///
+    /// Only the receiver authority recorded at share time may close the record; the rent is
+    /// returned to whoever paid for the share.
+    pub fn close_shared_record(_ctx: Context<CloseSharedRecord>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Sets until when a share of the caller's record is kept for the receiver, overriding
+    /// the config's retention policy.
+    ///
+    /// # Arguments
+    /// * `retain_until` - Unix timestamp after which the record may be expired, or 0 to use
+    ///   the config default
+    pub fn set_share_retention(ctx: Context<SetShareRetention>, retain_until: i64) -> Result<()> {
+        require!(retain_until >= 0, ErrorCode::InvalidPeriod);
+        ctx.accounts.shared_record.load_mut()?.retain_until = retain_until;
+        Ok(())
+    }
+
+    /// Permissionless crank closing a `SharedRecord` whose retention window has passed, so
+    /// downstream copies don't live forever. The rent is returned to whoever paid for the
+    /// share.
+    pub fn expire_shared_record(ctx: Context<ExpireSharedRecord>) -> Result<()> {
+        let policy = if ctx.accounts.program_config.data_is_empty() {
+            RetentionPolicy {
+                default_retention: 0,
+            }
+        } else {
+            let config_info = ctx.accounts.program_config.to_account_info();
+            Account::<ProgramConfig>::try_from(&config_info)?.retention_policy
+        };
+
+        let record = ctx.accounts.shared_record.load()?;
+        let expires_at = record
+            .expires_at(&policy)
+            .ok_or(ErrorCode::RetentionNotElapsed)?;
+        require!(
+            Clock::get()?.unix_timestamp >= expires_at,
+            ErrorCode::RetentionNotElapsed
+        );
+
+        emit!(SharedRecordExpiredEvent {
+            shared_record: ctx.accounts.shared_record.key(),
+            patient_data: record.patient_data,
+            receiver: record.receiver,
+            computation_offset: record.computation_offset,
+        });
+        Ok(())
+    }
+
+    /// Permissionless crank marking a share as timed out once `SHARE_TIMEOUT_SLOTS` have
+    /// passed since it was queued without its callback running.
+    pub fn time_out_share(ctx: Context<TimeOutShare>) -> Result<()> {
+        let share_status = &mut ctx.accounts.share_status;
+        require!(
+            share_status.status == SHARE_STATUS_QUEUED,
+            ErrorCode::ShareNotPending
+        );
+        let slot = Clock::get()?.slot;
+        require!(
+            slot >= share_status.queued_slot.saturating_add(SHARE_TIMEOUT_SLOTS),
+            ErrorCode::ShareTimeoutNotReached
+        );
+        share_status.status = SHARE_STATUS_TIMED_OUT;
+        share_status.resolved_slot = slot;
+
+        emit!(ShareTimedOutEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            patient_data: share_status.patient_data,
+            computation_offset: share_status.computation_offset,
+        });
+        Ok(())
+    }
+
+    /// Lets the patient abandon a share that is still queued or has timed out.
+    ///
+    /// Marks its `ShareStatus` cancelled and closes the unfilled `SharedRecord` and the
+    /// receiver's `InboxEntry`, returning their rent to whoever paid for the share; a late
+    /// callback then fails and its output is discarded. The computation account belongs to
+    /// the Arcium program, which offers no instruction to reclaim it early, so its rent is
+    /// left to the cluster.
+    ///
+    /// # Arguments
+    /// * `computation_offset` - Offset of the share computation to abandon
+    pub fn cancel_share(ctx: Context<CancelShare>, computation_offset: u64) -> Result<()> {
+        let share_status = &mut ctx.accounts.share_status;
+        require!(
+            matches!(
+                share_status.status,
+                SHARE_STATUS_QUEUED | SHARE_STATUS_TIMED_OUT
+            ),
+            ErrorCode::ShareNotPending
+        );
+        {
+            let record = ctx.accounts.shared_record.load()?;
+            require_keys_eq!(
+                record.patient_data,
+                share_status.patient_data,
+                ErrorCode::Unauthorized
+            );
+            require!(
+                record.computation_offset == computation_offset,
+                ErrorCode::Unauthorized
+            );
+        }
+        let entry = &ctx.accounts.inbox_entry;
+        require_keys_eq!(
+            entry.patient_data,
+            share_status.patient_data,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            entry.computation_offset == computation_offset,
+            ErrorCode::Unauthorized
+        );
+        share_status.status = SHARE_STATUS_CANCELLED;
+        share_status.resolved_slot = Clock::get()?.slot;
+
+        emit!(ShareCancelledEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            patient_data: share_status.patient_data,
+            computation_offset,
+        });
+        Ok(())
+    }
+
+    /// Re-queues a failed, timed-out or cancelled `share_patient_data` under a fresh
+    /// `computation_offset`: the receiver and purpose are taken from the original share's
+    /// `AccessLogEntry`.
+    ///
+    /// The signer is authorized again against the current state exactly as in
+    /// `share_patient_data`: the patient, a caregiver whose `DelegationAccount` still holds
+    /// the share scope, or a member of a delegated `Organization`. Having queued the original
+    /// share is not enough, so a revoked delegation cannot retry it. Receiver revocations, the
+    /// receiver allowlist, data-residency rules and the pause state are checked again as
+    /// well; share extensions are not called. The original `ShareStatus` is
+    /// marked retried so it cannot be retried twice, and the new share gets its own.
+    ///
+    /// # Arguments
+    /// * `computation_offset` - Fresh offset of the new computation
+    /// * `previous_offset` - Offset of the share being retried
+    /// * `sender_pub_key` - Patient's public key the stored record is encrypted with
+    /// * `nonce` - Nonce of the stored record's encryption
+    pub fn retry_share(
+        ctx: Context<RetryShare>,
+        computation_offset: u64,
+        previous_offset: u64,
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_comp_def(
+            &ctx.accounts.comp_def_registry,
+            "share_patient_data",
+            COMP_DEF_OFFSET_SHARE_PATIENT_DATA,
+        )?;
+        require!(
+            matches!(
+                ctx.accounts.previous_status.status,
+                SHARE_STATUS_FAILED | SHARE_STATUS_TIMED_OUT | SHARE_STATUS_CANCELLED
+            ),
+            ErrorCode::ShareNotRetryable
+        );
+        let patient_data = ctx.accounts.patient_data.key();
+        let payer = ctx.accounts.payer.key();
+        let previous = &ctx.accounts.previous_access_log;
+        let (receiver, purpose) = (previous.receiver, previous.purpose);
+        let role = match ctx.accounts.organization.as_deref() {
+            Some(organization) => authorize_organization_member(
+                &payer,
+                &patient_data,
+                ctx.accounts.delegation.as_deref(),
+                organization,
+                DELEGATION_SCOPE_SHARE,
+            )?,
+            None => authorize_patient_or_delegate(
+                &payer,
+                &patient_data,
+                ctx.accounts.delegation.as_deref(),
+                DELEGATION_SCOPE_SHARE,
+            )?,
+        };
+        check_sections_unlocked(&ctx.accounts.section_locks, ALL_SECTIONS)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_receiver_allowlist(&ctx.accounts.receiver_allowlist, &receiver)?;
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+
+        let receiver_nonce =
+            ctx.accounts
+                .share_nonce
+                .advance(patient_data, receiver, ctx.bumps.share_nonce)?;
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(patient_data, 8, PATIENT_CIPHERTEXTS_LEN),
+        ];
+
+        init_shared_record(
+            &ctx.accounts.shared_record,
+            patient_data,
+            receiver,
+            ctx.accounts.receiver_authority.key(),
+            payer,
+            computation_offset,
+        )?;
+        let access_log = log_access(
+            patient_data,
+            receiver,
+            payer,
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let entry = new_inbox_entry(
+            receiver,
+            ctx.accounts.receiver_authority.key(),
+            payer,
+            patient_data,
+            computation_offset,
+            ALL_SECTIONS,
+            ctx.bumps.inbox_entry,
+        )?;
+        ctx.accounts.inbox_entry.set_inner(entry);
+
+        let slot = Clock::get()?.slot;
+        ctx.accounts.share_status.set_inner(ShareStatus {
+            patient_data,
+            computation_offset,
+            status: SHARE_STATUS_QUEUED,
+            queued_slot: slot,
+            resolved_slot: 0,
+            failure_reason: 0,
+            bump: ctx.bumps.share_status,
+        });
+        let previous_status = &mut ctx.accounts.previous_status;
+        previous_status.status = SHARE_STATUS_RETRIED;
+        previous_status.resolved_slot = slot;
+
+        emit!(ShareQueuedEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            patient_data,
+            computation_offset,
+            receiver,
+            purpose,
+            accessor: payer,
+        });
+        emit!(ShareRetriedEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            patient_data,
+            previous_offset,
+            computation_offset,
+        });
+
+        let shared_record = ctx.accounts.shared_record.key();
+        let inbox_entry = ctx.accounts.inbox_entry.key();
+        let share_status = ctx.accounts.share_status.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![SharePatientDataCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: shared_record,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: inbox_entry,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: share_status,
+                    is_writable: true,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    /// Lets the patient flag an entry of their record, e.g. to dispute what a provider wrote.
+    ///
+    /// Attaches an encrypted annotation and a disputed status to the entry in a separate
+    /// `EntryFlag` account; the entry itself is never modified. Calling it again replaces the
+    /// annotation and status. Once flagged, the entry can only be shared through
+    /// `share_flagged_entry`, which always discloses the annotation alongside it.
+    ///
+    /// # Arguments
+    /// * `entry_id` - Id of the entry being flagged
+    /// * `disputed` - Whether the patient disputes the entry
+    /// * `encryption_key` - x25519 public key the annotation was encrypted with
+    /// * `nonce` - Nonce the annotation was encrypted with
+    /// * `annotation` - Encrypted annotation fields
+    pub fn flag_entry(
+        ctx: Context<FlagEntry>,
+        entry_id: u64,
+        disputed: bool,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        annotation: [[u8; 32]; ANNOTATION_FIELDS],
+    ) -> Result<()> {
+        let flag = &mut ctx.accounts.entry_flag;
+        flag.patient_data = ctx.accounts.patient_data.key();
+        flag.encryption_key = encryption_key;
+        flag.nonce = nonce;
+        flag.ciphertexts = annotation;
+        flag.entry_id = entry_id;
+        flag.disputed = disputed;
+        flag.flagged_at = Clock::get()?.unix_timestamp;
+        flag.bump = ctx.bumps.entry_flag;
+        ctx.accounts.record_entry.flagged = true;
+
+        emit!(RecordEntryFlaggedEvent {
+            patient_data: flag.patient_data,
+            entry_id,
+            disputed,
+        });
+        Ok(())
+    }
+
+    pub fn init_share_flagged_entry_comp_def(
+        ctx: Context<InitShareFlaggedEntryCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares a flagged entry together with the patient's annotation on it.
+    ///
+    /// The disputed status is public in the `EntryFlag` account. Authorization, revocation,
+    /// nonce and audit rules match `share_patient_data`.
+    pub fn share_flagged_entry(
+        ctx: Context<ShareFlaggedEntry>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            entry_section(ctx.accounts.record_entry.kind),
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+        let entry = &ctx.accounts.record_entry;
+        let flag = &ctx.accounts.entry_flag;
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(entry.encryption_key),
+            Argument::PlaintextU128(entry.nonce),
+            Argument::Account(
+                entry.key(),
+                RECORD_ENTRY_CIPHERTEXTS_OFFSET,
+                (ENTRY_FIELDS * 32) as u32,
+            ),
+            Argument::ArcisPubkey(flag.encryption_key),
+            Argument::PlaintextU128(flag.nonce),
+            Argument::Account(
+                flag.key(),
+                ENTRY_FLAG_CIPHERTEXTS_OFFSET,
+                (ANNOTATION_FIELDS * 32) as u32,
+            ),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    pub fn init_compute_cardio_risk_comp_def(
+        ctx: Context<InitComputeCardioRiskCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Computes a cardiovascular risk band from the stored record for a receiver.
+    ///
+    /// The MPC network scores age, gender, cardiovascular medical and family history and
+    /// out-of-range lipid panels, and only the resulting band (low, moderate, high, very high)
+    /// is re-encrypted for the receiver and emitted in `CardioRiskComputedEvent`. None of the
+    /// underlying values are disclosed. Authorization, revocation, nonce and audit rules match
+    /// `share_patient_data`.
+    pub fn compute_cardio_risk(
+        ctx: Context<ComputeCardioRisk>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_DEMOGRAPHICS | SECTION_HISTORY | SECTION_LAB_TESTS,
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ComputeCardioRiskCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "compute_cardio_risk")]
+    pub fn compute_cardio_risk_callback(
+        ctx: Context<ComputeCardioRiskCallback>,
+        output: ComputationOutputs<ComputeCardioRiskOutput>,
+    ) -> Result<()> {
+        let risk = match output {
+            ComputationOutputs::Success(ComputeCardioRiskOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(CardioRiskComputedEvent {
+            nonce: risk.nonce.to_le_bytes(),
+            risk_band: risk.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    pub fn init_check_drug_interaction_comp_def(
+        ctx: Context<InitCheckDrugInteractionCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Checks a proposed medication against the patient's record without disclosing it.
+    ///
+    /// The MPC network compares the candidate medication against the stored medications and
+    /// pharmacogenomic markers, and only an interact / no-interact flag is re-encrypted for the
+    /// receiver and emitted in `DrugInteractionCheckedEvent`. The candidate medication id is
+    /// encrypted under the receiver's key, so the pharmacist querying it is normally the
+    /// receiver. Requires a pharmacist credential NFT as in `share_patient_data_with_role`;
+    /// revocation, nonce and audit rules match `share_patient_data`.
+    ///
+    /// # Arguments
+    /// * `candidate_nonce` - Nonce the candidate medication id was encrypted with; must not
+    ///   be one of the latest used for this patient and receiver
+    /// * `candidate_med` - Encrypted id of the proposed medication
+    #[allow(clippy::too_many_arguments)]
+    pub fn check_drug_interaction(
+        ctx: Context<CheckDrugInteraction>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        candidate_nonce: u128,
+        candidate_med: [u8; 32],
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(ctx.accounts.credential_mint.decimals == 0, ErrorCode::InvalidCredentialMint);
+        require!(ctx.accounts.credential_token_account.amount >= 1, ErrorCode::MissingCredential);
+        check_credential_record(&ctx.accounts.credential_record)?;
+        check_credential_collection(
+           &ctx.accounts.role_config,
+           ctx.accounts.credential_metadata.as_deref(),
+           &ctx.accounts.credential_mint.key(),
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_MEDICATIONS | SECTION_HISTORY | SECTION_GENOMICS,
+        )?;
+        check_patient_policy(
+            &ctx.accounts.patient_policy,
+            (1 << CATEGORY_HEALTHCARE) | (1 << CATEGORY_GENOMICS),
+            ROLE_PHARMACIST,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+        ctx.accounts.share_nonce.consume_input_nonce(candidate_nonce)?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(candidate_nonce),
+            Argument::EncryptedU64(candidate_med),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            ROLE_PHARMACIST,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CheckDrugInteractionCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_drug_interaction")]
+    pub fn check_drug_interaction_callback(
+        ctx: Context<CheckDrugInteractionCallback>,
+        output: ComputationOutputs<CheckDrugInteractionOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(CheckDrugInteractionOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(DrugInteractionCheckedEvent {
+            nonce: result.nonce.to_le_bytes(),
+            interacts: result.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    /// Registers the monitor allowed to report sightings of the caller's honeytoken canary.
+    ///
+    /// The canary is the optional last field of `store_patient_data`. Clients register its
+    /// plaintext with an off-chain monitoring service, whose key is set here.
+    ///
+    /// # Arguments
+    /// * `monitor` - Key of the monitoring service that reports canary hits
+    pub fn set_canary_monitor(ctx: Context<SetCanaryMonitor>, monitor: Pubkey) -> Result<()> {
+        let canary = &mut ctx.accounts.canary_status;
+        canary.patient_data = ctx.accounts.patient_data.key();
+        canary.monitor = monitor;
+        canary.bump = ctx.bumps.canary_status;
+
+        let inbox = &mut ctx.accounts.patient_inbox;
+        inbox.patient_data = canary.patient_data;
+        inbox.bump = ctx.bumps.patient_inbox;
+        Ok(())
+    }
+
+    /// Records that the patient's canary was seen, i.e. that the record was likely decrypted
+    /// by someone who should not have it.
+    ///
+    /// Callable by the registered monitor or the patient. Bumps the hit counter, notifies the
+    /// patient's inbox and emits `CanaryHitEvent`.
+    ///
+    /// # Arguments
+    /// * `evidence` - Hash of where the canary was observed, kept for later investigation
+    pub fn report_canary_hit(ctx: Context<ReportCanaryHit>, evidence: [u8; 32]) -> Result<()> {
+        let canary = &mut ctx.accounts.canary_status;
+        let reporter = ctx.accounts.reporter.key();
+        let (patient_owner, _) =
+            Pubkey::find_program_address(&[b"patient_data", reporter.as_ref()], &ID);
+        require!(
+            reporter == canary.monitor || patient_owner == canary.patient_data,
+            ErrorCode::Unauthorized
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        canary.hit_count = canary.hit_count.saturating_add(1);
+        canary.last_hit_at = now;
+        canary.last_evidence = evidence;
+
+        ctx.accounts.patient_inbox.push(Notification {
+            kind: NOTIFICATION_CANARY_HIT,
+            subject: reporter,
+            due_at: now,
+            created_at: now,
+        });
+
+        emit!(CanaryHitEvent {
+            patient_data: canary.patient_data,
+            reporter,
+            hit_count: canary.hit_count,
+            evidence,
+        });
+        Ok(())
+    }
+
+    pub fn init_check_trial_eligibility_comp_def(
+        ctx: Context<InitCheckTrialEligibilityCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Pre-screens the patient for a clinical trial without disclosing any PHI.
+    ///
+    /// The plaintext criteria are evaluated against the encrypted record inside MPC and only
+    /// an eligible / not eligible flag is encrypted for the trial sponsor's key (`receiver`)
+    /// and emitted in `TrialEligibilityCheckedEvent`. The check is logged with the research
+    /// purpose. Authorization, revocation, nonce and audit rules match `share_patient_data`.
+    ///
+    /// # Arguments
+    /// * `criteria` - Age range, required medical history and excluded medications
+    pub fn check_trial_eligibility(
+        ctx: Context<CheckTrialEligibility>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        criteria: TrialCriteria,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(
+            criteria.min_age <= criteria.max_age && criteria.required_history < 1 << 10,
+            ErrorCode::InvalidTrialCriteria
+        );
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_DEMOGRAPHICS | SECTION_MEDICATIONS | SECTION_HISTORY,
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let mut args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::PlaintextU8(criteria.min_age),
+            Argument::PlaintextU8(criteria.max_age),
+            Argument::PlaintextU16(criteria.required_history),
+        ];
+        args.extend(
+            criteria
+                .excluded_medications
+                .iter()
+                .map(|med| Argument::PlaintextU64(*med)),
+        );
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            PURPOSE_RESEARCH,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CheckTrialEligibilityCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_trial_eligibility")]
+    pub fn check_trial_eligibility_callback(
+        ctx: Context<CheckTrialEligibilityCallback>,
+        output: ComputationOutputs<CheckTrialEligibilityOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(CheckTrialEligibilityOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(TrialEligibilityCheckedEvent {
+            nonce: result.nonce.to_le_bytes(),
+            eligible: result.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    /// Sets, per data category, which roles may ever receive it through a role-gated share.
+    ///
+    /// Enforced by every role-gated instruction (`share_patient_data_with_role`, its wrappers
+    /// and credential-gated checks such as `check_drug_interaction`) on top of its credential
+    /// checks, for the categories that instruction reads; a full-record share needs the role
+    /// to be allowed for every category. Without a policy every role is allowed.
+    ///
+    /// # Arguments
+    /// * `allowed_roles` - For each `CATEGORY_*`, a bit set of allowed `ROLE_*` (bit = 1 << role)
+    pub fn set_patient_policy(
+        ctx: Context<SetPatientPolicy>,
+        allowed_roles: [u16; DATA_CATEGORIES],
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.patient_policy;
+        policy.patient_data = ctx.accounts.patient_data.key();
+        policy.allowed_roles = allowed_roles;
+        policy.bump = ctx.bumps.patient_policy;
+        Ok(())
+    }
+
+    /// Locks one section of the caller's record, making it immutable and unshareable.
+    ///
+    /// While a section is locked, every instruction that discloses it fails with
+    /// `SectionLocked`: full-record shares and their retries, role-gated shares whose
+    /// `section_mask` includes it, per-category, split-record and selected-field shares,
+    /// approved share requests covering it, shares of record entries, encounters, capacity
+    /// chunks, lab leaves and vitals (which count as lab tests), research exports, cohort
+    /// queries (demographics and history), and every check or proof computed from it:
+    /// credential-gated checks, cardio risk, trial eligibility, attribute thresholds,
+    /// vaccination proofs, genetic marker matches, claim consistency and coverage checks.
+    /// Storing the section, co-signing updates to it, updating immunizations, appending or
+    /// amending entries of its kind and writing its capacity chunks fail the same way.
+    /// Break-glass access is not affected.
+    ///
+    /// # Arguments
+    /// * `section` - A single `SECTION_*` bit
+    pub fn lock_section(ctx: Context<UpdateSectionLocks>, section: u8) -> Result<()> {
+        require!(section.count_ones() == 1, ErrorCode::InvalidSections);
+        let locks = &mut ctx.accounts.section_locks;
+        locks.patient_data = ctx.accounts.patient_data.key();
+        locks.locked |= section;
+        locks.bump = ctx.bumps.section_locks;
+
+        emit!(SectionLocksUpdatedEvent {
+            patient_data: locks.patient_data,
+            locked: locks.locked,
+        });
+        Ok(())
+    }
+
+    /// Unlocks a section locked with `lock_section`.
+    ///
+    /// # Arguments
+    /// * `section` - A single `SECTION_*` bit
+    pub fn unlock_section(ctx: Context<UpdateSectionLocks>, section: u8) -> Result<()> {
+        require!(section.count_ones() == 1, ErrorCode::InvalidSections);
+        let locks = &mut ctx.accounts.section_locks;
+        locks.patient_data = ctx.accounts.patient_data.key();
+        locks.locked &= !section;
+        locks.bump = ctx.bumps.section_locks;
+
+        emit!(SectionLocksUpdatedEvent {
+            patient_data: locks.patient_data,
+            locked: locks.locked,
+        });
+        Ok(())
+    }
+
+    pub fn init_check_allergy_conflict_comp_def(
+        ctx: Context<InitCheckAllergyConflictCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Checks a proposed prescription against the patient's allergies and medical history.
+    ///
+    /// Only a safe / unsafe flag is re-encrypted for the receiver (normally the prescriber)
+    /// and emitted in `PrescriptionSafetyEvent`, so e-prescribing front-ends can block
+    /// dangerous orders without reading the record. Requires a doctor credential NFT as in
+    /// `share_patient_data_with_role`; revocation, nonce and audit rules match
+    /// `share_patient_data`.
+    ///
+    /// # Arguments
+    /// * `prescription_code` - Code of the proposed drug
+    #[allow(clippy::too_many_arguments)]
+    pub fn check_allergy_conflict(
+        ctx: Context<CheckAllergyConflict>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        prescription_code: u64,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(ctx.accounts.credential_mint.decimals == 0, ErrorCode::InvalidCredentialMint);
+        require!(ctx.accounts.credential_token_account.amount >= 1, ErrorCode::MissingCredential);
+        check_credential_record(&ctx.accounts.credential_record)?;
+        check_credential_collection(
+           &ctx.accounts.role_config,
+           ctx.accounts.credential_metadata.as_deref(),
+           &ctx.accounts.credential_mint.key(),
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_ALLERGIES | SECTION_HISTORY,
+        )?;
+        check_patient_policy(
+            &ctx.accounts.patient_policy,
+            (1 << CATEGORY_DEMOGRAPHICS) | (1 << CATEGORY_HEALTHCARE),
+            ROLE_DOCTOR,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::PlaintextU64(prescription_code),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            ROLE_DOCTOR,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CheckAllergyConflictCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_allergy_conflict")]
+    pub fn check_allergy_conflict_callback(
+        ctx: Context<CheckAllergyConflictCallback>,
+        output: ComputationOutputs<CheckAllergyConflictOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(CheckAllergyConflictOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(PrescriptionSafetyEvent {
+            nonce: result.nonce.to_le_bytes(),
+            unsafe_flag: result.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    /// Creates the program config; the caller becomes its authority.
+    ///
+    /// Also creates the subsidy pool, with the public-good subsidy mode switched off.
+    pub fn init_program_config(ctx: Context<InitProgramConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.program_config;
+        config.authority = ctx.accounts.payer.key();
+        config.subsidy_enabled = false;
+        config.hardship_credential_mint = Pubkey::default();
+        config.subsidy_cap_per_wallet = 0;
+        config.require_soulbound_credentials = false;
+        config.protocol_fee_bps = 0;
+        config.fee_destination = ctx.accounts.payer.key();
+        config.paused = false;
+        config.max_records_per_patient = 0;
+        config.share_rate_limit = 0;
+        config.share_rate_window = 0;
+        config.retention_policy = RetentionPolicy {
+            default_retention: DEFAULT_SHARE_RETENTION,
+        };
+        config.min_cohort_size = DEFAULT_MIN_COHORT_SIZE;
+        config.bump = ctx.bumps.program_config;
+
+        let pool = &mut ctx.accounts.subsidy_pool;
+        pool.total_funded = 0;
+        pool.total_disbursed = 0;
+        pool.bump = ctx.bumps.subsidy_pool;
+        Ok(())
+    }
+
+    /// Turns the public-good subsidy mode on or off.
+    ///
+    /// While enabled, wallets holding the financial hardship attestation credential can claim
+    /// lamports from the subsidy pool to cover rent and computation fees, up to a per-wallet
+    /// lifetime cap. Only the config authority may call it.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether subsidies can be claimed
+    /// * `hardship_credential_mint` - Mint of the financial hardship attestation credential
+    /// * `subsidy_cap_per_wallet` - Maximum lamports a single wallet can ever claim
+    pub fn set_subsidy_mode(
+        ctx: Context<SetSubsidyMode>,
+        enabled: bool,
+        hardship_credential_mint: Pubkey,
+        subsidy_cap_per_wallet: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.program_config;
+        config.subsidy_enabled = enabled;
+        config.hardship_credential_mint = hardship_credential_mint;
+        config.subsidy_cap_per_wallet = subsidy_cap_per_wallet;
+        Ok(())
+    }
+
+    /// Creates or updates the `RoleConfig` of a credentialed role.
+    ///
+    /// A role can only be used by role-gated shares and provider registrations once its
+    /// config exists, so new roles need no program upgrade. Role-gated shares for the role
+    /// require the credential mint's metadata to list `collection` as a verified collection,
+    /// so self-minted NFTs no longer pass, and, if set, the credential to be `credential_mint`.
+    /// `Pubkey::default()` turns either check off. `section_mask` limits which record
+    /// sections the role receives, e.g. `SECTION_DEMOGRAPHICS | SECTION_ALLERGIES` for
+    /// nurses. Only the config authority may call it.
+    ///
+    /// # Arguments
+    /// * `role` - Role id; the patient and caregiver roles cannot be configured
+    /// * `collection` - Collection mint of the role's credentials
+    /// * `credential_mint` - Single credential mint accepted for the role
+    /// * `section_mask` - `SECTION_*` bits the role may receive
+    pub fn set_role_config(
+        ctx: Context<SetRoleConfig>,
+        role: u8,
+        collection: Pubkey,
+        credential_mint: Pubkey,
+        section_mask: u8,
+    ) -> Result<()> {
+        require!(
+            role < MAX_ROLES && role != ROLE_PATIENT && role != ROLE_CAREGIVER,
+            ErrorCode::InvalidRole
+        );
+        let role_config = &mut ctx.accounts.role_config;
+        role_config.role = role;
+        role_config.collection = collection;
+        role_config.credential_mint = credential_mint;
+        role_config.section_mask = section_mask;
+        role_config.bump = ctx.bumps.role_config;
+        Ok(())
+    }
+
+    /// Updates the protocol-wide parameters. Only the config authority may call it.
+    ///
+    /// # Arguments
+    /// * `protocol_fee_bps` - Share of subscription payments taken as protocol fee, in basis
+    ///   points
+    /// * `fee_destination` - Wallet receiving protocol fees
+    /// * `paused` - Whether shares are rejected with `ProgramPaused`
+    /// * `max_records_per_patient` - Maximum entries appended to a record; 0 is unlimited
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        protocol_fee_bps: u16,
+        fee_destination: Pubkey,
+        paused: bool,
+        max_records_per_patient: u64,
+    ) -> Result<()> {
+        require!(protocol_fee_bps <= MAX_BPS, ErrorCode::InvalidFee);
+        let config = &mut ctx.accounts.program_config;
+        config.protocol_fee_bps = protocol_fee_bps;
+        config.fee_destination = fee_destination;
+        config.paused = paused;
+        config.max_records_per_patient = max_records_per_patient;
+
+        emit!(ConfigUpdatedEvent {
+            protocol_fee_bps,
+            fee_destination,
+            paused,
+            max_records_per_patient,
+        });
+        Ok(())
+    }
+
+    /// Sets how many role-gated shares a patient record may receive per receiver and window,
+    /// which also bounds `check_donor_match` queries per coordinator credential. A limit of 0
+    /// disables the check. Only the config authority may call it.
+    ///
+    /// # Arguments
+    /// * `share_rate_limit` - Shares allowed per (patient, receiver) pair and window
+    /// * `share_rate_window` - Length of a window in seconds
+    pub fn set_share_rate_limit(
+        ctx: Context<UpdateConfig>,
+        share_rate_limit: u32,
+        share_rate_window: i64,
+    ) -> Result<()> {
+        apply_share_rate_limit(
+            &mut ctx.accounts.program_config,
+            share_rate_limit,
+            share_rate_window,
+        )
+    }
+
+    /// Sets the receiver jurisdictions records tagged with `jurisdiction` may be shared to,
+    /// creating the rule set on first use. An empty `allowed` list removes the rule. Only
+    /// the config authority may call it.
+    ///
+    /// # Arguments
+    /// * `jurisdiction` - Record jurisdiction the rule applies to
+    /// * `allowed` - Receiver jurisdictions shares may go to (up to 8)
+    pub fn set_jurisdiction_rule(
+        ctx: Context<SetJurisdictionRule>,
+        jurisdiction: u16,
+        allowed: Vec<u16>,
+    ) -> Result<()> {
+        require!(
+            jurisdiction != JURISDICTION_UNTAGGED && allowed.len() <= MAX_ALLOWED_JURISDICTIONS,
+            ErrorCode::InvalidJurisdictionRule
+        );
+
+        let rules = &mut ctx.accounts.jurisdiction_rules;
+        rules.bump = ctx.bumps.jurisdiction_rules;
+        rules.rules.retain(|rule| rule.jurisdiction != jurisdiction);
+        if !allowed.is_empty() {
+            require!(
+                rules.rules.len() < MAX_JURISDICTION_RULES,
+                ErrorCode::InvalidJurisdictionRule
+            );
+            rules.rules.push(JurisdictionRule {
+                jurisdiction,
+                allowed: allowed.clone(),
+            });
+        }
+
+        emit!(JurisdictionRuleEvent {
+            jurisdiction,
+            allowed,
+        });
+        Ok(())
+    }
+
+    /// Registers the jurisdiction a receiver key operates in, checked against data-residency
+    /// rules when records are shared to it. Only the config authority may call it.
+    ///
+    /// # Arguments
+    /// * `receiver` - x25519 public key of the receiver
+    /// * `jurisdiction` - Jurisdiction code of the receiver
+    pub fn register_receiver_jurisdiction(
+        ctx: Context<RegisterReceiverJurisdiction>,
+        receiver: [u8; 32],
+        jurisdiction: u16,
+    ) -> Result<()> {
+        let registration = &mut ctx.accounts.receiver_jurisdiction;
+        registration.receiver = receiver;
+        registration.jurisdiction = jurisdiction;
+        registration.bump = ctx.bumps.receiver_jurisdiction;
+        Ok(())
+    }
+
+    /// Sets how long shared records without their own `retain_until` are kept before
+    /// `expire_shared_record` may close them. Only the config authority may call it.
+    ///
+    /// # Arguments
+    /// * `default_retention` - Retention in seconds, or 0 to keep records until closed
+    pub fn set_retention_policy(ctx: Context<UpdateConfig>, default_retention: i64) -> Result<()> {
+        require!(default_retention >= 0, ErrorCode::InvalidPeriod);
+        ctx.accounts.program_config.retention_policy = RetentionPolicy { default_retention };
+        Ok(())
+    }
+
+    /// Sets the k of k-anonymity for `aggregate_cohort`: how many cohort members must match
+    /// a query before its statistics are released. Only the config authority may call it.
+    /// Members are chosen by the caller, so k only counts matching records and cannot stop
+    /// a researcher padding the cohort with records they already know.
+    ///
+    /// # Arguments
+    /// * `min_cohort_size` - Matching members required, between 1 and the cohort size
+    pub fn set_min_cohort_size(ctx: Context<UpdateConfig>, min_cohort_size: u8) -> Result<()> {
+        require!(
+            min_cohort_size >= 1 && min_cohort_size as usize <= COHORT_SIZE,
+            ErrorCode::InvalidMinCohortSize
+        );
+        ctx.accounts.program_config.min_cohort_size = min_cohort_size;
+        Ok(())
+    }
+
+    /// Pauses the program: every share, MPC computation over record data and
+    /// `store_patient_data` fail with `ProgramPaused` until `unpause`, while closes and
+    /// revocations keep working. Meant for key compromises or MPC cluster incidents. Only the
+    /// config authority may call it.
+    ///
+    /// Break-glass access (`execute_break_glass`, `execute_emergency_summary`,
+    /// `share_advance_directive`) keeps working so emergency care is not blocked, and so do
+    /// `rotate_record_encryption` and `rekey_patient_data`, which only re-encrypt a record to
+    /// its owner and are how patients respond to a leaked key.
+    pub fn pause(ctx: Context<UpdateConfig>) -> Result<()> {
+        ctx.accounts.program_config.paused = true;
+        emit!(PauseEvent { paused: true });
+        Ok(())
+    }
+
+    /// Lifts a `pause`. Only the config authority may call it.
+    pub fn unpause(ctx: Context<UpdateConfig>) -> Result<()> {
+        ctx.accounts.program_config.paused = false;
+        emit!(PauseEvent { paused: false });
+        Ok(())
+    }
+
+    /// Hands the config authority to an M-of-N governance of `members`.
+    ///
+    /// Afterwards config updates, pauses, role configs and the provider registrar can only
+    /// be changed by a proposal made with `propose_admin_action`, approved by `threshold`
+    /// members with `approve_action` and applied with `execute_action`. Only the config
+    /// authority may call it.
+    ///
+    /// # Arguments
+    /// * `members` - Keys allowed to propose and approve admin actions
+    /// * `threshold` - Approvals needed to execute a proposal
+    pub fn init_governance(
+        ctx: Context<InitGovernance>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(
+            !members.is_empty() && members.len() <= MAX_GOVERNANCE_MEMBERS,
+            ErrorCode::InvalidGovernance
+        );
+        require!(
+            threshold > 0 && threshold as usize <= members.len(),
+            ErrorCode::InvalidGovernance
+        );
+        for (i, member) in members.iter().enumerate() {
+            require!(!members[..i].contains(member), ErrorCode::InvalidGovernance);
+        }
+
+        ctx.accounts.governance.set_inner(GovernanceAccount {
+            members,
+            threshold,
+            proposal_count: 0,
+            bump: ctx.bumps.governance,
+        });
+        ctx.accounts.program_config.authority = ctx.accounts.governance.key();
+        Ok(())
+    }
+
+    /// Proposes an admin action; the proposer's approval is counted. Only governance members
+    /// may call it.
+    ///
+    /// # Arguments
+    /// * `action` - Action applied once the proposal reaches the threshold
+    pub fn propose_admin_action(
+        ctx: Context<ProposeAdminAction>,
+        action: AdminAction,
+    ) -> Result<()> {
+        let governance = &mut ctx.accounts.governance;
+        require!(
+            governance.members.contains(&ctx.accounts.payer.key()),
+            ErrorCode::NotGovernanceMember
+        );
+        let proposal_id = governance.proposal_count;
+        governance.proposal_count += 1;
+
+        ctx.accounts.admin_proposal.set_inner(AdminProposal {
+            proposal_id,
+            proposer: ctx.accounts.payer.key(),
+            action,
+            approvals: vec![ctx.accounts.payer.key()],
+            executed: false,
+            created_at: Clock::get()?.unix_timestamp,
+            bump: ctx.bumps.admin_proposal,
+        });
+
+        emit!(AdminProposalEvent {
+            proposal_id,
+            member: ctx.accounts.payer.key(),
+            approvals: 1,
+            executed: false,
+        });
+        Ok(())
+    }
+
+    /// Approves a pending admin proposal. Only governance members may call it, once each.
+    pub fn approve_action(ctx: Context<ApproveAction>) -> Result<()> {
+        let member = ctx.accounts.member.key();
+        require!(
+            ctx.accounts.governance.members.contains(&member),
+            ErrorCode::NotGovernanceMember
+        );
+        let proposal = &mut ctx.accounts.admin_proposal;
+        require!(!proposal.executed, ErrorCode::ProposalExecuted);
+        require!(!proposal.approvals.contains(&member), ErrorCode::AlreadyApproved);
+        proposal.approvals.push(member);
+
+        emit!(AdminProposalEvent {
+            proposal_id: proposal.proposal_id,
+            member,
+            approvals: proposal.approvals.len() as u8,
+            executed: false,
+        });
+        Ok(())
+    }
+
+    /// Applies an admin proposal approved by the governance threshold. Anyone may call it;
+    /// the caller pays for a role config created by the action.
+    ///
+    /// `SetRoleConfig` actions need the optional `role_config` account of the role and
+    /// `SetRegistrar` actions the optional `provider_registry`.
+    pub fn execute_action(ctx: Context<ExecuteAction>) -> Result<()> {
+        let proposal = &mut ctx.accounts.admin_proposal;
+        require!(!proposal.executed, ErrorCode::ProposalExecuted);
+        require!(
+            proposal.approvals.len() >= ctx.accounts.governance.threshold as usize,
+            ErrorCode::ThresholdNotReached
+        );
+        proposal.executed = true;
+
+        let config = &mut ctx.accounts.program_config;
+        match proposal.action.clone() {
+            AdminAction::UpdateConfig {
+                protocol_fee_bps,
+                fee_destination,
+                paused,
+                max_records_per_patient,
+            } => {
+                require!(protocol_fee_bps <= MAX_BPS, ErrorCode::InvalidFee);
+                config.protocol_fee_bps = protocol_fee_bps;
+                config.fee_destination = fee_destination;
+                config.paused = paused;
+                config.max_records_per_patient = max_records_per_patient;
+                emit!(ConfigUpdatedEvent {
+                    protocol_fee_bps,
+                    fee_destination,
+                    paused,
+                    max_records_per_patient,
+                });
+            }
+            AdminAction::SetPaused { paused } => {
+                config.paused = paused;
+                emit!(PauseEvent { paused });
+            }
+            AdminAction::SetRoleConfig {
+                role,
+                collection,
+                credential_mint,
+                section_mask,
+            } => {
+                require!(
+                    role < MAX_ROLES && role != ROLE_PATIENT && role != ROLE_CAREGIVER,
+                    ErrorCode::InvalidRole
+                );
+                let role_config = ctx
+                    .accounts
+                    .role_config
+                    .as_deref_mut()
+                    .ok_or(ErrorCode::InvalidRole)?;
+                role_config.role = role;
+                role_config.collection = collection;
+                role_config.credential_mint = credential_mint;
+                role_config.section_mask = section_mask;
+                role_config.bump = ctx.bumps.role_config.ok_or(ErrorCode::InvalidRole)?;
+            }
+            AdminAction::SetShareRateLimit {
+                share_rate_limit,
+                share_rate_window,
+            } => apply_share_rate_limit(config, share_rate_limit, share_rate_window)?,
+            AdminAction::SetRegistrar { registrar } => {
+                let registry = ctx
+                    .accounts
+                    .provider_registry
+                    .as_deref_mut()
+                    .ok_or(ErrorCode::ProviderNotRegistered)?;
+                registry.registrar = registrar;
+            }
+        }
+
+        emit!(AdminProposalEvent {
+            proposal_id: proposal.proposal_id,
+            member: ctx.accounts.payer.key(),
+            approvals: proposal.approvals.len() as u8,
+            executed: true,
+        });
+        Ok(())
+    }
+
+    /// Sets whether `share_patient_data_with_role` only accepts soulbound credentials, i.e.
+    /// Token-2022 mints with the NonTransferable extension. Only the config authority may
+    /// call it.
+    pub fn set_soulbound_credentials(
+        ctx: Context<SetSoulboundCredentials>,
+        required: bool,
+    ) -> Result<()> {
+        ctx.accounts.program_config.require_soulbound_credentials = required;
+        Ok(())
+    }
+
+    /// Adds lamports to the subsidy pool. Anyone may fund it.
+    pub fn fund_subsidy_pool(ctx: Context<FundSubsidyPool>, amount: u64) -> Result<()> {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.subsidy_pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        let pool = &mut ctx.accounts.subsidy_pool;
+        pool.total_funded = pool.total_funded.saturating_add(amount);
+        Ok(())
+    }
+
+    /// Pays a subsidy from the pool to a wallet holding the hardship credential.
+    ///
+    /// The wallet's running total is tracked in its `SubsidyUsage` PDA and may never exceed
+    /// the configured cap. The usage account is paid for by `payer`, which may be a relayer
+    /// so that a wallet without any SOL can still claim.
+    ///
+    /// # Arguments
+    /// * `amount` - Lamports to claim
+    pub fn claim_subsidy(ctx: Context<ClaimSubsidy>, amount: u64) -> Result<()> {
+        let config = &ctx.accounts.program_config;
+        require!(config.subsidy_enabled, ErrorCode::SubsidyDisabled);
+        require!(
+            ctx.accounts.credential_mint.decimals == 0,
+            ErrorCode::InvalidCredentialMint
+        );
+        require!(
+            ctx.accounts.credential_token_account.amount >= 1,
+            ErrorCode::MissingCredential
+        );
+
+        let usage = &mut ctx.accounts.subsidy_usage;
+        let claimed = usage.claimed.checked_add(amount).ok_or(ErrorCode::SubsidyCapExceeded)?;
+        require!(
+            claimed <= config.subsidy_cap_per_wallet,
+            ErrorCode::SubsidyCapExceeded
+        );
+
+        let pool_info = ctx.accounts.subsidy_pool.to_account_info();
+        let rent_floor = Rent::get()?.minimum_balance(pool_info.data_len());
+        require!(
+            pool_info.lamports().saturating_sub(amount) >= rent_floor,
+            ErrorCode::InsufficientSubsidyPool
+        );
+        **pool_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.wallet.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        usage.wallet = ctx.accounts.wallet.key();
+        usage.claimed = claimed;
+        usage.bump = ctx.bumps.subsidy_usage;
+        let pool = &mut ctx.accounts.subsidy_pool;
+        pool.total_disbursed = pool.total_disbursed.saturating_add(amount);
+
+        emit!(SubsidyClaimedEvent {
+            wallet: usage.wallet,
+            amount,
+            claimed,
+        });
+        Ok(())
+    }
+
+    pub fn init_check_blood_compatibility_comp_def(
+        ctx: Context<InitCheckBloodCompatibilityCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Checks whether the patient can donate blood to a recipient, for transfusion and organ
+    /// donor workflows.
+    ///
+    /// The recipient's blood type is encrypted under the receiver's key (the requesting
+    /// hospital) and compared with the patient's stored blood type inside MPC; only a
+    /// compatible / incompatible flag is re-encrypted for the receiver and emitted in
+    /// `BloodCompatibilityCheckedEvent`. Neither blood type is revealed. Requires a provider
+    /// credential NFT as in `share_patient_data_with_role`; revocation, nonce and audit rules
+    /// match `share_patient_data`.
+    ///
+    /// # Arguments
+    /// * `recipient_nonce` - Nonce the recipient blood type was encrypted with; must not be
+    ///   one of the latest used for this patient and receiver
+    /// * `recipient_blood_type_ct` - Encrypted recipient blood type
+    #[allow(clippy::too_many_arguments)]
+    pub fn check_blood_compatibility(
+        ctx: Context<CheckBloodCompatibility>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        recipient_nonce: u128,
+        recipient_blood_type_ct: [u8; 32],
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(ctx.accounts.credential_mint.decimals == 0, ErrorCode::InvalidCredentialMint);
+        require!(ctx.accounts.credential_token_account.amount >= 1, ErrorCode::MissingCredential);
+        check_credential_record(&ctx.accounts.credential_record)?;
+        check_credential_collection(
+           &ctx.accounts.role_config,
+           ctx.accounts.credential_metadata.as_deref(),
+           &ctx.accounts.credential_mint.key(),
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_DEMOGRAPHICS)?;
+        check_patient_policy(
+            &ctx.accounts.patient_policy,
+            1 << CATEGORY_DEMOGRAPHICS,
+            ROLE_PROVIDER,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+        ctx.accounts.share_nonce.consume_input_nonce(recipient_nonce)?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(recipient_nonce),
+            Argument::EncryptedU8(recipient_blood_type_ct),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            ROLE_PROVIDER,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CheckBloodCompatibilityCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_blood_compatibility")]
+    pub fn check_blood_compatibility_callback(
+        ctx: Context<CheckBloodCompatibilityCallback>,
+        output: ComputationOutputs<CheckBloodCompatibilityOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(CheckBloodCompatibilityOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(BloodCompatibilityCheckedEvent {
+            nonce: result.nonce.to_le_bytes(),
+            compatible: result.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    /// Opts the caller's record into periodic re-encryption, or updates the policy.
+    ///
+    /// The record is re-encrypted by `rotate_record_encryption` every `interval_epochs`
+    /// epochs, limiting what a compromised key or nonce can expose over time. Passing
+    /// `interval_epochs = 0` opts out.
+    ///
+    /// # Arguments
+    /// * `interval_epochs` - Epochs between re-encryptions
+    /// * `encryption_key` - x25519 public key the stored record is currently encrypted with
+    /// * `encryption_nonce` - Nonce the stored record is currently encrypted with
+    /// * `next_encryption_key` - Key to re-encrypt to; the current key to only refresh nonces
+    pub fn set_rotation_policy(
+        ctx: Context<SetRotationPolicy>,
+        interval_epochs: u64,
+        encryption_key: [u8; 32],
+        encryption_nonce: u128,
+        next_encryption_key: [u8; 32],
+    ) -> Result<()> {
+        let rotation = &mut ctx.accounts.record_metadata.rotation;
+        require!(
+            rotation.pending_since_epoch.is_none(),
+            ErrorCode::RotationPending
+        );
+        rotation.interval_epochs = interval_epochs;
+        rotation.encryption_key = encryption_key;
+        rotation.encryption_nonce = encryption_nonce;
+        rotation.next_encryption_key = next_encryption_key;
+        rotation.last_rotated_epoch = Clock::get()?.epoch;
+        Ok(())
+    }
+
+    pub fn init_rotate_patient_data_comp_def(
+        ctx: Context<InitRotatePatientDataCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Permissionless crank re-encrypting a record whose rotation is due.
+    ///
+    /// Queues `rotate_patient_data`, which re-encrypts the record under the policy's next key
+    /// with a fresh nonce derived on-chain. The callback writes the new ciphertexts over the
+    /// record and appends the rotation to the history in `RecordMetadata`, where clients read
+    /// the key and nonce to use for later shares. A rotation whose callback never arrived can
+    /// be re-queued after one epoch.
+    pub fn rotate_record_encryption(
+        ctx: Context<RotateRecordEncryption>,
+        computation_offset: u64,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        let epoch = Clock::get()?.epoch;
+        let patient_data = ctx.accounts.patient_data.key();
+        let rotation = &mut ctx.accounts.record_metadata.rotation;
+        require!(rotation.interval_epochs > 0, ErrorCode::RotationNotDue);
+        require!(
+            epoch >= rotation.last_rotated_epoch.saturating_add(rotation.interval_epochs),
+            ErrorCode::RotationNotDue
+        );
+        if let Some(pending_since) = rotation.pending_since_epoch {
+            require!(epoch > pending_since.saturating_add(1), ErrorCode::RotationPending);
+        }
+        rotation.pending_since_epoch = Some(epoch);
+
+        let output_nonce = rotation_nonce(&patient_data, epoch, rotation.history.len());
+        let args = vec![
+            Argument::ArcisPubkey(rotation.next_encryption_key),
+            Argument::PlaintextU128(output_nonce),
+            Argument::ArcisPubkey(rotation.encryption_key),
+            Argument::PlaintextU128(rotation.encryption_nonce),
+            Argument::Account(patient_data, 8, PATIENT_CIPHERTEXTS_LEN),
+        ];
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let record_metadata = ctx.accounts.record_metadata.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RotatePatientDataCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: patient_data,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: record_metadata,
+                    is_writable: true,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    /// Writes a re-encrypted record back and records the rotation.
+    #[arcium_callback(encrypted_ix = "rotate_patient_data")]
+    pub fn rotate_patient_data_callback(
+        ctx: Context<RotatePatientDataCallback>,
+        output: ComputationOutputs<RotatePatientDataOutput>,
+    ) -> Result<()> {
+        let rotated = match output {
+            ComputationOutputs::Success(RotatePatientDataOutput { field_0 }) => Box::new(field_0),
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let encryption_key = ctx.accounts.record_metadata.rotation.next_encryption_key;
+        write_reencrypted_record(
+            &ctx.accounts.patient_data,
+            &mut ctx.accounts.record_metadata,
+            &rotated.ciphertexts,
+            encryption_key,
+            rotated.nonce,
+        )
+    }
+
+    pub fn init_rekey_patient_data_comp_def(
+        ctx: Context<InitRekeyPatientDataCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Re-encrypts the caller's whole record to a new owner key inside MPC, e.g. after the
+    /// x25519 key leaked or the patient moved to a new wallet.
+    ///
+    /// The callback overwrites the `PatientData` ciphertexts and records the new key and an
+    /// on-chain derived nonce in the rotation state of `RecordMetadata`, which later
+    /// rotations continue from. Fails with `RotationPending` while a rotation is in flight.
+    ///
+    /// # Arguments
+    /// * `encryption_key` - x25519 public key the stored record is currently encrypted with
+    /// * `encryption_nonce` - Nonce the stored record is currently encrypted with
+    /// * `new_encryption_key` - x25519 public key to re-encrypt the record to
+    pub fn rekey_patient_data(
+        ctx: Context<RekeyPatientData>,
+        computation_offset: u64,
+        encryption_key: [u8; 32],
+        encryption_nonce: u128,
+        new_encryption_key: [u8; 32],
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        let epoch = Clock::get()?.epoch;
+        let patient_data = ctx.accounts.patient_data.key();
+        let rotation = &mut ctx.accounts.record_metadata.rotation;
+        require!(
+            rotation.pending_since_epoch.is_none(),
+            ErrorCode::RotationPending
+        );
+        rotation.pending_since_epoch = Some(epoch);
+        rotation.next_encryption_key = new_encryption_key;
+
+        let output_nonce = rotation_nonce(&patient_data, epoch, rotation.history.len());
+        let args = vec![
+            Argument::ArcisPubkey(new_encryption_key),
+            Argument::PlaintextU128(output_nonce),
+            Argument::ArcisPubkey(encryption_key),
+            Argument::PlaintextU128(encryption_nonce),
+            Argument::Account(patient_data, 8, PATIENT_CIPHERTEXTS_LEN),
+        ];
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let record_metadata = ctx.accounts.record_metadata.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RekeyPatientDataCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: patient_data,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: record_metadata,
+                    is_writable: true,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    /// Writes a rekeyed record back and records its new key.
+    #[arcium_callback(encrypted_ix = "rekey_patient_data")]
+    pub fn rekey_patient_data_callback(
+        ctx: Context<RekeyPatientDataCallback>,
+        output: ComputationOutputs<RekeyPatientDataOutput>,
+    ) -> Result<()> {
+        let rekeyed = match output {
+            ComputationOutputs::Success(RekeyPatientDataOutput { field_0 }) => Box::new(field_0),
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let encryption_key = ctx.accounts.record_metadata.rotation.next_encryption_key;
+        write_reencrypted_record(
+            &ctx.accounts.patient_data,
+            &mut ctx.accounts.record_metadata,
+            &rekeyed.ciphertexts,
+            encryption_key,
+            rekeyed.nonce,
+        )
+    }
+
+    /// Registers (or replaces) the guardians that can recover the caller's record if their
+    /// wallet key is lost, cancelling any pending recovery.
+    ///
+    /// # Arguments
+    /// * `guardians` - Guardian public keys (up to 8)
+    /// * `threshold` - Number of guardians that must co-sign `initiate_recovery`
+    /// * `delay_seconds` - Time between initiation and completion, during which the patient
+    ///   can still `cancel_recovery`
+    pub fn set_recovery_policy(
+        ctx: Context<SetRecoveryPolicy>,
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+        delay_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            !guardians.is_empty() && guardians.len() <= MAX_RECOVERY_GUARDIANS,
+            ErrorCode::InvalidRecoveryPolicy
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= guardians.len(),
+            ErrorCode::InvalidRecoveryPolicy
+        );
+        require!(delay_seconds > 0, ErrorCode::InvalidRecoveryPolicy);
+
+        ctx.accounts.recovery_policy.set_inner(RecoveryPolicy {
+            patient_data: ctx.accounts.patient_data.key(),
+            guardians,
+            threshold,
+            delay_seconds,
+            new_owner: Pubkey::default(),
+            new_encryption_key: [0u8; 32],
+            encryption_key: [0u8; 32],
+            encryption_nonce: 0,
+            initiated_at: 0,
+            bump: ctx.bumps.recovery_policy,
+        });
+        Ok(())
+    }
+
+    /// Starts moving a record to `new_owner` after the patient lost their key.
+    ///
+    /// At least `threshold` distinct guardians must sign the transaction, as the payer or as
+    /// signer `remaining_accounts`. The recovery can be completed with `complete_recovery`
+    /// once the policy delay has elapsed, unless the patient cancels it first.
+    ///
+    /// # Arguments
+    /// * `new_owner` - Wallet the record is moved to
+    /// * `new_encryption_key` - x25519 public key of the new owner to re-encrypt the record to
+    /// * `encryption_key` - x25519 public key the stored record is currently encrypted with
+    /// * `encryption_nonce` - Nonce the stored record is currently encrypted with
+    pub fn initiate_recovery<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitiateRecovery<'info>>,
+        new_owner: Pubkey,
+        new_encryption_key: [u8; 32],
+        encryption_key: [u8; 32],
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.recovery_policy;
+        require!(policy.initiated_at == 0, ErrorCode::RecoveryPending);
+
+        let mut signers: Vec<Pubkey> = Vec::with_capacity(policy.guardians.len());
+        let payer = ctx.accounts.payer.to_account_info();
+        for info in std::iter::once(&payer).chain(ctx.remaining_accounts.iter()) {
+            let is_guardian = policy.guardians.contains(info.key);
+            if info.is_signer && is_guardian && !signers.contains(info.key) {
+                signers.push(*info.key);
+            }
+        }
+        require!(
+            signers.len() >= policy.threshold as usize,
+            ErrorCode::NotEnoughGuardians
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        policy.new_owner = new_owner;
+        policy.new_encryption_key = new_encryption_key;
+        policy.encryption_key = encryption_key;
+        policy.encryption_nonce = encryption_nonce;
+        policy.initiated_at = now;
+
+        emit!(RecoveryEvent {
+            patient_data: policy.patient_data,
+            new_owner,
+            executable_at: now.saturating_add(policy.delay_seconds),
+            completed: false,
+        });
+        Ok(())
+    }
+
+    /// Cancels a pending recovery of the caller's record.
+    pub fn cancel_recovery(ctx: Context<CancelRecovery>) -> Result<()> {
+        let policy = &mut ctx.accounts.recovery_policy;
+        require!(policy.initiated_at != 0, ErrorCode::NoPendingRecovery);
+        policy.initiated_at = 0;
+        policy.new_owner = Pubkey::default();
+        Ok(())
+    }
+
+    /// Completes a recovery once its delay has elapsed. Signed by the new owner.
+    ///
+    /// The record and its metadata move to the `patient_data` PDA of the new owner and the
+    /// old accounts and the recovery policy are closed to them. `rekey_patient_data` is
+    /// queued to re-encrypt the moved record to the new owner's key; its callback records
+    /// the new key in the record metadata. Entries, consents and other accounts derived from
+    /// the old record are not moved.
+    pub fn complete_recovery(
+        ctx: Context<CompleteRecovery>,
+        computation_offset: u64,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let policy = &ctx.accounts.recovery_policy;
+        require!(policy.initiated_at != 0, ErrorCode::NoPendingRecovery);
+        let executable_at = policy.initiated_at.saturating_add(policy.delay_seconds);
+        require!(
+            Clock::get()?.unix_timestamp >= executable_at,
+            ErrorCode::RecoveryDelayNotElapsed
+        );
+
+        drop(ctx.accounts.patient_data.load_init()?);
+        copy_patient_record(
+            &ctx.accounts.old_patient_data.to_account_info(),
+            &ctx.accounts.patient_data.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let epoch = Clock::get()?.epoch;
+        let patient_data = ctx.accounts.patient_data.key();
+        let old_metadata = &ctx.accounts.old_record_metadata;
+        let mut rotation = old_metadata.rotation.clone();
+        rotation.encryption_key = policy.encryption_key;
+        rotation.encryption_nonce = policy.encryption_nonce;
+        rotation.next_encryption_key = policy.new_encryption_key;
+        rotation.pending_since_epoch = Some(epoch);
+        let output_nonce = rotation_nonce(&patient_data, epoch, rotation.history.len());
+        ctx.accounts.record_metadata.set_inner(RecordMetadata {
+            patient_data,
+            created_by: old_metadata.created_by.clone(),
+            last_written_by: old_metadata.last_written_by.clone(),
+            last_written_at: old_metadata.last_written_at,
+            entry_count: 0,
+            rotation,
+            bump: ctx.bumps.record_metadata,
+        });
+
+        let args = vec![
+            Argument::ArcisPubkey(policy.new_encryption_key),
+            Argument::PlaintextU128(output_nonce),
+            Argument::ArcisPubkey(policy.encryption_key),
+            Argument::PlaintextU128(policy.encryption_nonce),
+            Argument::Account(patient_data, 8, PATIENT_CIPHERTEXTS_LEN),
+        ];
+
+        emit!(RecoveryEvent {
+            patient_data: policy.patient_data,
+            new_owner: ctx.accounts.payer.key(),
+            executable_at,
+            completed: true,
+        });
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let record_metadata = ctx.accounts.record_metadata.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RekeyPatientDataCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: patient_data,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: record_metadata,
+                    is_writable: true,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    /// Hands a pediatric record over to the patient once they come of age. Signed by both
+    /// the guardian and the patient.
+    ///
+    /// The record and its metadata move to the patient's own `patient_data` PDA; the old
+    /// accounts, the `Custody` account and the guardian's delegation are closed to the
+    /// guardian, ending their authority. `rekey_patient_data` is queued to re-encrypt the
+    /// record to the patient's key; its callback records the new key in the record metadata.
+    /// Entries, consents and other accounts derived from the old record are not moved.
+    ///
+    /// # Arguments
+    /// * `encryption_key` - x25519 public key the record is currently encrypted with
+    /// * `encryption_nonce` - Nonce the record is currently encrypted with
+    /// * `new_encryption_key` - x25519 public key of the patient to re-encrypt the record for
+    pub fn transfer_custody_to_patient(
+        ctx: Context<TransferCustodyToPatient>,
+        computation_offset: u64,
+        encryption_key: [u8; 32],
+        encryption_nonce: u128,
+        new_encryption_key: [u8; 32],
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let custody = &ctx.accounts.custody;
+        require!(
+            Clock::get()?.unix_timestamp >= custody.adult_at,
+            ErrorCode::PatientNotOfAge
+        );
+
+        drop(ctx.accounts.patient_data.load_init()?);
+        copy_patient_record(
+            &ctx.accounts.old_patient_data.to_account_info(),
+            &ctx.accounts.patient_data.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let epoch = Clock::get()?.epoch;
+        let patient_data = ctx.accounts.patient_data.key();
+        let old_metadata = &ctx.accounts.old_record_metadata;
+        let mut rotation = old_metadata.rotation.clone();
+        rotation.encryption_key = encryption_key;
+        rotation.encryption_nonce = encryption_nonce;
+        rotation.next_encryption_key = new_encryption_key;
+        rotation.pending_since_epoch = Some(epoch);
+        let output_nonce = rotation_nonce(&patient_data, epoch, rotation.history.len());
+        ctx.accounts.record_metadata.set_inner(RecordMetadata {
+            patient_data,
+            created_by: old_metadata.created_by.clone(),
+            last_written_by: old_metadata.last_written_by.clone(),
+            last_written_at: old_metadata.last_written_at,
+            entry_count: 0,
+            rotation,
+            bump: ctx.bumps.record_metadata,
+        });
+
+        let args = vec![
+            Argument::ArcisPubkey(new_encryption_key),
+            Argument::PlaintextU128(output_nonce),
+            Argument::ArcisPubkey(encryption_key),
+            Argument::PlaintextU128(encryption_nonce),
+            Argument::Account(patient_data, 8, PATIENT_CIPHERTEXTS_LEN),
+        ];
+
+        emit!(CustodyTransferredEvent {
+            old_patient_data: custody.patient_data,
+            patient_data,
+            guardian: custody.guardian,
+            patient: ctx.accounts.payer.key(),
+        });
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let record_metadata = ctx.accounts.record_metadata.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RekeyPatientDataCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: patient_data,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: record_metadata,
+                    is_writable: true,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    pub fn init_recommend_dose_comp_def(ctx: Context<InitRecommendDoseCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Recommends a dose of a drug adjusted to the patient's pharmacogenomic markers.
+    ///
+    /// Warfarin sensitivity, clopidogrel resistance and statin response are applied to
+    /// `base_dose` inside the MPC network, and only the adjusted dose is re-encrypted for the
+    /// receiver (normally the prescriber) and emitted in `DoseRecommendedEvent`. The dose is
+    /// rounded down to quarters of `base_dose`; 0 means the drug should be replaced. Requires
+    /// a doctor credential NFT as in `share_patient_data_with_role`; revocation, nonce and
+    /// audit rules match `share_patient_data`.
+    ///
+    /// # Arguments
+    /// * `drug_id` - Medication id of the drug to dose
+    /// * `base_dose` - Standard dose of the drug, in the prescriber's units
+    #[allow(clippy::too_many_arguments)]
+    pub fn recommend_dose(
+        ctx: Context<RecommendDose>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        drug_id: u64,
+        base_dose: u64,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(ctx.accounts.credential_mint.decimals == 0, ErrorCode::InvalidCredentialMint);
+        require!(ctx.accounts.credential_token_account.amount >= 1, ErrorCode::MissingCredential);
+        check_credential_record(&ctx.accounts.credential_record)?;
+        check_credential_collection(
+           &ctx.accounts.role_config,
+           ctx.accounts.credential_metadata.as_deref(),
+           &ctx.accounts.credential_mint.key(),
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_GENOMICS)?;
+        check_patient_policy(&ctx.accounts.patient_policy, 1 << CATEGORY_GENOMICS, ROLE_DOCTOR)?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::PlaintextU64(drug_id),
+            Argument::PlaintextU64(base_dose),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            ROLE_DOCTOR,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RecommendDoseCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "recommend_dose")]
+    pub fn recommend_dose_callback(
+        ctx: Context<RecommendDoseCallback>,
+        output: ComputationOutputs<RecommendDoseOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(RecommendDoseOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(DoseRecommendedEvent {
+            nonce: result.nonce.to_le_bytes(),
+            dose: result.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    pub fn init_prove_attribute_threshold_comp_def(
+        ctx: Context<InitProveAttributeThresholdCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Proves to a receiver whether a record attribute reaches a threshold.
+    ///
+    /// The MPC network evaluates `attribute >= threshold` and only the boolean is re-encrypted
+    /// for the receiver and emitted in `AttributeThresholdProvedEvent`, so services that need
+    /// a yes/no answer ("age ≥ 18", "BMI < 30" as the negation of "BMI ≥ 30") never see the
+    /// value itself. Authorization, revocation, nonce and audit rules match
+    /// `share_patient_data`.
+    ///
+    /// # Arguments
+    /// * `attribute_selector` - One of the `ATTRIBUTE_*` constants
+    /// * `threshold` - Inclusive lower bound, in the attribute's units
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_attribute_threshold(
+        ctx: Context<ProveAttributeThreshold>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        attribute_selector: u8,
+        threshold: u16,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(
+            attribute_selector <= ATTRIBUTE_BMI,
+            ErrorCode::InvalidAttributeSelector
+        );
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_DEMOGRAPHICS)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::PlaintextU8(attribute_selector),
+            Argument::PlaintextU16(threshold),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ProveAttributeThresholdCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "prove_attribute_threshold")]
+    pub fn prove_attribute_threshold_callback(
+        ctx: Context<ProveAttributeThresholdCallback>,
+        output: ComputationOutputs<ProveAttributeThresholdOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(ProveAttributeThresholdOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(AttributeThresholdProvedEvent {
+            nonce: result.nonce.to_le_bytes(),
+            at_least: result.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    /// Replaces the immunization section of a patient record.
+    ///
+    /// The patient may update their own record; providers (e.g. the vaccinating clinic) need a
+    /// credential NFT as for `append_entry`. The ciphertexts must be encrypted with the
+    /// record's key and nonce, in `PatientData` field order.
+    ///
+    /// # Arguments
+    /// * `role` - Role the author writes under (`ROLE_*`)
+    /// * `ciphertexts` - immunization_count, vaccine_codes, vaccination_dates, vaccine_doses
+    pub fn update_immunizations(
+        ctx: Context<UpdateImmunizations>,
+        role: u8,
+        ciphertexts: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(
+            ciphertexts.len() == IMMUNIZATION_FIELDS,
+            ErrorCode::InvalidInputLength
+        );
+        let author = resolve_author(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            role,
+            ctx.accounts.credential_mint.as_ref(),
+            ctx.accounts.credential_token_account.as_ref(),
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_IMMUNIZATIONS)?;
+
+        write_immunizations(&mut *ctx.accounts.patient_data.load_mut()?, &ciphertexts);
+
+        let metadata = &mut ctx.accounts.record_metadata;
+        metadata.last_written_by = author.clone();
+        metadata.last_written_at = Clock::get()?.unix_timestamp;
+
+        emit!(ImmunizationsUpdatedEvent {
+            patient_data: metadata.patient_data,
+            author,
+        });
+        emit!(PatientDataUpdatedEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            patient_data: metadata.patient_data,
+            updated_at: metadata.last_written_at,
+        });
+        Ok(())
+    }
+
+    pub fn init_prove_vaccination_comp_def(
+        ctx: Context<InitProveVaccinationCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Proves to a receiver whether the patient holds a valid record for a vaccine.
+    ///
+    /// Only the immunization section is passed to the `prove_vaccination` circuit, and only a
+    /// vaccinated / not vaccinated flag is re-encrypted for the receiver and emitted in
+    /// `VaccinationProvedEvent`. Authorization, revocation, nonce and audit rules match
+    /// `share_patient_data`.
+    ///
+    /// # Arguments
+    /// * `vaccine_code` - Code of the vaccine to prove
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_vaccination(
+        ctx: Context<ProveVaccination>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        vaccine_code: u64,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_IMMUNIZATIONS)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let (offset, length) = section_range(
+            offset_of!(PatientData, immunization_count),
+            Some(offset_of!(PatientData, icd10_codes)),
+        );
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), offset, length),
+            Argument::PlaintextU64(vaccine_code),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ProveVaccinationCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "prove_vaccination")]
+    pub fn prove_vaccination_callback(
+        ctx: Context<ProveVaccinationCallback>,
+        output: ComputationOutputs<ProveVaccinationOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(ProveVaccinationOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(VaccinationProvedEvent {
+            nonce: result.nonce.to_le_bytes(),
+            vaccinated: result.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    /// Publishes or updates the caller's receiver profile.
+    ///
+    /// The profile tells clients, before they share, which circuits the receiver can decrypt
+    /// outputs of, the Arcis key to encrypt for, the largest record it accepts and how long it
+    /// keeps data, so a share request can be validated without a failed computation.
+    ///
+    /// # Arguments
+    /// * `arcis_key` - x25519 public key shares should be encrypted for
+    /// * `supported_circuits` - Computation definition offsets the receiver supports (up to 16)
+    /// * `max_payload_fields` - Largest number of ciphertexts the receiver accepts in one share
+    /// * `preferred_categories` - Bit set of `CATEGORY_*` the receiver wants to receive
+    /// * `retention_policy_hash` - Hash of the receiver's published data retention policy
+    pub fn publish_receiver_profile(
+        ctx: Context<PublishReceiverProfile>,
+        arcis_key: [u8; 32],
+        supported_circuits: Vec<u32>,
+        max_payload_fields: u16,
+        preferred_categories: u8,
+        retention_policy_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            supported_circuits.len() <= MAX_PROFILE_CIRCUITS,
+            ErrorCode::TooManyCircuits
+        );
+        require!(
+            preferred_categories <= ALL_DATA_CATEGORIES,
+            ErrorCode::InvalidCategories
+        );
+
+        let profile = &mut ctx.accounts.receiver_profile;
+        profile.authority = ctx.accounts.authority.key();
+        profile.arcis_key = arcis_key;
+        profile.supported_circuits = supported_circuits;
+        profile.max_payload_fields = max_payload_fields;
+        profile.preferred_categories = preferred_categories;
+        profile.retention_policy_hash = retention_policy_hash;
+        profile.updated_at = Clock::get()?.unix_timestamp;
+        profile.bump = ctx.bumps.receiver_profile;
+
+        emit!(ReceiverProfilePublishedEvent {
+            receiver_profile: profile.key(),
+            authority: profile.authority,
+            arcis_key,
+        });
+        Ok(())
+    }
+
+    /// Removes the caller's receiver profile and refunds its rent.
+    pub fn close_receiver_profile(_ctx: Context<CloseReceiverProfile>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Opts the caller's record in or out of research use.
+    ///
+    /// Until `expires_at`, researchers may use the record in the research instructions
+    /// covered by `scopes`: the cohort aggregations need `RESEARCH_SCOPE_CLINICAL`,
+    /// `match_genetic_markers` needs `RESEARCH_SCOPE_GENOMIC` and `pseudonymize_for_research`
+    /// needs every scope; they fail with `ResearchConsentMissing` otherwise. The key and
+    /// nonce the record is encrypted with are registered here so the MPC network can read it.
+    ///
+    /// # Arguments
+    /// * `opted_in` - Whether the record may be used for research
+    /// * `scopes` - `RESEARCH_SCOPE_*` bits the consent covers
+    /// * `expires_at` - Unix timestamp the consent lapses at
+    /// * `encryption_key` - x25519 public key the stored record is encrypted with
+    /// * `encryption_nonce` - Nonce the stored record is encrypted with
+    pub fn set_research_consent(
+        ctx: Context<SetResearchConsent>,
+        opted_in: bool,
+        scopes: u8,
+        expires_at: i64,
+        encryption_key: [u8; 32],
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        if opted_in {
+            require!(
+                scopes != 0 && scopes & !RESEARCH_SCOPE_ALL == 0,
+                ErrorCode::InvalidResearchConsent
+            );
+            require!(
+                expires_at > Clock::get()?.unix_timestamp,
+                ErrorCode::InvalidResearchConsent
+            );
+        }
+        let consent = &mut ctx.accounts.research_consent;
+        consent.patient_data = ctx.accounts.patient_data.key();
+        consent.opted_in = opted_in;
+        consent.scopes = scopes;
+        consent.expires_at = expires_at;
+        consent.encryption_key = encryption_key;
+        consent.encryption_nonce = encryption_nonce;
+        consent.bump = ctx.bumps.research_consent;
+
+        let budget = &mut ctx.accounts.privacy_budget;
+        budget.patient_data = consent.patient_data;
+        budget.bump = ctx.bumps.privacy_budget;
+
+        let balance = &mut ctx.accounts.royalty_balance;
+        balance.patient_data = consent.patient_data;
+        balance.bump = ctx.bumps.royalty_balance;
+        Ok(())
+    }
+
+    pub fn init_aggregate_cohort_comp_def(ctx: Context<InitAggregateCohortCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Computes aggregate statistics over a cohort of consenting patients for a researcher.
+    ///
+    /// The MPC network reads the records of the cohort and only the prevalence of each
+    /// medical history flag and the mean age of the members matching the query are
+    /// encrypted for `researcher` and emitted in `CohortAggregatedEvent`; no individual
+    /// record is disclosed. When fewer members than the configured `min_cohort_size` match,
+    /// the statistics are zeroed and the encrypted `insufficient_cohort` flag is set instead,
+    /// so a narrow query cannot single out one patient.
+    ///
+    /// The caller picks the members, so the threshold does not hold against a researcher who
+    /// fills the cohort with records they control or already know: with the other members'
+    /// values known, the aggregate still reveals the remaining patient. Use
+    /// `aggregate_cohort_dp` where that matters.
+    ///
+    /// `remaining_accounts` must contain, for each of the 5 cohort members in order, its
+    /// `PatientData`, `ResearchConsent` and `SectionLocks` accounts (the latter may be
+    /// uninitialized), and every member must be opted in, appear only once and not have
+    /// locked its demographics or medical history.
+    ///
+    /// Passing a `RoyaltyPool` funded for `computation_offset` records the cohort in it so
+    /// the members are paid by `distribute_royalties` once the computation completes.
+    /// Queries bought from a dataset listing pass their `QueryPurchase`, which is consumed
+    /// and requires the pool and a query matching the listing's criteria.
+    ///
+    /// # Arguments
+    /// * `researcher` - x25519 public key the statistics are encrypted for
+    /// * `researcher_nonce` - Nonce for the researcher's encryption
+    /// * `condition` - Medical history flag members must have, or `ANY_CONDITION`
+    /// * `min_age` / `max_age` - Inclusive age range members must be in
+    #[allow(clippy::too_many_arguments)]
+    pub fn aggregate_cohort<'info>(
+        ctx: Context<'_, '_, '_, 'info, AggregateCohort<'info>>,
+        computation_offset: u64,
+        researcher: [u8; 32],
+        researcher_nonce: u128,
+        condition: u8,
+        min_age: u8,
+        max_age: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(
+            ctx.remaining_accounts.len() == COHORT_SIZE * 3,
+            ErrorCode::InvalidCohort
+        );
+        require!(
+            (condition < 10 || condition == ANY_CONDITION) && min_age <= max_age,
+            ErrorCode::InvalidCohortQuery
+        );
+        let min_cohort_size = min_cohort_size(&ctx.accounts.program_config)?;
+        if let Some(purchase) = ctx.accounts.query_purchase.as_deref_mut() {
+            require!(
+                !purchase.used
+                    && ctx.accounts.royalty_pool.is_some()
+                    && purchase.criteria_hash == cohort_criteria_hash(condition, min_age, max_age),
+                ErrorCode::InvalidQueryPurchase
+            );
+            purchase.used = true;
+        }
+
+        let mut args = vec![
+            Argument::ArcisPubkey(researcher),
+            Argument::PlaintextU128(researcher_nonce),
+        ];
+        let mut members = Vec::with_capacity(COHORT_SIZE);
+        for member in ctx.remaining_accounts.chunks(3) {
+            add_cohort_member(&member[0], &member[1], &member[2], &mut members, &mut args)?;
+        }
+        args.extend([
+            Argument::PlaintextU8(condition),
+            Argument::PlaintextU8(min_age),
+            Argument::PlaintextU8(max_age),
+            Argument::PlaintextU8(min_cohort_size),
+        ]);
+
+        let callback_accounts =
+            attach_royalty_pool(ctx.accounts.royalty_pool.as_deref_mut(), &members)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![AggregateCohortCallback::callback_ix(&callback_accounts)],
+        )?;
+
+        emit!(CohortQueuedEvent {
+            researcher: ctx.accounts.payer.key(),
+            computation_offset,
+            members,
+        });
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "aggregate_cohort")]
+    pub fn aggregate_cohort_callback(
+        ctx: Context<AggregateCohortCallback>,
+        output: ComputationOutputs<AggregateCohortOutput>,
+    ) -> Result<()> {
+        let stats = match output {
+            ComputationOutputs::Success(AggregateCohortOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        if let Some(pool) = ctx.accounts.royalty_pool.as_mut() {
+            pool.completed = true;
+        }
+
+        emit!(CohortAggregatedEvent {
+            nonce: stats.nonce.to_le_bytes(),
+            history_counts: ciphertext_array(&stats.ciphertexts, 0),
+            mean_age: stats.ciphertexts[10],
+            insufficient_cohort: stats.ciphertexts[11],
+        });
+        Ok(())
+    }
+
+    /// Approves an extension program to be called on share hooks, or updates its entry.
+    ///
+    /// Extensions (payments, research, prescriptions, ...) ship as separate programs that
+    /// implement the hook interface: an instruction named after each hook (`pre_share`,
+    /// `post_share`) taking `ShareHookArgs`, called with the patient record and the sharer
+    /// (neither signing nor writable) followed by the extension's own accounts. A failing
+    /// hook fails the share. Only the config authority may call it.
+    ///
+    /// Share callers pass, for every registered extension in registry order, the extension
+    /// program followed by its `extra_accounts` accounts in `remaining_accounts`.
+    ///
+    /// Hooks only run in `share_patient_data`. Role, section, entry, break-glass, care team
+    /// and other share paths do not call them, so an extension must not be relied on to see
+    /// or veto every disclosure of a record.
+    ///
+    /// # Arguments
+    /// * `program_id` - Extension program
+    /// * `hooks` - Bit set of `HOOK_*` the extension is called on
+    /// * `extra_accounts` - Number of extension accounts passed to each hook
+    pub fn register_extension(
+        ctx: Context<RegisterExtension>,
+        program_id: Pubkey,
+        hooks: u8,
+        extra_accounts: u8,
+    ) -> Result<()> {
+        require!(
+            hooks != 0 && hooks <= (HOOK_PRE_SHARE | HOOK_POST_SHARE),
+            ErrorCode::InvalidExtension
+        );
+        require_keys_neq!(program_id, ID, ErrorCode::InvalidExtension);
+
+        let registry = &mut ctx.accounts.extension_registry;
+        registry.bump = ctx.bumps.extension_registry;
+        let entry = ExtensionEntry {
+            program_id,
+            hooks,
+            extra_accounts,
+        };
+        match registry
+            .extensions
+            .iter_mut()
+            .find(|e| e.program_id == program_id)
+        {
+            Some(existing) => *existing = entry,
+            None => {
+                require!(
+                    registry.extensions.len() < MAX_EXTENSIONS,
+                    ErrorCode::InvalidExtension
+                );
+                registry.extensions.push(entry);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes an extension program from the registry. Only the config authority may call it.
+    pub fn remove_extension(ctx: Context<RemoveExtension>, program_id: Pubkey) -> Result<()> {
+        ctx.accounts
+            .extension_registry
+            .extensions
+            .retain(|e| e.program_id != program_id);
+        Ok(())
+    }
+
+    /// Approves a program to invoke `share_patient_data` via CPI.
+    ///
+    /// Insurance, scheduling and other third-party programs depend on this crate with the
+    /// `cpi` feature and call `cpi::share_patient_data` directly from their own top-level
+    /// instruction; the share checks that program against this allowlist and rejects
+    /// deeper invocation chains. Every other share instruction rejects CPI. Only the config
+    /// authority may call it.
+    pub fn add_cpi_caller(ctx: Context<AddCpiCaller>, program_id: Pubkey) -> Result<()> {
+        require_keys_neq!(program_id, ID, ErrorCode::CpiCallerNotAllowed);
+
+        let allowlist = &mut ctx.accounts.cpi_allowlist;
+        allowlist.bump = ctx.bumps.cpi_allowlist;
+        if !allowlist.programs.contains(&program_id) {
+            require!(
+                allowlist.programs.len() < MAX_CPI_CALLERS,
+                ErrorCode::CpiCallerNotAllowed
+            );
+            allowlist.programs.push(program_id);
+        }
+        Ok(())
+    }
+
+    /// Withdraws a program's approval to invoke shares via CPI. Only the config authority may
+    /// call it.
+    pub fn remove_cpi_caller(ctx: Context<RemoveCpiCaller>, program_id: Pubkey) -> Result<()> {
+        ctx.accounts
+            .cpi_allowlist
+            .programs
+            .retain(|p| *p != program_id);
+        Ok(())
+    }
+
+    /// Approves an Arcium cluster that computations may be pinned to.
+    ///
+    /// Every instruction queueing a computation takes an optional `cluster_offset`: without
+    /// it the computation runs on the MXE's own cluster, with it the passed cluster account
+    /// must be the cluster at that offset and the offset must be approved here, so hospitals
+    /// can pick clusters for latency or compliance. Only the config authority may call it.
+    pub fn approve_cluster(ctx: Context<ApproveCluster>, cluster_offset: u32) -> Result<()> {
+        let approved = &mut ctx.accounts.approved_clusters;
+        approved.bump = ctx.bumps.approved_clusters;
+        if !approved.clusters.contains(&cluster_offset) {
+            require!(
+                approved.clusters.len() < MAX_APPROVED_CLUSTERS,
+                ErrorCode::ClusterNotApproved
+            );
+            approved.clusters.push(cluster_offset);
+        }
+        Ok(())
+    }
+
+    /// Withdraws a cluster's approval; computations can no longer be pinned to it. Only the
+    /// config authority may call it.
+    pub fn remove_cluster(ctx: Context<RemoveCluster>, cluster_offset: u32) -> Result<()> {
+        ctx.accounts
+            .approved_clusters
+            .clusters
+            .retain(|c| *c != cluster_offset);
+        Ok(())
+    }
+
+    /// Stages a new circuit version for an instruction in the `CompDefRegistry`.
+    ///
+    /// Until an instruction is first upgraded, version 1 is its built-in circuit of the same
+    /// name. The staged computation definition must already be initialized on the Arcium
+    /// program; it only takes over once `finalize_comp_def` is called, so the old version
+    /// keeps serving shares during the rollout. Only the config authority may call it.
+    ///
+    /// # Arguments
+    /// * `name` - Instruction name, e.g. `share_patient_data`
+    /// * `version` - New version, greater than the active one
+    /// * `offset` - Computation definition offset of the new version
+    pub fn upgrade_comp_def(
+        ctx: Context<UpgradeCompDef>,
+        name: String,
+        version: u32,
+        offset: u32,
+    ) -> Result<()> {
+        require!(
+            !name.is_empty() && name.len() <= MAX_CIRCUIT_NAME_LEN,
+            ErrorCode::InvalidCompDefVersion
+        );
+        let comp_def_account = &ctx.accounts.comp_def_account;
+        require_keys_eq!(
+            comp_def_account.key(),
+            derive_comp_def_pda!(offset),
+            ErrorCode::InvalidCompDefVersion
+        );
+        require_keys_eq!(
+            *comp_def_account.owner,
+            ARCIUM_PROG_ID,
+            ErrorCode::InvalidCompDefVersion
+        );
+
+        let registry = &mut ctx.accounts.comp_def_registry;
+        registry.bump = ctx.bumps.comp_def_registry;
+        let index = match registry.circuits.iter().position(|c| c.name == name) {
+            Some(index) => index,
+            None => {
+                require!(
+                    registry.circuits.len() < MAX_VERSIONED_CIRCUITS,
+                    ErrorCode::InvalidCompDefVersion
+                );
+                registry.circuits.push(VersionedCircuit {
+                    comp_def_offset: comp_def_offset(&name),
+                    name: name.clone(),
+                    version: 1,
+                    pending_version: 0,
+                    pending_comp_def_offset: 0,
+                });
+                registry.circuits.len() - 1
+            }
+        };
+        let circuit = &mut registry.circuits[index];
+        require!(version > circuit.version, ErrorCode::InvalidCompDefVersion);
+        circuit.pending_version = version;
+        circuit.pending_comp_def_offset = offset;
+
+        emit!(CompDefUpgradedEvent {
+            name,
+            version,
+            comp_def_offset: offset,
+        });
+        Ok(())
+    }
+
+    /// Activates the circuit version staged with `upgrade_comp_def`, deprecating the
+    /// previous one: instructions checking the registry then reject the old computation
+    /// definition with `CompDefDeprecated`. Only the config authority may call it.
+    ///
+    /// # Arguments
+    /// * `name` - Instruction name the version was staged for
+    pub fn finalize_comp_def(ctx: Context<FinalizeCompDef>, name: String) -> Result<()> {
+        let circuit = ctx
+            .accounts
+            .comp_def_registry
+            .circuits
+            .iter_mut()
+            .find(|c| c.name == name && c.pending_version != 0)
+            .ok_or(ErrorCode::NoPendingCompDef)?;
+        let previous_version = circuit.version;
+        circuit.version = circuit.pending_version;
+        circuit.comp_def_offset = circuit.pending_comp_def_offset;
+        circuit.pending_version = 0;
+        circuit.pending_comp_def_offset = 0;
+
+        emit!(CompDefFinalizedEvent {
+            name,
+            version: circuit.version,
+            previous_version,
+            comp_def_offset: circuit.comp_def_offset,
+        });
+        Ok(())
+    }
+
+    pub fn init_aggregate_cohort_dp_comp_def(
+        ctx: Context<InitAggregateCohortDpCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Differentially private variant of `aggregate_cohort`.
+    ///
+    /// Only the medical history counts are released, each with binomial noise drawn inside
+    /// MPC; the number of coin flips grows as `epsilon_scaled` (epsilon * 100) shrinks. Every
+    /// member's `PrivacyBudget` is charged `epsilon_scaled`, and a member whose budget would
+    /// exceed the lifetime limit fails the query, so repeated queries cannot average the
+    /// noise away. The noise stops growing below `MIN_EPSILON_SCALED` (epsilon 0.25), so
+    /// smaller values, which would buy extra queries without extra noise, fail with
+    /// `InvalidEpsilon`.
+    ///
+    /// `remaining_accounts` must contain, for each of the 5 cohort members in order, its
+    /// `PatientData`, `ResearchConsent`, `SectionLocks` and (writable) `PrivacyBudget`
+    /// accounts.
+    ///
+    /// # Arguments
+    /// * `researcher` - x25519 public key the statistics are encrypted for
+    /// * `researcher_nonce` - Nonce for the researcher's encryption
+    /// * `epsilon_scaled` - Privacy loss of this query, epsilon * 100
+    pub fn aggregate_cohort_dp<'info>(
+        ctx: Context<'_, '_, '_, 'info, AggregateCohortDp<'info>>,
+        computation_offset: u64,
+        researcher: [u8; 32],
+        researcher_nonce: u128,
+        epsilon_scaled: u64,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(
+            ctx.remaining_accounts.len() == COHORT_SIZE * 4,
+            ErrorCode::InvalidCohort
+        );
+        require!(
+            (MIN_EPSILON_SCALED..=PRIVACY_BUDGET_LIMIT).contains(&epsilon_scaled),
+            ErrorCode::InvalidEpsilon
+        );
+
+        let mut args = vec![
+            Argument::ArcisPubkey(researcher),
+            Argument::PlaintextU128(researcher_nonce),
+        ];
+        let mut members = Vec::with_capacity(COHORT_SIZE);
+        for member in ctx.remaining_accounts.chunks(4) {
+            add_cohort_member(&member[0], &member[1], &member[2], &mut members, &mut args)?;
+
+            let budget_info = &member[3];
+            let (budget_address, _) =
+                Pubkey::find_program_address(&[b"privacy_budget", member[0].key.as_ref()], &ID);
+            require_keys_eq!(budget_info.key(), budget_address, ErrorCode::InvalidCohort);
+            let mut budget = Account::<PrivacyBudget>::try_from(budget_info)?;
+            let consumed = budget.consumed.saturating_add(epsilon_scaled);
+            require!(consumed <= PRIVACY_BUDGET_LIMIT, ErrorCode::PrivacyBudgetExhausted);
+            budget.consumed = consumed;
+            budget.query_count = budget.query_count.saturating_add(1);
+            budget.exit(&ID)?;
+        }
+
+        let noise_trials = (NOISE_TRIALS_AT_UNIT_EPSILON * 100 / epsilon_scaled)
+            .clamp(1, MAX_NOISE_TRIALS) as u8;
+        args.push(Argument::PlaintextU8(noise_trials));
+
+        let callback_accounts =
+            attach_royalty_pool(ctx.accounts.royalty_pool.as_deref_mut(), &members)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![AggregateCohortDpCallback::callback_ix(&callback_accounts)],
+        )?;
+
+        emit!(CohortQueuedEvent {
+            researcher: ctx.accounts.payer.key(),
+            computation_offset,
+            members,
+        });
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "aggregate_cohort_dp")]
+    pub fn aggregate_cohort_dp_callback(
+        ctx: Context<AggregateCohortDpCallback>,
+        output: ComputationOutputs<AggregateCohortDpOutput>,
+    ) -> Result<()> {
+        let counts = match output {
+            ComputationOutputs::Success(AggregateCohortDpOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        if let Some(pool) = ctx.accounts.royalty_pool.as_mut() {
+            pool.completed = true;
+        }
+
+        emit!(NoisyCohortAggregatedEvent {
+            nonce: counts.nonce.to_le_bytes(),
+            history_counts: ciphertext_array(&counts.ciphertexts, 0),
+        });
+        Ok(())
+    }
+
+    /// Funds a royalty pool paying the patients of the cohort aggregated at
+    /// `computation_offset`. The pool must be passed to `aggregate_cohort` or
+    /// `aggregate_cohort_dp` with the same offset.
+    ///
+    /// # Arguments
+    /// * `computation_offset` - Offset the aggregation will be queued at
+    /// * `amount` - Lamports shared between the cohort members
+    pub fn fund_royalty_pool(
+        ctx: Context<FundRoyaltyPool>,
+        computation_offset: u64,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.royalty_pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        ctx.accounts.royalty_pool.set_inner(RoyaltyPool {
+            researcher: ctx.accounts.payer.key(),
+            computation_offset,
+            amount,
+            members: Vec::new(),
+            completed: false,
+            bump: ctx.bumps.royalty_pool,
+        });
+        Ok(())
+    }
+
+    /// Splits a completed royalty pool equally between the cohort members and closes it,
+    /// returning the rent and any rounding remainder to the researcher. Anyone may call it.
+    ///
+    /// `remaining_accounts` must contain the (writable) `RoyaltyBalance` of every cohort
+    /// member, in cohort order.
+    pub fn distribute_royalties<'info>(
+        ctx: Context<'_, '_, '_, 'info, DistributeRoyalties<'info>>,
+    ) -> Result<()> {
+        let pool = &ctx.accounts.royalty_pool;
+        require!(pool.completed, ErrorCode::RoyaltiesNotReady);
+        require!(
+            ctx.remaining_accounts.len() == pool.members.len(),
+            ErrorCode::InvalidCohort
+        );
+
+        let share = pool.amount / pool.members.len() as u64;
+        let pool_info = pool.to_account_info();
+        for (member, balance_info) in pool.members.iter().zip(ctx.remaining_accounts) {
+            let (balance_address, _) =
+                Pubkey::find_program_address(&[b"royalty_balance", member.as_ref()], &ID);
+            require_keys_eq!(balance_info.key(), balance_address, ErrorCode::InvalidCohort);
+            let mut balance = Account::<RoyaltyBalance>::try_from(balance_info)?;
+            balance.claimable = balance.claimable.saturating_add(share);
+            balance.total_earned = balance.total_earned.saturating_add(share);
+            balance.exit(&ID)?;
+
+            **pool_info.try_borrow_mut_lamports()? -= share;
+            **balance_info.try_borrow_mut_lamports()? += share;
+        }
+
+        emit!(RoyaltiesDistributedEvent {
+            researcher: pool.researcher,
+            computation_offset: pool.computation_offset,
+            members: pool.members.clone(),
+            share,
+        });
+        Ok(())
+    }
+
+    /// Pays the royalties credited to the caller's record out to the caller.
+    pub fn claim_royalty(ctx: Context<ClaimRoyalty>) -> Result<()> {
+        let balance = &mut ctx.accounts.royalty_balance;
+        let amount = balance.claimable;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        balance.claimable = 0;
+
+        **balance.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.payer.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(RoyaltyClaimedEvent {
+            patient_data: balance.patient_data,
+            amount,
+        });
+        Ok(())
+    }
+
+    /// Lists an anonymized-cohort query of the caller's organization for sale. Researchers
+    /// buy single queries with `purchase_query`; only `aggregate_cohort` queries whose
+    /// condition and age range hash to `criteria_hash` (see `cohort_criteria_hash`) can use
+    /// them. Only the organization authority may call it.
+    ///
+    /// # Arguments
+    /// * `listing_id` - Organization-chosen identifier of the listing
+    /// * `criteria_hash` - `cohort_criteria_hash` of the listed query
+    /// * `price` - Lamports a query costs, paid to the contributing patients
+    /// * `max_queries` - Number of queries that can be sold
+    pub fn create_listing(
+        ctx: Context<CreateListing>,
+        listing_id: u64,
+        criteria_hash: [u8; 32],
+        price: u64,
+        max_queries: u32,
+    ) -> Result<()> {
+        require!(price > 0 && max_queries > 0, ErrorCode::InvalidAmount);
+        ctx.accounts.dataset_listing.set_inner(DatasetListing {
+            organization: ctx.accounts.organization.key(),
+            listing_id,
+            criteria_hash,
+            price,
+            max_queries,
+            queries_sold: 0,
+            active: true,
+            bump: ctx.bumps.dataset_listing,
+        });
+        emit!(DatasetListedEvent {
+            listing: ctx.accounts.dataset_listing.key(),
+            organization: ctx.accounts.organization.key(),
+            criteria_hash,
+            price,
+            max_queries,
+        });
+        Ok(())
+    }
+
+    /// Stops or resumes sales of a listing. Only the organization authority may call it.
+    pub fn set_listing_active(ctx: Context<UpdateListing>, active: bool) -> Result<()> {
+        ctx.accounts.dataset_listing.active = active;
+        Ok(())
+    }
+
+    /// Buys one query of a dataset listing, to be run as the `aggregate_cohort` computation
+    /// at `computation_offset`.
+    ///
+    /// The price is escrowed in a `RoyaltyPool` for that offset, which `aggregate_cohort`
+    /// must be passed together with the `QueryPurchase`; once the aggregation completes,
+    /// `distribute_royalties` splits it between the contributing patients' royalty balances.
+    /// A purchase authorizes exactly one aggregation. Fails with `ListingUnavailable` when the
+    /// listing is inactive or sold out.
+    ///
+    /// # Arguments
+    /// * `computation_offset` - Offset the aggregation will be queued at
+    pub fn purchase_query(ctx: Context<PurchaseQuery>, computation_offset: u64) -> Result<()> {
+        let listing = &mut ctx.accounts.dataset_listing;
+        require!(
+            listing.active && listing.queries_sold < listing.max_queries,
+            ErrorCode::ListingUnavailable
+        );
+        listing.queries_sold += 1;
+        let (price, criteria_hash) = (listing.price, listing.criteria_hash);
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.royalty_pool.to_account_info(),
+                },
+            ),
+            price,
+        )?;
+        ctx.accounts.royalty_pool.set_inner(RoyaltyPool {
+            researcher: ctx.accounts.payer.key(),
+            computation_offset,
+            amount: price,
+            members: Vec::new(),
+            completed: false,
+            bump: ctx.bumps.royalty_pool,
+        });
+        ctx.accounts.query_purchase.set_inner(QueryPurchase {
+            listing: ctx.accounts.dataset_listing.key(),
+            researcher: ctx.accounts.payer.key(),
+            computation_offset,
+            criteria_hash,
+            used: false,
+            bump: ctx.bumps.query_purchase,
+        });
+
+        emit!(QueryPurchasedEvent {
+            listing: ctx.accounts.dataset_listing.key(),
+            researcher: ctx.accounts.payer.key(),
+            computation_offset,
+            price,
+        });
+        Ok(())
+    }
+
+    pub fn init_match_genetic_markers_comp_def(
+        ctx: Context<InitMatchGeneticMarkersCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Private set intersection between a patient's genetic markers and a researcher's list.
+    ///
+    /// The researcher encrypts up to 8 marker ids (0 for unused slots) under their own key;
+    /// only the number of markers the patient carries is encrypted back to them and emitted
+    /// in `GeneticMarkersMatchedEvent`, for rare-disease cohort discovery. The patient must
+    /// have an active genomic consent from `set_research_consent`, which supplies the
+    /// record's key and nonce.
+    ///
+    /// # Arguments
+    /// * `researcher` - x25519 public key of the researcher, used for the query and the result
+    /// * `researcher_nonce` - Nonce for the result
+    /// * `query_nonce` - Nonce the query markers were encrypted with
+    /// * `query_markers` - Encrypted marker ids
+    pub fn match_genetic_markers(
+        ctx: Context<MatchGeneticMarkers>,
+        computation_offset: u64,
+        researcher: [u8; 32],
+        researcher_nonce: u128,
+        query_nonce: u128,
+        query_markers: [[u8; 32]; MARKER_QUERY_SIZE],
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let consent = &ctx.accounts.research_consent;
+        consent.check_active(RESEARCH_SCOPE_GENOMIC)?;
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_GENOMICS)?;
+        let mut args = vec![
+            Argument::ArcisPubkey(researcher),
+            Argument::PlaintextU128(researcher_nonce),
+            Argument::ArcisPubkey(consent.encryption_key),
+            Argument::PlaintextU128(consent.encryption_nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::ArcisPubkey(researcher),
+            Argument::PlaintextU128(query_nonce),
+        ];
+        args.extend(query_markers.iter().map(|m| Argument::EncryptedU64(*m)));
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![MatchGeneticMarkersCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "match_genetic_markers")]
+    pub fn match_genetic_markers_callback(
+        ctx: Context<MatchGeneticMarkersCallback>,
+        output: ComputationOutputs<MatchGeneticMarkersOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(MatchGeneticMarkersOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(GeneticMarkersMatchedEvent {
+            nonce: result.nonce.to_le_bytes(),
+            match_count: result.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    pub fn init_analyze_lab_trend_comp_def(
+        ctx: Context<InitAnalyzeLabTrendCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Analyzes the trend of one lab test type for the treating physician.
+    ///
+    /// The MPC network compares the earliest and latest results of `test_type` and looks for
+    /// runs of out-of-range flags; only an anomaly flag and the trend direction (stable,
+    /// rising, falling) are re-encrypted for the receiver and emitted in
+    /// `LabTrendAnalyzedEvent`. Requires a doctor credential NFT as in
+    /// `share_patient_data_with_role`; revocation, nonce and audit rules match
+    /// `share_patient_data`.
+    ///
+    /// # Arguments
+    /// * `test_type` - Lab test type to analyze, as stored in `lab_test_types`
+    #[allow(clippy::too_many_arguments)]
+    pub fn analyze_lab_trend(
+        ctx: Context<AnalyzeLabTrend>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        test_type: u8,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(ctx.accounts.credential_mint.decimals == 0, ErrorCode::InvalidCredentialMint);
+        require!(ctx.accounts.credential_token_account.amount >= 1, ErrorCode::MissingCredential);
+        check_credential_record(&ctx.accounts.credential_record)?;
+        check_credential_collection(
+           &ctx.accounts.role_config,
+           ctx.accounts.credential_metadata.as_deref(),
+           &ctx.accounts.credential_mint.key(),
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_LAB_TESTS)?;
+        check_patient_policy(&ctx.accounts.patient_policy, 1 << CATEGORY_LAB_TESTS, ROLE_DOCTOR)?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::PlaintextU8(test_type),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            ROLE_DOCTOR,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![AnalyzeLabTrendCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "analyze_lab_trend")]
+    pub fn analyze_lab_trend_callback(
+        ctx: Context<AnalyzeLabTrendCallback>,
+        output: ComputationOutputs<AnalyzeLabTrendOutput>,
+    ) -> Result<()> {
+        let trend = match output {
+            ComputationOutputs::Success(AnalyzeLabTrendOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(LabTrendAnalyzedEvent {
+            nonce: trend.nonce.to_le_bytes(),
+            anomaly: trend.ciphertexts[0],
+            direction: trend.ciphertexts[1],
+        });
+        Ok(())
+    }
+
+    /// Creates the caller's `VitalsSeries`, a ring buffer remote-monitoring devices stream
+    /// encrypted vitals into.
+    ///
+    /// # Arguments
+    /// * `device` - Wallet of the device allowed to append batches
+    pub fn init_vitals_series(ctx: Context<InitVitalsSeries>, device: Pubkey) -> Result<()> {
+        let mut series = ctx.accounts.vitals_series.load_init()?;
+        series.patient_data = ctx.accounts.patient_data.key();
+        series.device = device;
+        Ok(())
+    }
+
+    /// Appends a batch of encrypted vitals samples to a `VitalsSeries`, overwriting the
+    /// oldest batch once the ring buffer is full. Only the series' device may call it.
+    ///
+    /// The samples are encrypted together as one `VitalsSamples` struct; the batch's first
+    /// and last sample times are stored in plaintext so receivers can pick a window.
+    /// Batches must be appended in time order.
+    ///
+    /// # Arguments
+    /// * `encryption_key` - x25519 public key the batch is encrypted with
+    /// * `nonce` - Nonce the batch is encrypted with
+    /// * `first_timestamp` - Unix timestamp of the batch's first sample
+    /// * `last_timestamp` - Unix timestamp of the batch's last sample
+    /// * `ciphertexts` - Encrypted samples, 24 ciphertexts
+    pub fn append_vitals_batch(
+        ctx: Context<AppendVitalsBatch>,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        first_timestamp: i64,
+        last_timestamp: i64,
+        ciphertexts: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(
+            ciphertexts.len() == VITALS_BATCH_FIELDS,
+            ErrorCode::InvalidInputLength
+        );
+        require!(
+            first_timestamp <= last_timestamp,
+            ErrorCode::InvalidVitalsBatch
+        );
+
+        let mut series = ctx.accounts.vitals_series.load_mut()?;
+        if let Some(latest) = series.latest() {
+            require!(
+                first_timestamp >= latest.last_timestamp,
+                ErrorCode::InvalidVitalsBatch
+            );
+        }
+        let batch_index = series.next_batch;
+        let batch = &mut series.batches[batch_index as usize % VITALS_RING_BATCHES];
+        batch.encryption_key = encryption_key;
+        batch.nonce = nonce.to_le_bytes();
+        batch.first_timestamp = first_timestamp;
+        batch.last_timestamp = last_timestamp;
+        batch.ciphertexts.copy_from_slice(&ciphertexts);
+        series.next_batch += 1;
+
+        emit!(VitalsBatchAppendedEvent {
+            vitals_series: ctx.accounts.vitals_series.key(),
+            batch_index,
+            first_timestamp,
+            last_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn init_share_vitals_window_comp_def(
+        ctx: Context<InitShareVitalsWindowCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares the samples of one vitals batch taken within `[start, end]`.
+    ///
+    /// The MPC network zeroes the batch's samples outside the window and re-encrypts the
+    /// rest for the receiver, emitted in `VitalsWindowSharedEvent`. Only batches still in
+    /// the ring buffer can be shared. Authorization, revocation, nonce and audit rules match
+    /// `share_patient_data`.
+    ///
+    /// # Arguments
+    /// * `batch_index` - Sequence number of the batch, as emitted in `VitalsBatchAppendedEvent`
+    /// * `start` - Unix timestamp of the window start
+    /// * `end` - Unix timestamp of the window end
+    #[allow(clippy::too_many_arguments)]
+    pub fn share_vitals_window(
+        ctx: Context<ShareVitalsWindow>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        batch_index: u64,
+        start: i64,
+        end: i64,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_LAB_TESTS)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+        require!(start <= end, ErrorCode::InvalidVitalsWindow);
+
+        let (encryption_key, nonce, offset) = {
+            let series = ctx.accounts.vitals_series.load()?;
+            let batch = series
+                .batch(batch_index)
+                .ok_or(ErrorCode::InvalidVitalsWindow)?;
+            require!(
+                start <= batch.last_timestamp && end >= batch.first_timestamp,
+                ErrorCode::InvalidVitalsWindow
+            );
+            (
+                batch.encryption_key,
+                u128::from_le_bytes(batch.nonce),
+                VitalsSeries::ciphertexts_offset(batch_index),
+            )
+        };
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(encryption_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(
+                ctx.accounts.vitals_series.key(),
+                offset,
+                (VITALS_BATCH_FIELDS * 32) as u32,
+            ),
+            Argument::PlaintextU64(start as u64),
+            Argument::PlaintextU64(end as u64),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ShareVitalsWindowCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "share_vitals_window")]
+    pub fn share_vitals_window_callback(
+        ctx: Context<ShareVitalsWindowCallback>,
+        output: ComputationOutputs<ShareVitalsWindowOutput>,
+    ) -> Result<()> {
+        let samples = match output {
+            ComputationOutputs::Success(ShareVitalsWindowOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(VitalsWindowSharedEvent {
+            nonce: samples.nonce.to_le_bytes(),
+            ciphertexts: samples.ciphertexts,
+        });
+        Ok(())
+    }
+
+    /// Creates the caller's `GlucoseSeries`, a ring buffer a continuous glucose monitor
+    /// streams encrypted readings into.
+    ///
+    /// # Arguments
+    /// * `device` - Wallet of the monitor allowed to append batches
+    pub fn init_glucose_series(ctx: Context<InitGlucoseSeries>, device: Pubkey) -> Result<()> {
+        let mut series = ctx.accounts.glucose_series.load_init()?;
+        series.patient_data = ctx.accounts.patient_data.key();
+        series.device = device;
+        Ok(())
+    }
+
+    /// Appends a batch of encrypted glucose readings to a `GlucoseSeries`, overwriting the
+    /// oldest batch once the ring buffer is full. Only the series' device may call it.
+    ///
+    /// Readings are in mg/dL, with 0 marking a missed reading. Batches must be appended in
+    /// time order.
+    ///
+    /// # Arguments
+    /// * `encryption_key` - x25519 public key the batch is encrypted with
+    /// * `nonce` - Nonce the batch is encrypted with
+    /// * `first_timestamp` - Unix timestamp of the batch's first reading
+    /// * `last_timestamp` - Unix timestamp of the batch's last reading
+    /// * `ciphertexts` - Encrypted readings, 24 ciphertexts
+    pub fn append_glucose_batch(
+        ctx: Context<AppendGlucoseBatch>,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        first_timestamp: i64,
+        last_timestamp: i64,
+        ciphertexts: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(
+            ciphertexts.len() == GLUCOSE_READINGS_PER_BATCH,
+            ErrorCode::InvalidInputLength
+        );
+        require!(
+            first_timestamp <= last_timestamp,
+            ErrorCode::InvalidGlucoseBatch
+        );
+
+        let mut series = ctx.accounts.glucose_series.load_mut()?;
+        if let Some(latest) = series.latest() {
+            require!(
+                first_timestamp >= latest.last_timestamp,
+                ErrorCode::InvalidGlucoseBatch
+            );
+        }
+        let batch_index = series.next_batch;
+        let batch = &mut series.batches[batch_index as usize % GLUCOSE_RING_BATCHES];
+        batch.encryption_key = encryption_key;
+        batch.nonce = nonce.to_le_bytes();
+        batch.first_timestamp = first_timestamp;
+        batch.last_timestamp = last_timestamp;
+        batch.ciphertexts.copy_from_slice(&ciphertexts);
+        series.next_batch += 1;
+
+        emit!(GlucoseBatchAppendedEvent {
+            glucose_series: ctx.accounts.glucose_series.key(),
+            batch_index,
+            first_timestamp,
+            last_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn init_compute_glucose_metrics_comp_def(
+        ctx: Context<InitComputeGlucoseMetricsCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Computes time in range and estimated A1c over three consecutive glucose batches for
+    /// the endocrinologist.
+    ///
+    /// The MPC network counts the readings within 70-180 mg/dL and derives the glucose
+    /// management indicator from their mean; only the two metrics are re-encrypted for the
+    /// receiver and emitted in `GlucoseMetricsComputedEvent`, never the readings. Requires
+    /// an endocrinologist credential NFT as in `share_patient_data_with_role`; revocation,
+    /// nonce and audit rules match `share_patient_data`.
+    ///
+    /// # Arguments
+    /// * `batch_index` - Sequence number of the first batch, as emitted in
+    ///   `GlucoseBatchAppendedEvent`
+    pub fn compute_glucose_metrics(
+        ctx: Context<ComputeGlucoseMetrics>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        batch_index: u64,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(ctx.accounts.credential_mint.decimals == 0, ErrorCode::InvalidCredentialMint);
+        require!(ctx.accounts.credential_token_account.amount >= 1, ErrorCode::MissingCredential);
+        check_credential_record(&ctx.accounts.credential_record)?;
+        check_credential_collection(
+            &ctx.accounts.role_config,
+            ctx.accounts.credential_metadata.as_deref(),
+            &ctx.accounts.credential_mint.key(),
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_LAB_TESTS)?;
+        check_patient_policy(
+            &ctx.accounts.patient_policy,
+            1 << CATEGORY_LAB_TESTS,
+            ROLE_ENDOCRINOLOGIST,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let mut args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+        ];
+        {
+            let series = ctx.accounts.glucose_series.load()?;
+            for index in batch_index..batch_index + GLUCOSE_METRICS_BATCHES {
+                let batch = series
+                    .batch(index)
+                    .ok_or(ErrorCode::GlucoseBatchNotRetained)?;
+                args.push(Argument::ArcisPubkey(batch.encryption_key));
+                args.push(Argument::PlaintextU128(u128::from_le_bytes(batch.nonce)));
+                args.push(Argument::Account(
+                    ctx.accounts.glucose_series.key(),
+                    GlucoseSeries::ciphertexts_offset(index),
+                    (GLUCOSE_READINGS_PER_BATCH * 32) as u32,
+                ));
+            }
+        }
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            ROLE_ENDOCRINOLOGIST,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ComputeGlucoseMetricsCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "compute_glucose_metrics")]
+    pub fn compute_glucose_metrics_callback(
+        ctx: Context<ComputeGlucoseMetricsCallback>,
+        output: ComputationOutputs<ComputeGlucoseMetricsOutput>,
+    ) -> Result<()> {
+        let metrics = match output {
+            ComputationOutputs::Success(ComputeGlucoseMetricsOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(GlucoseMetricsComputedEvent {
+            nonce: metrics.nonce.to_le_bytes(),
+            time_in_range: metrics.ciphertexts[0],
+            estimated_a1c: metrics.ciphertexts[1],
+        });
+        Ok(())
+    }
+
+    /// Stores or replaces the caller's mental health section.
+    ///
+    /// The section lives in its own `MentalHealthData` account, outside `PatientData`, so
+    /// no general-purpose share, delegation or role grant ever reads it; it can only be
+    /// disclosed with `share_mental_health`.
+    ///
+    /// # Arguments
+    /// * `encryption_key` - x25519 public key the section is encrypted with
+    /// * `nonce` - Nonce the section is encrypted with
+    /// * `ciphertexts` - Encrypted diagnoses, therapy note references and medication flags
+    pub fn store_mental_health_data(
+        ctx: Context<StoreMentalHealthData>,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        ciphertexts: [[u8; 32]; MENTAL_HEALTH_FIELDS],
+    ) -> Result<()> {
+        let section = &mut ctx.accounts.mental_health_data;
+        section.patient_data = ctx.accounts.patient_data.key();
+        section.encryption_key = encryption_key;
+        section.nonce = nonce;
+        section.ciphertexts = ciphertexts;
+        section.updated_at = Clock::get()?.unix_timestamp;
+        section.bump = ctx.bumps.mental_health_data;
+        Ok(())
+    }
+
+    /// Consents to disclosing the caller's mental health section to `receiver` until
+    /// `expires_at`. The grant is separate from every other consent and is required, in
+    /// addition to the patient's signature, by each `share_mental_health`.
+    ///
+    /// # Arguments
+    /// * `receiver` - x25519 public key of the recipient
+    /// * `expires_at` - Unix timestamp after which the grant is no longer valid
+    pub fn grant_mental_health_access(
+        ctx: Context<GrantMentalHealthAccess>,
+        receiver: [u8; 32],
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            ErrorCode::MentalHealthGrantExpired
+        );
+
+        let grant = &mut ctx.accounts.mental_health_grant;
+        grant.patient_data = ctx.accounts.patient_data.key();
+        grant.receiver = receiver;
+        grant.expires_at = expires_at;
+        grant.bump = ctx.bumps.mental_health_grant;
+
+        emit!(MentalHealthAccessEvent {
+            patient_data: grant.patient_data,
+            receiver,
+            expires_at,
+        });
+        Ok(())
+    }
+
+    /// Withdraws a mental health grant, closing it and returning rent to the patient.
+    ///
+    /// # Arguments
+    /// * `receiver` - x25519 public key of the recipient whose grant is withdrawn
+    pub fn revoke_mental_health_access(
+        ctx: Context<RevokeMentalHealthAccess>,
+        receiver: [u8; 32],
+    ) -> Result<()> {
+        emit!(MentalHealthAccessEvent {
+            patient_data: ctx.accounts.patient_data.key(),
+            receiver,
+            expires_at: 0,
+        });
+        Ok(())
+    }
+
+    pub fn init_share_mental_health_comp_def(
+        ctx: Context<InitShareMentalHealthCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares the patient's mental health section with a receiver holding an unexpired
+    /// `MentalHealthGrant`.
+    ///
+    /// The patient must sign every call, even when another wallet pays; delegations and
+    /// credentials are not accepted. Revocation, allowlist, jurisdiction, nonce and audit
+    /// rules match `share_patient_data`, and the re-encrypted section is emitted in
+    /// `MentalHealthSharedEvent`.
+    pub fn share_mental_health(
+        ctx: Context<ShareMentalHealth>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_receiver_allowlist(&ctx.accounts.receiver_allowlist, &receiver)?;
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+        require!(
+            Clock::get()?.unix_timestamp < ctx.accounts.mental_health_grant.expires_at,
+            ErrorCode::MentalHealthGrantExpired
+        );
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+        let section = &ctx.accounts.mental_health_data;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(section.encryption_key),
+            Argument::PlaintextU128(section.nonce),
+            Argument::Account(
+                section.key(),
+                MENTAL_HEALTH_CIPHERTEXTS_OFFSET,
+                (MENTAL_HEALTH_FIELDS * 32) as u32,
+            ),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.patient.key(),
+            ROLE_PATIENT,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ShareMentalHealthCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "share_mental_health")]
+    pub fn share_mental_health_callback(
+        ctx: Context<ShareMentalHealthCallback>,
+        output: ComputationOutputs<ShareMentalHealthOutput>,
+    ) -> Result<()> {
+        let section = match output {
+            ComputationOutputs::Success(ShareMentalHealthOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(MentalHealthSharedEvent {
+            nonce: section.nonce.to_le_bytes(),
+            ciphertexts: section.ciphertexts,
+        });
+        Ok(())
+    }
+
+    /// Creates the caller's obstetric record.
+    ///
+    /// The record lives in its own `ObstetricData` account, outside `PatientData`, and is
+    /// only disclosed to OB/GYN credential holders through `share_obstetric_data`.
+    ///
+    /// # Arguments
+    /// * `encryption_key` - x25519 public key the record is encrypted with
+    /// * `nonce` - Nonce the record is encrypted with
+    /// * `ciphertexts` - Encrypted `ObstetricData` fields
+    pub fn store_obstetric_data(
+        ctx: Context<StoreObstetricData>,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        ciphertexts: [[u8; 32]; OBSTETRIC_FIELDS],
+    ) -> Result<()> {
+        let record = &mut ctx.accounts.obstetric_data;
+        record.patient_data = ctx.accounts.patient_data.key();
+        record.bump = ctx.bumps.obstetric_data;
+        write_obstetric_data(record, encryption_key, nonce, ciphertexts)
+    }
+
+    /// Replaces the caller's obstetric record, e.g. after a new pregnancy or delivery.
+    ///
+    /// # Arguments
+    /// * `encryption_key` - x25519 public key the record is encrypted with
+    /// * `nonce` - Nonce the record is encrypted with
+    /// * `ciphertexts` - Encrypted `ObstetricData` fields
+    pub fn update_obstetric_data(
+        ctx: Context<UpdateObstetricData>,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        ciphertexts: [[u8; 32]; OBSTETRIC_FIELDS],
+    ) -> Result<()> {
+        write_obstetric_data(
+            &mut ctx.accounts.obstetric_data,
+            encryption_key,
+            nonce,
+            ciphertexts,
+        )
+    }
+
+    /// Attaches an off-chain imaging study (e.g. a DICOM archive) to slot `index` of the
+    /// caller's record, replacing any study previously attached there.
+    ///
+    /// Slots match `PatientData::imaging_types`/`imaging_dates`. Only the encrypted storage
+    /// URI and a plaintext SHA-256 hash of the content are kept on-chain: the receiver of a
+    /// share can check the downloaded study against the hash, and the URI stays readable only
+    /// by holders of the patient's key.
+    ///
+    /// # Arguments
+    /// * `index` - Imaging slot the study belongs to
+    /// * `encryption_key` - x25519 public key the storage URI is encrypted with
+    /// * `nonce` - Nonce the storage URI is encrypted with
+    /// * `storage_uri` - Encrypted storage URI, 16 bytes per ciphertext
+    /// * `content_hash` - SHA-256 hash of the study content
+    pub fn attach_imaging_study(
+        ctx: Context<AttachImagingStudy>,
+        index: u8,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        storage_uri: [[u8; 32]; STORAGE_URI_CIPHERTEXTS],
+        content_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            (index as usize) < MAX_IMAGING_STUDIES,
+            ErrorCode::InvalidImagingStudy
+        );
+        require!(content_hash != [0u8; 32], ErrorCode::InvalidImagingStudy);
+
+        let studies = &mut ctx.accounts.imaging_studies;
+        studies.patient_data = ctx.accounts.patient_data.key();
+        studies.bump = ctx.bumps.imaging_studies;
+        studies.studies[index as usize] = ImagingStudy {
+            encryption_key,
+            nonce,
+            storage_uri,
+            content_hash,
+            attached_at: Clock::get()?.unix_timestamp,
+        };
+
+        emit!(ImagingStudyAttachedEvent {
+            patient_data: studies.patient_data,
+            index,
+            content_hash,
+        });
+        Ok(())
+    }
+
+    pub fn init_share_obstetric_data_comp_def(
+        ctx: Context<InitShareObstetricDataCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares the patient's obstetric record with an OB/GYN.
+    ///
+    /// Requires an OB/GYN credential NFT as in `share_patient_data_with_role`, under the
+    /// `ROLE_OBGYN` role config; credentials of other roles cannot read the record. The
+    /// re-encrypted record is emitted in `ObstetricDataSharedEvent`. Revocation, nonce and
+    /// audit rules match `share_patient_data`.
+    pub fn share_obstetric_data(
+        ctx: Context<ShareObstetricData>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(ctx.accounts.credential_mint.decimals == 0, ErrorCode::InvalidCredentialMint);
+        require!(ctx.accounts.credential_token_account.amount >= 1, ErrorCode::MissingCredential);
+        check_credential_record(&ctx.accounts.credential_record)?;
+        check_credential_collection(
+            &ctx.accounts.role_config,
+            ctx.accounts.credential_metadata.as_deref(),
+            &ctx.accounts.credential_mint.key(),
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+        check_patient_policy(
+            &ctx.accounts.patient_policy,
+            1 << CATEGORY_HEALTHCARE,
+            ROLE_OBGYN,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+        let record = &ctx.accounts.obstetric_data;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(record.encryption_key),
+            Argument::PlaintextU128(record.nonce),
+            Argument::Account(
+                record.key(),
+                OBSTETRIC_CIPHERTEXTS_OFFSET,
+                (OBSTETRIC_FIELDS * 32) as u32,
+            ),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            ROLE_OBGYN,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ShareObstetricDataCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "share_obstetric_data")]
+    pub fn share_obstetric_data_callback(
+        ctx: Context<ShareObstetricDataCallback>,
+        output: ComputationOutputs<ShareObstetricDataOutput>,
+    ) -> Result<()> {
+        let record = match output {
+            ComputationOutputs::Success(ShareObstetricDataOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(ObstetricDataSharedEvent {
+            nonce: record.nonce.to_le_bytes(),
+            ciphertexts: record.ciphertexts,
+        });
+        Ok(())
+    }
+
+    /// Registers an encrypted file attachment (PDF, scanned document, ...) on the caller's
+    /// record.
+    ///
+    /// The file itself is encrypted with a symmetric content key and stored off-chain. The
+    /// attachment keeps its SHA-256 hash in plaintext, and the content key and storage URI
+    /// encrypted to the patient, so `share_attachment_key` can hand both to a receiver.
+    ///
+    /// # Arguments
+    /// * `attachment_id` - Caller-chosen identifier, unique per patient record
+    /// * `attachment_type` - One of the `ATTACHMENT_TYPE_*` constants
+    /// * `content_hash` - SHA-256 hash of the encrypted file
+    /// * `encryption_key` - x25519 public key the content key and URI are encrypted with
+    /// * `nonce` - Nonce the content key and URI are encrypted with
+    /// * `ciphertexts` - Encrypted content key followed by the encrypted storage URI
+    pub fn register_attachment(
+        ctx: Context<RegisterAttachment>,
+        attachment_id: u64,
+        attachment_type: u8,
+        content_hash: [u8; 32],
+        encryption_key: [u8; 32],
+        nonce: u128,
+        ciphertexts: [[u8; 32]; ATTACHMENT_CIPHERTEXTS],
+    ) -> Result<()> {
+        require!(
+            attachment_type <= ATTACHMENT_TYPE_OTHER && content_hash != [0u8; 32],
+            ErrorCode::InvalidAttachment
+        );
+
+        let attachment = &mut ctx.accounts.attachment;
+        attachment.patient_data = ctx.accounts.patient_data.key();
+        attachment.encryption_key = encryption_key;
+        attachment.nonce = nonce;
+        attachment.ciphertexts = ciphertexts;
+        attachment.attachment_id = attachment_id;
+        attachment.attachment_type = attachment_type;
+        attachment.content_hash = content_hash;
+        attachment.created_at = Clock::get()?.unix_timestamp;
+        attachment.bump = ctx.bumps.attachment;
+
+        emit!(AttachmentRegisteredEvent {
+            patient_data: attachment.patient_data,
+            attachment: attachment.key(),
+            attachment_type,
+            content_hash,
+        });
+        Ok(())
+    }
+
+    pub fn init_share_attachment_key_comp_def(
+        ctx: Context<InitShareAttachmentKeyCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Re-wraps an attachment's content key and storage URI to a receiver inside MPC.
+    ///
+    /// The receiver decrypts both from `AttachmentKeySharedEvent`, fetches the file and
+    /// checks it against the attachment's content hash. Authorization, revocation,
+    /// allowlist, data-residency, pause, nonce and audit rules match `share_patient_data`.
+    pub fn share_attachment_key(
+        ctx: Context<ShareAttachmentKey>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_receiver_allowlist(&ctx.accounts.receiver_allowlist, &receiver)?;
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+        let attachment = &ctx.accounts.attachment;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(attachment.encryption_key),
+            Argument::PlaintextU128(attachment.nonce),
+            Argument::Account(
+                attachment.key(),
+                ATTACHMENT_CIPHERTEXTS_OFFSET,
+                (ATTACHMENT_CIPHERTEXTS * 32) as u32,
+            ),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ShareAttachmentKeyCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "share_attachment_key")]
+    pub fn share_attachment_key_callback(
+        ctx: Context<ShareAttachmentKeyCallback>,
+        output: ComputationOutputs<ShareAttachmentKeyOutput>,
+    ) -> Result<()> {
+        let secrets = match output {
+            ComputationOutputs::Success(ShareAttachmentKeyOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(AttachmentKeySharedEvent {
+            nonce: secrets.nonce.to_le_bytes(),
+            ciphertexts: secrets.ciphertexts,
+        });
+        Ok(())
+    }
+
+    /// Grows the caller's record beyond its fixed eight medications or ten lab tests.
+    ///
+    /// The record account is reallocated, with `payer` funding the extra rent, and the new
+    /// slots are appended to its tail in chunks of `CAPACITY_CHUNK_FIELDS` ciphertexts: one
+    /// per medication, four per lab test (type, date, value, flag). Each chunk carries its own
+    /// encryption key and nonce, is written with `write_capacity_chunk` and shared with
+    /// `share_capacity_chunk`; record-wide shares and rotations keep covering only the fixed
+    /// layout.
+    ///
+    /// # Arguments
+    /// * `section` - `SECTION_MEDICATIONS` or `SECTION_LAB_TESTS`
+    /// * `additional_slots` - Slots to add, a multiple of 8 medications or 2 lab tests
+    pub fn expand_capacity(
+        ctx: Context<ExpandCapacity>,
+        section: u8,
+        additional_slots: u16,
+    ) -> Result<()> {
+        let chunks = capacity_chunks_for(section, additional_slots)?;
+        let info = ctx.accounts.patient_data.to_account_info();
+        let mut data = info.try_borrow_mut_data()?;
+        let (mut medication_chunks, mut lab_chunks) = capacity_chunks(&data);
+
+        if section == SECTION_MEDICATIONS {
+            // Make room for the new medication chunks in front of the lab chunks
+            let labs_start = capacity_chunk_start(medication_chunks);
+            let labs_end = labs_start + lab_chunks as usize * CAPACITY_CHUNK_LEN;
+            let shift = chunks as usize * CAPACITY_CHUNK_LEN;
+            data.copy_within(labs_start..labs_end, labs_start + shift);
+            data[labs_start..labs_start + shift].fill(0);
+            medication_chunks += chunks;
+        } else {
+            lab_chunks += chunks;
+        }
+        data[RECORD_TAIL_OFFSET..RECORD_TAIL_OFFSET + 2]
+            .copy_from_slice(&medication_chunks.to_le_bytes());
+        data[RECORD_TAIL_OFFSET + 2..RECORD_TAIL_OFFSET + 4]
+            .copy_from_slice(&lab_chunks.to_le_bytes());
+
+        emit!(CapacityExpandedEvent {
+            patient_data: ctx.accounts.patient_data.key(),
+            medication_chunks,
+            lab_chunks,
+        });
+        Ok(())
+    }
+
+    /// Writes one chunk of the caller's expanded capacity.
+    ///
+    /// # Arguments
+    /// * `section` - `SECTION_MEDICATIONS` or `SECTION_LAB_TESTS`
+    /// * `chunk_index` - Index of the chunk within the section's expanded capacity
+    /// * `encryption_key` - x25519 public key the chunk is encrypted with
+    /// * `nonce` - Nonce the chunk is encrypted with
+    /// * `ciphertexts` - Encrypted medications, or lab tests as type, date, value and flag
+    pub fn write_capacity_chunk(
+        ctx: Context<WriteCapacityChunk>,
+        section: u8,
+        chunk_index: u16,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        ciphertexts: [[u8; 32]; CAPACITY_CHUNK_FIELDS],
+    ) -> Result<()> {
+        check_sections_unlocked(&ctx.accounts.section_locks, section)?;
+        let info = ctx.accounts.patient_data.to_account_info();
+        {
+            let mut data = info.try_borrow_mut_data()?;
+            let start = capacity_chunk_offset(&data, section, chunk_index)?;
+            let chunk = &mut data[start..start + CAPACITY_CHUNK_LEN];
+            chunk[..32].copy_from_slice(&encryption_key);
+            chunk[32..48].copy_from_slice(&nonce.to_le_bytes());
+            chunk[48..].copy_from_slice(bytemuck::cast_slice(&ciphertexts));
+        }
+
+        emit!(PatientDataUpdatedEvent {
+            schema_version: EVENT_SCHEMA_VERSION,
+            patient_data: info.key(),
+            updated_at: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn init_share_capacity_chunk_comp_def(
+        ctx: Context<InitShareCapacityChunkCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares one chunk of a record's expanded capacity with a receiver.
+    ///
+    /// Passes exactly the chunk's byte range of the record account to the
+    /// `share_capacity_chunk` circuit; the result is emitted in `CapacityChunkSharedEvent`.
+    /// Authorization, revocation, allowlist, data-residency, pause, nonce and audit rules
+    /// match `share_patient_data`.
+    pub fn share_capacity_chunk(
+        ctx: Context<ShareCapacityChunk>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        section: u8,
+        chunk_index: u16,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, section)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_receiver_allowlist(&ctx.accounts.receiver_allowlist, &receiver)?;
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let (start, encryption_key, nonce) = {
+            let info = ctx.accounts.patient_data.to_account_info();
+            let data = info.try_borrow_data()?;
+            let start = capacity_chunk_offset(&data, section, chunk_index)?;
+            let encryption_key: [u8; 32] = data[start..start + 32].try_into().unwrap();
+            let nonce = u128::from_le_bytes(data[start + 32..start + 48].try_into().unwrap());
+            (start, encryption_key, nonce)
+        };
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(encryption_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(
+                ctx.accounts.patient_data.key(),
+                (start + 48) as u32,
+                (CAPACITY_CHUNK_FIELDS * 32) as u32,
+            ),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ShareCapacityChunkCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "share_capacity_chunk")]
+    pub fn share_capacity_chunk_callback(
+        ctx: Context<ShareCapacityChunkCallback>,
+        output: ComputationOutputs<ShareCapacityChunkOutput>,
+    ) -> Result<()> {
+        let chunk = match output {
+            ComputationOutputs::Success(ShareCapacityChunkOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(CapacityChunkSharedEvent {
+            nonce: chunk.nonce.to_le_bytes(),
+            ciphertexts: chunk.ciphertexts,
+        });
+        Ok(())
+    }
+
+    /// Creates the caller's compressed lab history, an append-only Merkle tree of lab
+    /// results of which only the root is kept on-chain.
+    pub fn init_lab_history(ctx: Context<InitLabHistory>) -> Result<()> {
+        let history = &mut ctx.accounts.lab_history;
+        history.patient_data = ctx.accounts.patient_data.key();
+        history.root = empty_lab_history_root();
+        history.leaf_count = 0;
+        history.bump = ctx.bumps.lab_history;
+        Ok(())
+    }
+
+    /// Appends an encrypted lab result to the caller's lab history.
+    ///
+    /// The result becomes leaf `leaf_count`. `proof` holds the sibling hashes from that
+    /// leaf up to the root, and must show the slot is still empty under the current root;
+    /// the same path then yields the new root. The leaf itself is only kept in
+    /// `LabLeafAppendedEvent`, from which clients rebuild the tree.
+    ///
+    /// # Arguments
+    /// * `encryption_key` - x25519 public key the result is encrypted with
+    /// * `nonce` - Nonce the result is encrypted with
+    /// * `ciphertexts` - Encrypted test type, date, value and range flag
+    /// * `proof` - Sibling hashes of the next empty leaf, from the leaf up
+    pub fn append_lab_leaf(
+        ctx: Context<AppendLabLeaf>,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        ciphertexts: [[u8; 32]; LAB_LEAF_FIELDS],
+        proof: [[u8; 32]; LAB_HISTORY_DEPTH],
+    ) -> Result<()> {
+        let history = &mut ctx.accounts.lab_history;
+        require!(
+            history.leaf_count < 1 << LAB_HISTORY_DEPTH,
+            ErrorCode::LabHistoryFull
+        );
+        let index = history.leaf_count;
+        require!(
+            lab_history_root([0u8; 32], index, &proof) == history.root,
+            ErrorCode::InvalidMerkleProof
+        );
+
+        let leaf = lab_leaf_hash(&encryption_key, nonce, &ciphertexts);
+        history.root = lab_history_root(leaf, index, &proof);
+        history.leaf_count += 1;
+
+        emit!(LabLeafAppendedEvent {
+            patient_data: history.patient_data,
+            index,
+            encryption_key,
+            nonce: nonce.to_le_bytes(),
+            ciphertexts,
+            root: history.root,
+        });
+        Ok(())
+    }
+
+    pub fn init_share_lab_leaf_comp_def(ctx: Context<InitShareLabLeafCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Re-encrypts one lab result of the patient's lab history for a receiver.
+    ///
+    /// The client supplies the leaf and its proof. The program checks the leaf against the
+    /// current root and hands exactly those ciphertexts to the circuit, so only committed
+    /// results can be re-encrypted. The result is emitted in `LabLeafSharedEvent`. With a
+    /// 16-level proof the transaction needs the share lookup table (see
+    /// `create_share_lookup_table`). Authorization, revocation, allowlist, data-residency,
+    /// pause, nonce and audit rules match `share_patient_data`.
+    pub fn share_lab_leaf(
+        ctx: Context<ShareLabLeaf>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        leaf: LabLeaf,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_LAB_TESTS)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_receiver_allowlist(&ctx.accounts.receiver_allowlist, &receiver)?;
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let history = &ctx.accounts.lab_history;
+        require!(
+            leaf.index < history.leaf_count,
+            ErrorCode::InvalidMerkleProof
+        );
+        let leaf_hash = lab_leaf_hash(&leaf.encryption_key, leaf.nonce, &leaf.ciphertexts);
+        require!(
+            lab_history_root(leaf_hash, leaf.index, &leaf.proof) == history.root,
+            ErrorCode::InvalidMerkleProof
+        );
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(leaf.encryption_key),
+            Argument::PlaintextU128(leaf.nonce),
+            Argument::EncryptedU8(leaf.ciphertexts[0]),
+            Argument::EncryptedU32(leaf.ciphertexts[1]),
+            Argument::EncryptedU16(leaf.ciphertexts[2]),
+            Argument::EncryptedU8(leaf.ciphertexts[3]),
+        ];
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ShareLabLeafCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "share_lab_leaf")]
+    pub fn share_lab_leaf_callback(
+        ctx: Context<ShareLabLeafCallback>,
+        output: ComputationOutputs<ShareLabLeafOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(ShareLabLeafOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(LabLeafSharedEvent {
+            nonce: result.nonce.to_le_bytes(),
+            ciphertexts: result.ciphertexts,
+        });
+        Ok(())
+    }
+
+    /// Opens a telemedicine session between the caller's record and a provider.
+    ///
+    /// # Arguments
+    /// * `session_id` - Caller-chosen identifier, unique per patient record
+    /// * `provider` - Wallet of the provider holding the visit
+    /// * `scheduled_at` - Unix timestamp the visit is scheduled for
+    pub fn open_session(
+        ctx: Context<OpenSession>,
+        session_id: u64,
+        provider: Pubkey,
+        scheduled_at: i64,
+    ) -> Result<()> {
+        let session = &mut ctx.accounts.tele_session;
+        session.patient_data = ctx.accounts.patient_data.key();
+        session.provider = provider;
+        session.session_id = session_id;
+        session.scheduled_at = scheduled_at;
+        session.closed_at = 0;
+        session.status = SESSION_OPEN;
+        session.note_entry_ids = Vec::new();
+        session.bump = ctx.bumps.tele_session;
+
+        emit!(TeleSessionOpenedEvent {
+            patient_data: session.patient_data,
+            session: session.key(),
+            provider,
+            scheduled_at,
+        });
+        Ok(())
+    }
+
+    /// Documents an open telemedicine session with an encrypted visit note.
+    ///
+    /// The note is appended to the patient record as an `ENTRY_KIND_VISIT_NOTE` entry, exactly
+    /// as with `append_entry`, and its id is linked from the session. Only the session's
+    /// provider may write, under a credential NFT for `role`.
+    ///
+    /// # Arguments
+    /// * `role` - Role the provider writes under (`ROLE_*`)
+    /// * `payload` - Encrypted note fields with the key and nonce they were encrypted with
+    pub fn append_session_note(
+        ctx: Context<AppendSessionNote>,
+        role: u8,
+        payload: EncryptedEntry,
+    ) -> Result<()> {
+        let session = &ctx.accounts.tele_session;
+        require!(session.status == SESSION_OPEN, ErrorCode::SessionClosed);
+        require!(
+            session.note_entry_ids.len() < MAX_SESSION_NOTES,
+            ErrorCode::TooManySessionNotes
+        );
+        require_keys_eq!(
+            ctx.accounts.payer.key(),
+            session.provider,
+            ErrorCode::Unauthorized
+        );
+
+        let author = resolve_author(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            role,
+            ctx.accounts.credential_mint.as_ref(),
+            ctx.accounts.credential_token_account.as_ref(),
+        )?;
+
+        let entry_id = write_entry(
+            &ctx.accounts.program_config,
+            &mut ctx.accounts.record_metadata,
+            &mut ctx.accounts.record_entry,
+            author.clone(),
+            ENTRY_KIND_VISIT_NOTE,
+            &payload,
+            None,
+        )?;
+        let entry = &mut ctx.accounts.record_entry;
+        entry.patient_data = ctx.accounts.patient_data.key();
+        entry.bump = ctx.bumps.record_entry;
+        ctx.accounts.tele_session.note_entry_ids.push(entry_id);
+
+        emit!(RecordEntryAppendedEvent {
+            patient_data: entry.patient_data,
+            entry: entry.key(),
+            entry_id,
+            kind: ENTRY_KIND_VISIT_NOTE,
+            author,
+        });
+        Ok(())
+    }
+
+    /// Closes a telemedicine session; callable by the patient or the session's provider.
+    pub fn close_session(ctx: Context<CloseSession>) -> Result<()> {
+        let session = &mut ctx.accounts.tele_session;
+        require!(session.status == SESSION_OPEN, ErrorCode::SessionClosed);
+        let signer = ctx.accounts.signer.key();
+        let (owned, _) = Pubkey::find_program_address(&[b"patient_data", signer.as_ref()], &ID);
+        require!(
+            signer == session.provider || owned == session.patient_data,
+            ErrorCode::Unauthorized
+        );
+
+        session.status = SESSION_CLOSED;
+        session.closed_at = Clock::get()?.unix_timestamp;
+
+        emit!(TeleSessionClosedEvent {
+            patient_data: session.patient_data,
+            session: session.key(),
+            notes: session.note_entry_ids.len() as u8,
+        });
+        Ok(())
+    }
+
+    /// Books an appointment with a provider on the caller's record. Booking counts as the
+    /// patient's confirmation; the provider confirms with `confirm_appointment`.
+    ///
+    /// The appointment's `VisitGrant` is created alongside it, inactive until the provider
+    /// confirms, with its rent paid by the patient.
+    ///
+    /// # Arguments
+    /// * `appointment_id` - Caller-chosen identifier, unique per patient record
+    /// * `provider` - Wallet of the provider holding the visit
+    /// * `starts_at` - Unix timestamp the visit window opens
+    /// * `ends_at` - Unix timestamp the visit window closes
+    pub fn book_appointment(
+        ctx: Context<BookAppointment>,
+        appointment_id: u64,
+        provider: Pubkey,
+        starts_at: i64,
+        ends_at: i64,
+    ) -> Result<()> {
+        require!(
+            starts_at < ends_at && ends_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidAppointment
+        );
+
+        let appointment = &mut ctx.accounts.appointment;
+        appointment.patient_data = ctx.accounts.patient_data.key();
+        appointment.provider = provider;
+        appointment.appointment_id = appointment_id;
+        appointment.starts_at = starts_at;
+        appointment.ends_at = ends_at;
+        appointment.status = APPOINTMENT_BOOKED;
+        appointment.bump = ctx.bumps.appointment;
+
+        ctx.accounts.visit_grant.set_inner(VisitGrant {
+            patient_data: appointment.patient_data,
+            appointment: appointment.key(),
+            provider,
+            rent_payer: ctx.accounts.payer.key(),
+            expires_at: ends_at,
+            active: false,
+            bump: ctx.bumps.visit_grant,
+        });
+
+        emit!(AppointmentUpdatedEvent {
+            patient_data: appointment.patient_data,
+            appointment: appointment.key(),
+            provider,
+            status: APPOINTMENT_BOOKED,
+        });
+        Ok(())
+    }
+
+    /// Confirms a booked appointment as its provider, which activates the appointment's
+    /// `VisitGrant` until the end of the visit window. The grant only covers the visit
+    /// itself: it lets the provider write the visit back with `append_visit_summary`, but
+    /// not share the record, and caregiver delegations are left untouched. It is tracked in
+    /// the patient's `GrantExpiryIndex` and revoked by `cancel_appointment`.
+    pub fn confirm_appointment(ctx: Context<ConfirmAppointment>) -> Result<()> {
+        let appointment = &mut ctx.accounts.appointment;
+        require!(
+            appointment.status == APPOINTMENT_BOOKED
+                && appointment.ends_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidAppointment
+        );
+        appointment.status = APPOINTMENT_CONFIRMED;
+        ctx.accounts.visit_grant.active = true;
+
+        let patient_data = appointment.patient_data;
+        let index = &mut ctx.accounts.grant_expiry_index;
+        index.patient_data = patient_data;
+        index.bump = ctx.bumps.grant_expiry_index;
+        index.upsert(
+            ctx.accounts.visit_grant.key(),
+            GRANT_KIND_VISIT,
+            appointment.ends_at,
+        )?;
+
+        emit!(AppointmentUpdatedEvent {
+            patient_data,
+            appointment: appointment.key(),
+            provider: appointment.provider,
+            status: APPOINTMENT_CONFIRMED,
+        });
+        Ok(())
+    }
+
+    /// Cancels an appointment; callable by the patient or the provider. The appointment's
+    /// `VisitGrant` is closed with its rent returned to the patient who booked it; for a
+    /// confirmed appointment the patient's `GrantExpiryIndex` must also be passed so the
+    /// grant stops being tracked.
+    pub fn cancel_appointment(ctx: Context<CancelAppointment>) -> Result<()> {
+        let appointment = &mut ctx.accounts.appointment;
+        let signer = ctx.accounts.signer.key();
+        let (owned, _) = Pubkey::find_program_address(&[b"patient_data", signer.as_ref()], &ID);
+        require!(
+            signer == appointment.provider || owned == appointment.patient_data,
+            ErrorCode::Unauthorized
+        );
+        require!(
+            appointment.status != APPOINTMENT_CANCELLED,
+            ErrorCode::InvalidAppointment
+        );
+
+        if appointment.status == APPOINTMENT_CONFIRMED {
+            ctx.accounts
+                .grant_expiry_index
+                .as_deref_mut()
+                .ok_or(ErrorCode::InvalidAppointment)?
+                .remove(&ctx.accounts.visit_grant.key());
+        }
+        appointment.status = APPOINTMENT_CANCELLED;
+
+        emit!(AppointmentUpdatedEvent {
+            patient_data: appointment.patient_data,
+            appointment: appointment.key(),
+            provider: appointment.provider,
+            status: APPOINTMENT_CANCELLED,
+        });
+        Ok(())
+    }
+
+    /// Pays for an open telemedicine session, locking `amount` of `mint` in a `SessionEscrow`
+    /// vault until `settle_session` splits it between the provider and the protocol fee
+    /// destination. A session can be paid once.
+    ///
+    /// # Arguments
+    /// * `amount` - Tokens paid for the visit, in base units of `mint`
+    pub fn pay_for_session(ctx: Context<PayForSession>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.tele_session.status == SESSION_OPEN,
+            ErrorCode::SessionClosed
+        );
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.payer_token_account.to_account_info(),
+                    to: ctx.accounts.session_vault.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let escrow = &mut ctx.accounts.session_escrow;
+        escrow.tele_session = ctx.accounts.tele_session.key();
+        escrow.payer = ctx.accounts.payer.key();
+        escrow.mint = ctx.accounts.mint.key();
+        escrow.vault = ctx.accounts.session_vault.key();
+        escrow.amount = amount;
+        escrow.bump = ctx.bumps.session_escrow;
+
+        emit!(TeleSessionPaidEvent {
+            session: escrow.tele_session,
+            payer: escrow.payer,
+            mint: escrow.mint,
+            amount,
+        });
+        Ok(())
+    }
+
+    /// Releases the payment of a closed telemedicine session: the provider receives it less
+    /// the protocol fee, which goes to the fee destination's token account. The escrow and
+    /// its vault are closed to the payer. Callable by anyone, so it can be sent in the same
+    /// transaction as `close_session`.
+    pub fn settle_session(ctx: Context<SettleSession>) -> Result<()> {
+        require!(
+            ctx.accounts.tele_session.status == SESSION_CLOSED,
+            ErrorCode::SessionNotClosed
+        );
+
+        let escrow = &ctx.accounts.session_escrow;
+        let fee = ctx.accounts.program_config.protocol_fee(escrow.amount);
+        let seeds: &[&[u8]] = &[
+            b"session_escrow",
+            escrow.tele_session.as_ref(),
+            &[escrow.bump],
+        ];
+        for (to, amount) in [
+            (&ctx.accounts.provider_token_account, escrow.amount - fee),
+            (&ctx.accounts.fee_token_account, fee),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: ctx.accounts.session_vault.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: escrow.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                amount,
+            )?;
+        }
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::CloseAccount {
+                account: ctx.accounts.session_vault.to_account_info(),
+                destination: ctx.accounts.payer.to_account_info(),
+                authority: escrow.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+
+        emit!(TeleSessionSettledEvent {
+            session: escrow.tele_session,
+            provider: ctx.accounts.tele_session.provider,
+            provider_amount: escrow.amount - fee,
+            fee,
+        });
+        Ok(())
+    }
+
+    /// Creates the provider registry or changes its registrar. Only the config authority may
+    /// call it.
+    ///
+    /// # Arguments
+    /// * `registrar` - Key allowed to register and deactivate provider credentials
+    pub fn set_registrar(ctx: Context<SetRegistrar>, registrar: Pubkey) -> Result<()> {
+        let registry = &mut ctx.accounts.provider_registry;
+        registry.registrar = registrar;
+        registry.bump = ctx.bumps.provider_registry;
+        Ok(())
+    }
+
+    /// Registers a credential mint as a verified provider credential for `role`.
+    ///
+    /// Role-gated shares only accept credential mints registered here, so a self-made
+    /// 0-decimal mint no longer passes as a license. Re-registering a mint updates its role
+    /// and license and re-activates it. Only the registrar may call it.
+    ///
+    /// # Arguments
+    /// * `mint` - Credential NFT mint
+    /// * `role` - Role the credential grants; must have a `RoleConfig`
+    /// * `license_hash` - Hash of the provider's license document
+    pub fn register_provider(
+        ctx: Context<RegisterProvider>,
+        mint: Pubkey,
+        role: u8,
+        license_hash: [u8; 32],
+    ) -> Result<()> {
+        let record = &mut ctx.accounts.provider_record;
+        if record.mint == Pubkey::default() {
+            let registry = &mut ctx.accounts.provider_registry;
+            registry.provider_count = registry.provider_count.saturating_add(1);
+        }
+        record.mint = mint;
+        record.role = role;
+        record.license_hash = license_hash;
+        record.active = true;
+        record.registered_at = Clock::get()?.unix_timestamp;
+        record.bump = ctx.bumps.provider_record;
+
+        emit!(ProviderRegisteredEvent {
+            mint,
+            role,
+            license_hash,
+        });
+        Ok(())
+    }
+
+    /// Deactivates a provider credential so it can no longer be used for role-gated shares.
+    /// Only the registrar may call it.
+    pub fn deactivate_provider(ctx: Context<DeactivateProvider>, mint: Pubkey) -> Result<()> {
+        ctx.accounts.provider_record.active = false;
+        emit!(ProviderDeactivatedEvent { mint });
+        Ok(())
+    }
+
+    /// Registers the ed25519 key a laboratory signs its results with, or re-activates it.
+    /// Only the registrar may call it.
+    ///
+    /// # Arguments
+    /// * `lab` - Laboratory signing key
+    pub fn register_lab(ctx: Context<RegisterLab>, lab: Pubkey) -> Result<()> {
+        let signer = &mut ctx.accounts.lab_signer;
+        signer.lab = lab;
+        signer.active = true;
+        signer.registered_at = Clock::get()?.unix_timestamp;
+        signer.bump = ctx.bumps.lab_signer;
+
+        emit!(LabRegisteredEvent { lab });
+        Ok(())
+    }
+
+    /// Deactivates a laboratory signing key so it can no longer attest lab results. Existing
+    /// attestations are kept. Only the registrar may call it.
+    pub fn deactivate_lab(ctx: Context<DeactivateLab>, lab: Pubkey) -> Result<()> {
+        ctx.accounts.lab_signer.active = false;
+        emit!(LabDeactivatedEvent { lab });
+        Ok(())
+    }
+
+    /// Records a registered laboratory's signature over the lab section of a record.
+    ///
+    /// The lab signs `patient_data || digest` off-chain, where `digest` is the SHA-256 of
+    /// the record's lab section ciphertexts (`lab_test_count` through `imaging_dates`). The
+    /// transaction carries the signature in an Ed25519 program instruction placed directly
+    /// before this one; the program checks it through the instructions sysvar against the
+    /// digest of the lab section as currently stored, then writes a `LabAttestation` with
+    /// the lab key, slot and digest. Anyone may submit the signature.
+    ///
+    /// # Arguments
+    /// * `lab` - Registered laboratory signing key
+    pub fn attest_lab_results(ctx: Context<AttestLabResults>, lab: Pubkey) -> Result<()> {
+        let patient_data = ctx.accounts.patient_data.key();
+        let digest = {
+            let data = ctx.accounts.patient_data.load()?;
+            let lab_section =
+                offset_of!(PatientData, lab_test_count)..offset_of!(PatientData, canary);
+            hashv(&[&bytemuck::bytes_of(&*data)[lab_section]]).to_bytes()
+        };
+        let message = [patient_data.as_ref(), digest.as_ref()].concat();
+        check_ed25519_signature(&ctx.accounts.instructions_sysvar, &lab, &message)?;
+
+        let slot = Clock::get()?.slot;
+        ctx.accounts.lab_attestation.set_inner(LabAttestation {
+            patient_data,
+            lab,
+            slot,
+            digest,
+            bump: ctx.bumps.lab_attestation,
+        });
+
+        emit!(LabResultsAttestedEvent {
+            patient_data,
+            lab,
+            slot,
+            digest,
+        });
+        Ok(())
+    }
+
+    /// Records a credentialed provider's attestation of one section of a record.
+    ///
+    /// The provider vouches for `digest`, a hash of the section contents they reviewed,
+    /// computed off-chain. The `Attestation` links provider, record, section and time, and is
+    /// overwritten when the same provider attests the section again.
+    ///
+    /// # Arguments
+    /// * `section` - A single `SECTION_*` bit
+    /// * `digest` - Hash of the attested section contents
+    pub fn attest_section(
+        ctx: Context<AttestSection>,
+        section: u8,
+        digest: [u8; 32],
+    ) -> Result<()> {
+        require!(section.count_ones() == 1, ErrorCode::InvalidSections);
+        check_credential_record(&ctx.accounts.credential_record)?;
+
+        let patient_data = ctx.accounts.patient_data.key();
+        let provider = ctx.accounts.payer.key();
+        let attested_at = Clock::get()?.unix_timestamp;
+        ctx.accounts.attestation.set_inner(Attestation {
+            patient_data,
+            provider,
+            credential_mint: ctx.accounts.credential_mint.key(),
+            role: ctx.accounts.provider_record.role,
+            section,
+            digest,
+            attested_at,
+            bump: ctx.bumps.attestation,
+        });
+
+        emit!(SectionAttestedEvent {
+            patient_data,
+            provider,
+            section,
+            digest,
+            attested_at,
+        });
+        Ok(())
+    }
+
+    /// Submits an insurance claim for care given to a patient, encrypted by the provider for
+    /// the MPC network.
+    ///
+    /// The caller must hold a valid provider credential of any role but `ROLE_INSURER`. The
+    /// claim is checked against the record with `verify_claim_consistency` and then decided
+    /// by `insurer` with `adjudicate_claim`; neither step discloses the claim or the record.
+    ///
+    /// # Arguments
+    /// * `claim_id` - Provider-chosen identifier of the claim
+    /// * `insurer` - Wallet of the insurer the claim is submitted to
+    /// * `encryption_key` - x25519 public key the claim fields are encrypted with
+    /// * `nonce` - Nonce the claim fields are encrypted with
+    /// * `cpt_codes_ct` - Encrypted CPT codes of the billed lines, 0 for unused lines
+    /// * `amount_ct` - Encrypted billed amount in cents
+    /// * `diagnosis_ct` - Encrypted ICD-10 code of the primary diagnosis
+    #[allow(clippy::too_many_arguments)]
+    pub fn submit_claim(
+        ctx: Context<SubmitClaim>,
+        claim_id: u64,
+        insurer: Pubkey,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        cpt_codes_ct: [[u8; 32]; CLAIM_LINES],
+        amount_ct: [u8; 32],
+        diagnosis_ct: [u8; 32],
+    ) -> Result<()> {
+        check_credential_record(&ctx.accounts.credential_record)?;
+        require!(
+            ctx.accounts.provider_record.role != ROLE_INSURER,
+            ErrorCode::CredentialRoleMismatch
+        );
+
+        let patient_data = ctx.accounts.patient_data.key();
+        let provider = ctx.accounts.payer.key();
+        ctx.accounts.claim.set_inner(Claim {
+            patient_data,
+            provider,
+            insurer,
+            claim_id,
+            encryption_key,
+            nonce,
+            cpt_codes: cpt_codes_ct,
+            amount: amount_ct,
+            diagnosis: diagnosis_ct,
+            status: CLAIM_STATUS_SUBMITTED,
+            consistency_nonce: 0,
+            consistency_score: [0; 32],
+            submitted_at: Clock::get()?.unix_timestamp,
+            adjudicated_at: 0,
+            bump: ctx.bumps.claim,
+        });
+
+        emit!(ClaimSubmittedEvent {
+            claim: ctx.accounts.claim.key(),
+            patient_data,
+            provider,
+            insurer,
+            claim_id,
+        });
+        Ok(())
+    }
+
+    pub fn init_verify_claim_consistency_comp_def(
+        ctx: Context<InitVerifyClaimConsistencyCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Scores a submitted claim against the patient's stored procedures, imaging, lab tests
+    /// and coded diagnoses inside MPC.
+    ///
+    /// Only the consistency score (the percentage of the claim's checks the record
+    /// supports) is encrypted for `insurer_key`, stored in the claim and emitted in
+    /// `ClaimConsistencyEvent`. Only the claim's insurer, holding a valid `ROLE_INSURER`
+    /// credential, may call it.
+    ///
+    /// # Arguments
+    /// * `insurer_key` - x25519 public key the score is encrypted for
+    /// * `insurer_nonce` - Nonce for the insurer's encryption
+    /// * `sender_pub_key` - x25519 public key the stored record is encrypted with
+    /// * `nonce` - Nonce the stored record is encrypted with
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_claim_consistency(
+        ctx: Context<VerifyClaimConsistency>,
+        computation_offset: u64,
+        insurer_key: [u8; 32],
+        insurer_nonce: u128,
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        check_credential_record(&ctx.accounts.credential_record)?;
+        check_sections_unlocked(
+            &ctx.accounts.section_locks,
+            SECTION_HISTORY | SECTION_IMAGING | SECTION_LAB_TESTS,
+        )?;
+        let claim = &ctx.accounts.claim;
+        require!(
+            claim.status == CLAIM_STATUS_SUBMITTED,
+            ErrorCode::InvalidClaimStatus
+        );
+
+        let mut args = vec![
+            Argument::ArcisPubkey(insurer_key),
+            Argument::PlaintextU128(insurer_nonce),
+            Argument::ArcisPubkey(claim.encryption_key),
+            Argument::PlaintextU128(claim.nonce),
+        ];
+        args.extend(
+            claim
+                .cpt_codes
+                .iter()
+                .map(|code| Argument::EncryptedU32(*code)),
+        );
+        args.extend([
+            Argument::EncryptedU64(claim.amount),
+            Argument::EncryptedU64(claim.diagnosis),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+        ]);
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let claim = ctx.accounts.claim.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![VerifyClaimConsistencyCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: claim,
+                    is_writable: true,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    /// Stores the encrypted consistency score in the claim, making it ready for
+    /// `adjudicate_claim`.
+    #[arcium_callback(encrypted_ix = "verify_claim_consistency")]
+    pub fn verify_claim_consistency_callback(
+        ctx: Context<VerifyClaimConsistencyCallback>,
+        output: ComputationOutputs<VerifyClaimConsistencyOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(VerifyClaimConsistencyOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let claim = &mut ctx.accounts.claim;
+        claim.consistency_nonce = result.nonce;
+        claim.consistency_score = result.ciphertexts[0];
+        claim.status = CLAIM_STATUS_VERIFIED;
+
+        emit!(ClaimConsistencyEvent {
+            claim: claim.key(),
+            nonce: result.nonce.to_le_bytes(),
+            score: result.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    /// Approves or denies a claim once its consistency score is available. Only the claim's
+    /// insurer, holding a valid `ROLE_INSURER` credential, may call it.
+    pub fn adjudicate_claim(ctx: Context<AdjudicateClaim>, approved: bool) -> Result<()> {
+        check_credential_record(&ctx.accounts.credential_record)?;
+        let claim = &mut ctx.accounts.claim;
+        require!(
+            claim.status == CLAIM_STATUS_VERIFIED,
+            ErrorCode::InvalidClaimStatus
+        );
+        claim.status = if approved {
+            CLAIM_STATUS_APPROVED
+        } else {
+            CLAIM_STATUS_DENIED
+        };
+        claim.adjudicated_at = Clock::get()?.unix_timestamp;
+
+        emit!(ClaimAdjudicatedEvent {
+            claim: claim.key(),
+            insurer: claim.insurer,
+            approved,
+        });
+        Ok(())
+    }
+
+    /// Publishes or updates one of the caller's pre-authorization policies, evaluated by
+    /// `check_coverage`. Conditions are medical history flag indices; the masks hold one bit
+    /// per flag. Only holders of a valid `ROLE_INSURER` credential may call it.
+    ///
+    /// # Arguments
+    /// * `policy_id` - Insurer-chosen identifier of the policy
+    /// * `insurer_key` - x25519 public key decisions are encrypted for
+    /// * `required_condition` - Flag the patient must have, or `ANY_CONDITION`
+    /// * `excluded_conditions` - Flags denying coverage
+    /// * `review_conditions` - Flags sending the request to review
+    /// * `max_procedures` - Recorded procedures above which the request goes to review
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_coverage_policy(
+        ctx: Context<SetCoveragePolicy>,
+        policy_id: u64,
+        insurer_key: [u8; 32],
+        required_condition: u8,
+        excluded_conditions: u16,
+        review_conditions: u16,
+        max_procedures: u8,
+    ) -> Result<()> {
+        check_credential_record(&ctx.accounts.credential_record)?;
+        require!(
+            (required_condition < 10 || required_condition == ANY_CONDITION)
+                && (excluded_conditions | review_conditions) >> 10 == 0,
+            ErrorCode::InvalidCoveragePolicy
+        );
+        ctx.accounts.coverage_policy.set_inner(CoveragePolicy {
+            insurer: ctx.accounts.payer.key(),
+            policy_id,
+            insurer_key,
+            required_condition,
+            excluded_conditions,
+            review_conditions,
+            max_procedures,
+            bump: ctx.bumps.coverage_policy,
+        });
+        Ok(())
+    }
+
+    pub fn init_check_coverage_comp_def(ctx: Context<InitCheckCoverageCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Checks a pre-authorization request against an insurer's `CoveragePolicy` without the
+    /// insurer seeing the record.
+    ///
+    /// The MPC network evaluates the policy on the record's diagnoses and procedures, and
+    /// only the `COVERAGE_*` decision is encrypted for both the insurer's policy key and
+    /// `patient_key`, then emitted in `CoverageCheckedEvent`. Authorized like
+    /// `share_patient_data` (patient or a delegate with the share scope).
+    ///
+    /// # Arguments
+    /// * `patient_key` - x25519 public key the patient's copy of the decision is encrypted for
+    /// * `patient_nonce` - Nonce for the patient's copy
+    /// * `insurer_nonce` - Nonce for the insurer's copy
+    /// * `sender_pub_key` - x25519 public key the stored record is encrypted with
+    /// * `nonce` - Nonce the stored record is encrypted with
+    #[allow(clippy::too_many_arguments)]
+    pub fn check_coverage(
+        ctx: Context<CheckCoverage>,
+        computation_offset: u64,
+        patient_key: [u8; 32],
+        patient_nonce: u128,
+        insurer_nonce: u128,
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, SECTION_HISTORY)?;
+
+        let policy = &ctx.accounts.coverage_policy;
+        let args = vec![
+            Argument::ArcisPubkey(policy.insurer_key),
+            Argument::PlaintextU128(insurer_nonce),
+            Argument::ArcisPubkey(patient_key),
+            Argument::PlaintextU128(patient_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::PlaintextU8(policy.required_condition),
+            Argument::PlaintextU16(policy.excluded_conditions),
+            Argument::PlaintextU16(policy.review_conditions),
+            Argument::PlaintextU8(policy.max_procedures),
+        ];
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let patient_data = ctx.accounts.patient_data.key();
+        let coverage_policy = ctx.accounts.coverage_policy.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CheckCoverageCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: patient_data,
+                    is_writable: false,
+                },
+                CallbackAccount {
+                    pubkey: coverage_policy,
+                    is_writable: false,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_coverage")]
+    pub fn check_coverage_callback(
+        ctx: Context<CheckCoverageCallback>,
+        output: ComputationOutputs<CheckCoverageOutput>,
+    ) -> Result<()> {
+        let (insurer, patient) = match output {
+            ComputationOutputs::Success(CheckCoverageOutput {
+                field_0:
+                    CheckCoverageOutputStruct0 {
+                        field_0: insurer,
+                        field_1: patient,
+                    },
+            }) => (insurer, patient),
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(CoverageCheckedEvent {
+            patient_data: ctx.accounts.patient_data.key(),
+            coverage_policy: ctx.accounts.coverage_policy.key(),
+            insurer_nonce: insurer.nonce.to_le_bytes(),
+            insurer_decision: insurer.ciphertexts[0],
+            patient_nonce: patient.nonce.to_le_bytes(),
+            patient_decision: patient.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    /// Asks two or more specialists for independent opinions on the caller's record.
+    ///
+    /// The record sections the specialists need are shared to each of them with
+    /// `share_patient_data_scoped`; each then files an encrypted verdict with
+    /// `submit_opinion`, and `compare_opinions` tells the patient whether two verdicts agree.
+    ///
+    /// # Arguments
+    /// * `request_id` - Caller-chosen identifier, unique per patient record
+    /// * `specialists` - Distinct wallets of the specialists asked
+    pub fn request_second_opinion(
+        ctx: Context<RequestSecondOpinion>,
+        request_id: u64,
+        specialists: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            (2..=MAX_OPINION_SPECIALISTS).contains(&specialists.len())
+                && specialists
+                    .iter()
+                    .enumerate()
+                    .all(|(i, s)| !specialists[..i].contains(s)),
+            ErrorCode::InvalidSecondOpinion
+        );
+
+        let request = &mut ctx.accounts.second_opinion;
+        request.patient_data = ctx.accounts.patient_data.key();
+        request.request_id = request_id;
+        request.specialists = specialists.clone();
+        request.opinions = 0;
+        request.bump = ctx.bumps.second_opinion;
+
+        emit!(SecondOpinionRequestedEvent {
+            patient_data: request.patient_data,
+            second_opinion: request.key(),
+            specialists,
+        });
+        Ok(())
+    }
+
+    /// Files the caller's opinion on a second-opinion request. The verdict is a code agreed
+    /// upon out of band (e.g. a diagnosis or recommended treatment), encrypted for the MXE so
+    /// `compare_opinions` can compare it with the others. Only the specialists asked may call
+    /// it, once each.
+    ///
+    /// # Arguments
+    /// * `encryption_key` - x25519 public key the verdict is encrypted with
+    /// * `nonce` - Nonce the verdict is encrypted with
+    /// * `verdict` - Encrypted verdict code
+    pub fn submit_opinion(
+        ctx: Context<SubmitOpinion>,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        verdict: [u8; 32],
+    ) -> Result<()> {
+        let specialist = ctx.accounts.payer.key();
+        let request = &ctx.accounts.second_opinion;
+        require!(
+            request.specialists.contains(&specialist),
+            ErrorCode::Unauthorized
+        );
+
+        ctx.accounts.opinion.set_inner(Opinion {
+            second_opinion: ctx.accounts.second_opinion.key(),
+            specialist,
+            encryption_key,
+            nonce,
+            verdict,
+            submitted_at: Clock::get()?.unix_timestamp,
+            bump: ctx.bumps.opinion,
+        });
+        ctx.accounts.second_opinion.opinions += 1;
+
+        emit!(OpinionSubmittedEvent {
+            second_opinion: ctx.accounts.second_opinion.key(),
+            opinion: ctx.accounts.opinion.key(),
+            specialist,
+        });
+        Ok(())
+    }
+
+    pub fn init_compare_opinions_comp_def(ctx: Context<InitCompareOpinionsCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Compares two opinions filed on the caller's second-opinion request. Only whether the
+    /// verdicts agree is encrypted for `patient_key` and emitted in `OpinionsComparedEvent`.
+    ///
+    /// # Arguments
+    /// * `patient_key` - x25519 public key the result is encrypted for
+    /// * `patient_nonce` - Nonce for the result
+    pub fn compare_opinions(
+        ctx: Context<CompareOpinions>,
+        computation_offset: u64,
+        patient_key: [u8; 32],
+        patient_nonce: u128,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require_keys_neq!(
+            ctx.accounts.first_opinion.key(),
+            ctx.accounts.other_opinion.key(),
+            ErrorCode::InvalidSecondOpinion
+        );
+
+        let (first, other) = (&ctx.accounts.first_opinion, &ctx.accounts.other_opinion);
+        let args = vec![
+            Argument::ArcisPubkey(patient_key),
+            Argument::PlaintextU128(patient_nonce),
+            Argument::ArcisPubkey(first.encryption_key),
+            Argument::PlaintextU128(first.nonce),
+            Argument::EncryptedU8(first.verdict),
+            Argument::ArcisPubkey(other.encryption_key),
+            Argument::PlaintextU128(other.nonce),
+            Argument::EncryptedU8(other.verdict),
+        ];
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let second_opinion = ctx.accounts.second_opinion.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CompareOpinionsCallback::callback_ix(&[CallbackAccount {
+                pubkey: second_opinion,
+                is_writable: false,
+            }])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "compare_opinions")]
+    pub fn compare_opinions_callback(
+        ctx: Context<CompareOpinionsCallback>,
+        output: ComputationOutputs<CompareOpinionsOutput>,
+    ) -> Result<()> {
+        let result = match output {
+            ComputationOutputs::Success(CompareOpinionsOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(OpinionsComparedEvent {
+            second_opinion: ctx.accounts.second_opinion.key(),
+            nonce: result.nonce.to_le_bytes(),
+            agree: result.ciphertexts[0],
+        });
+        Ok(())
+    }
+
+    /// Creates a care team for the caller's record.
+    ///
+    /// Records shared to the team with `share_to_care_team` are encrypted under the MXE's own
+    /// key and only released to a member once `threshold` members approved their access, so
+    /// no single member can pull the record on their own.
+    ///
+    /// # Arguments
+    /// * `team_id` - Caller-chosen identifier, unique per patient record
+    /// * `members` - Distinct wallets of the team members
+    /// * `threshold` - Member approvals needed to release the record, at least 2
+    pub fn create_care_team(
+        ctx: Context<CreateCareTeam>,
+        team_id: u64,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(
+            members.len() <= MAX_CARE_TEAM_MEMBERS
+                && (2..=members.len()).contains(&(threshold as usize))
+                && members
+                    .iter()
+                    .enumerate()
+                    .all(|(i, m)| !members[..i].contains(m)),
+            ErrorCode::InvalidCareTeam
+        );
+
+        let team = &mut ctx.accounts.care_team;
+        team.patient_data = ctx.accounts.patient_data.key();
+        team.team_id = team_id;
+        team.members = members.clone();
+        team.threshold = threshold;
+        team.bump = ctx.bumps.care_team;
+
+        emit!(CareTeamCreatedEvent {
+            patient_data: team.patient_data,
+            care_team: team.key(),
+            members,
+            threshold,
+        });
+        Ok(())
+    }
+
+    pub fn init_share_to_care_team_comp_def(
+        ctx: Context<InitShareToCareTeamCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Re-encrypts the caller's record under the MXE's key into a `TeamRecord` of one of
+    /// their care teams, which members then access with `request_team_access`. The team copy
+    /// is not readable by anyone, so receiver gates and access logging apply when a member
+    /// releases it with `release_team_record`.
+    ///
+    /// # Arguments
+    /// * `sender_pub_key` - x25519 public key the stored record is encrypted with
+    /// * `nonce` - Nonce the stored record is encrypted with
+    /// * `team_nonce` - Nonce of the team copy's encryption
+    pub fn share_to_care_team(
+        ctx: Context<ShareToCareTeam>,
+        computation_offset: u64,
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        team_nonce: u128,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        check_sections_unlocked(&ctx.accounts.section_locks, ALL_SECTIONS)?;
+
+        {
+            let mut record = ctx.accounts.team_record.load_init()?;
+            record.care_team = ctx.accounts.care_team.key();
+            record.computation_offset = computation_offset;
+        }
+
+        let args = vec![
+            Argument::PlaintextU128(team_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+        ];
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let team_record = ctx.accounts.team_record.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ShareToCareTeamCallback::callback_ix(&[CallbackAccount {
+                pubkey: team_record,
+                is_writable: true,
+            }])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "share_to_care_team")]
+    pub fn share_to_care_team_callback(
+        ctx: Context<ShareToCareTeamCallback>,
+        output: ComputationOutputs<ShareToCareTeamOutput>,
+    ) -> Result<()> {
+        let shared = match output {
+            ComputationOutputs::Success(ShareToCareTeamOutput { field_0 }) => Box::new(field_0),
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let mut record = ctx.accounts.team_record.load_mut()?;
+        record.nonce = shared.nonce.to_le_bytes();
+        record.ciphertexts.copy_from_slice(&shared.ciphertexts);
+        record.filled = 1;
+
+        emit!(TeamRecordSharedEvent {
+            care_team: record.care_team,
+            team_record: ctx.accounts.team_record.key(),
+        });
+        Ok(())
+    }
+
+    /// Asks for access to a care team's record as one of its members, counting as the
+    /// caller's own approval.
+    pub fn request_team_access(ctx: Context<RequestTeamAccess>) -> Result<()> {
+        let member = ctx.accounts.payer.key();
+        let index = ctx
+            .accounts
+            .care_team
+            .members
+            .iter()
+            .position(|m| *m == member)
+            .ok_or(ErrorCode::Unauthorized)?;
+        require!(
+            ctx.accounts.team_record.load()?.filled == 1,
+            ErrorCode::TeamRecordNotReady
+        );
+
+        let access = &mut ctx.accounts.team_access;
+        access.care_team = ctx.accounts.care_team.key();
+        access.team_record = ctx.accounts.team_record.key();
+        access.member = member;
+        access.approvals = 1 << index;
+        access.bump = ctx.bumps.team_access;
+
+        emit!(TeamAccessApprovedEvent {
+            team_access: access.key(),
+            member,
+            approver: member,
+            approvals: 1,
+        });
+        Ok(())
+    }
+
+    /// Approves another member's pending access to a care team's record.
+    pub fn approve_team_access(ctx: Context<ApproveTeamAccess>) -> Result<()> {
+        let approver = ctx.accounts.approver.key();
+        let index = ctx
+            .accounts
+            .care_team
+            .members
+            .iter()
+            .position(|m| *m == approver)
+            .ok_or(ErrorCode::Unauthorized)?;
+
+        let access = &mut ctx.accounts.team_access;
+        access.approvals |= 1 << index;
+
+        emit!(TeamAccessApprovedEvent {
+            team_access: access.key(),
+            member: access.member,
+            approver,
+            approvals: access.approvals.count_ones() as u8,
+        });
+        Ok(())
+    }
+
+    pub fn init_release_team_record_comp_def(
+        ctx: Context<InitReleaseTeamRecordCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Re-encrypts a care team's record for the calling member once their access has been
+    /// approved by the team's threshold of members. The approvals are consumed: the access
+    /// request is closed to the member, and the record is delivered in
+    /// `ReceivedPatientDataEvent`s. `member_key` goes through the same revocation, allowlist
+    /// and jurisdiction checks as a `share_patient_data` receiver, and the release is written
+    /// to the patient's access log.
+    ///
+    /// # Arguments
+    /// * `member_key` - x25519 public key the record is re-encrypted for
+    /// * `member_nonce` - Nonce for the member's copy
+    pub fn release_team_record(
+        ctx: Context<ReleaseTeamRecord>,
+        computation_offset: u64,
+        member_key: [u8; 32],
+        member_nonce: u128,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        let approvals = ctx.accounts.team_access.approvals.count_ones();
+        require!(
+            approvals >= ctx.accounts.care_team.threshold as u32,
+            ErrorCode::TeamAccessNotApproved
+        );
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_receiver_allowlist(&ctx.accounts.receiver_allowlist, &member_key)?;
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            member_key,
+            ctx.accounts.payer.key(),
+            ROLE_PROVIDER,
+            PURPOSE_TREATMENT,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+
+        let team_nonce = u128::from_le_bytes(ctx.accounts.team_record.load()?.nonce);
+        let args = vec![
+            Argument::ArcisPubkey(member_key),
+            Argument::PlaintextU128(member_nonce),
+            Argument::PlaintextU128(team_nonce),
+            Argument::Account(
+                ctx.accounts.team_record.key(),
+                TEAM_RECORD_CIPHERTEXTS_OFFSET,
+                PATIENT_CIPHERTEXTS_LEN,
+            ),
+        ];
+
+        emit!(TeamRecordReleasedEvent {
+            team_record: ctx.accounts.team_record.key(),
+            member: ctx.accounts.payer.key(),
+            computation_offset,
+        });
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ReleaseTeamRecordCallback::callback_ix(&[])],
+        )?;
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "release_team_record")]
+    pub fn release_team_record_callback(
+        ctx: Context<ReleaseTeamRecordCallback>,
+        output: ComputationOutputs<ReleaseTeamRecordOutput>,
+    ) -> Result<()> {
+        let released = match output {
+            ComputationOutputs::Success(ReleaseTeamRecordOutput { field_0 }) => Box::new(field_0),
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit_received_patient_data(released.nonce.to_le_bytes(), &released.ciphertexts);
+        Ok(())
+    }
+
+    /// Issues or renews the validity record of a credential mint.
+    ///
+    /// Every role-gated share checks this record, so a lapsed license stops working at
+    /// `expires_at` without further action. Re-issuing a revoked credential clears the
+    /// revocation. Only the registrar may call it.
+    ///
+    /// # Arguments
+    /// * `mint` - Credential NFT mint
+    /// * `expires_at` - Unix timestamp at which the license lapses
+    pub fn issue_credential(
+        ctx: Context<IssueCredential>,
+        mint: Pubkey,
+        expires_at: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at > now, ErrorCode::CredentialExpired);
+        let record = &mut ctx.accounts.credential_record;
+        record.mint = mint;
+        record.expires_at = expires_at;
+        record.revoked = false;
+        record.issued_at = now;
+        record.bump = ctx.bumps.credential_record;
+
+        emit!(CredentialIssuedEvent { mint, expires_at });
+        Ok(())
+    }
+
+    /// Revokes a credential, e.g. after a license suspension. Only the registrar may call it.
+    pub fn revoke_credential(ctx: Context<RevokeCredential>, mint: Pubkey) -> Result<()> {
+        ctx.accounts.credential_record.revoked = true;
+        emit!(CredentialRevokedEvent { mint });
+        Ok(())
+    }
+
+    /// Creates an organization (hospital, clinic) managed by the caller.
+    ///
+    /// Patients grant access to the organization by delegating to its account with
+    /// `delegate_authority`; its members can then share on the patient's behalf, so staff
+    /// changes never require patients to re-grant access.
+    ///
+    /// # Arguments
+    /// * `org_id` - Caller-chosen identifier, unique per authority
+    /// * `credential_mint` - Organization-level credential NFT mint issued to staff
+    pub fn create_organization(
+        ctx: Context<CreateOrganization>,
+        org_id: u64,
+        credential_mint: Pubkey,
+    ) -> Result<()> {
+        let organization = &mut ctx.accounts.organization;
+        organization.authority = ctx.accounts.authority.key();
+        organization.org_id = org_id;
+        organization.credential_mint = credential_mint;
+        organization.members = Vec::new();
+        organization.bump = ctx.bumps.organization;
+        Ok(())
+    }
+
+    /// Adds a provider to an organization. Only the organization authority may call it.
+    pub fn add_member(ctx: Context<UpdateOrganization>, member: Pubkey) -> Result<()> {
+        let organization = &mut ctx.accounts.organization;
+        require!(
+            !organization.members.contains(&member),
+            ErrorCode::AlreadyOrganizationMember
+        );
+        require!(
+            organization.members.len() < MAX_ORGANIZATION_MEMBERS,
+            ErrorCode::TooManyOrganizationMembers
+        );
+        organization.members.push(member);
+
+        emit!(OrganizationMemberEvent {
+            organization: organization.key(),
+            member,
+            added: true,
+        });
+        Ok(())
+    }
+
+    /// Removes a provider from an organization. Only the organization authority may call it.
+    pub fn remove_member(ctx: Context<UpdateOrganization>, member: Pubkey) -> Result<()> {
+        let organization = &mut ctx.accounts.organization;
+        require!(
+            organization.members.contains(&member),
+            ErrorCode::NotOrganizationMember
+        );
+        organization.members.retain(|m| *m != member);
+
+        emit!(OrganizationMemberEvent {
+            organization: organization.key(),
+            member,
+            added: false,
+        });
+        Ok(())
+    }
+
+    /// Receives the role-scoped record once the `share_patient_data_scoped` computation
+    /// completes; stored, emitted and tracked in the share's `ShareStatus`, when it has
+    /// one, like `share_patient_data_callback`. For a paid
+    /// request, also releases its escrow to the patient, or, if the computation aborted,
+    /// unlocks it so the requester can get a refund through `cancel_request` right away.
+    #[arcium_callback(encrypted_ix = "share_patient_data_scoped")]
+    pub fn share_patient_data_scoped_callback(
+        ctx: Context<SharePatientDataScopedCallback>,
+        output: ComputationOutputs<SharePatientDataScopedOutput>,
+    ) -> Result<()> {
+        let shared = match output {
+            ComputationOutputs::Success(SharePatientDataScopedOutput { field_0 }) => {
+                Box::new(field_0)
+            }
+            _ => {
+                if let Some(escrow) = ctx.accounts.access_escrow.as_mut() {
+                    let computation_offset = ctx.accounts.shared_record.load()?.computation_offset;
+                    if !escrow.released && escrow.computation_offset == computation_offset {
+                        escrow.approved_at = 0;
+                    }
+                }
+                let reason = ErrorCode::AbortedComputation.into();
+                let share_status = ctx.accounts.share_status.as_mut();
+                let shared_record = &ctx.accounts.shared_record;
+                resolve_share_status(share_status, shared_record, SHARE_STATUS_FAILED, reason)?;
+                return emit_share_failed(&ctx.accounts.shared_record, reason);
+            }
+        };
+
+        let nonce = shared.nonce.to_le_bytes();
+        {
+            let mut record = ctx.accounts.shared_record.load_mut()?;
+            record.nonce = nonce;
+            record.ciphertexts.copy_from_slice(&shared.ciphertexts);
+            record.filled = 1;
+        }
+        if let Some(entry) = ctx.accounts.inbox_entry.as_mut() {
+            entry.delivered_at = Clock::get()?.unix_timestamp;
+        }
+        let share_status = ctx.accounts.share_status.as_mut();
+        resolve_share_status(
+            share_status,
+            &ctx.accounts.shared_record,
+            SHARE_STATUS_COMPLETED,
+            0,
+        )?;
+        if let Some(escrow) = ctx.accounts.access_escrow.as_mut() {
+            let computation_offset = ctx.accounts.shared_record.load()?.computation_offset;
+            require!(
+                !escrow.released && escrow.computation_offset == computation_offset,
+                ErrorCode::InvalidPayee
+            );
+            let (Some(vault), Some(payee), Some(token_program)) = (
+                ctx.accounts.escrow_vault.as_ref(),
+                ctx.accounts.patient_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            ) else {
+                return err!(ErrorCode::InvalidPayee);
+            };
+            require_keys_eq!(vault.key(), escrow.vault, ErrorCode::InvalidPayee);
+            require_keys_eq!(payee.key(), escrow.payee, ErrorCode::InvalidPayee);
+            escrow_transfer(escrow, vault, payee, token_program, escrow.amount)?;
+            escrow.released = true;
+        }
+
+        emit_received_patient_data(nonce, &shared.ciphertexts);
+        emit_share_completed(&ctx.accounts.shared_record)
+    }
+
+    pub fn init_share_selected_fields_comp_def(
+        ctx: Context<InitShareSelectedFieldsCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Shares only the record fields selected by `mask` with a receiver; every other field
+    /// arrives as an encrypted zero, so e.g. a pharmacist can get allergies and medications
+    /// without the rest of the history.
+    ///
+    /// Bit i of `mask` selects the i-th field of `PatientData`, arrays counting as one field
+    /// (see `SELECTABLE_FIELDS`). The patient can share any fields; a delegate can only share
+    /// fields the patient consented to for this receiver with `grant_field_consent`, failing
+    /// with `FieldsNotConsented` otherwise. Revocation, allowlist, nonce and audit rules are
+    /// those of `share_patient_data`, and the result is stored in the share's `SharedRecord`.
+    ///
+    /// # Arguments
+    /// * `mask` - Fields to disclose, a non-zero subset of `ALL_SELECTABLE_FIELDS`
+    #[allow(clippy::too_many_arguments)]
+    pub fn share_selected_fields(
+        ctx: Context<ShareSelectedFields>,
+        computation_offset: u64,
+        receiver: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        mask: u128,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(
+            mask != 0 && mask & !ALL_SELECTABLE_FIELDS == 0,
+            ErrorCode::InvalidFieldMask
+        );
+        check_sections_unlocked(&ctx.accounts.section_locks, selected_field_sections(mask))?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        if role != ROLE_PATIENT {
+            let consent = ctx
+                .accounts
+                .field_consent
+                .as_ref()
+                .ok_or(ErrorCode::FieldsNotConsented)?;
+            require!(
+                consent.field_mask & mask == mask,
+                ErrorCode::FieldsNotConsented
+            );
+        }
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_receiver_allowlist(&ctx.accounts.receiver_allowlist, &receiver)?;
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::PlaintextU128(mask),
+        ];
+
+        init_shared_record(
+            &ctx.accounts.shared_record,
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.receiver_authority.key(),
+            ctx.accounts.payer.key(),
+            computation_offset,
+        )?;
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.accounts.payer.key(),
+            role,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let entry = new_inbox_entry(
+            receiver,
+            ctx.accounts.receiver_authority.key(),
+            ctx.accounts.payer.key(),
+            ctx.accounts.patient_data.key(),
+            computation_offset,
+            ALL_SECTIONS,
+            ctx.bumps.inbox_entry,
+        )?;
+        ctx.accounts.inbox_entry.set_inner(entry);
+
+        let shared_record = ctx.accounts.shared_record.key();
+        let inbox_entry = ctx.accounts.inbox_entry.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ShareSelectedFieldsCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: shared_record,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: inbox_entry,
+                    is_writable: true,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    /// Receives the field-masked record once the `share_selected_fields` computation
+    /// completes; stored and emitted like `share_patient_data_callback`.
+    #[arcium_callback(encrypted_ix = "share_selected_fields")]
+    pub fn share_selected_fields_callback(
+        ctx: Context<ShareSelectedFieldsCallback>,
+        output: ComputationOutputs<ShareSelectedFieldsOutput>,
+    ) -> Result<()> {
+        let shared = match output {
+            ComputationOutputs::Success(ShareSelectedFieldsOutput { field_0 }) => Box::new(field_0),
+            _ => {
+                let reason = ErrorCode::AbortedComputation.into();
+                return emit_share_failed(&ctx.accounts.shared_record, reason);
+            }
+        };
+
+        let nonce = shared.nonce.to_le_bytes();
+        {
+            let mut record = ctx.accounts.shared_record.load_mut()?;
+            record.nonce = nonce;
+            record.ciphertexts.copy_from_slice(&shared.ciphertexts);
+            record.filled = 1;
+        }
+        if let Some(entry) = ctx.accounts.inbox_entry.as_mut() {
+            entry.delivered_at = Clock::get()?.unix_timestamp;
+        }
+
+        emit_received_patient_data(nonce, &shared.ciphertexts);
+        emit_share_completed(&ctx.accounts.shared_record)
+    }
+
+    pub fn init_pseudonymize_for_research_comp_def(
+        ctx: Context<InitPseudonymizeForResearchCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    /// Exports a de-identified copy of the record to a researcher key: inside MPC the
+    /// patient id is zeroed, age is bucketed into 5-year bands and dates are coarsened to
+    /// month granularity before the record is re-encrypted for `researcher`.
+    ///
+    /// Authorized like `share_patient_data` (patient or a delegate with the share scope) and
+    /// subject to the same revocation, allowlist, nonce and audit rules; the record also
+    /// needs an active research consent covering every scope. The access log entry is
+    /// recorded with `PURPOSE_RESEARCH` and flagged as `de_identified`.
+    pub fn pseudonymize_for_research(
+        ctx: Context<PseudonymizeForResearch>,
+        computation_offset: u64,
+        researcher: [u8; 32],
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        let role = authorize_patient_or_delegate(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.patient_data.key(),
+            ctx.accounts.delegation.as_deref(),
+            DELEGATION_SCOPE_SHARE,
+        )?;
+        ctx.accounts
+            .research_consent
+            .check_active(RESEARCH_SCOPE_ALL)?;
+        check_sections_unlocked(&ctx.accounts.section_locks, ALL_SECTIONS)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_receiver_allowlist(&ctx.accounts.receiver_allowlist, &researcher)?;
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            researcher,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(researcher),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+        ];
+
+        init_shared_record(
+            &ctx.accounts.shared_record,
+            ctx.accounts.patient_data.key(),
+            researcher,
+            ctx.accounts.receiver_authority.key(),
+            ctx.accounts.payer.key(),
+            computation_offset,
+        )?;
+
+        let mut access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            researcher,
+            ctx.accounts.payer.key(),
+            role,
+            PURPOSE_RESEARCH,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        access_log.de_identified = true;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let entry = new_inbox_entry(
+            researcher,
+            ctx.accounts.receiver_authority.key(),
+            ctx.accounts.payer.key(),
+            ctx.accounts.patient_data.key(),
+            computation_offset,
+            ALL_SECTIONS,
+            ctx.bumps.inbox_entry,
+        )?;
+        ctx.accounts.inbox_entry.set_inner(entry);
+
+        let shared_record = ctx.accounts.shared_record.key();
+        let inbox_entry = ctx.accounts.inbox_entry.key();
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![PseudonymizeForResearchCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: shared_record,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: inbox_entry,
+                    is_writable: true,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    /// Receives the de-identified record once the `pseudonymize_for_research` computation
+    /// completes; stored and emitted like `share_patient_data_callback`.
+    #[arcium_callback(encrypted_ix = "pseudonymize_for_research")]
+    pub fn pseudonymize_for_research_callback(
+        ctx: Context<PseudonymizeForResearchCallback>,
+        output: ComputationOutputs<PseudonymizeForResearchOutput>,
+    ) -> Result<()> {
+        let shared = match output {
+            ComputationOutputs::Success(PseudonymizeForResearchOutput { field_0 }) => {
+                Box::new(field_0)
+            }
+            _ => {
+                let reason = ErrorCode::AbortedComputation.into();
+                return emit_share_failed(&ctx.accounts.shared_record, reason);
+            }
+        };
+
+        let nonce = shared.nonce.to_le_bytes();
+        {
+            let mut record = ctx.accounts.shared_record.load_mut()?;
+            record.nonce = nonce;
+            record.ciphertexts.copy_from_slice(&shared.ciphertexts);
+            record.filled = 1;
+        }
+        if let Some(entry) = ctx.accounts.inbox_entry.as_mut() {
+            entry.delivered_at = Clock::get()?.unix_timestamp;
+        }
+
+        emit_received_patient_data(nonce, &shared.ciphertexts);
+        emit_share_completed(&ctx.accounts.shared_record)
+    }
+
+    /// Lets delegates share the fields in `field_mask` of the caller's record with
+    /// `receiver` through `share_selected_fields`, replacing any earlier consent for it.
+    ///
+    /// # Arguments
+    /// * `receiver` - x25519 public key of the recipient
+    /// * `field_mask` - Consented fields, a non-zero subset of `ALL_SELECTABLE_FIELDS`
+    pub fn grant_field_consent(
+        ctx: Context<GrantFieldConsent>,
+        receiver: [u8; 32],
+        field_mask: u128,
+    ) -> Result<()> {
+        require!(
+            field_mask != 0 && field_mask & !ALL_SELECTABLE_FIELDS == 0,
+            ErrorCode::InvalidFieldMask
+        );
+        ctx.accounts.field_consent.set_inner(FieldConsent {
+            patient_data: ctx.accounts.patient_data.key(),
+            receiver,
+            field_mask,
+            bump: ctx.bumps.field_consent,
+        });
+        Ok(())
+    }
+
+    /// Withdraws the field consent for `receiver`, closing it to the patient. Delegates can
+    /// no longer share any fields with the receiver; the patient still can.
+    ///
+    /// # Arguments
+    /// * `receiver` - x25519 public key of the recipient
+    pub fn revoke_field_consent(
+        _ctx: Context<RevokeFieldConsent>,
+        _receiver: [u8; 32],
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Adds a receiver key to the caller's `ReceiverAllowlist`, creating it on first use.
+    ///
+    /// Once the allowlist exists, `share_patient_data` only re-encrypts for keys on it, so
+    /// patients get a default-deny posture for their record.
+    ///
+    /// # Arguments
+    /// * `receiver` - x25519 public key of the recipient to allow
+    pub fn add_receiver(ctx: Context<AddReceiver>, receiver: [u8; 32]) -> Result<()> {
+        let allowlist = &mut ctx.accounts.receiver_allowlist;
+        allowlist.patient_data = ctx.accounts.patient_data.key();
+        allowlist.bump = ctx.bumps.receiver_allowlist;
+        if allowlist.receivers.contains(&receiver) {
+            return Ok(());
+        }
+        require!(
+            allowlist.receivers.len() < MAX_ALLOWLISTED_RECEIVERS,
+            ErrorCode::AllowlistFull
+        );
+        allowlist.receivers.push(receiver);
+        Ok(())
+    }
+
+    /// Removes a receiver key from the caller's `ReceiverAllowlist`. The allowlist stays in
+    /// place even when emptied, so sharing remains denied until a key is added again.
+    ///
+    /// # Arguments
+    /// * `receiver` - x25519 public key of the recipient to remove
+    pub fn remove_receiver(ctx: Context<RemoveReceiver>, receiver: [u8; 32]) -> Result<()> {
+        ctx.accounts
+            .receiver_allowlist
+            .receivers
+            .retain(|allowed| *allowed != receiver);
+        Ok(())
+    }
+
+    /// Tags the caller's record with a data-residency jurisdiction.
+    ///
+    /// While the jurisdiction has a rule set with `set_jurisdiction_rule`, every instruction
+    /// re-encrypting the record (or part of it) for a receiver key fails with
+    /// `JurisdictionNotAllowed` unless the receiver is registered in an allowed
+    /// jurisdiction: full-record, section, entry, encounter, selected-field, research and
+    /// subscription shares, approved requests, break-glass shares and care-team releases.
+    ///
+    /// # Arguments
+    /// * `jurisdiction` - Jurisdiction code, or `JURISDICTION_UNTAGGED` to remove the tag
+    pub fn set_jurisdiction(ctx: Context<SetJurisdiction>, jurisdiction: u16) -> Result<()> {
+        ctx.accounts.patient_data.load_mut()?.jurisdiction = jurisdiction;
+        Ok(())
+    }
+
+    /// Asks a patient to share their record with `receiver`.
+    ///
+    /// Creates a `ShareRequest` the patient answers with `approve_request` or
+    /// `deny_request`, so clinicians no longer coordinate shares off-chain. The caller may
+    /// hold one open request per patient.
+    ///
+    /// # Arguments
+    /// * `patient` - Wallet of the patient whose record is requested
+    /// * `receiver` - x25519 public key the record should be re-encrypted for
+    /// * `purpose_code` - `PURPOSE_*` of the request, recorded in the access log on approval
+    /// * `sections_mask` - `SECTION_*` bits of the record requested
+    pub fn request_access(
+        ctx: Context<RequestAccess>,
+        patient: Pubkey,
+        receiver: [u8; 32],
+        purpose_code: u8,
+        sections_mask: u8,
+    ) -> Result<()> {
+        let request = new_share_request(
+            ctx.accounts.patient_data.key(),
+            ctx.accounts.payer.key(),
+            receiver,
+            purpose_code,
+            sections_mask,
+            ctx.bumps.share_request,
+        )?;
+        ctx.accounts.share_request.set_inner(request);
+
+        emit!(ShareRequestedEvent {
+            patient,
+            request: ctx.accounts.share_request.key(),
+            requester: ctx.accounts.payer.key(),
+            purpose: purpose_code,
+            sections_mask,
+        });
+        Ok(())
+    }
+
+    /// Asks a patient for a share like `request_access`, paying `amount` of `mint` for it.
+    ///
+    /// The payment is locked in an `AccessEscrow` vault and released to the patient's token
+    /// account by the share callback once the re-encryption succeeded. If the patient denies
+    /// the request, or the approved share has not completed `PAID_ACCESS_TIMEOUT` seconds
+    /// later, the requester gets it back with `cancel_request`.
+    ///
+    /// # Arguments
+    /// * `patient` - Wallet of the patient whose record is requested
+    /// * `receiver` - x25519 public key the record should be re-encrypted for
+    /// * `purpose_code` - `PURPOSE_*` of the request, recorded in the access log on approval
+    /// * `sections_mask` - `SECTION_*` bits of the record requested
+    /// * `amount` - Tokens paid for the share, in base units of `mint`
+    pub fn request_paid_access(
+        ctx: Context<RequestPaidAccess>,
+        patient: Pubkey,
+        receiver: [u8; 32],
+        purpose_code: u8,
+        sections_mask: u8,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let request = new_share_request(
+            ctx.accounts.patient_data.key(),
+            ctx.accounts.payer.key(),
+            receiver,
+            purpose_code,
+            sections_mask,
+            ctx.bumps.share_request,
+        )?;
+        ctx.accounts.share_request.set_inner(request);
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.requester_token_account.to_account_info(),
+                    to: ctx.accounts.escrow_vault.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let escrow = &mut ctx.accounts.access_escrow;
+        escrow.patient_data = ctx.accounts.patient_data.key();
+        escrow.requester = ctx.accounts.payer.key();
+        escrow.mint = ctx.accounts.mint.key();
+        escrow.vault = ctx.accounts.escrow_vault.key();
+        escrow.amount = amount;
+        escrow.payee = Pubkey::default();
+        escrow.computation_offset = 0;
+        escrow.created_at = Clock::get()?.unix_timestamp;
+        escrow.approved_at = 0;
+        escrow.released = false;
+        escrow.bump = ctx.bumps.access_escrow;
+
+        emit!(ShareRequestedEvent {
+            patient,
+            request: ctx.accounts.share_request.key(),
+            requester: escrow.requester,
+            purpose: purpose_code,
+            sections_mask,
+        });
+        Ok(())
+    }
+
+    /// Withdraws a paid request and refunds its escrow to the requester, closing the escrow
+    /// and any still pending `ShareRequest`.
+    ///
+    /// A request that was never approved, or whose share computation aborted, can be
+    /// cancelled at any time. Once approved, the payment is refunded only if the share did
+    /// not complete within `PAID_ACCESS_TIMEOUT` seconds; after a successful share the call
+    /// just reclaims the emptied accounts' rent.
+    pub fn cancel_request(ctx: Context<CancelRequest>) -> Result<()> {
+        let escrow = &ctx.accounts.access_escrow;
+        if !escrow.released {
+            if escrow.approved_at != 0 {
+                require!(
+                    Clock::get()?.unix_timestamp >= escrow.approved_at + PAID_ACCESS_TIMEOUT,
+                    ErrorCode::EscrowLocked
+                );
+            }
+            let refund = ctx.accounts.escrow_vault.amount;
+            escrow_transfer(
+                escrow,
+                &ctx.accounts.escrow_vault,
+                &ctx.accounts.requester_token_account,
+                &ctx.accounts.token_program,
+                refund,
+            )?;
+        }
+
+        let seeds: &[&[u8]] = &[
+            b"access_escrow",
+            escrow.patient_data.as_ref(),
+            escrow.requester.as_ref(),
+            &[escrow.bump],
+        ];
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::CloseAccount {
+                account: ctx.accounts.escrow_vault.to_account_info(),
+                destination: ctx.accounts.requester.to_account_info(),
+                authority: escrow.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+
+        // Close the request too if the patient never answered it, so it cannot be approved
+        // without payment later
+        let request = ctx.accounts.share_request.to_account_info();
+        if !request.data_is_empty() {
+            let requester = ctx.accounts.requester.to_account_info();
+            **requester.try_borrow_mut_lamports()? += request.lamports();
+            **request.try_borrow_mut_lamports()? = 0;
+            request.try_borrow_mut_data()?.fill(0);
+            request.assign(&System::id());
+        }
+        Ok(())
+    }
+
+    /// Approves a `ShareRequest`: queues the share of the requested sections to the
+    /// request's receiver and closes the request, returning its rent to the requester.
+    ///
+    /// The requester becomes the receiver authority of the resulting `SharedRecord`. For a
+    /// paid request, pass its `access_escrow` and the patient's token account of the escrow
+    /// mint; the share callback pays the escrow out to it.
+    ///
+    /// # Arguments
+    /// * `sender_pub_key` - Patient's public key the stored record is encrypted with
+    /// * `nonce` - Nonce of the stored record's encryption
+    pub fn approve_request(
+        ctx: Context<ApproveRequest>,
+        computation_offset: u64,
+        sender_pub_key: [u8; 32],
+        nonce: u128,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let request_key = ctx.accounts.share_request.key();
+        let ShareRequest {
+            requester,
+            receiver,
+            purpose,
+            sections_mask,
+            ..
+        } = **ctx.accounts.share_request;
+        check_sections_unlocked(&ctx.accounts.section_locks, sections_mask)?;
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(sender_pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::PlaintextU8(sections_mask),
+        ];
+
+        init_shared_record(
+            &ctx.accounts.shared_record,
+            ctx.accounts.patient_data.key(),
+            receiver,
+            requester,
+            ctx.accounts.payer.key(),
+            computation_offset,
+        )?;
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            requester,
+            ROLE_PROVIDER,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        emit!(ShareRequestResolvedEvent {
+            request: request_key,
+            requester,
+            approved: true,
+        });
+
+        let entry = new_inbox_entry(
+            receiver,
+            requester,
+            ctx.accounts.payer.key(),
+            ctx.accounts.patient_data.key(),
+            computation_offset,
+            sections_mask,
+            ctx.bumps.inbox_entry,
+        )?;
+        ctx.accounts.inbox_entry.set_inner(entry);
+
+        let mut callback_accounts = vec![
+            CallbackAccount {
+                pubkey: ctx.accounts.shared_record.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.inbox_entry.key(),
+                is_writable: true,
+            },
+        ];
+        // A paid request releases its escrow to the patient from the share callback
+        if let Some(escrow) = ctx.accounts.access_escrow.as_deref_mut() {
+            let payee = ctx
+                .accounts
+                .patient_token_account
+                .as_deref()
+                .ok_or(ErrorCode::InvalidPayee)?;
+            require_keys_eq!(payee.owner, ctx.accounts.payer.key(), ErrorCode::InvalidPayee);
+            require_keys_eq!(payee.mint, escrow.mint, ErrorCode::InvalidPayee);
+            escrow.payee = payee.key();
+            escrow.computation_offset = computation_offset;
+            escrow.approved_at = Clock::get()?.unix_timestamp;
+            callback_accounts.extend([
+                // The request share has no `ShareStatus`; the program id marks it absent
+                CallbackAccount {
+                    pubkey: ID,
+                    is_writable: false,
+                },
+                CallbackAccount {
+                    pubkey: escrow.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: escrow.vault,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: escrow.payee,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: anchor_spl::token::ID,
+                    is_writable: false,
+                },
+            ]);
+        }
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![SharePatientDataScopedCallback::callback_ix(&callback_accounts)],
+        )?;
+        Ok(())
+    }
+
+    /// Denies a `ShareRequest` and closes it, returning its rent to the requester.
+    pub fn deny_request(ctx: Context<DenyRequest>) -> Result<()> {
+        emit!(ShareRequestResolvedEvent {
+            request: ctx.accounts.share_request.key(),
+            requester: ctx.accounts.share_request.requester,
+            approved: false,
+        });
+        Ok(())
+    }
+
+    /// Marks an inbox entry consumed by closing it, returning its rent to the account that
+    /// paid for the share. Only the entry's receiver authority may call it.
+    pub fn ack_share(_ctx: Context<AckShare>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Publishes the patient's subscription plan: for `price` lamports per `period`, a
+    /// credentialed provider or insurer may share `sections_mask` of the record to itself
+    /// with `share_with_subscription` without a patient signature per share.
+    ///
+    /// The key and nonce the stored record is encrypted with are registered with the plan so
+    /// subscribers can queue the re-encryption; update them with `create_access_plan` after
+    /// re-encrypting the record.
+    ///
+    /// # Arguments
+    /// * `price` - Lamports paid to the patient per period
+    /// * `period` - Length of a subscription period in seconds
+    /// * `sections_mask` - `SECTION_*` bits subscribers may share
+    /// * `encryption_key` - x25519 public key the stored record is encrypted with
+    /// * `encryption_nonce` - Nonce the stored record is encrypted with
+    pub fn create_access_plan(
+        ctx: Context<CreateAccessPlan>,
+        price: u64,
+        period: i64,
+        sections_mask: u8,
+        encryption_key: [u8; 32],
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        require!(period > 0, ErrorCode::InvalidPeriod);
+        require!(sections_mask != 0, ErrorCode::InvalidSections);
+        ctx.accounts.access_plan.set_inner(AccessPlan {
+            patient_data: ctx.accounts.patient_data.key(),
+            patient: ctx.accounts.payer.key(),
+            price,
+            period,
+            sections_mask,
+            encryption_key,
+            encryption_nonce,
+            bump: ctx.bumps.access_plan,
+        });
+        Ok(())
+    }
+
+    /// Subscribes the caller, who must hold an active registered credential, to a
+    /// patient's access plan for one period, paying the plan price to the patient.
+    ///
+    /// # Arguments
+    /// * `patient` - Wallet of the patient whose plan is subscribed to
+    /// * `receiver` - x25519 public key shares are re-encrypted for
+    pub fn subscribe(
+        ctx: Context<Subscribe>,
+        patient: Pubkey,
+        receiver: [u8; 32],
+    ) -> Result<()> {
+        check_credential_record(&ctx.accounts.credential_record)?;
+        pay_access_plan(
+            &ctx.accounts.access_plan,
+            &ctx.accounts.program_config,
+            &ctx.accounts.payer,
+            &ctx.accounts.patient,
+            &ctx.accounts.fee_destination,
+            &ctx.accounts.system_program,
+        )?;
+
+        let plan = &ctx.accounts.access_plan;
+        let expires_at = Clock::get()?.unix_timestamp.saturating_add(plan.period);
+        ctx.accounts.active_subscription.set_inner(ActiveSubscription {
+            access_plan: plan.key(),
+            subscriber: ctx.accounts.payer.key(),
+            receiver,
+            sections_mask: plan.sections_mask,
+            expires_at,
+            bump: ctx.bumps.active_subscription,
+        });
+
+        emit!(SubscriptionEvent {
+            patient,
+            subscriber: ctx.accounts.payer.key(),
+            expires_at,
+        });
+        Ok(())
+    }
+
+    /// Extends the caller's subscription by one period, paying the plan price again. A
+    /// lapsed subscription restarts from now.
+    pub fn renew_subscription(ctx: Context<RenewSubscription>) -> Result<()> {
+        pay_access_plan(
+            &ctx.accounts.access_plan,
+            &ctx.accounts.program_config,
+            &ctx.accounts.payer,
+            &ctx.accounts.patient,
+            &ctx.accounts.fee_destination,
+            &ctx.accounts.system_program,
+        )?;
+
+        let plan = &ctx.accounts.access_plan;
+        let subscription = &mut ctx.accounts.active_subscription;
+        let now = Clock::get()?.unix_timestamp;
+        subscription.expires_at = subscription.expires_at.max(now).saturating_add(plan.period);
+        subscription.sections_mask = plan.sections_mask;
+
+        emit!(SubscriptionEvent {
+            patient: plan.patient,
+            subscriber: subscription.subscriber,
+            expires_at: subscription.expires_at,
+        });
+        Ok(())
+    }
+
+    /// Ends the caller's subscription and closes it. The current period is not refunded.
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        emit!(SubscriptionEvent {
+            patient: ctx.accounts.access_plan.patient,
+            subscriber: ctx.accounts.payer.key(),
+            expires_at: 0,
+        });
+        Ok(())
+    }
+
+    /// Shares sections of a patient's record to an active subscriber's receiver key without
+    /// a patient signature. The subscriber pays for the share and becomes the receiver
+    /// authority of the resulting `SharedRecord`.
+    ///
+    /// # Arguments
+    /// * `sections` - `SECTION_*` bits to share, within the subscription's sections
+    /// * `purpose` - `PURPOSE_*` code recorded in the access log
+    pub fn share_with_subscription(
+        ctx: Context<ShareWithSubscription>,
+        computation_offset: u64,
+        sections: u8,
+        purpose: u8,
+        cluster_offset: Option<u32>,
+    ) -> Result<()> {
+        check_not_cpi()?;
+        check_cluster(
+            &ctx.accounts.mxe_account,
+            &ctx.accounts.approved_clusters,
+            cluster_offset,
+            &ctx.accounts.cluster_account,
+        )?;
+        check_sections_unlocked(&ctx.accounts.section_locks, sections)?;
+        check_not_paused(&ctx.accounts.program_config)?;
+        require!(
+            ctx.accounts.receiver_revocation.data_is_empty(),
+            ErrorCode::ReceiverRevoked
+        );
+        check_jurisdiction(
+            &ctx.accounts.patient_data,
+            &ctx.accounts.jurisdiction_rules,
+            &ctx.accounts.receiver_jurisdiction,
+        )?;
+
+        let ActiveSubscription {
+            subscriber,
+            receiver,
+            sections_mask,
+            expires_at,
+            ..
+        } = **ctx.accounts.active_subscription;
+        require!(
+            Clock::get()?.unix_timestamp < expires_at,
+            ErrorCode::SubscriptionExpired
+        );
+        require!(
+            sections != 0 && sections & !sections_mask == 0,
+            ErrorCode::InvalidSections
+        );
+
+        let receiver_nonce = ctx.accounts.share_nonce.advance(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            ctx.bumps.share_nonce,
+        )?;
+
+        let args = vec![
+            Argument::ArcisPubkey(receiver),
+            Argument::PlaintextU128(receiver_nonce),
+            Argument::ArcisPubkey(ctx.accounts.access_plan.encryption_key),
+            Argument::PlaintextU128(ctx.accounts.access_plan.encryption_nonce),
+            Argument::Account(ctx.accounts.patient_data.key(), 8, PATIENT_CIPHERTEXTS_LEN),
+            Argument::PlaintextU8(sections),
+        ];
+
+        init_shared_record(
+            &ctx.accounts.shared_record,
+            ctx.accounts.patient_data.key(),
+            receiver,
+            subscriber,
+            subscriber,
+            computation_offset,
+        )?;
+
+        let access_log = log_access(
+            ctx.accounts.patient_data.key(),
+            receiver,
+            subscriber,
+            ROLE_PROVIDER,
+            purpose,
+            computation_offset,
+            ctx.bumps.access_log_entry,
+        )?;
+        ctx.accounts.access_log_entry.set_inner(access_log);
+
+        ctx.accounts.audit_index.record_share(&Clock::get()?)?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let entry = new_inbox_entry(
+            receiver,
+            subscriber,
+            subscriber,
+            ctx.accounts.patient_data.key(),
+            computation_offset,
+            sections,
+            ctx.bumps.inbox_entry,
+        )?;
+        ctx.accounts.inbox_entry.set_inner(entry);
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![SharePatientDataScopedCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.shared_record.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.inbox_entry.key(),
+                    is_writable: true,
+                },
+            ])],
+        )?;
+        Ok(())
+    }
+
+    /// Receives the re-encrypted record once the `share_patient_data` computation completes.
+    ///
+    /// Persists the receiver's ciphertexts into the share's `SharedRecord`, so receivers that
+    /// were offline can still fetch them, and emits them as `ReceivedBasicPatientDataEvent`,
+    /// `ReceivedHealthcareDataEvent`, `ReceivedGenomicDataEvent`, `ReceivedLabTestDataEvent`,
+    /// `ReceivedImmunizationDataEvent` and `ReceivedTerminologyCodesEvent`.
+    /// The output is moved to the heap and each event is emitted from its own stack frame to
+    /// keep stack usage within the BPF limit.
+    ///
+    /// Resolves the share's `ShareStatus`, when it has one, to completed or failed; a late
+    /// callback overrides a timed-out status but leaves a retried share untouched, and a
+    /// status whose computation offset does not match the `SharedRecord` is rejected. An
+    /// aborted computation is recorded with the `AbortedComputation` error code as its
+    /// failure reason and emits `ShareFailedEvent`.
+    #[arcium_callback(encrypted_ix = "share_patient_data")]
+    pub fn share_patient_data_callback(
+        ctx: Context<SharePatientDataCallback>,
+        output: ComputationOutputs<SharePatientDataOutput>,
+    ) -> Result<()> {
+        let shared = match output {
+            ComputationOutputs::Success(SharePatientDataOutput { field_0 }) => Box::new(field_0),
+            _ => {
+                let reason = ErrorCode::AbortedComputation.into();
+                let share_status = ctx.accounts.share_status.as_mut();
+                let shared_record = &ctx.accounts.shared_record;
+                resolve_share_status(share_status, shared_record, SHARE_STATUS_FAILED, reason)?;
+                return emit_share_failed(&ctx.accounts.shared_record, reason);
+            }
+        };
+
+        let nonce = shared.nonce.to_le_bytes();
+        {
+            let mut record = ctx.accounts.shared_record.load_mut()?;
+            record.nonce = nonce;
+            record.ciphertexts.copy_from_slice(&shared.ciphertexts);
+            record.filled = 1;
+        }
+        if let Some(entry) = ctx.accounts.inbox_entry.as_mut() {
+            entry.delivered_at = Clock::get()?.unix_timestamp;
+        }
+        let share_status = ctx.accounts.share_status.as_mut();
+        resolve_share_status(
+            share_status,
+            &ctx.accounts.shared_record,
+            SHARE_STATUS_COMPLETED,
+            0,
+        )?;
+
+        emit_received_patient_data(nonce, &shared.ciphertexts);
+        emit_share_completed(&ctx.accounts.shared_record)
+    }
+}
+
+#[derive(Accounts)]
+pub struct StorePatientData<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<PatientData>(),
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RecordMetadata::INIT_SPACE,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(child_id: u64)]
+pub struct StorePediatricPatientData<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<PatientData>(),
+        seeds = [b"pediatric_patient_data", payer.key().as_ref(), &child_id.to_le_bytes()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RecordMetadata::INIT_SPACE,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Custody::INIT_SPACE,
+        seeds = [b"custody", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub custody: Box<Account<'info, Custody>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + DelegationAccount::INIT_SPACE,
+        seeds = [b"delegation", patient_data.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub delegation: Box<Account<'info, DelegationAccount>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[queue_computation_accounts("share_patient_data", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct SharePatientData<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_PATIENT_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(seeds = [b"comp_def_registry"], bump)]
+    /// CHECK: circuit version registry, only read once a circuit has been upgraded.
+    pub comp_def_registry: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    /// CHECK: wallet allowed to close the shared record; only its key is stored.
+    pub receiver_authority: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<SharedRecord>(),
+        seeds = [
+            b"shared_record",
+            patient_data.key().as_ref(),
+            receiver.as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"extension_registry"],
+        bump,
+    )]
+    /// CHECK: extension registry, only read if extensions have been registered.
+    pub extension_registry: UncheckedAccount<'info>,
+    pub organization: Option<Box<Account<'info, Organization>>>,
+    #[account(
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver allowlist, only read if the patient has created one.
+    pub receiver_allowlist: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + InboxEntry::INIT_SPACE,
+        seeds = [b"inbox_entry", receiver.as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub inbox_entry: Box<Account<'info, InboxEntry>>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(seeds = [b"cpi_allowlist"], bump)]
+    /// CHECK: CPI allowlist, only read when the share is invoked via CPI.
+    pub cpi_allowlist: UncheckedAccount<'info>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ShareStatus::INIT_SPACE,
+        seeds = [b"share_status", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub share_status: Box<Account<'info, ShareStatus>>,
+}
+
+#[queue_computation_accounts("share_patient_data_scoped", payer)]
+#[derive(Accounts)]
+#[instruction(role: u8, computation_offset: u64, receiver: [u8; 32])]
+pub struct SharePatientDataWithRole<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_PATIENT_DATA_SCOPED)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(seeds = [b"comp_def_registry"], bump)]
+    /// CHECK: circuit version registry, only read once a circuit has been upgraded.
+    pub comp_def_registry: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"patient_policy", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: patient policy, only read if the patient has set one.
+    pub patient_policy: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate_limit", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub rate_limit: Box<Account<'info, RateLimit>>,
+
+    // Credential NFT accounts, minted by either the Token or the Token-2022 program
+    #[account(mint::token_program = token_program)]
+    pub credential_mint: Box<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+        token::token_program = token_program,
+    )]
+    pub credential_token_account:
+        Box<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+    #[account(
+        seeds = [
+            b"metadata",
+            anchor_spl::metadata::mpl_token_metadata::ID.as_ref(),
+            credential_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::metadata::mpl_token_metadata::ID,
+    )]
+    pub credential_metadata: Option<Box<Account<'info, anchor_spl::metadata::MetadataAccount>>>,
+    #[account(seeds = [b"program_config"], bump = program_config.bump)]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+    #[account(
+        seeds = [b"role_config", role.to_le_bytes().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Box<Account<'info, RoleConfig>>,
+    #[account(
+        seeds = [b"provider_record", credential_mint.key().as_ref()],
+        bump = provider_record.bump,
+    )]
+    pub provider_record: Box<Account<'info, ProviderRecord>>,
+    pub token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    /// CHECK: wallet allowed to close the shared record; only its key is stored.
+    pub receiver_authority: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<SharedRecord>(),
+        seeds = [
+            b"shared_record",
+            patient_data.key().as_ref(),
+            receiver.as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + InboxEntry::INIT_SPACE,
+        seeds = [b"inbox_entry", receiver.as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub inbox_entry: Box<Account<'info, InboxEntry>>,
+    #[account(
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver allowlist, only read if the patient has created one.
+    pub receiver_allowlist: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ShareStatus::INIT_SPACE,
+        seeds = [b"share_status", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub share_status: Box<Account<'info, ShareStatus>>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(receiver: [u8; 32])]
+pub struct RevokeReceiver<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ReceiverRevocation::INIT_SPACE,
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub receiver_revocation: Account<'info, ReceiverRevocation>,
+}
+
+#[derive(Accounts)]
+#[instruction(receiver: [u8; 32])]
+pub struct ReinstateReceiver<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump = receiver_revocation.bump,
+    )]
+    pub receiver_revocation: Account<'info, ReceiverRevocation>,
+}
+
+#[callback_accounts("share_patient_data")]
+#[derive(Accounts)]
+pub struct SharePatientDataCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_PATIENT_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(mut)]
+    pub inbox_entry: Option<Account<'info, InboxEntry>>,
+    #[account(mut)]
+    pub share_status: Option<Account<'info, ShareStatus>>,
+}
+
+#[callback_accounts("share_patient_data_scoped")]
+#[derive(Accounts)]
+pub struct SharePatientDataScopedCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_PATIENT_DATA_SCOPED)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(mut)]
+    pub inbox_entry: Option<Account<'info, InboxEntry>>,
+    #[account(mut)]
+    pub share_status: Option<Account<'info, ShareStatus>>,
+    #[account(mut)]
+    pub access_escrow: Option<Account<'info, AccessEscrow>>,
+    #[account(mut)]
+    pub escrow_vault: Option<Account<'info, anchor_spl::token::TokenAccount>>,
+    #[account(mut)]
+    pub patient_token_account: Option<Account<'info, anchor_spl::token::TokenAccount>>,
+    pub token_program: Option<Program<'info, anchor_spl::token::Token>>,
+}
+
+#[queue_computation_accounts("share_selected_fields", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareSelectedFields<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_SELECTED_FIELDS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    /// CHECK: wallet allowed to close the shared record; only its key is stored.
+    pub receiver_authority: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<SharedRecord>(),
+        seeds = [
+            b"shared_record",
+            patient_data.key().as_ref(),
+            receiver.as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver allowlist, only read if the patient has created one.
+    pub receiver_allowlist: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + InboxEntry::INIT_SPACE,
+        seeds = [b"inbox_entry", receiver.as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub inbox_entry: Box<Account<'info, InboxEntry>>,
+    #[account(
+        seeds = [b"field_consent", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub field_consent: Option<Box<Account<'info, FieldConsent>>>,
+}
+
+#[callback_accounts("share_selected_fields")]
+#[derive(Accounts)]
+pub struct ShareSelectedFieldsCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_SELECTED_FIELDS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(mut)]
+    pub inbox_entry: Option<Account<'info, InboxEntry>>,
+}
+
+#[init_computation_definition_accounts("share_selected_fields", payer)]
+#[derive(Accounts)]
+pub struct InitShareSelectedFieldsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("pseudonymize_for_research", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, researcher: [u8; 32])]
+pub struct PseudonymizeForResearch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PSEUDONYMIZE_FOR_RESEARCH)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), researcher.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", researcher.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), researcher.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    /// CHECK: wallet allowed to close the shared record; only its key is stored.
+    pub receiver_authority: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<SharedRecord>(),
+        seeds = [
+            b"shared_record",
+            patient_data.key().as_ref(),
+            researcher.as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver allowlist, only read if the patient has created one.
+    pub receiver_allowlist: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"research_consent", patient_data.key().as_ref()],
+        bump = research_consent.bump,
+    )]
+    pub research_consent: Box<Account<'info, ResearchConsent>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + InboxEntry::INIT_SPACE,
+        seeds = [b"inbox_entry", researcher.as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub inbox_entry: Box<Account<'info, InboxEntry>>,
+}
+
+#[callback_accounts("pseudonymize_for_research")]
+#[derive(Accounts)]
+pub struct PseudonymizeForResearchCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PSEUDONYMIZE_FOR_RESEARCH)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(mut)]
+    pub inbox_entry: Option<Account<'info, InboxEntry>>,
+}
+
+#[init_computation_definition_accounts("pseudonymize_for_research", payer)]
+#[derive(Accounts)]
+pub struct InitPseudonymizeForResearchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("share_patient_data_scoped", payer)]
+#[derive(Accounts)]
+pub struct InitSharePatientDataScopedCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSharedRecord<'info> {
+    pub receiver_authority: Signer<'info>,
+    #[account(mut)]
+    /// CHECK: original rent payer, checked against the shared record.
+    pub rent_payer: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = receiver_authority @ ErrorCode::Unauthorized,
+        has_one = rent_payer @ ErrorCode::Unauthorized,
+    )]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+}
+
+#[derive(Accounts)]
+pub struct SetShareRetention<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        has_one = patient_data @ ErrorCode::Unauthorized,
+    )]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireSharedRecord<'info> {
+    #[account(mut)]
+    /// CHECK: original rent payer, checked against the shared record.
+    pub rent_payer: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = rent_payer @ ErrorCode::Unauthorized,
+    )]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TimeOutShare<'info> {
+    #[account(mut)]
+    pub share_status: Account<'info, ShareStatus>,
+}
+
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CancelShare<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"share_status", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump = share_status.bump,
+    )]
+    pub share_status: Account<'info, ShareStatus>,
+    #[account(mut)]
+    /// CHECK: original rent payer, checked against the shared record and inbox entry.
+    pub rent_payer: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = rent_payer @ ErrorCode::Unauthorized,
+    )]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = rent_payer @ ErrorCode::Unauthorized,
+    )]
+    pub inbox_entry: Box<Account<'info, InboxEntry>>,
+}
+
+#[queue_computation_accounts("share_patient_data", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, previous_offset: u64)]
+pub struct RetryShare<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_PATIENT_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(seeds = [b"comp_def_registry"], bump)]
+    /// CHECK: circuit version registry, only read once a circuit has been upgraded.
+    pub comp_def_registry: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"share_status", patient_data.key().as_ref(), &previous_offset.to_le_bytes()],
+        bump = previous_status.bump,
+    )]
+    pub previous_status: Box<Account<'info, ShareStatus>>,
+    #[account(
+        seeds = [b"access_log", patient_data.key().as_ref(), &previous_offset.to_le_bytes()],
+        bump = previous_access_log.bump,
+    )]
+    pub previous_access_log: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        seeds = [
+            b"receiver_revocation",
+            patient_data.key().as_ref(),
+            previous_access_log.receiver.as_ref(),
+        ],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [
+            b"share_nonce",
+            patient_data.key().as_ref(),
+            previous_access_log.receiver.as_ref(),
+        ],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    /// CHECK: wallet allowed to close the shared record; only its key is stored.
+    pub receiver_authority: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<SharedRecord>(),
+        seeds = [
+            b"shared_record",
+            patient_data.key().as_ref(),
+            previous_access_log.receiver.as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + InboxEntry::INIT_SPACE,
+        seeds = [
+            b"inbox_entry",
+            previous_access_log.receiver.as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub inbox_entry: Box<Account<'info, InboxEntry>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ShareStatus::INIT_SPACE,
+        seeds = [b"share_status", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub share_status: Box<Account<'info, ShareStatus>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    pub organization: Option<Box<Account<'info, Organization>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver allowlist, only read if the patient has created one.
+    pub receiver_allowlist: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_jurisdiction", previous_access_log.receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+}
+
+/// Records the outcome of a share in its `ShareStatus`, for shares that track one.
+/// `failure_reason` is the program error code of a failed share, 0 otherwise.
+///
+/// The status must belong to the same share as `shared_record`. Only a queued or timed-out
+/// share is resolved; a share that was already resolved, retried or cancelled keeps its
+/// status.
+fn resolve_share_status(
+    share_status: Option<&mut Account<ShareStatus>>,
+    shared_record: &AccountLoader<SharedRecord>,
+    status: u8,
+    failure_reason: u32,
+) -> Result<()> {
+    if let Some(share_status) = share_status {
+        {
+            let record = shared_record.load()?;
+            require_keys_eq!(
+                share_status.patient_data,
+                record.patient_data,
+                ErrorCode::Unauthorized
+            );
+            require!(
+                share_status.computation_offset == record.computation_offset,
+                ErrorCode::Unauthorized
+            );
+        }
+        if !matches!(
+            share_status.status,
+            SHARE_STATUS_QUEUED | SHARE_STATUS_TIMED_OUT
+        ) {
+            return Ok(());
+        }
+        share_status.status = status;
+        share_status.failure_reason = failure_reason;
+        share_status.resolved_slot = Clock::get()?.slot;
+    }
+    Ok(())
+}
+
+/// Fills in the header of a new `SharedRecord`; the share callback writes the ciphertexts.
+fn init_shared_record(
+    shared_record: &AccountLoader<SharedRecord>,
+    patient_data: Pubkey,
+    receiver: [u8; 32],
+    receiver_authority: Pubkey,
+    rent_payer: Pubkey,
+    computation_offset: u64,
+) -> Result<()> {
+    let mut record = shared_record.load_init()?;
+    record.patient_data = patient_data;
+    record.receiver = receiver;
+    record.receiver_authority = receiver_authority;
+    record.rent_payer = rent_payer;
+    record.computation_offset = computation_offset;
+    record.created_at = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+/// Emits `ShareCompletedEvent` once a share callback has filled its `SharedRecord`.
+fn emit_share_completed(shared_record: &AccountLoader<SharedRecord>) -> Result<()> {
+    let record = shared_record.load()?;
+    emit!(ShareCompletedEvent {
+        schema_version: EVENT_SCHEMA_VERSION,
+        shared_record: shared_record.key(),
+        patient_data: record.patient_data,
+        receiver: record.receiver,
+        computation_offset: record.computation_offset,
+    });
+    Ok(())
+}
+
+/// Emits `ShareFailedEvent` for a share whose computation aborted. The callback succeeds so
+/// the event is kept; the `SharedRecord` stays unfilled.
+fn emit_share_failed(shared_record: &AccountLoader<SharedRecord>, reason: u32) -> Result<()> {
+    let record = shared_record.load()?;
+    emit!(ShareFailedEvent {
+        schema_version: EVENT_SCHEMA_VERSION,
+        shared_record: shared_record.key(),
+        patient_data: record.patient_data,
+        receiver: record.receiver,
+        computation_offset: record.computation_offset,
+        reason,
+    });
+    Ok(())
+}
+
+/// Builds the receiver's `InboxEntry` for a share queued now; the share callback sets
+/// `delivered_at`.
+fn new_inbox_entry(
+    receiver: [u8; 32],
+    receiver_authority: Pubkey,
+    rent_payer: Pubkey,
+    patient_data: Pubkey,
+    computation_offset: u64,
+    sections: u8,
+    bump: u8,
+) -> Result<InboxEntry> {
+    Ok(InboxEntry {
+        receiver,
+        receiver_authority,
+        rent_payer,
+        patient_data,
+        computation_offset,
+        sections,
+        queued_at: Clock::get()?.unix_timestamp,
+        delivered_at: 0,
+        bump,
+    })
+}
+
+/// Creates the `SharedRecord` PDA of one batch share receiver, which cannot be declared
+/// with an `init` constraint because it is passed through remaining accounts.
+fn create_shared_record<'info>(
+    shared_record: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    patient_data: &Pubkey,
+    entry: &BatchShareReceiver,
+) -> Result<()> {
+    let offset_bytes = entry.computation_offset.to_le_bytes();
+    create_program_account(
+        shared_record,
+        payer,
+        system_program,
+        &[
+            b"shared_record",
+            patient_data.as_ref(),
+            entry.receiver.as_ref(),
+            &offset_bytes,
+        ],
+        8 + core::mem::size_of::<SharedRecord>(),
+    )?;
+
+    let loader = AccountLoader::<SharedRecord>::try_from_unchecked(&ID, shared_record)?;
+    init_shared_record(
+        &loader,
+        *patient_data,
+        entry.receiver,
+        entry.receiver_authority,
+        payer.key(),
+        entry.computation_offset,
+    )?;
+    loader.exit(&ID)
+}
+
+/// Creates a program-owned PDA at `target`, checking it matches `seeds`, and returns its bump.
+fn create_program_account<'info>(
+    target: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    seeds: &[&[u8]],
+    space: usize,
+) -> Result<u8> {
+    let (address, bump) = Pubkey::find_program_address(seeds, &ID);
+    require_keys_eq!(target.key(), address, ErrorCode::InvalidBatch);
+
+    let bump_seed = [bump];
+    let mut signer_seeds = seeds.to_vec();
+    signer_seeds.push(&bump_seed);
+    anchor_lang::system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::CreateAccount {
+                from: payer.to_account_info(),
+                to: target.clone(),
+            },
+            &[&signer_seeds],
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &ID,
+    )?;
+    Ok(bump)
+}
+
+/// Copies `N` consecutive ciphertexts starting at `start`.
+fn ciphertext_array<const N: usize>(ciphertexts: &[[u8; 32]], start: usize) -> [[u8; 32]; N] {
+    let mut out = [[0u8; 32]; N];
+    out.copy_from_slice(&ciphertexts[start..start + N]);
+    out
+}
+
+/// Emits a re-encrypted `PatientData` as the four `Received*` section events.
+fn emit_received_patient_data(nonce: [u8; 16], ciphertexts: &[[u8; 32]]) {
+    emit_received_basic_patient_data(nonce, ciphertexts);
+    emit_received_healthcare_data(nonce, ciphertexts);
+    emit_received_genomic_data(nonce, ciphertexts);
+    emit_received_lab_test_data(nonce, ciphertexts);
+    emit_received_immunization_data(nonce, ciphertexts);
+    emit_received_terminology_codes(nonce, ciphertexts);
+}
+
+#[inline(never)]
+fn emit_received_basic_patient_data(nonce: [u8; 16], c: &[[u8; 32]]) {
+    emit!(ReceivedBasicPatientDataEvent {
+        nonce,
+        patient_id: c[0],
+        age: c[1],
+        gender: c[2],
+        blood_type: c[3],
+        weight: c[4],
+        height: c[5],
+        allergies: ciphertext_array(c, 6),
+    });
+}
+
+#[inline(never)]
+fn emit_received_healthcare_data(nonce: [u8; 16], c: &[[u8; 32]]) {
+    emit!(ReceivedHealthcareDataEvent {
+        nonce,
+        medical_history: ciphertext_array(c, 11),
+        medication_count: c[21],
+        medications: ciphertext_array(c, 22),
+        procedure_count: c[30],
+        procedure_dates: ciphertext_array(c, 31),
+        family_history: ciphertext_array(c, 39),
+    });
+}
+
+#[inline(never)]
+fn emit_received_genomic_data(nonce: [u8; 16], c: &[[u8; 32]]) {
+    emit!(ReceivedGenomicDataEvent {
+        nonce,
+        variant_count: c[44],
+        genetic_markers: ciphertext_array(c, 45),
+        variant_significance: ciphertext_array(c, 60),
+        carrier_status: ciphertext_array(c, 75),
+        pharmacogenomic_markers: ciphertext_array(c, 80),
+        ancestry_components: ciphertext_array(c, 83),
+    });
+}
+
+#[inline(never)]
+fn emit_received_lab_test_data(nonce: [u8; 16], c: &[[u8; 32]]) {
+    emit!(ReceivedLabTestDataEvent {
+        nonce,
+        lab_test_count: c[90],
+        lab_test_types: ciphertext_array(c, 91),
+        lab_test_dates: ciphertext_array(c, 101),
+        lab_test_values: ciphertext_array(c, 111),
+        lab_test_flags: ciphertext_array(c, 121),
+        imaging_count: c[131],
+        imaging_types: ciphertext_array(c, 132),
+        imaging_dates: ciphertext_array(c, 142),
+    });
+}
+
+#[inline(never)]
+fn emit_received_immunization_data(nonce: [u8; 16], c: &[[u8; 32]]) {
+    emit!(ReceivedImmunizationDataEvent {
+        nonce,
+        immunization_count: c[153],
+        vaccine_codes: ciphertext_array(c, 154),
+        vaccination_dates: ciphertext_array(c, 162),
+        vaccine_doses: ciphertext_array(c, 170),
+    });
+}
+
+#[inline(never)]
+fn emit_received_terminology_codes(nonce: [u8; 16], c: &[[u8; 32]]) {
+    emit!(ReceivedTerminologyCodesEvent {
+        nonce,
+        icd10_codes: ciphertext_array(c, 178),
+        rxnorm_codes: ciphertext_array(c, 188),
+        loinc_codes: ciphertext_array(c, 196),
+    });
+}
+
+/// Writes the ciphertexts of a full record, as passed to `store_patient_data`, in
+/// `PatientData` field order.
+///
+/// The record's ciphertext fields are contiguous and in the same order as the input, so the
+/// input is copied into the account memory in one go rather than field by field.
+fn write_patient_ciphertexts(data: &mut PatientData, ciphertexts: &[[u8; 32]]) -> Result<()> {
+    // Expect 152 fields, indexed exactly as emitted in the callback, plus an optional canary,
+    // optional immunizations and optional terminology codes
+    if ciphertexts.len() != 152
+        && ciphertexts.len() != 153
+        && ciphertexts.len() != 153 + IMMUNIZATION_FIELDS
+        && ciphertexts.len() != 153 + IMMUNIZATION_FIELDS + TERMINOLOGY_FIELDS
+    {
+        return Err(ErrorCode::InvalidInputLength.into());
+    }
+
+    let input: &[u8] = bytemuck::cast_slice(ciphertexts);
+    bytemuck::bytes_of_mut(data)[..input.len()].copy_from_slice(input);
+    Ok(())
+}
+
+// `write_patient_ciphertexts` relies on input index `i` living at byte `32 * i` of the record
+const _: () = assert!(offset_of!(PatientData, canary) == 152 * 32);
+const _: () = assert!(offset_of!(PatientData, immunization_count) == 153 * 32);
+const _: () = assert!(offset_of!(PatientData, icd10_codes) == (153 + IMMUNIZATION_FIELDS) * 32);
+const _: () = assert!(
+    offset_of!(PatientData, jurisdiction) == (153 + IMMUNIZATION_FIELDS + TERMINOLOGY_FIELDS) * 32
+);
+
+/// Builds the `RecordMetadata` of a record first stored now by `author`.
+fn new_record_metadata(
+    patient_data: Pubkey,
+    author: EntryAuthor,
+    bump: u8,
+) -> Result<RecordMetadata> {
+    Ok(RecordMetadata {
+        patient_data,
+        created_by: author.clone(),
+        last_written_by: author,
+        last_written_at: Clock::get()?.unix_timestamp,
+        entry_count: 0,
+        rotation: RotationPolicy::default(),
+        bump,
+    })
+}
+
+/// Writes the immunization section of a record, in `PatientData` field order.
+fn write_immunizations(data: &mut PatientData, c: &[[u8; 32]]) {
+    data.immunization_count = c[0];
+    data.vaccine_codes = ciphertext_array(c, 1);
+    data.vaccination_dates = ciphertext_array(c, 9);
+    data.vaccine_doses = ciphertext_array(c, 17);
+}
+
+#[init_computation_definition_accounts("share_patient_data", payer)]
+#[derive(Accounts)]
+pub struct InitSharePatientDataCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("share_emergency_data", payer)]
+#[derive(Accounts)]
+pub struct InitShareEmergencyDataCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("share_advance_directive", payer)]
+#[derive(Accounts)]
+pub struct InitShareAdvanceDirectiveCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("emergency_summary", payer)]
+#[derive(Accounts)]
+pub struct InitEmergencySummaryCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetEmergencyPolicy<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + EmergencyPolicy::INIT_SPACE,
+        seeds = [b"emergency_policy", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub emergency_policy: Account<'info, EmergencyPolicy>,
+}
+
+#[derive(Accounts)]
+pub struct OpenBreakGlass<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [b"emergency_policy", patient_data.key().as_ref()],
+        bump = emergency_policy.bump,
+    )]
+    pub emergency_policy: Account<'info, EmergencyPolicy>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + BreakGlassRequest::INIT_SPACE,
+        seeds = [b"break_glass", patient_data.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub break_glass_request: Account<'info, BreakGlassRequest>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveBreakGlass<'info> {
+    pub guardian: Signer<'info>,
+    #[account(
+        seeds = [b"emergency_policy", break_glass_request.patient_data.as_ref()],
+        bump = emergency_policy.bump,
+    )]
+    pub emergency_policy: Account<'info, EmergencyPolicy>,
+    #[account(mut)]
+    pub break_glass_request: Account<'info, BreakGlassRequest>,
+}
+
+#[queue_computation_accounts("share_emergency_data", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ExecuteBreakGlass<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_EMERGENCY_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [b"emergency_policy", patient_data.key().as_ref()],
+        bump = emergency_policy.bump,
+    )]
+    pub emergency_policy: Box<Account<'info, EmergencyPolicy>>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"break_glass", patient_data.key().as_ref(), payer.key().as_ref()],
+        bump = break_glass_request.bump,
+    )]
+    pub break_glass_request: Box<Account<'info, BreakGlassRequest>>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), break_glass_request.receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_jurisdiction", break_glass_request.receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), break_glass_request.receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+}
+
+#[queue_computation_accounts("emergency_summary", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ExecuteEmergencySummary<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_EMERGENCY_SUMMARY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [b"emergency_policy", patient_data.key().as_ref()],
+        bump = emergency_policy.bump,
+    )]
+    pub emergency_policy: Box<Account<'info, EmergencyPolicy>>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"break_glass", patient_data.key().as_ref(), payer.key().as_ref()],
+        bump = break_glass_request.bump,
+    )]
+    pub break_glass_request: Box<Account<'info, BreakGlassRequest>>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), break_glass_request.receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_jurisdiction", break_glass_request.receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), break_glass_request.receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+}
+
+#[callback_accounts("share_emergency_data")]
+#[derive(Accounts)]
+pub struct ShareEmergencyDataCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_EMERGENCY_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[callback_accounts("emergency_summary")]
+#[derive(Accounts)]
+pub struct EmergencySummaryCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_EMERGENCY_SUMMARY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAdvanceDirective<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub witness: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AdvanceDirective::INIT_SPACE,
+        seeds = [b"advance_directive", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub advance_directive: Account<'info, AdvanceDirective>,
+}
+
+#[queue_computation_accounts("share_advance_directive", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ShareAdvanceDirective<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_ADVANCE_DIRECTIVE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [b"advance_directive", patient_data.key().as_ref()],
+        bump = advance_directive.bump,
+    )]
+    pub advance_directive: Box<Account<'info, AdvanceDirective>>,
+    #[account(
+        seeds = [b"break_glass", patient_data.key().as_ref(), payer.key().as_ref()],
+        bump = break_glass_request.bump,
+    )]
+    pub break_glass_request: Box<Account<'info, BreakGlassRequest>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), break_glass_request.receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    #[account(
+        seeds = [
+            b"receiver_revocation",
+            patient_data.key().as_ref(),
+            break_glass_request.receiver.as_ref(),
+        ],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_jurisdiction", break_glass_request.receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+
+    // Credential NFT accounts
+    pub credential_mint: Box<Account<'info, anchor_spl::token::Mint>>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.amount > 0 @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+    #[account(
+        seeds = [b"provider_record", credential_mint.key().as_ref()],
+        bump = provider_record.bump,
+        constraint = provider_record.active @ ErrorCode::ProviderNotRegistered,
+        constraint = matches!(provider_record.role, ROLE_DOCTOR | ROLE_NURSE)
+            @ ErrorCode::CredentialRoleMismatch,
+    )]
+    pub provider_record: Box<Account<'info, ProviderRecord>>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+}
+
+#[callback_accounts("share_advance_directive")]
+#[derive(Accounts)]
+pub struct ShareAdvanceDirectiveCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_ADVANCE_DIRECTIVE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    pub advance_directive: Account<'info, AdvanceDirective>,
+}
+
+#[derive(Accounts)]
+pub struct SetDonorProfile<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + DonorProfile::INIT_SPACE,
+        seeds = [b"donor_profile", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub donor_profile: Box<Account<'info, DonorProfile>>,
+}
+
+#[init_computation_definition_accounts("check_donor_match", payer)]
+#[derive(Accounts)]
+pub struct InitCheckDonorMatchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("check_donor_match", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, coordinator_key: [u8; 32])]
+pub struct CheckDonorMatch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_DONOR_MATCH)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [b"donor_profile", patient_data.key().as_ref()],
+        bump = donor_profile.bump,
+    )]
+    pub donor_profile: Box<Account<'info, DonorProfile>>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), coordinator_key.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"program_config"], bump = program_config.bump)]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate_limit", patient_data.key().as_ref(), credential_mint.key().as_ref()],
+        bump,
+    )]
+    pub rate_limit: Box<Account<'info, RateLimit>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+
+    // Credential NFT accounts
+    pub credential_mint: Box<Account<'info, anchor_spl::token::Mint>>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.amount > 0 @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+    #[account(
+        seeds = [b"provider_record", credential_mint.key().as_ref()],
+        bump = provider_record.bump,
+        constraint = provider_record.active @ ErrorCode::ProviderNotRegistered,
+        constraint = provider_record.role == ROLE_TRANSPLANT_COORDINATOR
+            @ ErrorCode::CredentialRoleMismatch,
+    )]
+    pub provider_record: Box<Account<'info, ProviderRecord>>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+}
+
+#[callback_accounts("check_donor_match")]
+#[derive(Accounts)]
+pub struct CheckDonorMatchCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_DONOR_MATCH)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    /// CHECK: donor record that was scored, passed by `check_donor_match`
+    pub patient_data: UncheckedAccount<'info>,
+    /// CHECK: coordinator that asked for the score, passed by `check_donor_match`
+    pub coordinator: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64, shard: u8)]
+pub struct InitAuditIndexShard<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AuditIndexShard::INIT_SPACE,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &epoch.to_le_bytes(),
+            &[shard],
+        ],
+        bump,
+    )]
+    pub audit_index: Account<'info, AuditIndexShard>,
+}
+
+#[derive(Accounts)]
+pub struct AggregateAuditIndex<'info> {
+    pub patient_data: AccountLoader<'info, PatientData>,
+}
+
+#[derive(Accounts)]
+pub struct CreateShareLookupTable<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + LookupTableConfig::INIT_SPACE,
+        seeds = [b"lookup_table_config"],
+        bump,
+    )]
+    pub lookup_table_config: Account<'info, LookupTableConfig>,
+    #[account(
+        seeds = [b"lookup_table_authority"],
+        bump,
+    )]
+    /// CHECK: PDA authority of the lookup table, holds no data.
+    pub lookup_table_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    /// CHECK: lookup table being created, address checked against the derivation.
+    pub lookup_table: UncheckedAccount<'info>,
+    #[account(address = address_lookup_table::program::ID)]
+    /// CHECK: address lookup table program.
+    pub address_lookup_table_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendShareLookupTable<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"lookup_table_config"],
+        bump = lookup_table_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+        has_one = lookup_table @ ErrorCode::InvalidLookupTable,
+    )]
+    pub lookup_table_config: Account<'info, LookupTableConfig>,
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"lookup_table_authority"],
+        bump,
+    )]
+    /// CHECK: PDA authority of the lookup table, holds no data.
+    pub lookup_table_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    /// CHECK: lookup table, checked against the config.
+    pub lookup_table: UncheckedAccount<'info>,
+    #[account(address = address_lookup_table::program::ID)]
+    /// CHECK: address lookup table program.
+    pub address_lookup_table_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts shared by the lookup table create and extend paths.
+pub struct LookupTableAccounts<'a, 'info> {
+    pub payer: &'a Signer<'info>,
+    pub lookup_table_config: &'a mut Account<'info, LookupTableConfig>,
+    pub lookup_table_authority: &'a UncheckedAccount<'info>,
+    pub lookup_table: &'a UncheckedAccount<'info>,
+    pub system_program: &'a Program<'info, System>,
+}
+
+impl<'info> CreateShareLookupTable<'info> {
+    pub fn to_lookup_table_accounts(&mut self) -> LookupTableAccounts<'_, 'info> {
+        LookupTableAccounts {
+            payer: &self.payer,
+            lookup_table_config: &mut self.lookup_table_config,
+            lookup_table_authority: &self.lookup_table_authority,
+            lookup_table: &self.lookup_table,
+            system_program: &self.system_program,
+        }
+    }
+}
+
+impl<'info> ExtendShareLookupTable<'info> {
+    pub fn to_lookup_table_accounts(&mut self) -> LookupTableAccounts<'_, 'info> {
+        LookupTableAccounts {
+            payer: &self.payer,
+            lookup_table_config: &mut self.lookup_table_config,
+            lookup_table_authority: &self.lookup_table_authority,
+            lookup_table: &self.lookup_table,
+            system_program: &self.system_program,
+        }
+    }
+}
+
+/// Extends the share lookup table via CPI signed by the lookup table authority PDA
+/// and emits the table's address for clients.
+fn extend_share_lookup_table_inner(
+    accounts: LookupTableAccounts<'_, '_>,
+    authority_bump: u8,
+    addresses: Vec<Pubkey>,
+) -> Result<()> {
+    let added = addresses.len() as u16;
+    let extend_ix = alt_instruction::extend_lookup_table(
+        accounts.lookup_table.key(),
+        accounts.lookup_table_authority.key(),
+        Some(accounts.payer.key()),
+        addresses,
+    );
+    invoke_signed(
+        &extend_ix,
+        &[
+            accounts.lookup_table.to_account_info(),
+            accounts.lookup_table_authority.to_account_info(),
+            accounts.payer.to_account_info(),
+            accounts.system_program.to_account_info(),
+        ],
+        &[&[b"lookup_table_authority", &[authority_bump]]],
+    )?;
+
+    let config = accounts.lookup_table_config;
+    config.address_count = config
+        .address_count
+        .checked_add(added)
+        .ok_or(ErrorCode::InvalidLookupTable)?;
+
+    emit!(ShareLookupTableEvent {
+        lookup_table: config.lookup_table,
+        authority: config.authority,
+        address_count: config.address_count,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct DelegateAuthority<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + DelegationAccount::INIT_SPACE,
+        seeds = [b"delegation", patient_data.key().as_ref(), delegate.as_ref()],
+        bump,
+    )]
+    pub delegation: Account<'info, DelegationAccount>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + GrantExpiryIndex::INIT_SPACE,
+        seeds = [b"grant_expiry_index", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub grant_expiry_index: Box<Account<'info, GrantExpiryIndex>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PatientInbox::INIT_SPACE,
+        seeds = [b"patient_inbox", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub patient_inbox: Box<Account<'info, PatientInbox>>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct RevokeDelegation<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"delegation", patient_data.key().as_ref(), delegate.as_ref()],
+        bump = delegation.bump,
+    )]
+    pub delegation: Account<'info, DelegationAccount>,
+    #[account(
+        mut,
+        seeds = [b"grant_expiry_index", patient_data.key().as_ref()],
+        bump = grant_expiry_index.bump,
+    )]
+    pub grant_expiry_index: Box<Account<'info, GrantExpiryIndex>>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAllAccess<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"grant_expiry_index", patient_data.key().as_ref()],
+        bump = grant_expiry_index.bump,
+    )]
+    pub grant_expiry_index: Option<Box<Account<'info, GrantExpiryIndex>>>,
+}
+
+#[derive(Accounts)]
+pub struct ErasePatientData<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump = record_metadata.bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+    #[account(
+        mut,
+        seeds = [b"grant_expiry_index", patient_data.key().as_ref()],
+        bump = grant_expiry_index.bump,
+    )]
+    pub grant_expiry_index: Option<Box<Account<'info, GrantExpiryIndex>>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Tombstone::INIT_SPACE,
+        seeds = [b"tombstone", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub tombstone: Account<'info, Tombstone>,
+}
+
+/// Checks that a break-glass request is executable at `now`: approved by the policy's
+/// guardian threshold, or open for longer than its time delay.
+///
+/// Returns the number of approvals and whether the threshold was met.
+fn check_break_glass_ready(
+    policy: &EmergencyPolicy,
+    request: &BreakGlassRequest,
+    now: i64,
+) -> Result<(u8, bool)> {
+    let approvals = request.approval_mask.count_ones() as u8;
+    let approved = approvals >= policy.threshold;
+    let delay_elapsed =
+        policy.delay_seconds > 0 && now >= request.opened_at.saturating_add(policy.delay_seconds);
+    require!(approved || delay_elapsed, ErrorCode::BreakGlassNotReady);
+    Ok((approvals, approved))
+}
+
+/// Whether a remaining account is one of the program's `VisitGrant`s.
+fn is_visit_grant(account: &AccountInfo) -> Result<bool> {
+    Ok(account.owner == &ID
+        && account
+            .try_borrow_data()?
+            .starts_with(VisitGrant::DISCRIMINATOR))
+}
+
+/// Closes a `VisitGrant` passed in `revoke_all_access` or `erase_patient_data`, refunding
+/// the patient, and cancels its appointment, which must be the next remaining account.
+fn revoke_visit_grant<'info>(
+    grant: &'info AccountInfo<'info>,
+    accounts: &mut std::slice::Iter<'info, AccountInfo<'info>>,
+    patient_data: Pubkey,
+    payer: &AccountInfo<'info>,
+) -> Result<()> {
+    let visit_grant = Account::<VisitGrant>::try_from(grant)?;
+    require_keys_eq!(
+        visit_grant.patient_data,
+        patient_data,
+        ErrorCode::InvalidDelegation
+    );
+    require_keys_eq!(visit_grant.rent_payer, payer.key(), ErrorCode::Unauthorized);
+
+    let appointment_info = accounts.next().ok_or(ErrorCode::InvalidInputLength)?;
+    require_keys_eq!(
+        appointment_info.key(),
+        visit_grant.appointment,
+        ErrorCode::InvalidAppointment
+    );
+    let mut appointment = Account::<Appointment>::try_from(appointment_info)?;
+    appointment.status = APPOINTMENT_CANCELLED;
+    appointment.exit(&ID)?;
+    emit!(AppointmentUpdatedEvent {
+        patient_data,
+        appointment: appointment.key(),
+        provider: appointment.provider,
+        status: APPOINTMENT_CANCELLED,
+    });
+
+    visit_grant.close(payer.clone())
+}
+
+/// Checks that `signer` owns `patient_data` or holds an unexpired delegation covering `scope`.
+///
+/// Returns the role the signer acts under: `ROLE_PATIENT` or `ROLE_CAREGIVER`.
+fn authorize_patient_or_delegate(
+    signer: &Pubkey,
+    patient_data: &Pubkey,
+    delegation: Option<&Account<'_, DelegationAccount>>,
+    scope: u8,
+) -> Result<u8> {
+    let (owned, _) = Pubkey::find_program_address(&[b"patient_data", signer.as_ref()], &ID);
+    if owned == *patient_data {
+        return Ok(ROLE_PATIENT);
+    }
+
+    let delegation = delegation.ok_or(ErrorCode::Unauthorized)?;
+    require_keys_eq!(delegation.patient_data, *patient_data, ErrorCode::Unauthorized);
+    require_keys_eq!(delegation.delegate, *signer, ErrorCode::Unauthorized);
+    require!(delegation.scope & scope == scope, ErrorCode::DelegationScopeExceeded);
+    require!(
+        Clock::get()?.unix_timestamp < delegation.expires_at,
+        ErrorCode::DelegationExpired
+    );
+    Ok(ROLE_CAREGIVER)
+}
+
+/// Authorizes `signer` as a member of `organization` acting under a delegation the patient
+/// granted to the organization itself.
+fn authorize_organization_member(
+    signer: &Pubkey,
+    patient_data: &Pubkey,
+    delegation: Option<&Account<'_, DelegationAccount>>,
+    organization: &Account<'_, Organization>,
+    scope: u8,
+) -> Result<u8> {
+    require!(organization.members.contains(signer), ErrorCode::NotOrganizationMember);
+
+    let delegation = delegation.ok_or(ErrorCode::Unauthorized)?;
+    require_keys_eq!(delegation.patient_data, *patient_data, ErrorCode::Unauthorized);
+    require_keys_eq!(delegation.delegate, organization.key(), ErrorCode::Unauthorized);
+    require!(delegation.scope & scope == scope, ErrorCode::DelegationScopeExceeded);
+    require!(
+        Clock::get()?.unix_timestamp < delegation.expires_at,
+        ErrorCode::DelegationExpired
+    );
+    Ok(ROLE_PROVIDER)
+}
+
+#[init_computation_definition_accounts("share_demographics", payer)]
+#[derive(Accounts)]
+pub struct InitShareDemographicsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_demographics", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareDemographics<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_DEMOGRAPHICS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[init_computation_definition_accounts("share_healthcare", payer)]
+#[derive(Accounts)]
+pub struct InitShareHealthcareCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_healthcare", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareHealthcare<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_HEALTHCARE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[init_computation_definition_accounts("share_genomics", payer)]
+#[derive(Accounts)]
+pub struct InitShareGenomicsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_genomics", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareGenomics<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_GENOMICS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[init_computation_definition_accounts("share_lab_tests", payer)]
+#[derive(Accounts)]
+pub struct InitShareLabTestsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_lab_tests", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareLabTests<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_LAB_TESTS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitRecordIndex<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(seeds = [b"patient_data", payer.key().as_ref()], bump)]
+    /// CHECK: address the split record is keyed by; holds no `PatientData` account.
+    pub patient_data: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RecordIndex::INIT_SPACE,
+        seeds = [b"record_index", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub record_index: Box<Account<'info, RecordIndex>>,
+}
+
+#[derive(Accounts)]
+pub struct StoreDemographicsSection<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(seeds = [b"patient_data", payer.key().as_ref()], bump)]
+    /// CHECK: address the split record is keyed by; holds no `PatientData` account.
+    pub patient_data: UncheckedAccount<'info>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"record_index", patient_data.key().as_ref()],
+        bump = record_index.bump,
+    )]
+    pub record_index: Box<Account<'info, RecordIndex>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + DemographicsAccount::INIT_SPACE,
+        seeds = [b"demographics_section", record_index.key().as_ref()],
+        bump,
+    )]
+    pub section: Box<Account<'info, DemographicsAccount>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[queue_computation_accounts("share_demographics", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareDemographicsSection<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_DEMOGRAPHICS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = record_index.patient_data)]
+    /// CHECK: address the split record is keyed by; holds no `PatientData` account.
+    pub patient_data: UncheckedAccount<'info>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"record_index", record_index.patient_data.as_ref()],
+        bump = record_index.bump,
+    )]
+    pub record_index: Box<Account<'info, RecordIndex>>,
+    #[account(
+        seeds = [b"demographics_section", record_index.key().as_ref()],
+        bump = section.bump,
+    )]
+    pub section: Box<Account<'info, DemographicsAccount>>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StoreHealthcareSection<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(seeds = [b"patient_data", payer.key().as_ref()], bump)]
+    /// CHECK: address the split record is keyed by; holds no `PatientData` account.
+    pub patient_data: UncheckedAccount<'info>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"record_index", patient_data.key().as_ref()],
+        bump = record_index.bump,
+    )]
+    pub record_index: Box<Account<'info, RecordIndex>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + HealthcareAccount::INIT_SPACE,
+        seeds = [b"healthcare_section", record_index.key().as_ref()],
+        bump,
+    )]
+    pub section: Box<Account<'info, HealthcareAccount>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[queue_computation_accounts("share_healthcare", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareHealthcareSection<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_HEALTHCARE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = record_index.patient_data)]
+    /// CHECK: address the split record is keyed by; holds no `PatientData` account.
+    pub patient_data: UncheckedAccount<'info>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"record_index", record_index.patient_data.as_ref()],
+        bump = record_index.bump,
+    )]
+    pub record_index: Box<Account<'info, RecordIndex>>,
+    #[account(
+        seeds = [b"healthcare_section", record_index.key().as_ref()],
+        bump = section.bump,
+    )]
+    pub section: Box<Account<'info, HealthcareAccount>>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StoreGenomicsSection<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(seeds = [b"patient_data", payer.key().as_ref()], bump)]
+    /// CHECK: address the split record is keyed by; holds no `PatientData` account.
+    pub patient_data: UncheckedAccount<'info>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"record_index", patient_data.key().as_ref()],
+        bump = record_index.bump,
+    )]
+    pub record_index: Box<Account<'info, RecordIndex>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + GenomicAccount::INIT_SPACE,
+        seeds = [b"genomics_section", record_index.key().as_ref()],
+        bump,
+    )]
+    pub section: Box<Account<'info, GenomicAccount>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[queue_computation_accounts("share_genomics", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareGenomicsSection<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_GENOMICS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = record_index.patient_data)]
+    /// CHECK: address the split record is keyed by; holds no `PatientData` account.
+    pub patient_data: UncheckedAccount<'info>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"record_index", record_index.patient_data.as_ref()],
+        bump = record_index.bump,
+    )]
+    pub record_index: Box<Account<'info, RecordIndex>>,
+    #[account(
+        seeds = [b"genomics_section", record_index.key().as_ref()],
+        bump = section.bump,
+    )]
+    pub section: Box<Account<'info, GenomicAccount>>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StoreLabTestsSection<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(seeds = [b"patient_data", payer.key().as_ref()], bump)]
+    /// CHECK: address the split record is keyed by; holds no `PatientData` account.
+    pub patient_data: UncheckedAccount<'info>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"record_index", patient_data.key().as_ref()],
+        bump = record_index.bump,
+    )]
+    pub record_index: Box<Account<'info, RecordIndex>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + LabAccount::INIT_SPACE,
+        seeds = [b"lab_tests_section", record_index.key().as_ref()],
+        bump,
+    )]
+    pub section: Box<Account<'info, LabAccount>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[queue_computation_accounts("share_lab_tests", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareLabTestsSection<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_LAB_TESTS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = record_index.patient_data)]
+    /// CHECK: address the split record is keyed by; holds no `PatientData` account.
+    pub patient_data: UncheckedAccount<'info>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"record_index", record_index.patient_data.as_ref()],
+        bump = record_index.bump,
+    )]
+    pub record_index: Box<Account<'info, RecordIndex>>,
+    #[account(
+        seeds = [b"lab_tests_section", record_index.key().as_ref()],
+        bump = section.bump,
+    )]
+    pub section: Box<Account<'info, LabAccount>>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+/// Records a stored section in a split record's index.
+fn index_record_section(index: &mut RecordIndex, sections: u8) -> Result<()> {
+    index.sections |= sections;
+    index.updated_at = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+/// Returns the `(offset, length)` of a `PatientData` section for `Argument::Account`,
+/// accounting for the 8-byte account discriminator. A section without an `end` runs to the
+/// end of the account.
+fn section_range(start: usize, end: Option<usize>) -> (u32, u32) {
+    let end = end.unwrap_or(PATIENT_CIPHERTEXTS_LEN as usize);
+    ((8 + start) as u32, (end - start) as u32)
+}
+
+#[queue_computation_accounts("share_patient_data", payer)]
+#[derive(Accounts)]
+#[instruction(batch_id: u64)]
+pub struct SharePatientDataMulti<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(mut)]
+    /// CHECK: computation account of the first receiver; replaced per receiver from
+    /// remaining accounts, each checked against its computation offset.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_PATIENT_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ShareBatch::INIT_SPACE,
+        seeds = [b"share_batch", patient_data.key().as_ref(), &batch_id.to_le_bytes()],
+        bump,
+    )]
+    pub share_batch: Box<Account<'info, ShareBatch>>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver allowlist, only read if the patient has created one.
+    pub receiver_allowlist: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+/// A receiver of a batch share and the computation offset queued for it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BatchShareReceiver {
+    pub receiver: [u8; 32],
+    pub computation_offset: u64,
+    /// Wallet allowed to close the receiver's `SharedRecord`
+    pub receiver_authority: Pubkey,
+}
+
+/// Issues the receiver nonce for one receiver of a batch share from its `ShareNonce`,
+/// creating the PDA on first use. The counter is shared with single shares, so batch and
+/// single shares to the same receiver never reuse a nonce.
+fn advance_batch_share_nonce<'info>(
+    share_nonce: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    patient_data: Pubkey,
+    receiver: [u8; 32],
+) -> Result<u128> {
+    let seeds: &[&[u8]] = &[b"share_nonce", patient_data.as_ref(), receiver.as_ref()];
+    let mut nonces = if share_nonce.data_is_empty() {
+        let bump = create_program_account(
+            share_nonce,
+            payer,
+            system_program,
+            seeds,
+            8 + ShareNonce::INIT_SPACE,
+        )?;
+        ShareNonce {
+            patient_data,
+            receiver,
+            counter: 0,
+            last_nonce: 0,
+            recent_input_nonces: Vec::new(),
+            bump,
+        }
+    } else {
+        let (address, _) = Pubkey::find_program_address(seeds, &ID);
+        require_keys_eq!(share_nonce.key(), address, ErrorCode::InvalidBatch);
+        ShareNonce::try_deserialize(&mut &share_nonce.try_borrow_data()?[..])?
+    };
+    let bump = nonces.bump;
+    let receiver_nonce = nonces.advance(patient_data, receiver, bump)?;
+    nonces.try_serialize(&mut &mut share_nonce.try_borrow_mut_data()?[..])?;
+    Ok(receiver_nonce)
+}
+
+#[derive(Accounts)]
+pub struct CrankGrantExpiryReminders<'info> {
+    #[account(
+        mut,
+        seeds = [b"grant_expiry_index", grant_expiry_index.patient_data.as_ref()],
+        bump = grant_expiry_index.bump,
+    )]
+    pub grant_expiry_index: Box<Account<'info, GrantExpiryIndex>>,
+    #[account(
+        mut,
+        seeds = [b"patient_inbox", grant_expiry_index.patient_data.as_ref()],
+        bump = patient_inbox.bump,
+    )]
+    pub patient_inbox: Box<Account<'info, PatientInbox>>,
+}
+
+#[derive(Accounts)]
+pub struct AppendEntry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump = record_metadata.bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+    #[account(seeds = [b"program_config"], bump = program_config.bump)]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RecordEntry::INIT_SPACE,
+        seeds = [
+            b"record_entry",
+            patient_data.key().as_ref(),
+            &record_metadata.entry_count.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub record_entry: Box<Account<'info, RecordEntry>>,
+
+    // Credential NFT accounts, required unless the patient writes to their own record
+    pub credential_mint: Option<Account<'info, anchor_spl::token::Mint>>,
+    pub credential_token_account: Option<Account<'info, anchor_spl::token::TokenAccount>>,
+}
+
+/// Determines the author of a write to `patient_data`.
+///
+/// The patient may write as `ROLE_PATIENT` without a credential; everyone else must hold a
+/// credential NFT (0 decimals, balance >= 1, owned by the signer) for a provider role.
+fn resolve_author(
+    signer: &Pubkey,
+    patient_data: &Pubkey,
+    role: u8,
+    credential_mint: Option<&Account<'_, anchor_spl::token::Mint>>,
+    credential_token_account: Option<&Account<'_, anchor_spl::token::TokenAccount>>,
+) -> Result<EntryAuthor> {
+    if role == ROLE_PATIENT {
+        let (owned, _) = Pubkey::find_program_address(&[b"patient_data", signer.as_ref()], &ID);
+        require_keys_eq!(owned, *patient_data, ErrorCode::Unauthorized);
+        return Ok(EntryAuthor {
+            provider: *signer,
+            role,
+            credential_mint: Pubkey::default(),
+        });
+    }
+
+    require!(role <= ROLE_LAB_TECHNICIAN, ErrorCode::InvalidRole);
+    let mint = credential_mint.ok_or(ErrorCode::MissingCredential)?;
+    let token_account = credential_token_account.ok_or(ErrorCode::MissingCredential)?;
+    require_keys_eq!(token_account.owner, *signer, ErrorCode::Unauthorized);
+    require_keys_eq!(token_account.mint, mint.key(), ErrorCode::Unauthorized);
+    require!(mint.decimals == 0, ErrorCode::InvalidCredentialMint);
+    require!(token_account.amount >= 1, ErrorCode::MissingCredential);
+
+    Ok(EntryAuthor {
+        provider: *signer,
+        role,
+        credential_mint: mint.key(),
+    })
+}
+
+/// Fills a freshly created entry and advances the record's entry counter, up to the
+/// configured maximum number of entries per record.
+///
+/// Returns the id assigned to the entry. The caller sets `patient_data` and `bump`.
+fn write_entry(
+    config: &ProgramConfig,
+    metadata: &mut RecordMetadata,
+    entry: &mut RecordEntry,
+    author: EntryAuthor,
+    kind: u8,
+    payload: &EncryptedEntry,
+    supersedes: Option<u64>,
+) -> Result<u64> {
+    require!(
+        payload.ciphertexts.len() == ENTRY_FIELDS,
+        ErrorCode::InvalidInputLength
+    );
+    let now = Clock::get()?.unix_timestamp;
+
+    let entry_id = metadata.entry_count;
+    require!(
+        config.max_records_per_patient == 0 || entry_id < config.max_records_per_patient,
+        ErrorCode::TooManyRecords
+    );
+    metadata.entry_count = entry_id.checked_add(1).ok_or(ErrorCode::InvalidEntry)?;
+    metadata.last_written_by = author.clone();
+    metadata.last_written_at = now;
+
+    entry.encryption_key = payload.encryption_key;
+    entry.nonce = payload.nonce;
+    entry.ciphertexts.copy_from_slice(&payload.ciphertexts);
+    entry.entry_id = entry_id;
+    entry.kind = kind;
+    entry.author = author;
+    entry.created_at = now;
+    entry.supersedes = supersedes;
+    entry.superseded_by = None;
+    entry.flagged = false;
+    Ok(entry_id)
+}
+
+#[init_computation_definition_accounts("share_entry", payer)]
+#[derive(Accounts)]
+pub struct InitShareEntryCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_entry", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareEntry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_ENTRY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(
+        constraint = record_entry.patient_data == patient_data.key() @ ErrorCode::InvalidEntry,
+    )]
+    pub record_entry: Box<Account<'info, RecordEntry>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[queue_computation_accounts("share_entry", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareEncounter<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_ENTRY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(
+        constraint = encounter.patient_data == patient_data.key() @ ErrorCode::InvalidEncounter,
+    )]
+    pub encounter: Box<Account<'info, Encounter>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[init_computation_definition_accounts("share_entry_history", payer)]
+#[derive(Accounts)]
+pub struct InitShareEntryHistoryCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_entry_history", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareEntryHistory<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_ENTRY_HISTORY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(
+        constraint = record_entry.patient_data == patient_data.key() @ ErrorCode::InvalidEntry,
+    )]
+    pub record_entry: Box<Account<'info, RecordEntry>>,
+    #[account(
+        constraint = previous_entry.patient_data == patient_data.key() @ ErrorCode::InvalidEntry,
+        constraint = record_entry.supersedes == Some(previous_entry.entry_id) @ ErrorCode::InvalidEntry,
+    )]
+    pub previous_entry: Box<Account<'info, RecordEntry>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(original_entry_id: u64)]
+pub struct AmendEntry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump = record_metadata.bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+    #[account(seeds = [b"program_config"], bump = program_config.bump)]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+    #[account(
+        mut,
+        seeds = [
+            b"record_entry",
+            patient_data.key().as_ref(),
+            &original_entry_id.to_le_bytes(),
+        ],
+        bump = original_entry.bump,
+    )]
+    pub original_entry: Box<Account<'info, RecordEntry>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RecordEntry::INIT_SPACE,
+        seeds = [
+            b"record_entry",
+            patient_data.key().as_ref(),
+            &record_metadata.entry_count.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub amended_entry: Box<Account<'info, RecordEntry>>,
+
+    // Credential NFT accounts, required unless the patient writes to their own record
+    pub credential_mint: Option<Account<'info, anchor_spl::token::Mint>>,
+    pub credential_token_account: Option<Account<'info, anchor_spl::token::TokenAccount>>,
+}
+
+#[derive(Accounts)]
+#[instruction(encounter_id: u64)]
+pub struct AppendVisitSummary<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [b"visit_grant", visit_grant.appointment.as_ref()],
+        bump = visit_grant.bump,
+    )]
+    pub visit_grant: Box<Account<'info, VisitGrant>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Encounter::INIT_SPACE,
+        seeds = [b"encounter", patient_data.key().as_ref(), &encounter_id.to_le_bytes()],
+        bump,
+    )]
+    pub encounter: Box<Account<'info, Encounter>>,
+
+    // Credential NFT accounts
+    pub credential_mint: Box<Account<'info, anchor_spl::token::Mint>>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.amount > 0 @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+    #[account(
+        seeds = [b"provider_record", credential_mint.key().as_ref()],
+        bump = provider_record.bump,
+        constraint = provider_record.active @ ErrorCode::ProviderNotRegistered,
+    )]
+    pub provider_record: Box<Account<'info, ProviderRecord>>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+}
+
+#[derive(Accounts)]
+#[instruction(section: u8)]
+pub struct ProposeUpdate<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PendingUpdate::INIT_SPACE,
+        seeds = [b"pending_update", patient_data.key().as_ref(), &[section]],
+        bump,
+    )]
+    pub pending_update: Box<Account<'info, PendingUpdate>>,
+
+    // Credential NFT accounts, required unless the patient proposes the update
+    pub credential_mint: Option<Account<'info, anchor_spl::token::Mint>>,
+    pub credential_token_account: Option<Account<'info, anchor_spl::token::TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveUpdate<'info> {
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump = record_metadata.bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"pending_update", patient_data.key().as_ref(), &[pending_update.section]],
+        bump = pending_update.bump,
+    )]
+    pub pending_update: Box<Account<'info, PendingUpdate>>,
+    #[account(mut, address = pending_update.proposer.provider)]
+    /// CHECK: proposer of the update, receives the pending update's rent.
+    pub proposer: UncheckedAccount<'info>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+
+    // Credential NFT accounts, required unless the patient approves the update
+    pub credential_mint: Option<Account<'info, anchor_spl::token::Mint>>,
+    pub credential_token_account: Option<Account<'info, anchor_spl::token::TokenAccount>>,
+}
+
+#[derive(Accounts)]
+#[instruction(section: u8)]
+pub struct CancelUpdate<'info> {
+    pub payer: Signer<'info>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"pending_update", patient_data.key().as_ref(), &[section]],
+        bump = pending_update.bump,
+    )]
+    pub pending_update: Box<Account<'info, PendingUpdate>>,
+    #[account(mut, address = pending_update.proposer.provider)]
+    /// CHECK: proposer of the update, receives the pending update's rent.
+    pub proposer: UncheckedAccount<'info>,
+}
+
+/// Byte ranges of `PatientData` holding a single record section, in field order.
+///
+/// The history section spans two ranges around the medications.
+fn section_byte_ranges(section: u8) -> Result<Vec<core::ops::Range<usize>>> {
+    let ranges = match section {
+        SECTION_DEMOGRAPHICS => {
+            vec![offset_of!(PatientData, patient_id)..offset_of!(PatientData, allergies)]
+        }
+        SECTION_ALLERGIES => {
+            vec![offset_of!(PatientData, allergies)..offset_of!(PatientData, medical_history)]
+        }
+        SECTION_MEDICATIONS => vec![
+            offset_of!(PatientData, medication_count)..offset_of!(PatientData, procedure_count),
+        ],
+        SECTION_HISTORY => vec![
+            offset_of!(PatientData, medical_history)..offset_of!(PatientData, medication_count),
+            offset_of!(PatientData, procedure_count)..offset_of!(PatientData, variant_count),
+        ],
+        SECTION_GENOMICS => {
+            vec![offset_of!(PatientData, variant_count)..offset_of!(PatientData, lab_test_count)]
+        }
+        SECTION_LAB_TESTS => {
+            vec![offset_of!(PatientData, lab_test_count)..offset_of!(PatientData, imaging_count)]
+        }
+        SECTION_IMAGING => {
+            vec![offset_of!(PatientData, imaging_count)..offset_of!(PatientData, canary)]
+        }
+        SECTION_IMMUNIZATIONS => {
+            vec![offset_of!(PatientData, immunization_count)..offset_of!(PatientData, icd10_codes)]
+        }
+        _ => return err!(ErrorCode::InvalidSections),
+    };
+    Ok(ranges)
+}
+
+/// Returns the keys of the `Encounter` accounts in `accounts`, failing with
+/// `InvalidEncounter` unless every one of them belongs to `patient_data`.
+fn included_encounters(patient_data: &Pubkey, accounts: &[AccountInfo]) -> Result<Vec<Pubkey>> {
+    accounts
+        .iter()
+        .map(|info| {
+            let encounter = Account::<Encounter>::try_from(info)?;
+            require_keys_eq!(
+                encounter.patient_data,
+                *patient_data,
+                ErrorCode::InvalidEncounter
+            );
+            Ok(info.key())
+        })
+        .collect()
+}
+
+/// Validates and stores the share rate limit in the program config.
+fn apply_share_rate_limit(
+    config: &mut ProgramConfig,
+    share_rate_limit: u32,
+    share_rate_window: i64,
+) -> Result<()> {
+    require!(
+        share_rate_limit == 0 || share_rate_window > 0,
+        ErrorCode::InvalidPeriod
+    );
+    config.share_rate_limit = share_rate_limit;
+    config.share_rate_window = share_rate_window;
+    Ok(())
+}
+
+/// Fails with `ProgramPaused` while the program config, if initialized, is paused.
+fn check_not_paused(program_config: &UncheckedAccount) -> Result<()> {
+    if program_config.data_is_empty() {
+        return Ok(());
+    }
+    let config_info = program_config.to_account_info();
+    let config = Account::<ProgramConfig>::try_from(&config_info)?;
+    require!(!config.paused, ErrorCode::ProgramPaused);
+    Ok(())
+}
+
+/// k-anonymity threshold of cohort queries, the default while the config is uninitialized.
+fn min_cohort_size(program_config: &UncheckedAccount) -> Result<u8> {
+    if program_config.data_is_empty() {
+        return Ok(DEFAULT_MIN_COHORT_SIZE);
+    }
+    let config_info = program_config.to_account_info();
+    Ok(Account::<ProgramConfig>::try_from(&config_info)?.min_cohort_size)
+}
+
+/// Fails if the instruction is invoked via CPI rather than directly by a transaction.
+fn check_not_cpi() -> Result<()> {
+    require!(
+        get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT,
+        ErrorCode::CpiCallerNotAllowed
+    );
+    Ok(())
+}
+
+/// Checks that a share invoked via CPI comes from a program on the CPI allowlist.
+///
+/// Only a direct CPI from a top-level instruction is accepted: at that stack height the
+/// caller is the program of the transaction's current top-level instruction, read from the
+/// instructions sysvar. Deeper invocation chains are rejected, as the instructions sysvar
+/// cannot tell which program in the chain made the call. Direct invocations pass without
+/// reading the allowlist.
+fn check_cpi_caller(
+    cpi_allowlist: &UncheckedAccount,
+    instructions_sysvar: &AccountInfo,
+) -> Result<()> {
+    let stack_height = get_stack_height();
+    if stack_height == TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Ok(());
+    }
+    require!(
+        stack_height == TRANSACTION_LEVEL_STACK_HEIGHT + 1,
+        ErrorCode::CpiCallerNotAllowed
+    );
+    require!(
+        !cpi_allowlist.data_is_empty(),
+        ErrorCode::CpiCallerNotAllowed
+    );
+    let caller = get_instruction_relative(0, instructions_sysvar)?.program_id;
+    let allowlist_info = cpi_allowlist.to_account_info();
+    let allowlist = Account::<CpiAllowlist>::try_from(&allowlist_info)?;
+    require!(
+        allowlist.programs.contains(&caller),
+        ErrorCode::CpiCallerNotAllowed
+    );
+    Ok(())
+}
+
+/// Checks that the instruction before the current one is an Ed25519 program instruction
+/// verifying a single signature by `signer` over exactly `message`.
+///
+/// The signature, key and message must all be inline in that instruction, as produced by
+/// the standard Ed25519 instruction builders; the Ed25519 program itself has already
+/// verified the signature by the time this instruction runs.
+fn check_ed25519_signature(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let ix = get_instruction_relative(-1, instructions_sysvar)
+        .map_err(|_| error!(ErrorCode::InvalidLabSignature))?;
+    require_keys_eq!(
+        ix.program_id,
+        ed25519_program::ID,
+        ErrorCode::InvalidLabSignature
+    );
+
+    // One signature: a 2-byte header followed by a single 14-byte offsets record
+    let data = &ix.data;
+    require!(
+        data.len() >= 16 && data[0] == 1,
+        ErrorCode::InvalidLabSignature
+    );
+    let read = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let (signature_ix, key_offset, key_ix) = (read(4), read(6) as usize, read(8));
+    let (message_offset, message_size, message_ix) =
+        (read(10) as usize, read(12) as usize, read(14));
+    require!(
+        signature_ix == u16::MAX && key_ix == u16::MAX && message_ix == u16::MAX,
+        ErrorCode::InvalidLabSignature
+    );
+
+    let key = data.get(key_offset..key_offset + 32);
+    let signed = data.get(message_offset..message_offset + message_size);
+    require!(
+        key == Some(signer.as_ref()) && signed == Some(message),
+        ErrorCode::InvalidLabSignature
+    );
+    Ok(())
+}
+
+/// Builds the access log entry of a share and emits it as an `AuditLogEvent`.
+fn log_access(
+    patient_data: Pubkey,
+    receiver: [u8; 32],
+    accessor: Pubkey,
+    role: u8,
+    purpose: u8,
+    computation_offset: u64,
+    bump: u8,
+) -> Result<AccessLogEntry> {
+    require!(purpose <= PURPOSE_PATIENT_REQUEST, ErrorCode::InvalidPurpose);
+    let entry = AccessLogEntry {
+        patient_data,
+        receiver,
+        accessor,
+        role,
+        purpose,
+        computation_offset,
+        timestamp: Clock::get()?.unix_timestamp,
+        bump,
+        de_identified: false,
+    };
+
+    emit!(AuditLogEvent {
+        patient_data,
+        receiver,
+        accessor,
+        role,
+        purpose,
+        computation_offset,
+        timestamp: entry.timestamp,
+    });
+    Ok(entry)
+}
+
+#[derive(Accounts)]
+#[instruction(entry_id: u64)]
+pub struct FlagEntry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"record_entry", patient_data.key().as_ref(), &entry_id.to_le_bytes()],
+        bump = record_entry.bump,
+    )]
+    pub record_entry: Box<Account<'info, RecordEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + EntryFlag::INIT_SPACE,
+        seeds = [b"entry_flag", patient_data.key().as_ref(), &entry_id.to_le_bytes()],
+        bump,
+    )]
+    pub entry_flag: Box<Account<'info, EntryFlag>>,
+}
+
+#[init_computation_definition_accounts("share_flagged_entry", payer)]
+#[derive(Accounts)]
+pub struct InitShareFlaggedEntryCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_flagged_entry", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareFlaggedEntry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_FLAGGED_ENTRY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(
+        constraint = record_entry.patient_data == patient_data.key() @ ErrorCode::InvalidEntry,
+    )]
+    pub record_entry: Box<Account<'info, RecordEntry>>,
+    #[account(
+        seeds = [
+            b"entry_flag",
+            patient_data.key().as_ref(),
+            &record_entry.entry_id.to_le_bytes(),
+        ],
+        bump = entry_flag.bump,
+    )]
+    pub entry_flag: Box<Account<'info, EntryFlag>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[init_computation_definition_accounts("compute_cardio_risk", payer)]
+#[derive(Accounts)]
+pub struct InitComputeCardioRiskCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("compute_cardio_risk", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ComputeCardioRisk<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPUTE_CARDIO_RISK)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("compute_cardio_risk")]
+#[derive(Accounts)]
+pub struct ComputeCardioRiskCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPUTE_CARDIO_RISK)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("check_drug_interaction", payer)]
+#[derive(Accounts)]
+pub struct InitCheckDrugInteractionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("check_drug_interaction", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct CheckDrugInteraction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_DRUG_INTERACTION)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"patient_policy", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: patient policy, only read if the patient has set one.
+    pub patient_policy: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+    #[account(
+        seeds = [
+            b"metadata",
+            anchor_spl::metadata::mpl_token_metadata::ID.as_ref(),
+            credential_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::metadata::mpl_token_metadata::ID,
+    )]
+    pub credential_metadata: Option<Box<Account<'info, anchor_spl::metadata::MetadataAccount>>>,
+    #[account(
+        seeds = [b"role_config", ROLE_PHARMACIST.to_le_bytes().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Box<Account<'info, RoleConfig>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("check_drug_interaction")]
+#[derive(Accounts)]
+pub struct CheckDrugInteractionCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_DRUG_INTERACTION)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCanaryMonitor<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CanaryStatus::INIT_SPACE,
+        seeds = [b"canary_status", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub canary_status: Account<'info, CanaryStatus>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PatientInbox::INIT_SPACE,
+        seeds = [b"patient_inbox", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub patient_inbox: Box<Account<'info, PatientInbox>>,
+}
+
+#[derive(Accounts)]
+pub struct ReportCanaryHit<'info> {
+    pub reporter: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"canary_status", canary_status.patient_data.as_ref()],
+        bump = canary_status.bump,
+    )]
+    pub canary_status: Account<'info, CanaryStatus>,
+    #[account(
+        mut,
+        seeds = [b"patient_inbox", canary_status.patient_data.as_ref()],
+        bump = patient_inbox.bump,
+    )]
+    pub patient_inbox: Box<Account<'info, PatientInbox>>,
+}
+
+#[init_computation_definition_accounts("check_trial_eligibility", payer)]
+#[derive(Accounts)]
+pub struct InitCheckTrialEligibilityCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("check_trial_eligibility", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct CheckTrialEligibility<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_TRIAL_ELIGIBILITY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("check_trial_eligibility")]
+#[derive(Accounts)]
+pub struct CheckTrialEligibilityCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_TRIAL_ELIGIBILITY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+/// Plaintext inclusion and exclusion criteria of a clinical trial.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TrialCriteria {
+    /// Minimum patient age, inclusive
+    pub min_age: u8,
+    /// Maximum patient age, inclusive
+    pub max_age: u8,
+    /// Bit i requires `medical_history[i]` (diabetes, hypertension, ...)
+    pub required_history: u16,
+    /// Medications that exclude the patient; 0 marks an unused slot
+    pub excluded_medications: [u64; MAX_EXCLUDED_MEDICATIONS],
+}
+
+#[derive(Accounts)]
+pub struct SetPatientPolicy<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PatientPolicy::INIT_SPACE,
+        seeds = [b"patient_policy", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub patient_policy: Account<'info, PatientPolicy>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSectionLocks<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + SectionLocks::INIT_SPACE,
+        seeds = [b"section_locks", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub section_locks: Account<'info, SectionLocks>,
+}
+
+/// Requires the credential's Metaplex metadata to carry the verified collection of the
+/// role's `RoleConfig`. Roles without a configured collection are not checked.
+fn check_credential_collection(
+    role_config: &RoleConfig,
+    metadata: Option<&Account<'_, anchor_spl::metadata::MetadataAccount>>,
+    mint: &Pubkey,
+) -> Result<()> {
+    let collection = role_config.collection;
+    if collection == Pubkey::default() {
+        return Ok(());
+    }
+    let metadata = metadata.ok_or(ErrorCode::UnverifiedCredentialCollection)?;
+    require_keys_eq!(metadata.mint, *mint, ErrorCode::UnverifiedCredentialCollection);
+    require!(
+        matches!(&metadata.collection, Some(c) if c.verified && c.key == collection),
+        ErrorCode::UnverifiedCredentialCollection
+    );
+    Ok(())
+}
+
+/// Whether `mint` is a Token-2022 mint with the NonTransferable extension, i.e. a soulbound
+/// credential.
+fn is_non_transferable(mint: &AccountInfo) -> Result<bool> {
+    use anchor_spl::token_2022::spl_token_2022::{
+        extension::{
+            non_transferable::NonTransferable, BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::Mint,
+    };
+
+    if *mint.owner != anchor_spl::token_2022::ID {
+        return Ok(false);
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(state.get_extension::<NonTransferable>().is_ok())
+}
+
+/// Record section a record entry of the given `ENTRY_KIND_*` belongs to; visit notes are
+/// part of the medical history.
+fn entry_section(kind: u8) -> u8 {
+    match kind {
+        ENTRY_KIND_LAB_RESULT => SECTION_LAB_TESTS,
+        ENTRY_KIND_MEDICATION => SECTION_MEDICATIONS,
+        _ => SECTION_HISTORY,
+    }
+}
+
+/// Record sections holding the fields `share_selected_fields` discloses for `mask`.
+fn selected_field_sections(mask: u128) -> u8 {
+    SELECTABLE_FIELD_SECTIONS
+        .iter()
+        .enumerate()
+        .filter(|&(field, _)| mask & (1 << field) != 0)
+        .fold(0, |sections, (_, section)| sections | section)
+}
+
+/// Data categories touched by a share of the given record sections.
+fn section_categories(sections: u8) -> u8 {
+    let mut categories = 0;
+    if sections & (SECTION_DEMOGRAPHICS | SECTION_ALLERGIES) != 0 {
+        categories |= 1 << CATEGORY_DEMOGRAPHICS;
+    }
+    if sections & (SECTION_MEDICATIONS | SECTION_HISTORY | SECTION_IMMUNIZATIONS) != 0 {
+        categories |= 1 << CATEGORY_HEALTHCARE;
+    }
+    if sections & SECTION_GENOMICS != 0 {
+        categories |= 1 << CATEGORY_GENOMICS;
+    }
+    if sections & (SECTION_LAB_TESTS | SECTION_IMAGING) != 0 {
+        categories |= 1 << CATEGORY_LAB_TESTS;
+    }
+    categories
+}
+
+/// Rejects credentials the registrar revoked or whose license has lapsed.
+fn check_credential_record(record: &CredentialRecord) -> Result<()> {
+    require!(!record.revoked, ErrorCode::CredentialRevoked);
+    require!(
+        Clock::get()?.unix_timestamp < record.expires_at,
+        ErrorCode::CredentialExpired
+    );
+    Ok(())
+}
+
+/// Fails with `SectionLocked` if the patient has locked any of `sections` (a bit set of
+/// `SECTION_*`) with `lock_section`.
+fn check_sections_unlocked(locks: &AccountInfo, sections: u8) -> Result<()> {
+    if locks.data_is_empty() {
+        return Ok(());
+    }
+    let locks = SectionLocks::try_deserialize(&mut &locks.try_borrow_data()?[..])?;
+    require!(locks.locked & sections == 0, ErrorCode::SectionLocked);
+    Ok(())
+}
+
+/// Checks that the patient's `PatientPolicy`, if any, lets `role` receive every category in
+/// `categories` (a bit set of `CATEGORY_*`).
+fn check_patient_policy(policy: &UncheckedAccount, categories: u8, role: u8) -> Result<()> {
+    if policy.data_is_empty() {
+        return Ok(());
+    }
+    let policy = PatientPolicy::try_deserialize(&mut &policy.try_borrow_data()?[..])?;
+    for (category, allowed) in policy.allowed_roles.iter().enumerate() {
+        if categories & (1 << category) != 0 {
+            require!(allowed & (1 << role) != 0, ErrorCode::RoleNotAllowed);
+        }
+    }
+    Ok(())
+}
+
+#[init_computation_definition_accounts("check_allergy_conflict", payer)]
+#[derive(Accounts)]
+pub struct InitCheckAllergyConflictCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("check_allergy_conflict", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct CheckAllergyConflict<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_ALLERGY_CONFLICT)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"patient_policy", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: patient policy, only read if the patient has set one.
+    pub patient_policy: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+    #[account(
+        seeds = [
+            b"metadata",
+            anchor_spl::metadata::mpl_token_metadata::ID.as_ref(),
+            credential_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::metadata::mpl_token_metadata::ID,
+    )]
+    pub credential_metadata: Option<Box<Account<'info, anchor_spl::metadata::MetadataAccount>>>,
+    #[account(
+        seeds = [b"role_config", ROLE_DOCTOR.to_le_bytes().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Box<Account<'info, RoleConfig>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("check_allergy_conflict")]
+#[derive(Accounts)]
+pub struct CheckAllergyConflictCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_ALLERGY_CONFLICT)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitProgramConfig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ProgramConfig::INIT_SPACE,
+        seeds = [b"program_config"],
+        bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SubsidyPool::INIT_SPACE,
+        seeds = [b"subsidy_pool"],
+        bump,
+    )]
+    pub subsidy_pool: Account<'info, SubsidyPool>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetSubsidyMode<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: u8)]
+pub struct SetRoleConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RoleConfig::INIT_SPACE,
+        seeds = [b"role_config", role.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub role_config: Account<'info, RoleConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitGovernance<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GovernanceAccount::INIT_SPACE,
+        seeds = [b"governance"],
+        bump,
+    )]
+    pub governance: Account<'info, GovernanceAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdminAction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [b"governance"],
+        bump = governance.bump,
+    )]
+    pub governance: Account<'info, GovernanceAccount>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AdminProposal::INIT_SPACE,
+        seeds = [b"admin_proposal", &governance.proposal_count.to_le_bytes()],
+        bump,
+    )]
+    pub admin_proposal: Box<Account<'info, AdminProposal>>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveAction<'info> {
+    pub member: Signer<'info>,
+    #[account(
+        seeds = [b"governance"],
+        bump = governance.bump,
+    )]
+    pub governance: Account<'info, GovernanceAccount>,
+    #[account(
+        mut,
+        seeds = [b"admin_proposal", &admin_proposal.proposal_id.to_le_bytes()],
+        bump = admin_proposal.bump,
+    )]
+    pub admin_proposal: Box<Account<'info, AdminProposal>>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteAction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"governance"],
+        bump = governance.bump,
+    )]
+    pub governance: Account<'info, GovernanceAccount>,
+    #[account(
+        mut,
+        seeds = [b"admin_proposal", &admin_proposal.proposal_id.to_le_bytes()],
+        bump = admin_proposal.bump,
+    )]
+    pub admin_proposal: Box<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        constraint = program_config.authority == governance.key() @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RoleConfig::INIT_SPACE,
+        seeds = [b"role_config", admin_proposal.action.role().to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub role_config: Option<Box<Account<'info, RoleConfig>>>,
+    #[account(
+        mut,
+        seeds = [b"provider_registry"],
+        bump = provider_registry.bump,
+    )]
+    pub provider_registry: Option<Box<Account<'info, ProviderRegistry>>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetSoulboundCredentials<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct FundSubsidyPool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"subsidy_pool"],
+        bump = subsidy_pool.bump,
+    )]
+    pub subsidy_pool: Account<'info, SubsidyPool>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSubsidy<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        seeds = [b"subsidy_pool"],
+        bump = subsidy_pool.bump,
+    )]
+    pub subsidy_pool: Account<'info, SubsidyPool>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + SubsidyUsage::INIT_SPACE,
+        seeds = [b"subsidy_usage", wallet.key().as_ref()],
+        bump,
+    )]
+    pub subsidy_usage: Account<'info, SubsidyUsage>,
+    #[account(
+        address = program_config.hardship_credential_mint @ ErrorCode::Unauthorized,
+    )]
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == wallet.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("check_blood_compatibility", payer)]
+#[derive(Accounts)]
+pub struct InitCheckBloodCompatibilityCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("check_blood_compatibility", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct CheckBloodCompatibility<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_BLOOD_COMPATIBILITY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"patient_policy", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: patient policy, only read if the patient has set one.
+    pub patient_policy: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+    #[account(
+        seeds = [
+            b"metadata",
+            anchor_spl::metadata::mpl_token_metadata::ID.as_ref(),
+            credential_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::metadata::mpl_token_metadata::ID,
+    )]
+    pub credential_metadata: Option<Box<Account<'info, anchor_spl::metadata::MetadataAccount>>>,
+    #[account(
+        seeds = [b"role_config", ROLE_PROVIDER.to_le_bytes().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Box<Account<'info, RoleConfig>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("check_blood_compatibility")]
+#[derive(Accounts)]
+pub struct CheckBloodCompatibilityCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_BLOOD_COMPATIBILITY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRotationPolicy<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump = record_metadata.bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+}
+
+#[init_computation_definition_accounts("rotate_patient_data", payer)]
+#[derive(Accounts)]
+pub struct InitRotatePatientDataCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("rotate_patient_data", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RotateRecordEncryption<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_ROTATE_PATIENT_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump = record_metadata.bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+}
+
+#[callback_accounts("rotate_patient_data")]
+#[derive(Accounts)]
+pub struct RotatePatientDataCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_ROTATE_PATIENT_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump = record_metadata.bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+}
+
+/// Derives the nonce of the next re-encryption of a record from its rotation count.
+fn rotation_nonce(patient_data: &Pubkey, epoch: u64, rotations: usize) -> u128 {
+    let digest = hashv(&[
+        b"rotation_nonce",
+        patient_data.as_ref(),
+        &epoch.to_le_bytes(),
+        &(rotations as u64).to_le_bytes(),
+    ]);
+    let mut nonce = [0u8; 16];
+    nonce.copy_from_slice(&digest.to_bytes()[..16]);
+    u128::from_le_bytes(nonce)
+}
+
+/// Overwrites a record with its re-encrypted ciphertexts and records the key and nonce they
+/// are now encrypted with in the rotation state and history.
+fn write_reencrypted_record(
+    patient_data: &AccountLoader<PatientData>,
+    record_metadata: &mut RecordMetadata,
+    ciphertexts: &[[u8; 32]],
+    encryption_key: [u8; 32],
+    encryption_nonce: u128,
+) -> Result<()> {
+    {
+        let info = patient_data.to_account_info();
+        let mut data = info.try_borrow_mut_data()?;
+        for (i, ciphertext) in ciphertexts.iter().enumerate() {
+            let start = 8 + i * 32;
+            data[start..start + 32].copy_from_slice(ciphertext);
+        }
+    }
+
+    let epoch = Clock::get()?.epoch;
+    let rotation = &mut record_metadata.rotation;
+    rotation.encryption_key = encryption_key;
+    rotation.encryption_nonce = encryption_nonce;
+    rotation.last_rotated_epoch = epoch;
+    rotation.pending_since_epoch = None;
+    if rotation.history.len() == MAX_ROTATION_HISTORY {
+        rotation.history.remove(0);
+    }
+    rotation.history.push(RotationRecord {
+        epoch,
+        encryption_key,
+        encryption_nonce,
+    });
+
+    emit!(RecordRotatedEvent {
+        patient_data: patient_data.key(),
+        epoch,
+        encryption_key,
+        encryption_nonce,
+    });
+    emit!(PatientDataUpdatedEvent {
+        schema_version: EVENT_SCHEMA_VERSION,
+        patient_data: patient_data.key(),
+        updated_at: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[init_computation_definition_accounts("rekey_patient_data", payer)]
+#[derive(Accounts)]
+pub struct InitRekeyPatientDataCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("rekey_patient_data", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RekeyPatientData<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REKEY_PATIENT_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump = record_metadata.bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+}
+
+#[callback_accounts("rekey_patient_data")]
+#[derive(Accounts)]
+pub struct RekeyPatientDataCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REKEY_PATIENT_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump = record_metadata.bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+}
+
+#[derive(Accounts)]
+pub struct SetRecoveryPolicy<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RecoveryPolicy::INIT_SPACE,
+        seeds = [b"recovery_policy", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub recovery_policy: Account<'info, RecoveryPolicy>,
+}
+
+#[derive(Accounts)]
+pub struct InitiateRecovery<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"recovery_policy", recovery_policy.patient_data.as_ref()],
+        bump = recovery_policy.bump,
+    )]
+    pub recovery_policy: Account<'info, RecoveryPolicy>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRecovery<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"recovery_policy", patient_data.key().as_ref()],
+        bump = recovery_policy.bump,
+    )]
+    pub recovery_policy: Account<'info, RecoveryPolicy>,
+}
+
+#[queue_computation_accounts("rekey_patient_data", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CompleteRecovery<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REKEY_PATIENT_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"recovery_policy", recovery_policy.patient_data.as_ref()],
+        bump = recovery_policy.bump,
+        constraint = recovery_policy.new_owner == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub recovery_policy: Box<Account<'info, RecoveryPolicy>>,
+    #[account(mut, close = payer, address = recovery_policy.patient_data)]
+    pub old_patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"record_metadata", old_patient_data.key().as_ref()],
+        bump = old_record_metadata.bump,
+    )]
+    pub old_record_metadata: Box<Account<'info, RecordMetadata>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<PatientData>(),
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RecordMetadata::INIT_SPACE,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[queue_computation_accounts("rekey_patient_data", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct TransferCustodyToPatient<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REKEY_PATIENT_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+    #[account(
+        mut,
+        close = guardian,
+        seeds = [b"custody", old_patient_data.key().as_ref()],
+        bump = custody.bump,
+        has_one = guardian @ ErrorCode::Unauthorized,
+    )]
+    pub custody: Box<Account<'info, Custody>>,
+    #[account(
+        mut,
+        close = guardian,
+        seeds = [
+            b"pediatric_patient_data",
+            guardian.key().as_ref(),
+            &custody.child_id.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub old_patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        close = guardian,
+        seeds = [b"record_metadata", old_patient_data.key().as_ref()],
+        bump = old_record_metadata.bump,
+    )]
+    pub old_record_metadata: Box<Account<'info, RecordMetadata>>,
+    #[account(
+        mut,
+        close = guardian,
+        seeds = [b"delegation", old_patient_data.key().as_ref(), guardian.key().as_ref()],
+        bump = guardian_delegation.bump,
+    )]
+    pub guardian_delegation: Box<Account<'info, DelegationAccount>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<PatientData>(),
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RecordMetadata::INIT_SPACE,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[init_computation_definition_accounts("recommend_dose", payer)]
+#[derive(Accounts)]
+pub struct InitRecommendDoseCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("recommend_dose", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct RecommendDose<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_RECOMMEND_DOSE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"patient_policy", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: patient policy, only read if the patient has set one.
+    pub patient_policy: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+    #[account(
+        seeds = [
+            b"metadata",
+            anchor_spl::metadata::mpl_token_metadata::ID.as_ref(),
+            credential_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::metadata::mpl_token_metadata::ID,
+    )]
+    pub credential_metadata: Option<Box<Account<'info, anchor_spl::metadata::MetadataAccount>>>,
+    #[account(
+        seeds = [b"role_config", ROLE_DOCTOR.to_le_bytes().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Box<Account<'info, RoleConfig>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("recommend_dose")]
+#[derive(Accounts)]
+pub struct RecommendDoseCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_RECOMMEND_DOSE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("prove_attribute_threshold", payer)]
+#[derive(Accounts)]
+pub struct InitProveAttributeThresholdCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("prove_attribute_threshold", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ProveAttributeThreshold<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROVE_ATTRIBUTE_THRESHOLD)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("prove_attribute_threshold")]
+#[derive(Accounts)]
+pub struct ProveAttributeThresholdCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROVE_ATTRIBUTE_THRESHOLD)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateImmunizations<'info> {
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump = record_metadata.bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+
+    // Credential NFT accounts, required unless the patient writes to their own record
+    pub credential_mint: Option<Account<'info, anchor_spl::token::Mint>>,
+    pub credential_token_account: Option<Account<'info, anchor_spl::token::TokenAccount>>,
+}
+
+#[init_computation_definition_accounts("prove_vaccination", payer)]
+#[derive(Accounts)]
+pub struct InitProveVaccinationCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("prove_vaccination", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ProveVaccination<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROVE_VACCINATION)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("prove_vaccination")]
+#[derive(Accounts)]
+pub struct ProveVaccinationCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROVE_VACCINATION)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PublishReceiverProfile<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ReceiverProfile::INIT_SPACE,
+        seeds = [b"receiver_profile", authority.key().as_ref()],
+        bump,
+    )]
+    pub receiver_profile: Box<Account<'info, ReceiverProfile>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseReceiverProfile<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"receiver_profile", authority.key().as_ref()],
+        bump = receiver_profile.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub receiver_profile: Box<Account<'info, ReceiverProfile>>,
+}
+
+#[derive(Accounts)]
+pub struct SetResearchConsent<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ResearchConsent::INIT_SPACE,
+        seeds = [b"research_consent", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub research_consent: Account<'info, ResearchConsent>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PrivacyBudget::INIT_SPACE,
+        seeds = [b"privacy_budget", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub privacy_budget: Account<'info, PrivacyBudget>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RoyaltyBalance::INIT_SPACE,
+        seeds = [b"royalty_balance", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub royalty_balance: Account<'info, RoyaltyBalance>,
+}
+
+/// Records the cohort in the royalty pool, if one is passed, and returns the callback
+/// accounts marking it completed.
+fn attach_royalty_pool(
+    pool: Option<&mut Account<RoyaltyPool>>,
+    members: &[Pubkey],
+) -> Result<Vec<CallbackAccount>> {
+    let Some(pool) = pool else {
+        return Ok(Vec::new());
+    };
+    require!(pool.members.is_empty(), ErrorCode::RoyaltyPoolInUse);
+    pool.members = members.to_vec();
+    Ok(vec![CallbackAccount {
+        pubkey: pool.key(),
+        is_writable: true,
+    }])
+}
+
+/// Hash identifying the `aggregate_cohort` query a dataset listing sells.
+pub fn cohort_criteria_hash(condition: u8, min_age: u8, max_age: u8) -> [u8; 32] {
+    hashv(&[b"cohort_criteria", &[condition, min_age, max_age]]).to_bytes()
+}
+
+/// Checks a cohort member's clinical research consent and section locks and appends the
+/// arguments reading its record.
+fn add_cohort_member(
+    patient_data: &AccountInfo,
+    consent_info: &AccountInfo,
+    locks_info: &AccountInfo,
+    members: &mut Vec<Pubkey>,
+    args: &mut Vec<Argument>,
+) -> Result<()> {
+    require!(!members.contains(patient_data.key), ErrorCode::InvalidCohort);
+    let (consent_address, _) = Pubkey::find_program_address(
+        &[b"research_consent", patient_data.key.as_ref()],
+        &ID,
+    );
+    require_keys_eq!(consent_info.key(), consent_address, ErrorCode::InvalidCohort);
+    let consent = Account::<ResearchConsent>::try_from(consent_info)?;
+    consent.check_active(RESEARCH_SCOPE_CLINICAL)?;
+    let (locks_address, _) =
+        Pubkey::find_program_address(&[b"section_locks", patient_data.key.as_ref()], &ID);
+    require_keys_eq!(locks_info.key(), locks_address, ErrorCode::InvalidCohort);
+    check_sections_unlocked(locks_info, SECTION_DEMOGRAPHICS | SECTION_HISTORY)?;
+
+    args.push(Argument::ArcisPubkey(consent.encryption_key));
+    args.push(Argument::PlaintextU128(consent.encryption_nonce));
+    args.push(Argument::Account(
+        patient_data.key(),
+        8,
+        PATIENT_CIPHERTEXTS_LEN,
+    ));
+    members.push(*patient_data.key);
+    Ok(())
+}
+
+#[init_computation_definition_accounts("aggregate_cohort", payer)]
+#[derive(Accounts)]
+pub struct InitAggregateCohortCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("aggregate_cohort", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct AggregateCohort<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_AGGREGATE_COHORT)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"royalty_pool", payer.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump = royalty_pool.bump,
+    )]
+    pub royalty_pool: Option<Box<Account<'info, RoyaltyPool>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"query_purchase", payer.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump = query_purchase.bump,
+    )]
+    pub query_purchase: Option<Box<Account<'info, QueryPurchase>>>,
+}
+
+#[callback_accounts("aggregate_cohort")]
+#[derive(Accounts)]
+pub struct AggregateCohortCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_AGGREGATE_COHORT)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub royalty_pool: Option<Account<'info, RoyaltyPool>>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterExtension<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ExtensionRegistry::INIT_SPACE,
+        seeds = [b"extension_registry"],
+        bump,
+    )]
+    pub extension_registry: Account<'info, ExtensionRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveExtension<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        seeds = [b"extension_registry"],
+        bump = extension_registry.bump,
+    )]
+    pub extension_registry: Account<'info, ExtensionRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct AddCpiCaller<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + CpiAllowlist::INIT_SPACE,
+        seeds = [b"cpi_allowlist"],
+        bump,
+    )]
+    pub cpi_allowlist: Account<'info, CpiAllowlist>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveCpiCaller<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        seeds = [b"cpi_allowlist"],
+        bump = cpi_allowlist.bump,
+    )]
+    pub cpi_allowlist: Account<'info, CpiAllowlist>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveCluster<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ApprovedClusters::INIT_SPACE,
+        seeds = [b"approved_clusters"],
+        bump,
+    )]
+    pub approved_clusters: Account<'info, ApprovedClusters>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveCluster<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        seeds = [b"approved_clusters"],
+        bump = approved_clusters.bump,
+    )]
+    pub approved_clusters: Account<'info, ApprovedClusters>,
+}
+
+#[derive(Accounts)]
+pub struct UpgradeCompDef<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + CompDefRegistry::INIT_SPACE,
+        seeds = [b"comp_def_registry"],
+        bump,
+    )]
+    pub comp_def_registry: Box<Account<'info, CompDefRegistry>>,
+    /// CHECK: computation definition of the new version, checked against its offset and the
+    /// Arcium program in the instruction.
+    pub comp_def_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeCompDef<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        mut,
+        seeds = [b"comp_def_registry"],
+        bump = comp_def_registry.bump,
+    )]
+    pub comp_def_registry: Box<Account<'info, CompDefRegistry>>,
+}
+
+/// Checks that `comp_def_offset` is the active version of the circuit behind instruction
+/// `name` in the `CompDefRegistry`. Instructions that were never upgraded, or a missing
+/// registry, run their built-in circuit.
+fn check_comp_def(registry: &UncheckedAccount, name: &str, comp_def_offset: u32) -> Result<()> {
+    if registry.data_is_empty() {
+        return Ok(());
+    }
+    let registry = CompDefRegistry::try_deserialize(&mut &registry.try_borrow_data()?[..])?;
+    if let Some(circuit) = registry.circuits.iter().find(|c| c.name == name) {
+        require!(
+            circuit.comp_def_offset == comp_def_offset,
+            ErrorCode::CompDefDeprecated
+        );
+    }
+    Ok(())
+}
+
+/// Checks the Arcium cluster a computation is queued on: the MXE's own cluster when no
+/// `cluster_offset` is given, otherwise the cluster at `cluster_offset`, which must be in
+/// the `ApprovedClusters` list.
+fn check_cluster(
+    mxe_account: &MXEAccount,
+    approved_clusters: &UncheckedAccount,
+    cluster_offset: Option<u32>,
+    cluster: &Account<Cluster>,
+) -> Result<()> {
+    let Some(cluster_offset) = cluster_offset else {
+        require_keys_eq!(
+            cluster.key(),
+            derive_cluster_pda!(mxe_account),
+            ErrorCode::ClusterNotApproved
+        );
+        return Ok(());
+    };
+    require!(
+        !approved_clusters.data_is_empty(),
+        ErrorCode::ClusterNotApproved
+    );
+    let approved =
+        ApprovedClusters::try_deserialize(&mut &approved_clusters.try_borrow_data()?[..])?;
+    require!(
+        approved.clusters.contains(&cluster_offset),
+        ErrorCode::ClusterNotApproved
+    );
+    let (address, _) = Pubkey::find_program_address(
+        &[CLUSTER_PDA_SEED, &cluster_offset.to_le_bytes()],
+        &ARCIUM_PROG_ID,
+    );
+    require_keys_eq!(cluster.key(), address, ErrorCode::ClusterNotApproved);
+    Ok(())
+}
+
+/// Pairs the registered extensions with their accounts from `remaining_accounts`.
+///
+/// Every registered extension must be present, in registry order, as its program account
+/// followed by its extra accounts; an empty registry account means no extensions.
+fn extension_hooks<'a, 'info>(
+    registry: &UncheckedAccount<'info>,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<Vec<(ExtensionEntry, &'a [AccountInfo<'info>])>> {
+    if registry.data_is_empty() {
+        return Ok(Vec::new());
+    }
+    let registry = ExtensionRegistry::try_deserialize(&mut &registry.try_borrow_data()?[..])?;
+
+    let mut hooks = Vec::with_capacity(registry.extensions.len());
+    let mut cursor = 0;
+    for entry in registry.extensions {
+        let end = cursor + 1 + entry.extra_accounts as usize;
+        require!(end <= remaining_accounts.len(), ErrorCode::MissingExtension);
+        let accounts = &remaining_accounts[cursor..end];
+        require_keys_eq!(accounts[0].key(), entry.program_id, ErrorCode::MissingExtension);
+        hooks.push((entry, accounts));
+        cursor = end;
+    }
+    Ok(hooks)
+}
+
+/// Calls `hook` on every extension registered for it.
+///
+/// `hook_accounts` are passed first, neither signing nor writable, followed by the
+/// extension's own accounts with the privileges they have in this transaction.
+fn call_extension_hooks<'info>(
+    extensions: &[(ExtensionEntry, &[AccountInfo<'info>])],
+    hook: u8,
+    args: &ShareHookArgs,
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let name: &[u8] = if hook == HOOK_PRE_SHARE {
+        b"global:pre_share"
+    } else {
+        b"global:post_share"
+    };
+    let mut data = hashv(&[name]).to_bytes()[..8].to_vec();
+    args.serialize(&mut data)?;
+
+    for (entry, accounts) in extensions.iter().filter(|(e, _)| e.hooks & hook != 0) {
+        let extra = &accounts[1..];
+        let metas = hook_accounts
+            .iter()
+            .map(|a| AccountMeta::new_readonly(a.key(), false))
+            .chain(extra.iter().map(|a| {
+                if a.is_writable {
+                    AccountMeta::new(a.key(), a.is_signer)
+                } else {
+                    AccountMeta::new_readonly(a.key(), a.is_signer)
+                }
+            }))
+            .collect();
+        let infos: Vec<AccountInfo<'info>> = hook_accounts
+            .iter()
+            .chain(extra.iter())
+            .chain(core::iter::once(&accounts[0]))
+            .cloned()
+            .collect();
+        invoke(
+            &Instruction {
+                program_id: entry.program_id,
+                accounts: metas,
+                data: data.clone(),
+            },
+            &infos,
+        )?;
+    }
+    Ok(())
+}
+
+#[init_computation_definition_accounts("aggregate_cohort_dp", payer)]
+#[derive(Accounts)]
+pub struct InitAggregateCohortDpCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("aggregate_cohort_dp", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct AggregateCohortDp<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_AGGREGATE_COHORT_DP)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"royalty_pool", payer.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump = royalty_pool.bump,
+    )]
+    pub royalty_pool: Option<Box<Account<'info, RoyaltyPool>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("aggregate_cohort_dp")]
+#[derive(Accounts)]
+pub struct AggregateCohortDpCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_AGGREGATE_COHORT_DP)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub royalty_pool: Option<Account<'info, RoyaltyPool>>,
+}
+
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct FundRoyaltyPool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RoyaltyPool::INIT_SPACE,
+        seeds = [b"royalty_pool", payer.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub royalty_pool: Account<'info, RoyaltyPool>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeRoyalties<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"royalty_pool",
+            royalty_pool.researcher.as_ref(),
+            &royalty_pool.computation_offset.to_le_bytes(),
+        ],
+        bump = royalty_pool.bump,
+        close = researcher,
+    )]
+    pub royalty_pool: Account<'info, RoyaltyPool>,
+    /// CHECK: researcher receiving the pool's rent and remainder, checked by the constraint
+    #[account(mut, address = royalty_pool.researcher)]
+    pub researcher: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRoyalty<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"royalty_balance", patient_data.key().as_ref()],
+        bump = royalty_balance.bump,
+    )]
+    pub royalty_balance: Account<'info, RoyaltyBalance>,
+}
+
+#[derive(Accounts)]
+#[instruction(listing_id: u64)]
+pub struct CreateListing<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [
+            b"organization",
+            organization.authority.as_ref(),
+            &organization.org_id.to_le_bytes(),
+        ],
+        bump = organization.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub organization: Box<Account<'info, Organization>>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + DatasetListing::INIT_SPACE,
+        seeds = [
+            b"dataset_listing",
+            organization.key().as_ref(),
+            &listing_id.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub dataset_listing: Account<'info, DatasetListing>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateListing<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [
+            b"organization",
+            organization.authority.as_ref(),
+            &organization.org_id.to_le_bytes(),
+        ],
+        bump = organization.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub organization: Box<Account<'info, Organization>>,
+    #[account(
+        mut,
+        seeds = [
+            b"dataset_listing",
+            organization.key().as_ref(),
+            &dataset_listing.listing_id.to_le_bytes(),
+        ],
+        bump = dataset_listing.bump,
+    )]
+    pub dataset_listing: Account<'info, DatasetListing>,
+}
+
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PurchaseQuery<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [
+            b"dataset_listing",
+            dataset_listing.organization.as_ref(),
+            &dataset_listing.listing_id.to_le_bytes(),
+        ],
+        bump = dataset_listing.bump,
+    )]
+    pub dataset_listing: Account<'info, DatasetListing>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RoyaltyPool::INIT_SPACE,
+        seeds = [b"royalty_pool", payer.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub royalty_pool: Account<'info, RoyaltyPool>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + QueryPurchase::INIT_SPACE,
+        seeds = [b"query_purchase", payer.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub query_purchase: Account<'info, QueryPurchase>,
+}
+
+#[init_computation_definition_accounts("match_genetic_markers", payer)]
+#[derive(Accounts)]
+pub struct InitMatchGeneticMarkersCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("match_genetic_markers", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct MatchGeneticMarkers<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_GENETIC_MARKERS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"research_consent", patient_data.key().as_ref()],
+        bump = research_consent.bump,
+    )]
+    pub research_consent: Account<'info, ResearchConsent>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("match_genetic_markers")]
+#[derive(Accounts)]
+pub struct MatchGeneticMarkersCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_GENETIC_MARKERS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("analyze_lab_trend", payer)]
+#[derive(Accounts)]
+pub struct InitAnalyzeLabTrendCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("analyze_lab_trend", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct AnalyzeLabTrend<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_ANALYZE_LAB_TREND)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"patient_policy", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: patient policy, only read if the patient has set one.
+    pub patient_policy: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+    #[account(
+        seeds = [
+            b"metadata",
+            anchor_spl::metadata::mpl_token_metadata::ID.as_ref(),
+            credential_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::metadata::mpl_token_metadata::ID,
+    )]
+    pub credential_metadata: Option<Box<Account<'info, anchor_spl::metadata::MetadataAccount>>>,
+    #[account(
+        seeds = [b"role_config", ROLE_DOCTOR.to_le_bytes().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Box<Account<'info, RoleConfig>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("analyze_lab_trend")]
+#[derive(Accounts)]
+pub struct AnalyzeLabTrendCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_ANALYZE_LAB_TREND)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitVitalsSeries<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<VitalsSeries>(),
+        seeds = [b"vitals_series", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub vitals_series: AccountLoader<'info, VitalsSeries>,
+}
+
+#[derive(Accounts)]
+pub struct AppendVitalsBatch<'info> {
+    pub device: Signer<'info>,
+    #[account(
+        mut,
+        has_one = device @ ErrorCode::Unauthorized,
+    )]
+    pub vitals_series: AccountLoader<'info, VitalsSeries>,
+}
+
+#[init_computation_definition_accounts("share_vitals_window", payer)]
+#[derive(Accounts)]
+pub struct InitShareVitalsWindowCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_vitals_window", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareVitalsWindow<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_VITALS_WINDOW)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(
+        seeds = [b"vitals_series", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub vitals_series: AccountLoader<'info, VitalsSeries>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("share_vitals_window")]
+#[derive(Accounts)]
+pub struct ShareVitalsWindowCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_VITALS_WINDOW)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitGlucoseSeries<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<GlucoseSeries>(),
+        seeds = [b"glucose_series", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub glucose_series: AccountLoader<'info, GlucoseSeries>,
+}
+
+#[derive(Accounts)]
+pub struct AppendGlucoseBatch<'info> {
+    pub device: Signer<'info>,
+    #[account(
+        mut,
+        has_one = device @ ErrorCode::Unauthorized,
+    )]
+    pub glucose_series: AccountLoader<'info, GlucoseSeries>,
+}
+
+#[init_computation_definition_accounts("compute_glucose_metrics", payer)]
+#[derive(Accounts)]
+pub struct InitComputeGlucoseMetricsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("compute_glucose_metrics", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ComputeGlucoseMetrics<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPUTE_GLUCOSE_METRICS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"patient_policy", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: patient policy, only read if the patient has set one.
+    pub patient_policy: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    #[account(
+        seeds = [b"glucose_series", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub glucose_series: AccountLoader<'info, GlucoseSeries>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+    #[account(
+        seeds = [
+            b"metadata",
+            anchor_spl::metadata::mpl_token_metadata::ID.as_ref(),
+            credential_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::metadata::mpl_token_metadata::ID,
+    )]
+    pub credential_metadata: Option<Box<Account<'info, anchor_spl::metadata::MetadataAccount>>>,
+    #[account(
+        seeds = [b"role_config", ROLE_ENDOCRINOLOGIST.to_le_bytes().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Box<Account<'info, RoleConfig>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("compute_glucose_metrics")]
+#[derive(Accounts)]
+pub struct ComputeGlucoseMetricsCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPUTE_GLUCOSE_METRICS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StoreMentalHealthData<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + MentalHealthData::INIT_SPACE,
+        seeds = [b"mental_health", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub mental_health_data: Box<Account<'info, MentalHealthData>>,
+}
+
+#[derive(Accounts)]
+#[instruction(receiver: [u8; 32])]
+pub struct GrantMentalHealthAccess<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + MentalHealthGrant::INIT_SPACE,
+        seeds = [b"mental_health_grant", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub mental_health_grant: Account<'info, MentalHealthGrant>,
+}
+
+#[derive(Accounts)]
+#[instruction(receiver: [u8; 32])]
+pub struct RevokeMentalHealthAccess<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"mental_health_grant", patient_data.key().as_ref(), receiver.as_ref()],
+        bump = mental_health_grant.bump,
+    )]
+    pub mental_health_grant: Account<'info, MentalHealthGrant>,
+}
+
+#[init_computation_definition_accounts("share_mental_health", payer)]
+#[derive(Accounts)]
+pub struct InitShareMentalHealthCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_mental_health", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareMentalHealth<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_MENTAL_HEALTH)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", patient.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver allowlist, only read if the patient has created one.
+    pub receiver_allowlist: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    #[account(
+        seeds = [b"mental_health", patient_data.key().as_ref()],
+        bump = mental_health_data.bump,
+    )]
+    pub mental_health_data: Box<Account<'info, MentalHealthData>>,
+    #[account(
+        seeds = [b"mental_health_grant", patient_data.key().as_ref(), receiver.as_ref()],
+        bump = mental_health_grant.bump,
+    )]
+    pub mental_health_grant: Box<Account<'info, MentalHealthGrant>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("share_mental_health")]
+#[derive(Accounts)]
+pub struct ShareMentalHealthCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_MENTAL_HEALTH)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StoreObstetricData<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ObstetricData::INIT_SPACE,
+        seeds = [b"obstetric_data", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub obstetric_data: Box<Account<'info, ObstetricData>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateObstetricData<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"obstetric_data", patient_data.key().as_ref()],
+        bump = obstetric_data.bump,
+    )]
+    pub obstetric_data: Box<Account<'info, ObstetricData>>,
+}
+
+#[derive(Accounts)]
+pub struct AttachImagingStudy<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ImagingStudies::INIT_SPACE,
+        seeds = [b"imaging_studies", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub imaging_studies: Box<Account<'info, ImagingStudies>>,
+}
+
+/// Writes new ciphertexts to an obstetric record.
+fn write_obstetric_data(
+    record: &mut ObstetricData,
+    encryption_key: [u8; 32],
+    nonce: u128,
+    ciphertexts: [[u8; 32]; OBSTETRIC_FIELDS],
+) -> Result<()> {
+    record.encryption_key = encryption_key;
+    record.nonce = nonce;
+    record.ciphertexts = ciphertexts;
+    record.updated_at = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+#[init_computation_definition_accounts("share_obstetric_data", payer)]
+#[derive(Accounts)]
+pub struct InitShareObstetricDataCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_obstetric_data", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareObstetricData<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_OBSTETRIC_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"patient_policy", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: patient policy, only read if the patient has set one.
+    pub patient_policy: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    #[account(
+        seeds = [b"obstetric_data", patient_data.key().as_ref()],
+        bump = obstetric_data.bump,
+    )]
+    pub obstetric_data: Box<Account<'info, ObstetricData>>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Box<Account<'info, CredentialRecord>>,
+    #[account(
+        seeds = [
+            b"metadata",
+            anchor_spl::metadata::mpl_token_metadata::ID.as_ref(),
+            credential_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::metadata::mpl_token_metadata::ID,
+    )]
+    pub credential_metadata: Option<Box<Account<'info, anchor_spl::metadata::MetadataAccount>>>,
+    #[account(
+        seeds = [b"role_config", ROLE_OBGYN.to_le_bytes().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Box<Account<'info, RoleConfig>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("share_obstetric_data")]
+#[derive(Accounts)]
+pub struct ShareObstetricDataCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_OBSTETRIC_DATA)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(attachment_id: u64)]
+pub struct RegisterAttachment<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Attachment::INIT_SPACE,
+        seeds = [b"attachment", patient_data.key().as_ref(), &attachment_id.to_le_bytes()],
+        bump,
+    )]
+    pub attachment: Box<Account<'info, Attachment>>,
+}
+
+#[init_computation_definition_accounts("share_attachment_key", payer)]
+#[derive(Accounts)]
+pub struct InitShareAttachmentKeyCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_attachment_key", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareAttachmentKey<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_ATTACHMENT_KEY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(
+        seeds = [
+            b"attachment",
+            patient_data.key().as_ref(),
+            &attachment.attachment_id.to_le_bytes(),
+        ],
+        bump = attachment.bump,
+    )]
+    pub attachment: Box<Account<'info, Attachment>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver allowlist, only read if the patient has created one.
+    pub receiver_allowlist: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("share_attachment_key")]
+#[derive(Accounts)]
+pub struct ShareAttachmentKeyCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_ATTACHMENT_KEY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitLabHistory<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + LabHistory::INIT_SPACE,
+        seeds = [b"lab_history", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub lab_history: Box<Account<'info, LabHistory>>,
+}
+
+#[derive(Accounts)]
+pub struct AppendLabLeaf<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"lab_history", patient_data.key().as_ref()],
+        bump = lab_history.bump,
+    )]
+    pub lab_history: Box<Account<'info, LabHistory>>,
+}
+
+/// Hashes a lab-history leaf from its encryption key, nonce and ciphertexts.
+fn lab_leaf_hash(
+    encryption_key: &[u8; 32],
+    nonce: u128,
+    ciphertexts: &[[u8; 32]; LAB_LEAF_FIELDS],
+) -> [u8; 32] {
+    let nonce = nonce.to_le_bytes();
+    let mut parts: Vec<&[u8]> = vec![
+        b"lab_leaf".as_slice(),
+        encryption_key.as_slice(),
+        nonce.as_slice(),
+    ];
+    parts.extend(ciphertexts.iter().map(|c| c.as_slice()));
+    hashv(&parts).to_bytes()
+}
+
+/// Root of the lab-history tree with `leaf` at `index`, given the sibling hashes from the
+/// leaf up.
+fn lab_history_root(leaf: [u8; 32], index: u64, proof: &[[u8; 32]; LAB_HISTORY_DEPTH]) -> [u8; 32] {
+    let mut node = leaf;
+    for (level, sibling) in proof.iter().enumerate() {
+        node = if (index >> level) & 1 == 0 {
+            hashv(&[b"lab_node", &node, sibling]).to_bytes()
+        } else {
+            hashv(&[b"lab_node", sibling, &node]).to_bytes()
+        };
+    }
+    node
+}
+
+/// Root of a lab-history tree whose leaves are all zero.
+fn empty_lab_history_root() -> [u8; 32] {
+    let mut node = [0u8; 32];
+    for _ in 0..LAB_HISTORY_DEPTH {
+        node = hashv(&[b"lab_node", &node, &node]).to_bytes();
+    }
+    node
+}
+
+#[init_computation_definition_accounts("share_lab_leaf", payer)]
+#[derive(Accounts)]
+pub struct InitShareLabLeafCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_lab_leaf", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareLabLeaf<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_LAB_LEAF)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(
+        seeds = [b"lab_history", patient_data.key().as_ref()],
+        bump = lab_history.bump,
+    )]
+    pub lab_history: Box<Account<'info, LabHistory>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver allowlist, only read if the patient has created one.
+    pub receiver_allowlist: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("share_lab_leaf")]
+#[derive(Accounts)]
+pub struct ShareLabLeafCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_LAB_LEAF)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(section: u8, additional_slots: u16)]
+pub struct ExpandCapacity<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+        realloc = expanded_record_len(&patient_data.to_account_info(), section, additional_slots),
+        realloc::payer = payer,
+        realloc::zero = true,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+}
+
+#[derive(Accounts)]
+pub struct WriteCapacityChunk<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+}
+
+/// Number of chunks `additional_slots` slots of `section` take up.
+fn capacity_chunks_for(section: u8, additional_slots: u16) -> Result<u16> {
+    let slots_per_chunk = match section {
+        SECTION_MEDICATIONS => CAPACITY_CHUNK_FIELDS,
+        SECTION_LAB_TESTS => CAPACITY_CHUNK_FIELDS / 4,
+        _ => return Err(ErrorCode::InvalidCapacityExpansion.into()),
+    } as u16;
+    require!(
+        additional_slots > 0 && additional_slots % slots_per_chunk == 0,
+        ErrorCode::InvalidCapacityExpansion
+    );
+    Ok(additional_slots / slots_per_chunk)
+}
+
+/// Medication and lab chunk counts of a record's expanded capacity; zero for a record that
+/// was never expanded.
+fn capacity_chunks(data: &[u8]) -> (u16, u16) {
+    match data.get(RECORD_TAIL_OFFSET..RECORD_TAIL_OFFSET + CAPACITY_HEADER_LEN) {
+        Some(header) => (
+            u16::from_le_bytes([header[0], header[1]]),
+            u16::from_le_bytes([header[2], header[3]]),
+        ),
+        None => (0, 0),
+    }
+}
+
+/// Byte offset of the expanded capacity chunk at `index`, counting medication chunks first.
+fn capacity_chunk_start(index: u16) -> usize {
+    RECORD_TAIL_OFFSET + CAPACITY_HEADER_LEN + index as usize * CAPACITY_CHUNK_LEN
+}
+
+/// Byte offset of chunk `chunk_index` of `section` in a record's expanded capacity.
+fn capacity_chunk_offset(data: &[u8], section: u8, chunk_index: u16) -> Result<usize> {
+    let (medication_chunks, lab_chunks) = capacity_chunks(data);
+    let (first, count) = match section {
+        SECTION_MEDICATIONS => (0, medication_chunks),
+        SECTION_LAB_TESTS => (medication_chunks, lab_chunks),
+        _ => return Err(ErrorCode::InvalidCapacityExpansion.into()),
+    };
+    require!(chunk_index < count, ErrorCode::InvalidCapacityChunk);
+    Ok(capacity_chunk_start(first + chunk_index))
+}
+
+/// Account size of a record after `expand_capacity`. Invalid arguments leave the chunk
+/// counts unchanged; the instruction then rejects them.
+fn expanded_record_len(info: &AccountInfo, section: u8, additional_slots: u16) -> usize {
+    let (medication_chunks, lab_chunks) = info
+        .try_borrow_data()
+        .map(|data| capacity_chunks(&data))
+        .unwrap_or_default();
+    let added = capacity_chunks_for(section, additional_slots).unwrap_or(0);
+    capacity_chunk_start(0)
+        + (medication_chunks as usize + lab_chunks as usize + added as usize) * CAPACITY_CHUNK_LEN
+}
+
+/// Copies a record, including any expanded capacity, into a `patient_data` account created
+/// in the same instruction, growing it to the old record's size at `payer`'s expense.
+fn copy_patient_record<'info>(
+    old: &AccountInfo<'info>,
+    new: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let len = old.data_len();
+    if new.data_len() < len {
+        let rent = Rent::get()?.minimum_balance(len).saturating_sub(new.lamports());
+        if rent > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    anchor_lang::system_program::Transfer {
+                        from: payer.clone(),
+                        to: new.clone(),
+                    },
+                ),
+                rent,
+            )?;
+        }
+        new.resize(len)?;
+    }
+    new.try_borrow_mut_data()?[8..].copy_from_slice(&old.try_borrow_data()?[8..]);
+    Ok(())
+}
+
+#[init_computation_definition_accounts("share_capacity_chunk", payer)]
+#[derive(Accounts)]
+pub struct InitShareCapacityChunkCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_capacity_chunk", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, receiver: [u8; 32])]
+pub struct ShareCapacityChunk<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_CAPACITY_CHUNK)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver allowlist, only read if the patient has created one.
+    pub receiver_allowlist: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", receiver.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("share_capacity_chunk")]
+#[derive(Accounts)]
+pub struct ShareCapacityChunkCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_CAPACITY_CHUNK)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_id: u64)]
+pub struct OpenSession<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TeleSession::INIT_SPACE,
+        seeds = [b"tele_session", patient_data.key().as_ref(), &session_id.to_le_bytes()],
+        bump,
+    )]
+    pub tele_session: Box<Account<'info, TeleSession>>,
+}
+
+#[derive(Accounts)]
+pub struct AppendSessionNote<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [
+            b"tele_session",
+            patient_data.key().as_ref(),
+            &tele_session.session_id.to_le_bytes(),
+        ],
+        bump = tele_session.bump,
+    )]
+    pub tele_session: Box<Account<'info, TeleSession>>,
+    #[account(
+        mut,
+        seeds = [b"record_metadata", patient_data.key().as_ref()],
+        bump = record_metadata.bump,
+    )]
+    pub record_metadata: Box<Account<'info, RecordMetadata>>,
+    #[account(seeds = [b"program_config"], bump = program_config.bump)]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RecordEntry::INIT_SPACE,
+        seeds = [
+            b"record_entry",
+            patient_data.key().as_ref(),
+            &record_metadata.entry_count.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub record_entry: Box<Account<'info, RecordEntry>>,
+
+    // Credential NFT accounts of the provider
+    pub credential_mint: Option<Account<'info, anchor_spl::token::Mint>>,
+    pub credential_token_account: Option<Account<'info, anchor_spl::token::TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSession<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"tele_session",
+            tele_session.patient_data.as_ref(),
+            &tele_session.session_id.to_le_bytes(),
+        ],
+        bump = tele_session.bump,
+    )]
+    pub tele_session: Box<Account<'info, TeleSession>>,
+}
+
+#[derive(Accounts)]
+#[instruction(appointment_id: u64)]
+pub struct BookAppointment<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Appointment::INIT_SPACE,
+        seeds = [b"appointment", patient_data.key().as_ref(), &appointment_id.to_le_bytes()],
+        bump,
+    )]
+    pub appointment: Box<Account<'info, Appointment>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + VisitGrant::INIT_SPACE,
+        seeds = [b"visit_grant", appointment.key().as_ref()],
+        bump,
+    )]
+    pub visit_grant: Box<Account<'info, VisitGrant>>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmAppointment<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [
+            b"appointment",
+            appointment.patient_data.as_ref(),
+            &appointment.appointment_id.to_le_bytes(),
+        ],
+        bump = appointment.bump,
+        constraint = appointment.provider == payer.key() @ ErrorCode::Unauthorized,
+    )]
+    pub appointment: Box<Account<'info, Appointment>>,
+    #[account(
+        mut,
+        seeds = [b"visit_grant", appointment.key().as_ref()],
+        bump = visit_grant.bump,
+    )]
+    pub visit_grant: Box<Account<'info, VisitGrant>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + GrantExpiryIndex::INIT_SPACE,
+        seeds = [b"grant_expiry_index", appointment.patient_data.as_ref()],
+        bump,
+    )]
+    pub grant_expiry_index: Box<Account<'info, GrantExpiryIndex>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAppointment<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"appointment",
+            appointment.patient_data.as_ref(),
+            &appointment.appointment_id.to_le_bytes(),
+        ],
+        bump = appointment.bump,
+    )]
+    pub appointment: Box<Account<'info, Appointment>>,
+    #[account(mut, address = visit_grant.rent_payer)]
+    /// CHECK: wallet that booked the appointment, receiving the grant's rent back.
+    pub rent_payer: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"visit_grant", appointment.key().as_ref()],
+        bump = visit_grant.bump,
+    )]
+    pub visit_grant: Box<Account<'info, VisitGrant>>,
+    #[account(
+        mut,
+        seeds = [b"grant_expiry_index", appointment.patient_data.as_ref()],
+        bump = grant_expiry_index.bump,
+    )]
+    pub grant_expiry_index: Option<Box<Account<'info, GrantExpiryIndex>>>,
+}
+
+#[derive(Accounts)]
+pub struct PayForSession<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, anchor_spl::token::Token>,
+    #[account(
+        seeds = [
+            b"tele_session",
+            tele_session.patient_data.as_ref(),
+            &tele_session.session_id.to_le_bytes(),
+        ],
+        bump = tele_session.bump,
+    )]
+    pub tele_session: Box<Account<'info, TeleSession>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SessionEscrow::INIT_SPACE,
+        seeds = [b"session_escrow", tele_session.key().as_ref()],
+        bump,
+    )]
+    pub session_escrow: Box<Account<'info, SessionEscrow>>,
+    pub mint: Box<Account<'info, anchor_spl::token::Mint>>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = payer,
+    )]
+    pub payer_token_account: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"session_vault", session_escrow.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = session_escrow,
+    )]
+    pub session_vault: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct SettleSession<'info> {
+    pub token_program: Program<'info, anchor_spl::token::Token>,
+    #[account(
+        seeds = [
+            b"tele_session",
+            tele_session.patient_data.as_ref(),
+            &tele_session.session_id.to_le_bytes(),
+        ],
+        bump = tele_session.bump,
+    )]
+    pub tele_session: Box<Account<'info, TeleSession>>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"session_escrow", tele_session.key().as_ref()],
+        bump = session_escrow.bump,
+        has_one = payer @ ErrorCode::Unauthorized,
+    )]
+    pub session_escrow: Box<Account<'info, SessionEscrow>>,
+    #[account(mut)]
+    /// CHECK: wallet that paid for the session, receiving the escrow rent.
+    pub payer: UncheckedAccount<'info>,
+    #[account(mut, address = session_escrow.vault)]
+    pub session_vault: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = session_escrow.mint,
+        token::authority = tele_session.provider,
+    )]
+    pub provider_token_account: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+    #[account(seeds = [b"program_config"], bump = program_config.bump)]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+    #[account(
+        mut,
+        token::mint = session_escrow.mint,
+        token::authority = program_config.fee_destination,
+    )]
+    pub fee_token_account: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct SetRegistrar<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ProviderRegistry::INIT_SPACE,
+        seeds = [b"provider_registry"],
+        bump,
+    )]
+    pub provider_registry: Account<'info, ProviderRegistry>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey, role: u8)]
+pub struct RegisterProvider<'info> {
+    #[account(mut)]
+    pub registrar: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [b"provider_registry"],
+        bump = provider_registry.bump,
+        has_one = registrar @ ErrorCode::Unauthorized,
+    )]
+    pub provider_registry: Account<'info, ProviderRegistry>,
+    #[account(
+        init_if_needed,
+        payer = registrar,
+        space = 8 + ProviderRecord::INIT_SPACE,
+        seeds = [b"provider_record", mint.as_ref()],
+        bump,
+    )]
+    pub provider_record: Account<'info, ProviderRecord>,
+    #[account(
+        seeds = [b"role_config", role.to_le_bytes().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Account<'info, RoleConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct DeactivateProvider<'info> {
+    pub registrar: Signer<'info>,
+    #[account(
+        seeds = [b"provider_registry"],
+        bump = provider_registry.bump,
+        has_one = registrar @ ErrorCode::Unauthorized,
+    )]
+    pub provider_registry: Account<'info, ProviderRegistry>,
+    #[account(
+        mut,
+        seeds = [b"provider_record", mint.as_ref()],
+        bump = provider_record.bump,
+    )]
+    pub provider_record: Account<'info, ProviderRecord>,
+}
+
+#[derive(Accounts)]
+#[instruction(lab: Pubkey)]
+pub struct RegisterLab<'info> {
+    #[account(mut)]
+    pub registrar: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"provider_registry"],
+        bump = provider_registry.bump,
+        has_one = registrar @ ErrorCode::Unauthorized,
+    )]
+    pub provider_registry: Account<'info, ProviderRegistry>,
+    #[account(
+        init_if_needed,
+        payer = registrar,
+        space = 8 + LabSigner::INIT_SPACE,
+        seeds = [b"lab_signer", lab.as_ref()],
+        bump,
+    )]
+    pub lab_signer: Account<'info, LabSigner>,
+}
+
+#[derive(Accounts)]
+#[instruction(lab: Pubkey)]
+pub struct DeactivateLab<'info> {
+    pub registrar: Signer<'info>,
+    #[account(
+        seeds = [b"provider_registry"],
+        bump = provider_registry.bump,
+        has_one = registrar @ ErrorCode::Unauthorized,
+    )]
+    pub provider_registry: Account<'info, ProviderRegistry>,
+    #[account(
+        mut,
+        seeds = [b"lab_signer", lab.as_ref()],
+        bump = lab_signer.bump,
+    )]
+    pub lab_signer: Account<'info, LabSigner>,
+}
+
+#[derive(Accounts)]
+#[instruction(lab: Pubkey)]
+pub struct AttestLabResults<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [b"lab_signer", lab.as_ref()],
+        bump = lab_signer.bump,
+        constraint = lab_signer.active @ ErrorCode::UnregisteredLab,
+    )]
+    pub lab_signer: Account<'info, LabSigner>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + LabAttestation::INIT_SPACE,
+        seeds = [b"lab_attestation", patient_data.key().as_ref(), lab.as_ref()],
+        bump,
+    )]
+    pub lab_attestation: Account<'info, LabAttestation>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(section: u8)]
+pub struct AttestSection<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Attestation::INIT_SPACE,
+        seeds = [b"attestation", patient_data.key().as_ref(), payer.key().as_ref(), &[section]],
+        bump,
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.amount > 0 @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"provider_record", credential_mint.key().as_ref()],
+        bump = provider_record.bump,
+        constraint = provider_record.active @ ErrorCode::ProviderNotRegistered,
+    )]
+    pub provider_record: Account<'info, ProviderRecord>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Account<'info, CredentialRecord>,
+}
+
+#[derive(Accounts)]
+#[instruction(claim_id: u64)]
+pub struct SubmitClaim<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Claim::INIT_SPACE,
+        seeds = [
+            b"claim",
+            patient_data.key().as_ref(),
+            payer.key().as_ref(),
+            &claim_id.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub claim: Box<Account<'info, Claim>>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.amount > 0 @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"provider_record", credential_mint.key().as_ref()],
+        bump = provider_record.bump,
+        constraint = provider_record.active @ ErrorCode::ProviderNotRegistered,
+    )]
+    pub provider_record: Account<'info, ProviderRecord>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Account<'info, CredentialRecord>,
+}
+
+#[init_computation_definition_accounts("verify_claim_consistency", payer)]
+#[derive(Accounts)]
+pub struct InitVerifyClaimConsistencyCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("verify_claim_consistency", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct VerifyClaimConsistency<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_VERIFY_CLAIM_CONSISTENCY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = claim.patient_data)]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [
+            b"claim",
+            claim.patient_data.as_ref(),
+            claim.provider.as_ref(),
+            &claim.claim_id.to_le_bytes(),
+        ],
+        bump = claim.bump,
+        has_one = insurer @ ErrorCode::Unauthorized,
+    )]
+    pub claim: Box<Account<'info, Claim>>,
+    /// CHECK: the claim's insurer, checked by the claim's `has_one` constraint
+    #[account(address = payer.key())]
+    pub insurer: UncheckedAccount<'info>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.amount > 0 @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"provider_record", credential_mint.key().as_ref()],
+        bump = provider_record.bump,
+        constraint = provider_record.active @ ErrorCode::ProviderNotRegistered,
+        constraint = provider_record.role == ROLE_INSURER @ ErrorCode::CredentialRoleMismatch,
+    )]
+    pub provider_record: Account<'info, ProviderRecord>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Account<'info, CredentialRecord>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("verify_claim_consistency")]
+#[derive(Accounts)]
+pub struct VerifyClaimConsistencyCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_VERIFY_CLAIM_CONSISTENCY)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub claim: Account<'info, Claim>,
+}
+
+#[derive(Accounts)]
+pub struct AdjudicateClaim<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"claim",
+            claim.patient_data.as_ref(),
+            claim.provider.as_ref(),
+            &claim.claim_id.to_le_bytes(),
+        ],
+        bump = claim.bump,
+        has_one = insurer @ ErrorCode::Unauthorized,
+    )]
+    pub claim: Box<Account<'info, Claim>>,
+    /// CHECK: the claim's insurer, checked by the claim's `has_one` constraint
+    #[account(address = payer.key())]
+    pub insurer: UncheckedAccount<'info>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.amount > 0 @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"provider_record", credential_mint.key().as_ref()],
+        bump = provider_record.bump,
+        constraint = provider_record.active @ ErrorCode::ProviderNotRegistered,
+        constraint = provider_record.role == ROLE_INSURER @ ErrorCode::CredentialRoleMismatch,
+    )]
+    pub provider_record: Account<'info, ProviderRecord>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Account<'info, CredentialRecord>,
+}
+
+#[derive(Accounts)]
+#[instruction(policy_id: u64)]
+pub struct SetCoveragePolicy<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CoveragePolicy::INIT_SPACE,
+        seeds = [b"coverage_policy", payer.key().as_ref(), &policy_id.to_le_bytes()],
+        bump,
+    )]
+    pub coverage_policy: Account<'info, CoveragePolicy>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.amount > 0 @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"provider_record", credential_mint.key().as_ref()],
+        bump = provider_record.bump,
+        constraint = provider_record.active @ ErrorCode::ProviderNotRegistered,
+        constraint = provider_record.role == ROLE_INSURER @ ErrorCode::CredentialRoleMismatch,
+    )]
+    pub provider_record: Account<'info, ProviderRecord>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Account<'info, CredentialRecord>,
+}
+
+#[init_computation_definition_accounts("check_coverage", payer)]
+#[derive(Accounts)]
+pub struct InitCheckCoverageCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("check_coverage", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CheckCoverage<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_COVERAGE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    pub delegation: Option<Box<Account<'info, DelegationAccount>>>,
+    #[account(
+        seeds = [
+            b"coverage_policy",
+            coverage_policy.insurer.as_ref(),
+            &coverage_policy.policy_id.to_le_bytes(),
+        ],
+        bump = coverage_policy.bump,
+    )]
+    pub coverage_policy: Box<Account<'info, CoveragePolicy>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("check_coverage")]
+#[derive(Accounts)]
+pub struct CheckCoverageCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_COVERAGE)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    /// CHECK: patient record the decision is about, passed by `check_coverage`
+    pub patient_data: UncheckedAccount<'info>,
+    /// CHECK: policy the decision was made under, passed by `check_coverage`
+    pub coverage_policy: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(request_id: u64)]
+pub struct RequestSecondOpinion<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SecondOpinion::INIT_SPACE,
+        seeds = [b"second_opinion", patient_data.key().as_ref(), &request_id.to_le_bytes()],
+        bump,
+    )]
+    pub second_opinion: Box<Account<'info, SecondOpinion>>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitOpinion<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [
+            b"second_opinion",
+            second_opinion.patient_data.as_ref(),
+            &second_opinion.request_id.to_le_bytes(),
+        ],
+        bump = second_opinion.bump,
+    )]
+    pub second_opinion: Box<Account<'info, SecondOpinion>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Opinion::INIT_SPACE,
+        seeds = [b"opinion", second_opinion.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub opinion: Box<Account<'info, Opinion>>,
+}
+
+#[init_computation_definition_accounts("compare_opinions", payer)]
+#[derive(Accounts)]
+pub struct InitCompareOpinionsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("compare_opinions", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CompareOpinions<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPARE_OPINIONS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [
+            b"second_opinion",
+            patient_data.key().as_ref(),
+            &second_opinion.request_id.to_le_bytes(),
+        ],
+        bump = second_opinion.bump,
+    )]
+    pub second_opinion: Box<Account<'info, SecondOpinion>>,
+    #[account(has_one = second_opinion @ ErrorCode::InvalidSecondOpinion)]
+    pub first_opinion: Box<Account<'info, Opinion>>,
+    #[account(has_one = second_opinion @ ErrorCode::InvalidSecondOpinion)]
+    pub other_opinion: Box<Account<'info, Opinion>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("compare_opinions")]
+#[derive(Accounts)]
+pub struct CompareOpinionsCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPARE_OPINIONS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    /// CHECK: second-opinion request the opinions were filed on, passed by `compare_opinions`
+    pub second_opinion: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(team_id: u64)]
+pub struct CreateCareTeam<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CareTeam::INIT_SPACE,
+        seeds = [b"care_team", patient_data.key().as_ref(), &team_id.to_le_bytes()],
+        bump,
+    )]
+    pub care_team: Box<Account<'info, CareTeam>>,
+}
+
+#[init_computation_definition_accounts("share_to_care_team", payer)]
+#[derive(Accounts)]
+pub struct InitShareToCareTeamCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("share_to_care_team", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ShareToCareTeam<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_TO_CARE_TEAM)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"care_team", patient_data.key().as_ref(), &care_team.team_id.to_le_bytes()],
+        bump = care_team.bump,
+    )]
+    pub care_team: Box<Account<'info, CareTeam>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<TeamRecord>(),
+        seeds = [b"team_record", care_team.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub team_record: AccountLoader<'info, TeamRecord>,
+}
+
+#[callback_accounts("share_to_care_team")]
+#[derive(Accounts)]
+pub struct ShareToCareTeamCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_TO_CARE_TEAM)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub team_record: AccountLoader<'info, TeamRecord>,
+}
+
+#[derive(Accounts)]
+pub struct RequestTeamAccess<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [
+            b"care_team",
+            care_team.patient_data.as_ref(),
+            &care_team.team_id.to_le_bytes(),
+        ],
+        bump = care_team.bump,
+    )]
+    pub care_team: Box<Account<'info, CareTeam>>,
+    #[account(has_one = care_team @ ErrorCode::InvalidCareTeam)]
+    pub team_record: AccountLoader<'info, TeamRecord>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TeamAccessRequest::INIT_SPACE,
+        seeds = [b"team_access", team_record.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub team_access: Box<Account<'info, TeamAccessRequest>>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveTeamAccess<'info> {
+    pub approver: Signer<'info>,
+    #[account(
+        seeds = [
+            b"care_team",
+            care_team.patient_data.as_ref(),
+            &care_team.team_id.to_le_bytes(),
+        ],
+        bump = care_team.bump,
+    )]
+    pub care_team: Box<Account<'info, CareTeam>>,
+    #[account(
+        mut,
+        seeds = [
+            b"team_access",
+            team_access.team_record.as_ref(),
+            team_access.member.as_ref(),
+        ],
+        bump = team_access.bump,
+        has_one = care_team @ ErrorCode::InvalidCareTeam,
+    )]
+    pub team_access: Box<Account<'info, TeamAccessRequest>>,
+}
+
+#[init_computation_definition_accounts("release_team_record", payer)]
+#[derive(Accounts)]
+pub struct InitReleaseTeamRecordCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("release_team_record", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, member_key: [u8; 32])]
+pub struct ReleaseTeamRecord<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_RELEASE_TEAM_RECORD)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        seeds = [
+            b"care_team",
+            care_team.patient_data.as_ref(),
+            &care_team.team_id.to_le_bytes(),
+        ],
+        bump = care_team.bump,
+    )]
+    pub care_team: Box<Account<'info, CareTeam>>,
+    #[account(has_one = care_team @ ErrorCode::InvalidCareTeam)]
+    pub team_record: AccountLoader<'info, TeamRecord>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"team_access", team_record.key().as_ref(), payer.key().as_ref()],
+        bump = team_access.bump,
+        has_one = care_team @ ErrorCode::InvalidCareTeam,
+    )]
+    pub team_access: Box<Account<'info, TeamAccessRequest>>,
+    #[account(address = care_team.patient_data @ ErrorCode::InvalidCareTeam)]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(seeds = [b"receiver_jurisdiction", member_key.as_ref()], bump)]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_revocation", patient_data.key().as_ref(), member_key.as_ref()],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver allowlist, only read if the patient has created one.
+    pub receiver_allowlist: UncheckedAccount<'info>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+}
+
+#[callback_accounts("release_team_record")]
+#[derive(Accounts)]
+pub struct ReleaseTeamRecordCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_RELEASE_TEAM_RECORD)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(org_id: u64)]
+pub struct CreateOrganization<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Organization::INIT_SPACE,
+        seeds = [b"organization", authority.key().as_ref(), &org_id.to_le_bytes()],
+        bump,
+    )]
+    pub organization: Box<Account<'info, Organization>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOrganization<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"organization",
+            organization.authority.as_ref(),
+            &organization.org_id.to_le_bytes(),
+        ],
+        bump = organization.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub organization: Box<Account<'info, Organization>>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct IssueCredential<'info> {
+    #[account(mut)]
+    pub registrar: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"provider_registry"],
+        bump = provider_registry.bump,
+        has_one = registrar @ ErrorCode::Unauthorized,
+    )]
+    pub provider_registry: Account<'info, ProviderRegistry>,
+    #[account(
+        init_if_needed,
+        payer = registrar,
+        space = 8 + CredentialRecord::INIT_SPACE,
+        seeds = [b"credential_record", mint.as_ref()],
+        bump,
+    )]
+    pub credential_record: Account<'info, CredentialRecord>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct RevokeCredential<'info> {
+    pub registrar: Signer<'info>,
+    #[account(
+        seeds = [b"provider_registry"],
+        bump = provider_registry.bump,
+        has_one = registrar @ ErrorCode::Unauthorized,
+    )]
+    pub provider_registry: Account<'info, ProviderRegistry>,
+    #[account(
+        mut,
+        seeds = [b"credential_record", mint.as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Account<'info, CredentialRecord>,
+}
+
+#[derive(Accounts)]
+pub struct AddReceiver<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ReceiverAllowlist::INIT_SPACE,
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub receiver_allowlist: Account<'info, ReceiverAllowlist>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveReceiver<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        seeds = [b"receiver_allowlist", patient_data.key().as_ref()],
+        bump = receiver_allowlist.bump,
+    )]
+    pub receiver_allowlist: Account<'info, ReceiverAllowlist>,
+}
+
+#[derive(Accounts)]
+#[instruction(receiver: [u8; 32])]
+pub struct GrantFieldConsent<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + FieldConsent::INIT_SPACE,
+        seeds = [b"field_consent", patient_data.key().as_ref(), receiver.as_ref()],
+        bump,
+    )]
+    pub field_consent: Account<'info, FieldConsent>,
+}
+
+#[derive(Accounts)]
+#[instruction(receiver: [u8; 32])]
+pub struct RevokeFieldConsent<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"field_consent", patient_data.key().as_ref(), receiver.as_ref()],
+        bump = field_consent.bump,
+    )]
+    pub field_consent: Account<'info, FieldConsent>,
+}
+
+#[derive(Accounts)]
+pub struct SetJurisdiction<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+}
+
+#[derive(Accounts)]
+pub struct SetJurisdictionRule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + JurisdictionRules::INIT_SPACE,
+        seeds = [b"jurisdiction_rules"],
+        bump,
+    )]
+    pub jurisdiction_rules: Account<'info, JurisdictionRules>,
+}
+
+#[derive(Accounts)]
+#[instruction(receiver: [u8; 32])]
+pub struct RegisterReceiverJurisdiction<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"program_config"],
+        bump = program_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ReceiverJurisdiction::INIT_SPACE,
+        seeds = [b"receiver_jurisdiction", receiver.as_ref()],
+        bump,
+    )]
+    pub receiver_jurisdiction: Account<'info, ReceiverJurisdiction>,
+}
+
+/// Checks the record's data-residency rule, if it is tagged and its jurisdiction has one:
+/// the receiver must be registered in an allowed jurisdiction.
+fn check_jurisdiction(
+    patient_data: &AccountLoader<PatientData>,
+    rules: &UncheckedAccount,
+    receiver_jurisdiction: &UncheckedAccount,
+) -> Result<()> {
+    let jurisdiction = patient_data.load()?.jurisdiction;
+    if jurisdiction == JURISDICTION_UNTAGGED || rules.data_is_empty() {
+        return Ok(());
+    }
+    let rules = JurisdictionRules::try_deserialize(&mut &rules.try_borrow_data()?[..])?;
+    let Some(rule) = rules.rules.iter().find(|r| r.jurisdiction == jurisdiction) else {
+        return Ok(());
+    };
+    require!(
+        !receiver_jurisdiction.data_is_empty(),
+        ErrorCode::JurisdictionNotAllowed
+    );
+    let registration =
+        ReceiverJurisdiction::try_deserialize(&mut &receiver_jurisdiction.try_borrow_data()?[..])?;
+    require!(
+        rule.allowed.contains(&registration.jurisdiction),
+        ErrorCode::JurisdictionNotAllowed
+    );
+    Ok(())
+}
+
+/// Checks that `receiver` is on the patient's `ReceiverAllowlist`, if the patient has one.
+fn check_receiver_allowlist(allowlist: &UncheckedAccount, receiver: &[u8; 32]) -> Result<()> {
+    if allowlist.data_is_empty() {
+        return Ok(());
+    }
+    let allowlist = ReceiverAllowlist::try_deserialize(&mut &allowlist.try_borrow_data()?[..])?;
+    require!(
+        allowlist.receivers.contains(receiver),
+        ErrorCode::ReceiverNotAllowlisted
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(patient: Pubkey)]
+pub struct RequestAccess<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", patient.as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ShareRequest::INIT_SPACE,
+        seeds = [b"share_request", patient_data.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub share_request: Box<Account<'info, ShareRequest>>,
+}
+
+#[queue_computation_accounts("share_patient_data_scoped", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ApproveRequest<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_PATIENT_DATA_SCOPED)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(mut)]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = requester,
+        seeds = [b"share_request", patient_data.key().as_ref(), share_request.requester.as_ref()],
+        bump = share_request.bump,
+    )]
+    pub share_request: Box<Account<'info, ShareRequest>>,
+    #[account(mut, address = share_request.requester)]
+    /// CHECK: requester receiving the request's rent, checked against the request.
+    pub requester: UncheckedAccount<'info>,
+    #[account(
+        seeds = [
+            b"receiver_revocation",
+            patient_data.key().as_ref(),
+            share_request.receiver.as_ref(),
+        ],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_jurisdiction", share_request.receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [b"share_nonce", patient_data.key().as_ref(), share_request.receiver.as_ref()],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<SharedRecord>(),
+        seeds = [
+            b"shared_record",
+            patient_data.key().as_ref(),
+            share_request.receiver.as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + InboxEntry::INIT_SPACE,
+        seeds = [
+            b"inbox_entry",
+            share_request.receiver.as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub inbox_entry: Box<Account<'info, InboxEntry>>,
+    #[account(
+        mut,
+        seeds = [
+            b"access_escrow",
+            patient_data.key().as_ref(),
+            share_request.requester.as_ref(),
+        ],
+        bump = access_escrow.bump,
+    )]
+    pub access_escrow: Option<Box<Account<'info, AccessEscrow>>>,
+    pub patient_token_account: Option<Box<Account<'info, anchor_spl::token::TokenAccount>>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DenyRequest<'info> {
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        mut,
+        close = requester,
+        seeds = [b"share_request", patient_data.key().as_ref(), share_request.requester.as_ref()],
+        bump = share_request.bump,
+    )]
+    pub share_request: Box<Account<'info, ShareRequest>>,
+    #[account(mut, address = share_request.requester)]
+    /// CHECK: requester receiving the request's rent, checked against the request.
+    pub requester: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AckShare<'info> {
+    pub receiver_authority: Signer<'info>,
+    #[account(mut)]
+    /// CHECK: original rent payer, checked against the inbox entry.
+    pub rent_payer: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = receiver_authority @ ErrorCode::Unauthorized,
+        has_one = rent_payer @ ErrorCode::Unauthorized,
+    )]
+    pub inbox_entry: Account<'info, InboxEntry>,
+}
+
+#[derive(Accounts)]
+#[instruction(patient: Pubkey)]
+pub struct RequestPaidAccess<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, anchor_spl::token::Token>,
+    #[account(
+        seeds = [b"patient_data", patient.as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ShareRequest::INIT_SPACE,
+        seeds = [b"share_request", patient_data.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub share_request: Box<Account<'info, ShareRequest>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessEscrow::INIT_SPACE,
+        seeds = [b"access_escrow", patient_data.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub access_escrow: Box<Account<'info, AccessEscrow>>,
+    pub mint: Box<Account<'info, anchor_spl::token::Mint>>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = payer,
+    )]
+    pub requester_token_account: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"escrow_vault", access_escrow.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = access_escrow,
+    )]
+    pub escrow_vault: Box<Account<'info, anchor_spl::token::TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRequest<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+    pub token_program: Program<'info, anchor_spl::token::Token>,
+    #[account(
+        mut,
+        close = requester,
+        seeds = [
+            b"access_escrow",
+            access_escrow.patient_data.as_ref(),
+            access_escrow.requester.as_ref(),
+        ],
+        bump = access_escrow.bump,
+        has_one = requester @ ErrorCode::Unauthorized,
+    )]
+    pub access_escrow: Account<'info, AccessEscrow>,
+    #[account(mut, address = access_escrow.vault)]
+    pub escrow_vault: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        mut,
+        token::mint = access_escrow.mint,
+        token::authority = requester,
+    )]
+    pub requester_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        mut,
+        seeds = [
+            b"share_request",
+            access_escrow.patient_data.as_ref(),
+            access_escrow.requester.as_ref(),
+        ],
+        bump,
+    )]
+    /// CHECK: pending share request, closed if it still exists.
+    pub share_request: UncheckedAccount<'info>,
+}
+
+/// Moves `amount` out of an escrow vault, signed by the `AccessEscrow` PDA.
+fn escrow_transfer<'info>(
+    escrow: &Account<'info, AccessEscrow>,
+    vault: &Account<'info, anchor_spl::token::TokenAccount>,
+    to: &Account<'info, anchor_spl::token::TokenAccount>,
+    token_program: &Program<'info, anchor_spl::token::Token>,
+    amount: u64,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[
+        b"access_escrow",
+        escrow.patient_data.as_ref(),
+        escrow.requester.as_ref(),
+        &[escrow.bump],
+    ];
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: vault.to_account_info(),
+                to: to.to_account_info(),
+                authority: escrow.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+    )
+}
+
+/// Builds a pending `ShareRequest` after validating its purpose and sections.
+fn new_share_request(
+    patient_data: Pubkey,
+    requester: Pubkey,
+    receiver: [u8; 32],
+    purpose: u8,
+    sections_mask: u8,
+    bump: u8,
+) -> Result<ShareRequest> {
+    require!(purpose <= PURPOSE_PATIENT_REQUEST, ErrorCode::InvalidPurpose);
+    require!(sections_mask != 0, ErrorCode::InvalidSections);
+    Ok(ShareRequest {
+        patient_data,
+        requester,
+        receiver,
+        purpose,
+        sections_mask,
+        requested_at: Clock::get()?.unix_timestamp,
+        bump,
+    })
+}
+
+#[derive(Accounts)]
+pub struct CreateAccessPlan<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", payer.key().as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AccessPlan::INIT_SPACE,
+        seeds = [b"access_plan", patient_data.key().as_ref()],
+        bump,
+    )]
+    pub access_plan: Account<'info, AccessPlan>,
+}
+
+#[derive(Accounts)]
+#[instruction(patient: Pubkey)]
+pub struct Subscribe<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"patient_data", patient.as_ref()],
+        bump,
+    )]
+    pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(
+        seeds = [b"access_plan", patient_data.key().as_ref()],
+        bump = access_plan.bump,
+    )]
+    pub access_plan: Account<'info, AccessPlan>,
+    #[account(mut, address = access_plan.patient)]
+    /// CHECK: patient receiving the plan price, checked against the plan.
+    pub patient: UncheckedAccount<'info>,
+    #[account(seeds = [b"program_config"], bump = program_config.bump)]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+    #[account(mut, address = program_config.fee_destination)]
+    /// CHECK: protocol fee destination, checked against the program config.
+    pub fee_destination: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ActiveSubscription::INIT_SPACE,
+        seeds = [b"active_subscription", access_plan.key().as_ref(), payer.key().as_ref()],
+        bump,
+    )]
+    pub active_subscription: Account<'info, ActiveSubscription>,
+
+    // Credential NFT accounts
+    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(
+        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
+        constraint = credential_token_account.amount > 0 @ ErrorCode::Unauthorized,
+    )]
+    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    #[account(
+        seeds = [b"provider_record", credential_mint.key().as_ref()],
+        bump = provider_record.bump,
+        constraint = provider_record.active @ ErrorCode::ProviderNotRegistered,
+    )]
+    pub provider_record: Account<'info, ProviderRecord>,
+    #[account(
+        seeds = [b"credential_record", credential_mint.key().as_ref()],
+        bump = credential_record.bump,
+    )]
+    pub credential_record: Account<'info, CredentialRecord>,
+}
+
+#[derive(Accounts)]
+pub struct RenewSubscription<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"access_plan", access_plan.patient_data.as_ref()],
+        bump = access_plan.bump,
+    )]
+    pub access_plan: Account<'info, AccessPlan>,
+    #[account(mut, address = access_plan.patient)]
+    /// CHECK: patient receiving the plan price, checked against the plan.
+    pub patient: UncheckedAccount<'info>,
+    #[account(seeds = [b"program_config"], bump = program_config.bump)]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+    #[account(mut, address = program_config.fee_destination)]
+    /// CHECK: protocol fee destination, checked against the program config.
+    pub fee_destination: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"active_subscription", access_plan.key().as_ref(), payer.key().as_ref()],
+        bump = active_subscription.bump,
+        has_one = access_plan @ ErrorCode::Unauthorized,
+    )]
+    pub active_subscription: Account<'info, ActiveSubscription>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub access_plan: Account<'info, AccessPlan>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"active_subscription", access_plan.key().as_ref(), payer.key().as_ref()],
+        bump = active_subscription.bump,
+        has_one = access_plan @ ErrorCode::Unauthorized,
+    )]
+    pub active_subscription: Account<'info, ActiveSubscription>,
+}
+
+#[queue_computation_accounts("share_patient_data_scoped", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ShareWithSubscription<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
         seeds = [&SIGN_PDA_SEED],
         bump,
         address = derive_sign_pda!(),
@@ -257,14 +22288,14 @@ pub struct SharePatientData<'info> {
     /// CHECK: computation_account, checked by the arcium program.
     pub computation_account: UncheckedAccount<'info>,
     #[account(
-        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_PATIENT_DATA)
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_PATIENT_DATA_SCOPED)
     )]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(
-        mut,
-        address = derive_cluster_pda!(mxe_account)
-    )]
+    #[account(mut)]
     pub cluster_account: Account<'info, Cluster>,
+    #[account(seeds = [b"approved_clusters"], bump)]
+    /// CHECK: approved cluster list, only read when a cluster offset is given.
+    pub approved_clusters: UncheckedAccount<'info>,
     #[account(
         mut,
         address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
@@ -276,212 +22307,3119 @@ pub struct SharePatientData<'info> {
     pub clock_account: Account<'info, ClockAccount>,
     pub system_program: Program<'info, System>,
     pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        seeds = [b"access_plan", access_plan.patient_data.as_ref()],
+        bump = access_plan.bump,
+    )]
+    pub access_plan: Box<Account<'info, AccessPlan>>,
+    #[account(address = access_plan.patient_data)]
     pub patient_data: AccountLoader<'info, PatientData>,
+    #[account(seeds = [b"section_locks", patient_data.key().as_ref()], bump)]
+    /// CHECK: section locks, only read if the patient has locked a section.
+    pub section_locks: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"active_subscription", access_plan.key().as_ref(), payer.key().as_ref()],
+        bump = active_subscription.bump,
+        has_one = access_plan @ ErrorCode::Unauthorized,
+    )]
+    pub active_subscription: Box<Account<'info, ActiveSubscription>>,
+    #[account(
+        seeds = [
+            b"receiver_revocation",
+            patient_data.key().as_ref(),
+            active_subscription.receiver.as_ref(),
+        ],
+        bump,
+    )]
+    /// CHECK: revocation marker, only checked for existence.
+    pub receiver_revocation: UncheckedAccount<'info>,
+    #[account(seeds = [b"jurisdiction_rules"], bump)]
+    /// CHECK: data-residency rules, only read if the config authority has set any.
+    pub jurisdiction_rules: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"receiver_jurisdiction", active_subscription.receiver.as_ref()],
+        bump,
+    )]
+    /// CHECK: receiver jurisdiction registration, only read if the record's jurisdiction has
+    /// a rule.
+    pub receiver_jurisdiction: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"audit_index",
+            patient_data.key().as_ref(),
+            &audit_index.epoch.to_le_bytes(),
+            &[audit_index.shard],
+        ],
+        bump = audit_index.bump,
+    )]
+    pub audit_index: Box<Account<'info, AuditIndexShard>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessLogEntry::INIT_SPACE,
+        seeds = [b"access_log", patient_data.key().as_ref(), &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub access_log_entry: Box<Account<'info, AccessLogEntry>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ShareNonce::INIT_SPACE,
+        seeds = [
+            b"share_nonce",
+            patient_data.key().as_ref(),
+            active_subscription.receiver.as_ref(),
+        ],
+        bump,
+    )]
+    pub share_nonce: Box<Account<'info, ShareNonce>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + core::mem::size_of::<SharedRecord>(),
+        seeds = [
+            b"shared_record",
+            patient_data.key().as_ref(),
+            active_subscription.receiver.as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub shared_record: AccountLoader<'info, SharedRecord>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + InboxEntry::INIT_SPACE,
+        seeds = [
+            b"inbox_entry",
+            active_subscription.receiver.as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub inbox_entry: Box<Account<'info, InboxEntry>>,
+    #[account(seeds = [b"program_config"], bump)]
+    /// CHECK: program config, only read if it has been initialized.
+    pub program_config: UncheckedAccount<'info>,
+}
+
+/// Pays one period of an access plan from the subscriber to the patient, less the
+/// protocol fee, which goes to the configured fee destination.
+fn pay_access_plan<'info>(
+    plan: &AccessPlan,
+    config: &ProgramConfig,
+    subscriber: &Signer<'info>,
+    patient: &UncheckedAccount<'info>,
+    fee_destination: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let fee = config.protocol_fee(plan.price);
+    for (to, amount) in [
+        (patient.to_account_info(), plan.price - fee),
+        (fee_destination.to_account_info(), fee),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: subscriber.to_account_info(),
+                    to,
+                },
+            ),
+            amount,
+        )?;
+    }
+    Ok(())
+}
+
+/// Basic patient demographics data event
+#[event]
+pub struct ReceivedBasicPatientDataEvent {
+    pub nonce: [u8; 16],
+    pub patient_id: [u8; 32],
+    pub age: [u8; 32],
+    pub gender: [u8; 32],
+    pub blood_type: [u8; 32],
+    pub weight: [u8; 32],
+    pub height: [u8; 32],
+    pub allergies: [[u8; 32]; 5],
+}
+
+/// Advanced healthcare data event (medical history, medications, procedures, family history)
+#[event]
+pub struct ReceivedHealthcareDataEvent {
+    pub nonce: [u8; 16],
+    pub medical_history: [[u8; 32]; 10],
+    pub medication_count: [u8; 32],
+    pub medications: [[u8; 32]; 8],
+    pub procedure_count: [u8; 32],
+    pub procedure_dates: [[u8; 32]; 8],
+    pub family_history: [[u8; 32]; 5],
+}
+
+/// Genomic analysis data event (genetic variants, markers, carrier status, ancestry)
+#[event]
+pub struct ReceivedGenomicDataEvent {
+    pub nonce: [u8; 16],
+    pub variant_count: [u8; 32],
+    pub genetic_markers: [[u8; 32]; 15],
+    pub variant_significance: [[u8; 32]; 15],
+    pub carrier_status: [[u8; 32]; 5],
+    pub pharmacogenomic_markers: [[u8; 32]; 3],
+    pub ancestry_components: [[u8; 32]; 7],
+}
+
+/// Lab test results and imaging data event
+#[event]
+pub struct ReceivedLabTestDataEvent {
+    pub nonce: [u8; 16],
+    pub lab_test_count: [u8; 32],
+    pub lab_test_types: [[u8; 32]; 10],
+    pub lab_test_dates: [[u8; 32]; 10],
+    pub lab_test_values: [[u8; 32]; 10],
+    pub lab_test_flags: [[u8; 32]; 10],
+    pub imaging_count: [u8; 32],
+    pub imaging_types: [[u8; 32]; 10],
+    pub imaging_dates: [[u8; 32]; 10],
+}
+
+/// Immunization records event
+#[event]
+pub struct ReceivedImmunizationDataEvent {
+    pub nonce: [u8; 16],
+    pub immunization_count: [u8; 32],
+    pub vaccine_codes: [[u8; 32]; 8],
+    pub vaccination_dates: [[u8; 32]; 8],
+    pub vaccine_doses: [[u8; 32]; 8],
+}
+
+/// Terminology codes event
+#[event]
+pub struct ReceivedTerminologyCodesEvent {
+    pub nonce: [u8; 16],
+    pub icd10_codes: [[u8; 32]; 10],
+    pub rxnorm_codes: [[u8; 32]; 8],
+    pub loinc_codes: [[u8; 32]; 10],
+}
+
+/// Emitted when a provider opens a break-glass emergency access request
+#[event]
+pub struct BreakGlassOpenedEvent {
+    pub patient_data: Pubkey,
+    pub requester: Pubkey,
+    pub receiver: [u8; 32],
+    pub opened_at: i64,
+}
+
+/// Emitted when an emergency share is queued under a break-glass request
+#[event]
+pub struct BreakGlassExecutedEvent {
+    pub patient_data: Pubkey,
+    pub requester: Pubkey,
+    pub receiver: [u8; 32],
+    pub approvals: u8,
+    pub via_time_delay: bool,
+    pub executed_at: i64,
+}
+
+/// Emergency view of `execute_break_glass`, encrypted for the request's receiver
+#[event]
+pub struct EmergencyDataEvent {
+    pub nonce: [u8; 16],
+    pub age: [u8; 32],
+    pub gender: [u8; 32],
+    pub blood_type: [u8; 32],
+    pub weight: [u8; 32],
+    pub height: [u8; 32],
+    pub allergies: [[u8; 32]; 5],
+    pub medical_history: [[u8; 32]; 10],
+    pub medication_count: [u8; 32],
+    pub medications: [[u8; 32]; 8],
+}
+
+/// Minimal emergency view of `execute_emergency_summary`, encrypted for the responder
+#[event]
+pub struct EmergencySummaryEvent {
+    pub nonce: [u8; 16],
+    pub blood_type: [u8; 32],
+    pub allergies: [[u8; 32]; 5],
+    pub critical_conditions: [[u8; 32]; 10],
+    pub medication_count: [u8; 32],
+    pub medications: [[u8; 32]; 8],
+}
+
+/// Emitted when a patient stores or replaces their advance directive
+#[event]
+pub struct AdvanceDirectiveUpdatedEvent {
+    pub patient_data: Pubkey,
+    pub witness: Pubkey,
+    pub document_hash: [u8; 32],
+}
+
+/// Advance directive flags shared under break-glass, encrypted for the responder
+#[event]
+pub struct AdvanceDirectiveSharedEvent {
+    pub patient_data: Pubkey,
+    pub nonce: [u8; 16],
+    pub directive_flags: [u8; 32],
+    pub document_hash: [u8; 32],
+}
+
+/// Donor eligibility of `check_donor_match`, encrypted for the transplant coordinator
+#[event]
+pub struct DonorMatchCheckedEvent {
+    pub patient_data: Pubkey,
+    pub coordinator: Pubkey,
+    pub nonce: [u8; 16],
+    pub eligible: [u8; 32],
+}
+
+/// Aggregated view over a patient's audit index shards
+#[event]
+pub struct AuditIndexAggregateEvent {
+    pub patient_data: Pubkey,
+    pub shards: u32,
+    pub total_shares: u64,
+    pub first_epoch: u64,
+    pub last_epoch: u64,
+    pub last_share_at: i64,
+}
+
+/// Emitted whenever the share lookup table is created or extended
+#[event]
+pub struct ShareLookupTableEvent {
+    pub lookup_table: Pubkey,
+    pub authority: Pubkey,
+    pub address_count: u16,
+}
+
+/// Emitted for every share with the receiver nonce derived on-chain for it
+#[event]
+pub struct ShareNonceEvent {
+    pub patient_data: Pubkey,
+    pub receiver: [u8; 32],
+    pub counter: u64,
+    pub receiver_nonce: u128,
+}
+
+/// Emitted when a batch share has queued one computation per receiver
+#[event]
+pub struct ShareBatchQueuedEvent {
+    pub patient_data: Pubkey,
+    pub batch_id: u64,
+    pub receivers: Vec<[u8; 32]>,
+    pub computation_offsets: Vec<u64>,
+}
+
+/// Emitted by the expiry crank for a grant that is about to lapse
+#[event]
+pub struct GrantExpiryReminderEvent {
+    pub patient_data: Pubkey,
+    pub grant: Pubkey,
+    pub grant_kind: u8,
+    pub expires_at: i64,
+}
+
+/// Emitted when a patient record is first stored
+#[event]
+pub struct PatientDataStoredEvent {
+    /// `EVENT_SCHEMA_VERSION` the event was emitted with
+    pub schema_version: u8,
+    pub patient_data: Pubkey,
+    pub author: EntryAuthor,
+}
+
+/// Emitted when the ciphertexts of a stored patient record change: immunization updates,
+/// rotations, re-keys and expanded capacity writes
+#[event]
+pub struct PatientDataUpdatedEvent {
+    pub schema_version: u8,
+    pub patient_data: Pubkey,
+    pub updated_at: i64,
+}
+
+/// Emitted when a whole-record share is queued for the MPC cluster
+#[event]
+pub struct ShareQueuedEvent {
+    pub schema_version: u8,
+    pub patient_data: Pubkey,
+    pub computation_offset: u64,
+    pub receiver: [u8; 32],
+    pub purpose: u8,
+    /// Signer of the share: the patient, a delegate or an organization member
+    pub accessor: Pubkey,
+}
+
+/// Emitted by the share callback once the receiver's ciphertexts are in the `SharedRecord`
+#[event]
+pub struct ShareCompletedEvent {
+    pub schema_version: u8,
+    pub shared_record: Pubkey,
+    pub patient_data: Pubkey,
+    pub receiver: [u8; 32],
+    pub computation_offset: u64,
+}
+
+/// Emitted by the share callback when the computation aborted
+#[event]
+pub struct ShareFailedEvent {
+    pub schema_version: u8,
+    pub shared_record: Pubkey,
+    pub patient_data: Pubkey,
+    pub receiver: [u8; 32],
+    pub computation_offset: u64,
+    /// Program error code of the failure, e.g. `AbortedComputation`
+    pub reason: u32,
+}
+
+/// Emitted when a pediatric record is handed over to the now-adult patient
+#[event]
+pub struct CustodyTransferredEvent {
+    pub old_patient_data: Pubkey,
+    pub patient_data: Pubkey,
+    pub guardian: Pubkey,
+    pub patient: Pubkey,
+}
+
+/// Emitted once when a patient revokes all access to their record
+#[event]
+pub struct AllAccessRevokedEvent {
+    pub patient_data: Pubkey,
+    pub delegations_revoked: u8,
+    pub revoked_at: i64,
+}
+
+/// Emitted when a patient erases their record
+#[event]
+pub struct PatientDataErasedEvent {
+    pub patient_data: Pubkey,
+    pub delegations_closed: u16,
+    pub shared_records_closed: u16,
+    pub erased_at: i64,
+}
+
+/// Emitted when an entry is appended to a patient record
+#[event]
+pub struct RecordEntryAppendedEvent {
+    pub patient_data: Pubkey,
+    pub entry: Pubkey,
+    pub entry_id: u64,
+    pub kind: u8,
+    pub author: EntryAuthor,
+}
+
+/// Emitted when an entry is corrected by a new, superseding entry
+#[event]
+pub struct RecordEntryAmendedEvent {
+    pub patient_data: Pubkey,
+    pub original_entry_id: u64,
+    pub amended_entry_id: u64,
+    pub kind: u8,
+    pub author: EntryAuthor,
+}
+
+/// Emitted when a provider writes a visit summary to a patient record
+#[event]
+pub struct VisitSummaryAppendedEvent {
+    pub patient_data: Pubkey,
+    pub encounter: Pubkey,
+    pub encounter_id: u64,
+    pub provider: Pubkey,
+}
+
+/// Emitted when a full-record share includes encounter accounts
+#[event]
+pub struct EncountersIncludedEvent {
+    pub patient_data: Pubkey,
+    pub computation_offset: u64,
+    pub receiver: [u8; 32],
+    pub encounters: Vec<Pubkey>,
+}
+
+/// Emitted when a patient locks or unlocks a record section
+#[event]
+pub struct SectionLocksUpdatedEvent {
+    pub patient_data: Pubkey,
+    pub locked: u8,
+}
+
+/// Emitted when a co-signed section update is proposed
+#[event]
+pub struct UpdateProposedEvent {
+    pub patient_data: Pubkey,
+    pub section: u8,
+    pub ciphertexts_hash: [u8; 32],
+    pub proposer: EntryAuthor,
+}
+
+/// Emitted when the second signer approves an update and it is written to the record
+#[event]
+pub struct UpdateAppliedEvent {
+    pub patient_data: Pubkey,
+    pub section: u8,
+    pub proposer: EntryAuthor,
+    pub approver: EntryAuthor,
+}
+
+/// Emitted when a pending update is withdrawn
+#[event]
+pub struct UpdateCancelledEvent {
+    pub patient_data: Pubkey,
+    pub section: u8,
+    pub cancelled_by: Pubkey,
+}
+
+/// Emitted for every disclosure, mirroring the `AccessLogEntry` written on-chain
+#[event]
+pub struct AuditLogEvent {
+    pub patient_data: Pubkey,
+    pub receiver: [u8; 32],
+    pub accessor: Pubkey,
+    pub role: u8,
+    pub purpose: u8,
+    pub computation_offset: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the patient flags or re-flags an entry of their record
+#[event]
+pub struct RecordEntryFlaggedEvent {
+    pub patient_data: Pubkey,
+    pub entry_id: u64,
+    pub disputed: bool,
+}
+
+/// Encrypted cardiovascular risk band computed by `compute_cardio_risk`
+#[event]
+pub struct CardioRiskComputedEvent {
+    pub nonce: [u8; 16],
+    pub risk_band: [u8; 32],
+}
+
+/// Encrypted interact / no-interact flag computed by `check_drug_interaction`
+#[event]
+pub struct DrugInteractionCheckedEvent {
+    pub nonce: [u8; 16],
+    pub interacts: [u8; 32],
+}
+
+/// Emitted when a patient's honeytoken canary is reported as seen
+#[event]
+pub struct CanaryHitEvent {
+    pub patient_data: Pubkey,
+    pub reporter: Pubkey,
+    pub hit_count: u64,
+    pub evidence: [u8; 32],
+}
+
+/// Encrypted eligibility flag computed by `check_trial_eligibility`, for the trial sponsor
+#[event]
+pub struct TrialEligibilityCheckedEvent {
+    pub nonce: [u8; 16],
+    pub eligible: [u8; 32],
+}
+
+/// Encrypted unsafe flag computed by `check_allergy_conflict` for a proposed prescription
+#[event]
+pub struct PrescriptionSafetyEvent {
+    pub nonce: [u8; 16],
+    pub unsafe_flag: [u8; 32],
+}
+
+/// Emitted when a hardship wallet claims a subsidy
+#[event]
+pub struct SubsidyClaimedEvent {
+    pub wallet: Pubkey,
+    pub amount: u64,
+    pub claimed: u64,
+}
+
+/// Encrypted compatibility flag computed by `check_blood_compatibility`
+#[event]
+pub struct BloodCompatibilityCheckedEvent {
+    pub nonce: [u8; 16],
+    pub compatible: [u8; 32],
+}
+
+/// Emitted when a record has been re-encrypted under a fresh key and/or nonce
+#[event]
+pub struct RecordRotatedEvent {
+    pub patient_data: Pubkey,
+    pub epoch: u64,
+    pub encryption_key: [u8; 32],
+    pub encryption_nonce: u128,
+}
+
+/// Encrypted adjusted dose computed by `recommend_dose`
+#[event]
+pub struct DoseRecommendedEvent {
+    pub nonce: [u8; 16],
+    pub dose: [u8; 32],
+}
+
+/// Encrypted `attribute >= threshold` flag computed by `prove_attribute_threshold`
+#[event]
+pub struct AttributeThresholdProvedEvent {
+    pub nonce: [u8; 16],
+    pub at_least: [u8; 32],
+}
+
+/// Emitted when the immunization section of a record is replaced
+#[event]
+pub struct ImmunizationsUpdatedEvent {
+    pub patient_data: Pubkey,
+    pub author: EntryAuthor,
+}
+
+/// Encrypted vaccinated flag computed by `prove_vaccination`
+#[event]
+pub struct VaccinationProvedEvent {
+    pub nonce: [u8; 16],
+    pub vaccinated: [u8; 32],
+}
+
+/// Emitted when a receiver publishes or updates its profile
+#[event]
+pub struct ReceiverProfilePublishedEvent {
+    pub receiver_profile: Pubkey,
+    pub authority: Pubkey,
+    pub arcis_key: [u8; 32],
+}
+
+/// Emitted when a cohort aggregation is queued, listing the records it reads
+#[event]
+pub struct CohortQueuedEvent {
+    pub researcher: Pubkey,
+    pub computation_offset: u64,
+    pub members: Vec<Pubkey>,
+}
+
+/// Encrypted cohort statistics computed by `aggregate_cohort`
+#[event]
+pub struct CohortAggregatedEvent {
+    pub nonce: [u8; 16],
+    pub history_counts: [[u8; 32]; 10],
+    pub mean_age: [u8; 32],
+    /// Encrypted flag set when too few members matched; the statistics are then zero
+    pub insufficient_cohort: [u8; 32],
+}
+
+/// Encrypted noisy cohort counts computed by `aggregate_cohort_dp`
+#[event]
+pub struct NoisyCohortAggregatedEvent {
+    pub nonce: [u8; 16],
+    pub history_counts: [[u8; 32]; 10],
+}
+
+/// Emitted when an organization lists a cohort query for sale
+#[event]
+pub struct DatasetListedEvent {
+    pub listing: Pubkey,
+    pub organization: Pubkey,
+    pub criteria_hash: [u8; 32],
+    pub price: u64,
+    pub max_queries: u32,
+}
+
+/// Emitted when a researcher buys a query of a dataset listing
+#[event]
+pub struct QueryPurchasedEvent {
+    pub listing: Pubkey,
+    pub researcher: Pubkey,
+    pub computation_offset: u64,
+    pub price: u64,
+}
+
+/// Emitted when a royalty pool is split between the members of its cohort
+#[event]
+pub struct RoyaltiesDistributedEvent {
+    pub researcher: Pubkey,
+    pub computation_offset: u64,
+    pub members: Vec<Pubkey>,
+    pub share: u64,
+}
+
+/// Emitted when a patient claims their research royalties
+#[event]
+pub struct RoyaltyClaimedEvent {
+    pub patient_data: Pubkey,
+    pub amount: u64,
+}
+
+/// Encrypted intersection size computed by `match_genetic_markers`
+#[event]
+pub struct GeneticMarkersMatchedEvent {
+    pub nonce: [u8; 16],
+    pub match_count: [u8; 32],
+}
+
+/// Encrypted anomaly flag and trend direction computed by `analyze_lab_trend`
+#[event]
+pub struct LabTrendAnalyzedEvent {
+    pub nonce: [u8; 16],
+    pub anomaly: [u8; 32],
+    pub direction: [u8; 32],
+}
+
+/// Emitted when a device appends a batch to a `VitalsSeries`
+#[event]
+pub struct VitalsBatchAppendedEvent {
+    pub vitals_series: Pubkey,
+    pub batch_index: u64,
+    pub first_timestamp: i64,
+    pub last_timestamp: i64,
+}
+
+/// Vitals samples within a window, re-encrypted by `share_vitals_window`
+#[event]
+pub struct VitalsWindowSharedEvent {
+    pub nonce: [u8; 16],
+    pub ciphertexts: [[u8; 32]; VITALS_BATCH_FIELDS],
+}
+
+/// Emitted when a monitor appends a batch to a `GlucoseSeries`
+#[event]
+pub struct GlucoseBatchAppendedEvent {
+    pub glucose_series: Pubkey,
+    pub batch_index: u64,
+    pub first_timestamp: i64,
+    pub last_timestamp: i64,
+}
+
+/// Encrypted time in range and estimated A1c computed by `compute_glucose_metrics`
+#[event]
+pub struct GlucoseMetricsComputedEvent {
+    pub nonce: [u8; 16],
+    pub time_in_range: [u8; 32],
+    pub estimated_a1c: [u8; 32],
+}
+
+/// Emitted when a patient grants or withdraws mental health access; `expires_at` is 0 on
+/// withdrawal
+#[event]
+pub struct MentalHealthAccessEvent {
+    pub patient_data: Pubkey,
+    pub receiver: [u8; 32],
+    pub expires_at: i64,
+}
+
+/// Mental health section re-encrypted by `share_mental_health`
+#[event]
+pub struct MentalHealthSharedEvent {
+    pub nonce: [u8; 16],
+    pub ciphertexts: [[u8; 32]; MENTAL_HEALTH_FIELDS],
+}
+
+/// Emitted when an off-chain imaging study is attached to a record
+#[event]
+pub struct ImagingStudyAttachedEvent {
+    pub patient_data: Pubkey,
+    pub index: u8,
+    pub content_hash: [u8; 32],
+}
+
+/// Emitted when a file attachment is registered on a record
+#[event]
+pub struct AttachmentRegisteredEvent {
+    pub patient_data: Pubkey,
+    pub attachment: Pubkey,
+    pub attachment_type: u8,
+    pub content_hash: [u8; 32],
+}
+
+/// Attachment content key and storage URI re-wrapped by `share_attachment_key`
+#[event]
+pub struct AttachmentKeySharedEvent {
+    pub nonce: [u8; 16],
+    pub ciphertexts: [[u8; 32]; ATTACHMENT_CIPHERTEXTS],
+}
+
+/// Emitted when a lab result is appended to a lab history; carries the full leaf
+#[event]
+pub struct LabLeafAppendedEvent {
+    pub patient_data: Pubkey,
+    pub index: u64,
+    pub encryption_key: [u8; 32],
+    pub nonce: [u8; 16],
+    pub ciphertexts: [[u8; 32]; LAB_LEAF_FIELDS],
+    pub root: [u8; 32],
+}
+
+/// Lab-history result re-encrypted by `share_lab_leaf`
+#[event]
+pub struct LabLeafSharedEvent {
+    pub nonce: [u8; 16],
+    pub ciphertexts: [[u8; 32]; LAB_LEAF_FIELDS],
+}
+
+/// Emitted when a record's capacity is expanded
+#[event]
+pub struct CapacityExpandedEvent {
+    pub patient_data: Pubkey,
+    pub medication_chunks: u16,
+    pub lab_chunks: u16,
+}
+
+/// Expanded capacity chunk re-encrypted by `share_capacity_chunk`
+#[event]
+pub struct CapacityChunkSharedEvent {
+    pub nonce: [u8; 16],
+    pub ciphertexts: [[u8; 32]; CAPACITY_CHUNK_FIELDS],
+}
+
+/// Obstetric record re-encrypted by `share_obstetric_data`
+#[event]
+pub struct ObstetricDataSharedEvent {
+    pub nonce: [u8; 16],
+    pub ciphertexts: [[u8; 32]; OBSTETRIC_FIELDS],
+}
+
+/// Emitted when a telemedicine session is opened
+#[event]
+pub struct TeleSessionOpenedEvent {
+    pub patient_data: Pubkey,
+    pub session: Pubkey,
+    pub provider: Pubkey,
+    pub scheduled_at: i64,
+}
+
+/// Emitted when a telemedicine session is closed
+#[event]
+pub struct TeleSessionClosedEvent {
+    pub patient_data: Pubkey,
+    pub session: Pubkey,
+    pub notes: u8,
+}
+
+/// Emitted when an appointment is booked, confirmed or cancelled
+#[event]
+pub struct AppointmentUpdatedEvent {
+    pub patient_data: Pubkey,
+    pub appointment: Pubkey,
+    pub provider: Pubkey,
+    pub status: u8,
+}
+
+/// Emitted when a telemedicine session is paid for
+#[event]
+pub struct TeleSessionPaidEvent {
+    pub session: Pubkey,
+    pub payer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Emitted when the payment of a closed telemedicine session is released
+#[event]
+pub struct TeleSessionSettledEvent {
+    pub session: Pubkey,
+    pub provider: Pubkey,
+    pub provider_amount: u64,
+    pub fee: u64,
+}
+
+/// Emitted when the registrar registers or updates a provider credential
+#[event]
+pub struct ProviderRegisteredEvent {
+    pub mint: Pubkey,
+    pub role: u8,
+    pub license_hash: [u8; 32],
+}
+
+/// Emitted when the registrar deactivates a provider credential
+#[event]
+pub struct ProviderDeactivatedEvent {
+    pub mint: Pubkey,
+}
+
+/// Emitted when the registrar registers or re-activates a laboratory signing key
+#[event]
+pub struct LabRegisteredEvent {
+    pub lab: Pubkey,
+}
+
+/// Emitted when the registrar deactivates a laboratory signing key
+#[event]
+pub struct LabDeactivatedEvent {
+    pub lab: Pubkey,
+}
+
+/// Emitted when a laboratory's signature over a record's lab section is recorded
+#[event]
+pub struct LabResultsAttestedEvent {
+    pub patient_data: Pubkey,
+    pub lab: Pubkey,
+    pub slot: u64,
+    pub digest: [u8; 32],
+}
+
+/// Emitted when a credentialed provider attests a section of a record
+#[event]
+pub struct SectionAttestedEvent {
+    pub patient_data: Pubkey,
+    pub provider: Pubkey,
+    pub section: u8,
+    pub digest: [u8; 32],
+    pub attested_at: i64,
+}
+
+/// Emitted when a provider submits an insurance claim
+#[event]
+pub struct ClaimSubmittedEvent {
+    pub claim: Pubkey,
+    pub patient_data: Pubkey,
+    pub provider: Pubkey,
+    pub insurer: Pubkey,
+    pub claim_id: u64,
+}
+
+/// Consistency score of a claim, encrypted for its insurer by `verify_claim_consistency`
+#[event]
+pub struct ClaimConsistencyEvent {
+    pub claim: Pubkey,
+    pub nonce: [u8; 16],
+    pub score: [u8; 32],
+}
+
+/// Emitted when an insurer approves or denies a claim
+#[event]
+pub struct ClaimAdjudicatedEvent {
+    pub claim: Pubkey,
+    pub insurer: Pubkey,
+    pub approved: bool,
+}
+
+/// Pre-authorization decision of `check_coverage`, encrypted for the insurer and the patient
+#[event]
+pub struct CoverageCheckedEvent {
+    pub patient_data: Pubkey,
+    pub coverage_policy: Pubkey,
+    pub insurer_nonce: [u8; 16],
+    pub insurer_decision: [u8; 32],
+    pub patient_nonce: [u8; 16],
+    pub patient_decision: [u8; 32],
+}
+
+/// Emitted when a patient asks specialists for a second opinion
+#[event]
+pub struct SecondOpinionRequestedEvent {
+    pub patient_data: Pubkey,
+    pub second_opinion: Pubkey,
+    pub specialists: Vec<Pubkey>,
+}
+
+/// Emitted when a specialist files an opinion
+#[event]
+pub struct OpinionSubmittedEvent {
+    pub second_opinion: Pubkey,
+    pub opinion: Pubkey,
+    pub specialist: Pubkey,
+}
+
+/// Whether two opinions agree, encrypted for the patient
+#[event]
+pub struct OpinionsComparedEvent {
+    pub second_opinion: Pubkey,
+    pub nonce: [u8; 16],
+    pub agree: [u8; 32],
+}
+
+/// Emitted when a patient creates a care team
+#[event]
+pub struct CareTeamCreatedEvent {
+    pub patient_data: Pubkey,
+    pub care_team: Pubkey,
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+/// Emitted when a record shared to a care team has been written
+#[event]
+pub struct TeamRecordSharedEvent {
+    pub care_team: Pubkey,
+    pub team_record: Pubkey,
+}
+
+/// Emitted when a member requests or approves access to a care team's record
+#[event]
+pub struct TeamAccessApprovedEvent {
+    pub team_access: Pubkey,
+    pub member: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,
+}
+
+/// Emitted when a care team's record is queued for release to a member
+#[event]
+pub struct TeamRecordReleasedEvent {
+    pub team_record: Pubkey,
+    pub member: Pubkey,
+    pub computation_offset: u64,
+}
+
+/// Emitted when `upgrade_comp_def` stages a new circuit version
+#[event]
+pub struct CompDefUpgradedEvent {
+    pub name: String,
+    pub version: u32,
+    pub comp_def_offset: u32,
+}
+
+/// Emitted when `finalize_comp_def` activates a staged circuit version
+#[event]
+pub struct CompDefFinalizedEvent {
+    pub name: String,
+    pub version: u32,
+    pub previous_version: u32,
+    pub comp_def_offset: u32,
+}
+
+/// Emitted when a provider joins or leaves an organization
+#[event]
+pub struct OrganizationMemberEvent {
+    pub organization: Pubkey,
+    pub member: Pubkey,
+    pub added: bool,
+}
+
+/// Emitted when the registrar issues or renews a credential
+#[event]
+pub struct CredentialIssuedEvent {
+    pub mint: Pubkey,
+    pub expires_at: i64,
+}
+
+/// Emitted when the registrar revokes a credential
+#[event]
+pub struct CredentialRevokedEvent {
+    pub mint: Pubkey,
+}
+
+/// Emitted when a subscription to a patient's access plan starts, is renewed or is
+/// cancelled (`expires_at` 0)
+#[event]
+pub struct SubscriptionEvent {
+    pub patient: Pubkey,
+    pub subscriber: Pubkey,
+    pub expires_at: i64,
+}
+
+/// Emitted when the config authority updates the protocol parameters
+#[event]
+pub struct ConfigUpdatedEvent {
+    pub protocol_fee_bps: u16,
+    pub fee_destination: Pubkey,
+    pub paused: bool,
+    pub max_records_per_patient: u64,
+}
+
+/// Emitted when an admin proposal is made, approved or executed
+#[event]
+pub struct AdminProposalEvent {
+    pub proposal_id: u64,
+    pub member: Pubkey,
+    pub approvals: u8,
+    pub executed: bool,
+}
+
+/// Emitted when guardians initiate a record recovery and when it completes
+#[event]
+pub struct RecoveryEvent {
+    pub patient_data: Pubkey,
+    pub new_owner: Pubkey,
+    pub executable_at: i64,
+    pub completed: bool,
+}
+
+/// Emitted when the config authority sets or removes a data-residency rule
+#[event]
+pub struct JurisdictionRuleEvent {
+    pub jurisdiction: u16,
+    pub allowed: Vec<u16>,
+}
+
+/// Emitted when `time_out_share` gives up on a share whose callback never arrived
+#[event]
+pub struct ShareTimedOutEvent {
+    pub schema_version: u8,
+    pub patient_data: Pubkey,
+    pub computation_offset: u64,
+}
+
+/// Emitted when the patient abandons a pending share with `cancel_share`
+#[event]
+pub struct ShareCancelledEvent {
+    pub schema_version: u8,
+    pub patient_data: Pubkey,
+    pub computation_offset: u64,
+}
+
+/// Emitted when `retry_share` re-queues a share under a new computation offset
+#[event]
+pub struct ShareRetriedEvent {
+    pub schema_version: u8,
+    pub patient_data: Pubkey,
+    pub previous_offset: u64,
+    pub computation_offset: u64,
+}
+
+/// Emitted when `expire_shared_record` closes a shared record past its retention window
+#[event]
+pub struct SharedRecordExpiredEvent {
+    pub shared_record: Pubkey,
+    pub patient_data: Pubkey,
+    pub receiver: [u8; 32],
+    pub computation_offset: u64,
+}
+
+/// Emitted when the program is paused or unpaused
+#[event]
+pub struct PauseEvent {
+    pub paused: bool,
+}
+
+/// Emitted when a provider asks a patient for a share
+#[event]
+pub struct ShareRequestedEvent {
+    pub patient: Pubkey,
+    pub request: Pubkey,
+    pub requester: Pubkey,
+    pub purpose: u8,
+    pub sections_mask: u8,
+}
+
+/// Emitted when a patient approves or denies a share request
+#[event]
+pub struct ShareRequestResolvedEvent {
+    pub request: Pubkey,
+    pub requester: Pubkey,
+    pub approved: bool,
+}
+
+/// Stores encrypted patient medical information including advanced healthcare,
+/// genomic analysis, and lab test results.
+#[account(zero_copy)]
+#[repr(C)]
+pub struct PatientData {
+    // Basic demographics
+    /// Encrypted unique patient identifier
+    pub patient_id: [u8; 32],
+    /// Encrypted patient age
+    pub age: [u8; 32],
+    /// Encrypted gender information
+    pub gender: [u8; 32],
+    /// Encrypted blood type
+    pub blood_type: [u8; 32],
+    /// Encrypted weight measurement
+    pub weight: [u8; 32],
+    /// Encrypted height measurement
+    pub height: [u8; 32],
+    /// Array of encrypted allergy information (up to 5 allergies)
+    pub allergies: [[u8; 32]; 5],
+    // Advanced healthcare
+    /// Encrypted medical history flags (diabetes, hypertension, heart_disease, cancer, stroke, asthma, copd, arthritis, osteoporosis, depression)
+    pub medical_history: [[u8; 32]; 10],
+    /// Encrypted medication count
+    pub medication_count: [u8; 32],
+    /// Array of encrypted medication IDs (up to 8 medications)
+    pub medications: [[u8; 32]; 8],
+    /// Encrypted procedure count
+    pub procedure_count: [u8; 32],
+    /// Array of encrypted procedure dates (days since epoch, up to 8 procedures)
+    pub procedure_dates: [[u8; 32]; 8],
+    /// Encrypted family history flags (diabetes, heart_disease, cancer, stroke, hypertension)
+    pub family_history: [[u8; 32]; 5],
+    // Genomic analysis
+    /// Encrypted genetic variant count
+    pub variant_count: [u8; 32],
+    /// Array of encrypted genetic marker identifiers (up to 15 variants)
+    pub genetic_markers: [[u8; 32]; 15],
+    /// Array of encrypted variant significance scores (up to 15)
+    pub variant_significance: [[u8; 32]; 15],
+    /// Encrypted carrier status flags (cystic_fibrosis, sickle_cell, tay_sachs, hemophilia, huntington)
+    pub carrier_status: [[u8; 32]; 5],
+    /// Encrypted pharmacogenomic markers (warfarin_sensitivity, clopidogrel_resistance, statin_response)
+    pub pharmacogenomic_markers: [[u8; 32]; 3],
+    /// Array of encrypted ancestry component percentages (up to 7 populations)
+    pub ancestry_components: [[u8; 32]; 7],
+    // Lab test results
+    /// Encrypted lab test count
+    pub lab_test_count: [u8; 32],
+    /// Array of encrypted lab test type identifiers (up to 10 tests)
+    pub lab_test_types: [[u8; 32]; 10],
+    /// Array of encrypted lab test dates (days since epoch, up to 10 tests)
+    pub lab_test_dates: [[u8; 32]; 10],
+    /// Array of encrypted lab test values (normalized, up to 10 tests)
+    pub lab_test_values: [[u8; 32]; 10],
+    /// Array of encrypted lab test normal range flags (0=low, 1=normal, 2=high, up to 10 tests)
+    pub lab_test_flags: [[u8; 32]; 10],
+    /// Encrypted imaging count
+    pub imaging_count: [u8; 32],
+    /// Array of encrypted imaging type identifiers (up to 10 imaging studies)
+    pub imaging_types: [[u8; 32]; 10],
+    /// Array of encrypted imaging dates (days since epoch, up to 10)
+    pub imaging_dates: [[u8; 32]; 10],
+    // Honeytoken
+    /// Encrypted per-patient canary value; seeing it outside a legitimate receiver signals
+    /// unauthorized decryption (all zeroes if the patient did not set one)
+    pub canary: [u8; 32],
+    // Immunizations
+    /// Encrypted count of tracked vaccines
+    pub immunization_count: [u8; 32],
+    /// Array of encrypted vaccine codes (up to 8)
+    pub vaccine_codes: [[u8; 32]; 8],
+    /// Array of encrypted dates of the latest dose (days since epoch, up to 8)
+    pub vaccination_dates: [[u8; 32]; 8],
+    /// Array of encrypted dose counts (up to 8)
+    pub vaccine_doses: [[u8; 32]; 8],
+    // Terminology codes
+    /// Array of encrypted ICD-10 codes, one per `medical_history` flag (ASCII, up to 8 bytes)
+    pub icd10_codes: [[u8; 32]; 10],
+    /// Array of encrypted RxNorm CUIs, one per `medications` entry
+    pub rxnorm_codes: [[u8; 32]; 8],
+    /// Array of encrypted LOINC codes, one per `lab_test_types` entry (ASCII, up to 8 bytes)
+    pub loinc_codes: [[u8; 32]; 10],
+    /// Plaintext data-residency jurisdiction of the record, `JURISDICTION_UNTAGGED` if none
+    pub jurisdiction: u16,
+}
+
+/// Marks a receiver as revoked for a single patient record.
+#[account]
+#[derive(InitSpace)]
+pub struct ReceiverRevocation {
+    /// Patient record the revocation applies to
+    pub patient_data: Pubkey,
+    /// Public key of the revoked receiver
+    pub receiver: [u8; 32],
+    /// Unix timestamp at which the receiver was revoked
+    pub revoked_at: i64,
+    pub bump: u8,
+}
+
+/// Patient-defined guardians and rules for break-glass emergency access.
+#[account]
+#[derive(InitSpace)]
+pub struct EmergencyPolicy {
+    /// Patient record the policy protects
+    pub patient_data: Pubkey,
+    /// Guardian keys allowed to approve emergency requests
+    #[max_len(MAX_EMERGENCY_GUARDIANS)]
+    pub guardians: Vec<Pubkey>,
+    /// Number of guardian approvals required to execute a request
+    pub threshold: u8,
+    /// Seconds after opening at which a request is executable without approvals (0 = disabled)
+    pub delay_seconds: i64,
+    pub bump: u8,
+}
+
+/// Guardians able to move a patient record to a new owner, seeded by the patient record.
+#[account]
+#[derive(InitSpace)]
+pub struct RecoveryPolicy {
+    /// Patient record the policy protects
+    pub patient_data: Pubkey,
+    /// Guardian keys allowed to co-sign a recovery
+    #[max_len(MAX_RECOVERY_GUARDIANS)]
+    pub guardians: Vec<Pubkey>,
+    /// Number of guardians that must co-sign a recovery
+    pub threshold: u8,
+    /// Seconds between initiating and completing a recovery
+    pub delay_seconds: i64,
+    /// Wallet the pending recovery moves the record to
+    pub new_owner: Pubkey,
+    /// x25519 public key the pending recovery re-encrypts the record to
+    pub new_encryption_key: [u8; 32],
+    /// x25519 public key the stored record is encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the stored record is encrypted with
+    pub encryption_nonce: u128,
+    /// Unix timestamp the pending recovery was initiated at; 0 when none is pending
+    pub initiated_at: i64,
+    pub bump: u8,
+}
+
+/// Guardian custody of a pediatric record, seeded by the record.
+#[account]
+#[derive(InitSpace)]
+pub struct Custody {
+    /// Pediatric record held in custody
+    pub patient_data: Pubkey,
+    /// Guardian who created the record and holds authority over it
+    pub guardian: Pubkey,
+    /// Guardian-chosen identifier of the child, part of the record's seeds
+    pub child_id: u64,
+    /// Unix timestamp from which custody can be transferred to the patient
+    pub adult_at: i64,
+    pub bump: u8,
+}
+
+/// An open emergency access request awaiting guardian approval.
+#[account]
+#[derive(InitSpace)]
+pub struct BreakGlassRequest {
+    /// Patient record being accessed
+    pub patient_data: Pubkey,
+    /// Provider that opened the request
+    pub requester: Pubkey,
+    /// Public key the emergency data will be encrypted for
+    pub receiver: [u8; 32],
+    /// Unix timestamp at which the request was opened
+    pub opened_at: i64,
+    /// Bitmask of approving guardians, indexed by position in the policy
+    pub approval_mask: u8,
+    pub bump: u8,
+}
+
+/// A patient's advance directive (DNR and similar), seeded by the patient record. Readable
+/// by hospital roles under break-glass with `share_advance_directive`; every change is
+/// co-signed by a witness.
+#[account]
+#[derive(InitSpace)]
+pub struct AdvanceDirective {
+    /// Patient record the directive belongs to
+    pub patient_data: Pubkey,
+    /// x25519 public key the flags are encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the flags are encrypted with
+    pub nonce: u128,
+    /// Encrypted `DIRECTIVE_*` bits
+    pub directive_flags: [u8; 32],
+    /// Hash of the signed directive document kept off-chain
+    pub document_hash: [u8; 32],
+    /// Witness who co-signed the last change
+    pub witness: Pubkey,
+    /// Unix timestamp of the last change
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+/// Organ donor consent and HLA typing of a patient, seeded by the patient record. Kept
+/// beside `PatientData` so existing records need not be resized.
+///
+/// The ciphertexts come first so that they sit right after the discriminator.
+#[account]
+#[derive(InitSpace)]
+pub struct DonorProfile {
+    /// Encrypted donor consent flag, then the HLA allele codes (A, A, B, B, DR, DR)
+    pub ciphertexts: [[u8; 32]; DONOR_PROFILE_FIELDS],
+    /// Patient record the profile belongs to
+    pub patient_data: Pubkey,
+    /// x25519 public key the profile is encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the profile is encrypted with
+    pub nonce: u128,
+    pub bump: u8,
+}
+
+/// One shard of a patient's audit index, scoped to a single epoch.
+#[account]
+#[derive(InitSpace)]
+pub struct AuditIndexShard {
+    /// Patient record the shard indexes
+    pub patient_data: Pubkey,
+    /// Epoch the shard was opened in; shares only write to current-epoch shards
+    pub epoch: u64,
+    /// Shard index within the epoch
+    pub shard: u8,
+    /// Number of shares recorded in this shard
+    pub share_count: u64,
+    /// Unix timestamp of the most recent share recorded in this shard
+    pub last_share_at: i64,
+    pub bump: u8,
+}
+
+impl AuditIndexShard {
+    /// Records a share in this shard, rejecting shards from a previous epoch.
+    pub fn record_share(&mut self, clock: &Clock) -> Result<()> {
+        require!(self.epoch == clock.epoch, ErrorCode::StaleAuditShard);
+        self.share_count = self.share_count.saturating_add(1);
+        self.last_share_at = clock.unix_timestamp;
+        Ok(())
+    }
+}
+
+/// Return data of `aggregate_audit_index`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AuditIndexAggregate {
+    pub patient_data: Pubkey,
+    pub shards: u32,
+    pub total_shares: u64,
+    pub first_epoch: u64,
+    pub last_epoch: u64,
+    pub last_share_at: i64,
+}
+
+/// Records the program-managed address lookup table used by share transactions.
+#[account]
+#[derive(InitSpace)]
+pub struct LookupTableConfig {
+    /// Key allowed to extend the lookup table
+    pub authority: Pubkey,
+    /// Address of the lookup table
+    pub lookup_table: Pubkey,
+    /// Number of addresses appended so far
+    pub address_count: u16,
+    pub bump: u8,
+}
+
+/// Authority granted by a patient to a caregiver to act on their record.
+#[account]
+#[derive(InitSpace)]
+pub struct DelegationAccount {
+    /// Patient record the delegation applies to
+    pub patient_data: Pubkey,
+    /// Caregiver allowed to act on the patient's behalf
+    pub delegate: Pubkey,
+    /// Bit flags of granted actions
+    pub scope: u8,
+    /// Unix timestamp after which the delegation is no longer valid
+    pub expires_at: i64,
+    /// Jurisdiction of the record when the delegation was granted
+    pub jurisdiction: u16,
+    pub bump: u8,
+}
+
+/// Role-gated shares counted for one (patient, receiver) pair, or donor match queries for
+/// one (patient, coordinator credential) pair, in the current rate limit window.
+#[account]
+#[derive(InitSpace)]
+pub struct RateLimit {
+    /// Patient record being shared
+    pub patient_data: Pubkey,
+    /// Public key of the receiver
+    pub receiver: [u8; 32],
+    /// Unix timestamp the current window started at
+    pub window_start: i64,
+    /// Shares made in the current window
+    pub count: u32,
+    pub bump: u8,
+}
+
+impl RateLimit {
+    /// Counts a share against the configured limit, starting a new window once the current
+    /// one has passed.
+    pub fn record_share(
+        &mut self,
+        config: &ProgramConfig,
+        patient_data: Pubkey,
+        receiver: [u8; 32],
+        bump: u8,
+    ) -> Result<()> {
+        self.patient_data = patient_data;
+        self.receiver = receiver;
+        self.bump = bump;
+        if config.share_rate_limit == 0 {
+            return Ok(());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        if now >= self.window_start.saturating_add(config.share_rate_window) {
+            self.window_start = now;
+            self.count = 0;
+        }
+        require!(self.count < config.share_rate_limit, ErrorCode::RateLimited);
+        self.count += 1;
+        Ok(())
+    }
+}
+
+/// Monotonic receiver-nonce source for one (patient, receiver) pair.
+#[account]
+#[derive(InitSpace)]
+pub struct ShareNonce {
+    /// Patient record being shared
+    pub patient_data: Pubkey,
+    /// Public key of the receiver
+    pub receiver: [u8; 32],
+    /// Number of nonces issued so far
+    pub counter: u64,
+    /// Most recently issued receiver nonce
+    pub last_nonce: u128,
+    /// Latest nonces of receiver-encrypted query inputs, oldest first
+    #[max_len(RECENT_INPUT_NONCES)]
+    pub recent_input_nonces: Vec<u128>,
+    pub bump: u8,
+}
+
+impl ShareNonce {
+    /// Issues the next receiver nonce and bumps the counter.
+    ///
+    /// The nonce is derived from the patient record, the receiver and the counter, so it
+    /// never repeats for a receiver even when several patients share with the same key.
+    pub fn advance(&mut self, patient_data: Pubkey, receiver: [u8; 32], bump: u8) -> Result<u128> {
+        let counter = self.counter;
+        let digest = hashv(&[
+            b"receiver_nonce",
+            patient_data.as_ref(),
+            receiver.as_ref(),
+            &counter.to_le_bytes(),
+        ]);
+        let mut nonce = [0u8; 16];
+        nonce.copy_from_slice(&digest.to_bytes()[..16]);
+
+        self.patient_data = patient_data;
+        self.receiver = receiver;
+        self.counter = counter.checked_add(1).ok_or(ErrorCode::NonceExhausted)?;
+        self.last_nonce = u128::from_le_bytes(nonce);
+        self.bump = bump;
+
+        emit!(ShareNonceEvent {
+            patient_data,
+            receiver,
+            counter,
+            receiver_nonce: self.last_nonce,
+        });
+        Ok(self.last_nonce)
+    }
+
+    /// Records the nonce a receiver encrypted a query input with, failing with
+    /// `NonceReplayed` if it was among the latest ones, so a replayed transaction cannot
+    /// re-run a query with a stale input.
+    pub fn consume_input_nonce(&mut self, nonce: u128) -> Result<()> {
+        require!(
+            !self.recent_input_nonces.contains(&nonce),
+            ErrorCode::NonceReplayed
+        );
+        if self.recent_input_nonces.len() == RECENT_INPUT_NONCES {
+            self.recent_input_nonces.remove(0);
+        }
+        self.recent_input_nonces.push(nonce);
+        Ok(())
+    }
+}
+
+/// Tracks the computations queued by a single batch share.
+#[account]
+#[derive(InitSpace)]
+pub struct ShareBatch {
+    /// Patient record that was shared
+    pub patient_data: Pubkey,
+    /// Caller-chosen batch identifier
+    pub batch_id: u64,
+    /// Unix timestamp at which the batch was queued
+    pub created_at: i64,
+    /// Receivers of the batch, in queue order
+    #[max_len(MAX_BATCH_RECEIVERS)]
+    pub receivers: Vec<[u8; 32]>,
+    /// Computation offsets queued for each receiver
+    #[max_len(MAX_BATCH_RECEIVERS)]
+    pub computation_offsets: Vec<u64>,
+    pub bump: u8,
+}
+
+/// A grant tracked by the expiry index.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct GrantExpiryEntry {
+    /// Account holding the grant (e.g. a `DelegationAccount`)
+    pub grant: Pubkey,
+    /// Kind of grant (`GRANT_KIND_*`)
+    pub kind: u8,
+    /// Unix timestamp at which the grant expires
+    pub expires_at: i64,
+    /// Whether a reminder has already been issued for the current expiry
+    pub reminded: bool,
+}
+
+/// Per-patient index of expiring grants, kept sorted by expiry.
+#[account]
+#[derive(InitSpace)]
+pub struct GrantExpiryIndex {
+    /// Patient record the grants belong to
+    pub patient_data: Pubkey,
+    /// Tracked grants, earliest expiry first
+    #[max_len(MAX_TRACKED_GRANTS)]
+    pub entries: Vec<GrantExpiryEntry>,
+    pub bump: u8,
+}
+
+impl GrantExpiryIndex {
+    /// Inserts or updates a grant, keeping entries ordered by expiry.
+    pub fn upsert(&mut self, grant: Pubkey, kind: u8, expires_at: i64) -> Result<()> {
+        self.remove(&grant);
+        require!(self.entries.len() < MAX_TRACKED_GRANTS, ErrorCode::GrantIndexFull);
+        let position = self.entries.partition_point(|e| e.expires_at <= expires_at);
+        self.entries.insert(
+            position,
+            GrantExpiryEntry {
+                grant,
+                kind,
+                expires_at,
+                reminded: false,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stops tracking a grant.
+    pub fn remove(&mut self, grant: &Pubkey) {
+        self.entries.retain(|e| e.grant != *grant);
+    }
+}
+
+/// A notification delivered to a patient's inbox.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Notification {
+    /// Kind of notification (`NOTIFICATION_*`)
+    pub kind: u8,
+    /// Account the notification is about
+    pub subject: Pubkey,
+    /// Unix timestamp the notification refers to (e.g. a grant's expiry)
+    pub due_at: i64,
+    /// Unix timestamp at which the notification was created
+    pub created_at: i64,
+}
+
+/// Patient notification inbox, a ring buffer of the most recent notifications.
+#[account]
+#[derive(InitSpace)]
+pub struct PatientInbox {
+    /// Patient record the inbox belongs to
+    pub patient_data: Pubkey,
+    /// Total number of notifications ever delivered
+    pub total: u64,
+    /// Most recent notifications; once full, the oldest slot is overwritten
+    #[max_len(MAX_INBOX_NOTIFICATIONS)]
+    pub notifications: Vec<Notification>,
+    pub bump: u8,
+}
+
+impl PatientInbox {
+    /// Appends a notification, overwriting the oldest one once the inbox is full.
+    pub fn push(&mut self, notification: Notification) {
+        if self.notifications.len() < MAX_INBOX_NOTIFICATIONS {
+            self.notifications.push(notification);
+        } else {
+            let slot = (self.total % MAX_INBOX_NOTIFICATIONS as u64) as usize;
+            self.notifications[slot] = notification;
+        }
+        self.total = self.total.saturating_add(1);
+    }
+}
+
+/// Who wrote a piece of clinical data, and under which credential.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct EntryAuthor {
+    /// Key of the author (the patient for `ROLE_PATIENT`)
+    pub provider: Pubkey,
+    /// Role the author wrote under (`ROLE_*`)
+    pub role: u8,
+    /// Credential mint backing the role (default for `ROLE_PATIENT`)
+    pub credential_mint: Pubkey,
+}
+
+/// Attribution and bookkeeping for a patient record.
+#[account]
+#[derive(InitSpace)]
+pub struct RecordMetadata {
+    /// Patient record the metadata describes
+    pub patient_data: Pubkey,
+    /// Author of the initial `store_patient_data` write
+    pub created_by: EntryAuthor,
+    /// Author of the most recent write
+    pub last_written_by: EntryAuthor,
+    /// Unix timestamp of the most recent write
+    pub last_written_at: i64,
+    /// Number of entries appended so far; also the id of the next entry
+    pub entry_count: u64,
+    /// Opt-in periodic re-encryption of the stored record
+    pub rotation: RotationPolicy,
+    pub bump: u8,
+}
+
+/// Periodic re-encryption policy and history of a patient record.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct RotationPolicy {
+    /// Re-encrypt every this many epochs; 0 when the patient has not opted in
+    pub interval_epochs: u64,
+    /// x25519 public key the stored ciphertexts are currently encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the stored ciphertexts are currently encrypted with
+    pub encryption_nonce: u128,
+    /// Key the next rotation re-encrypts to
+    pub next_encryption_key: [u8; 32],
+    /// Epoch of the latest rotation (or of opting in)
+    pub last_rotated_epoch: u64,
+    /// Epoch at which the in-flight rotation was queued, if any
+    pub pending_since_epoch: Option<u64>,
+    /// Most recent rotations, oldest first
+    #[max_len(MAX_ROTATION_HISTORY)]
+    pub history: Vec<RotationRecord>,
+}
+
+/// A completed re-encryption of a patient record.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RotationRecord {
+    /// Epoch in which the rotation completed
+    pub epoch: u64,
+    /// Key the record was re-encrypted to
+    pub encryption_key: [u8; 32],
+    /// Nonce of the new ciphertexts
+    pub encryption_nonce: u128,
+}
+
+/// An encrypted entry appended to a patient record.
+///
+/// The encryption key, nonce and ciphertexts come first so that the ciphertexts sit at the
+/// fixed `RECORD_ENTRY_CIPHERTEXTS_OFFSET` used by the entry sharing circuits.
+#[account]
+#[derive(InitSpace)]
+pub struct RecordEntry {
+    /// Patient record the entry belongs to
+    pub patient_data: Pubkey,
+    /// x25519 public key the ciphertexts were encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the ciphertexts were encrypted with
+    pub nonce: u128,
+    /// Encrypted entry fields
+    pub ciphertexts: [[u8; 32]; ENTRY_FIELDS],
+    /// Sequential id of the entry within the record
+    pub entry_id: u64,
+    /// Entry kind (`ENTRY_KIND_*`)
+    pub kind: u8,
+    /// Author of the entry
+    pub author: EntryAuthor,
+    /// Unix timestamp at which the entry was written
+    pub created_at: i64,
+    /// Id of the entry this one corrects, if it is an amendment
+    pub supersedes: Option<u64>,
+    /// Id of the entry that corrects this one, if it has been amended
+    pub superseded_by: Option<u64>,
+    /// Whether the patient has attached an `EntryFlag`; flagged entries are only shared
+    /// together with it
+    pub flagged: bool,
+    pub bump: u8,
+}
+
+/// An encrypted visit summary written back by a provider with `append_visit_summary`.
+///
+/// Laid out like `RecordEntry` up to the ciphertexts, so `share_encounter` can reuse the
+/// `share_entry` circuit.
+#[account]
+#[derive(InitSpace)]
+pub struct Encounter {
+    /// Patient record the encounter belongs to
+    pub patient_data: Pubkey,
+    /// x25519 public key the ciphertexts were encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the ciphertexts were encrypted with
+    pub nonce: u128,
+    /// Encrypted summary fields
+    pub ciphertexts: [[u8; 32]; ENTRY_FIELDS],
+    /// Client-chosen id of the encounter within the record
+    pub encounter_id: u64,
+    /// Provider who wrote the summary
+    pub provider: Pubkey,
+    /// Credential mint the provider wrote under
+    pub credential_mint: Pubkey,
+    /// Unix timestamp at which the summary was written
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// A proposed replacement of one record section awaiting the other party's approval.
+#[account]
+#[derive(InitSpace)]
+pub struct PendingUpdate {
+    /// Patient record the update applies to
+    pub patient_data: Pubkey,
+    /// Section being replaced (a single `SECTION_*` bit)
+    pub section: u8,
+    /// SHA-256 of the new section ciphertexts
+    pub ciphertexts_hash: [u8; 32],
+    /// Signer who proposed the update and the role they proposed it under
+    pub proposer: EntryAuthor,
+    /// Unix timestamp of the proposal
+    pub proposed_at: i64,
+    pub bump: u8,
+}
+
+/// Encrypted fields of a record entry, as submitted by its author.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EncryptedEntry {
+    /// x25519 public key the ciphertexts were encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the ciphertexts were encrypted with
+    pub nonce: u128,
+    /// Encrypted entry fields, exactly `ENTRY_FIELDS` of them
+    pub ciphertexts: Vec<[u8; 32]>,
+}
+
+/// Marker left by `erase_patient_data`, seeded by the erased patient record. Records nothing
+/// but the time of erasure.
+#[account]
+#[derive(InitSpace)]
+pub struct Tombstone {
+    /// Unix timestamp of the erasure
+    pub erased_at: i64,
+    pub bump: u8,
+}
+
+/// Re-encrypted output of a share, persisted for the receiver.
+///
+/// Seeded by (patient, receiver, computation_offset). The share instruction fills in the
+/// header and the callback writes the receiver's ciphertexts once the computation completes.
+#[account(zero_copy)]
+#[repr(C)]
+pub struct SharedRecord {
+    /// Patient record that was shared
+    pub patient_data: Pubkey,
+    /// Public key the ciphertexts are encrypted for
+    pub receiver: [u8; 32],
+    /// Wallet allowed to close the record after download
+    pub receiver_authority: Pubkey,
+    /// Account that paid the rent and receives it back on close
+    pub rent_payer: Pubkey,
+    /// Offset of the computation that produced the ciphertexts
+    pub computation_offset: u64,
+    /// Unix timestamp at which the share was queued
+    pub created_at: i64,
+    /// Unix timestamp after which the record may be expired; 0 uses the config default
+    pub retain_until: i64,
+    /// Nonce of the receiver's ciphertexts
+    pub nonce: [u8; 16],
+    /// 1 once the callback has written the ciphertexts
+    pub filled: u8,
+    pub _padding: [u8; 7],
+    /// Receiver's ciphertexts, in `PatientData` field order
+    pub ciphertexts: [[u8; 32]; 153 + IMMUNIZATION_FIELDS + TERMINOLOGY_FIELDS],
+}
+
+impl SharedRecord {
+    /// Unix timestamp after which the record may be expired, if any.
+    pub fn expires_at(&self, policy: &RetentionPolicy) -> Option<i64> {
+        if self.retain_until != 0 {
+            Some(self.retain_until)
+        } else if policy.default_retention > 0 {
+            Some(self.created_at.saturating_add(policy.default_retention))
+        } else {
+            None
+        }
+    }
+}
+
+/// A record shared to a care team, encrypted under the MXE's key, seeded by (care team,
+/// computation_offset). `share_to_care_team` fills in the header and its callback writes
+/// the ciphertexts.
+#[account(zero_copy)]
+#[repr(C)]
+pub struct TeamRecord {
+    /// Care team the record was shared to
+    pub care_team: Pubkey,
+    /// Offset of the computation that produced the ciphertexts
+    pub computation_offset: u64,
+    /// Nonce of the MXE ciphertexts
+    pub nonce: [u8; 16],
+    /// 1 once the callback has written the ciphertexts
+    pub filled: u8,
+    pub _padding: [u8; 7],
+    /// MXE ciphertexts, in `PatientData` field order
+    pub ciphertexts: [[u8; 32]; 153 + IMMUNIZATION_FIELDS + TERMINOLOGY_FIELDS],
+}
+
+/// Progress of a `share_patient_data` computation, seeded by (patient_data,
+/// computation_offset).
+#[account]
+#[derive(InitSpace)]
+pub struct ShareStatus {
+    /// Patient record that was shared
+    pub patient_data: Pubkey,
+    /// Offset of the share computation
+    pub computation_offset: u64,
+    /// One of the `SHARE_STATUS_*` constants
+    pub status: u8,
+    /// Slot at which the share was queued
+    pub queued_slot: u64,
+    /// Slot at which the status left queued; 0 while pending
+    pub resolved_slot: u64,
+    /// Program error code of a failed share, e.g. `AbortedComputation`; 0 otherwise
+    pub failure_reason: u32,
+    pub bump: u8,
+}
+
+/// Ring buffer of encrypted vitals batches streamed by a remote-monitoring device, seeded by
+/// the patient record.
+#[account(zero_copy)]
+#[repr(C)]
+pub struct VitalsSeries {
+    /// Patient record the vitals belong to
+    pub patient_data: Pubkey,
+    /// Device allowed to append batches
+    pub device: Pubkey,
+    /// Number of batches ever appended; the next one goes to slot `next_batch % 12`
+    pub next_batch: u64,
+    pub batches: [VitalsBatch; VITALS_RING_BATCHES],
+}
+
+impl VitalsSeries {
+    /// Most recently appended batch, if any.
+    pub fn latest(&self) -> Option<&VitalsBatch> {
+        self.next_batch
+            .checked_sub(1)
+            .and_then(|batch_index| self.batch(batch_index))
+    }
+
+    /// Batch with sequence number `batch_index`, if it is still in the ring buffer.
+    pub fn batch(&self, batch_index: u64) -> Option<&VitalsBatch> {
+        if batch_index >= self.next_batch
+            || self.next_batch - batch_index > VITALS_RING_BATCHES as u64
+        {
+            return None;
+        }
+        Some(&self.batches[batch_index as usize % VITALS_RING_BATCHES])
+    }
+
+    /// Byte offset of the ciphertexts of batch `batch_index` for `Argument::Account`,
+    /// including the account discriminator.
+    pub fn ciphertexts_offset(batch_index: u64) -> u32 {
+        let slot = batch_index as usize % VITALS_RING_BATCHES;
+        (8 + offset_of!(VitalsSeries, batches)
+            + slot * core::mem::size_of::<VitalsBatch>()
+            + offset_of!(VitalsBatch, ciphertexts)) as u32
+    }
+}
+
+/// One batch of vitals samples, encrypted together.
+#[zero_copy]
+#[repr(C)]
+pub struct VitalsBatch {
+    /// x25519 public key the batch is encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the batch is encrypted with
+    pub nonce: [u8; 16],
+    /// Unix timestamp of the first sample
+    pub first_timestamp: i64,
+    /// Unix timestamp of the last sample
+    pub last_timestamp: i64,
+    /// Encrypted `VitalsSamples`, in field order
+    pub ciphertexts: [[u8; 32]; VITALS_BATCH_FIELDS],
+}
+
+/// Ring buffer of encrypted continuous glucose monitoring readings, seeded by the patient
+/// record.
+#[account(zero_copy)]
+#[repr(C)]
+pub struct GlucoseSeries {
+    /// Patient record the readings belong to
+    pub patient_data: Pubkey,
+    /// Monitor allowed to append batches
+    pub device: Pubkey,
+    /// Number of batches ever appended; the next one goes to slot `next_batch % 12`
+    pub next_batch: u64,
+    pub batches: [GlucoseBatch; GLUCOSE_RING_BATCHES],
+}
+
+impl GlucoseSeries {
+    /// Most recently appended batch, if any.
+    pub fn latest(&self) -> Option<&GlucoseBatch> {
+        self.next_batch
+            .checked_sub(1)
+            .and_then(|batch_index| self.batch(batch_index))
+    }
+
+    /// Batch with sequence number `batch_index`, if it is still in the ring buffer.
+    pub fn batch(&self, batch_index: u64) -> Option<&GlucoseBatch> {
+        if batch_index >= self.next_batch
+            || self.next_batch - batch_index > GLUCOSE_RING_BATCHES as u64
+        {
+            return None;
+        }
+        Some(&self.batches[batch_index as usize % GLUCOSE_RING_BATCHES])
+    }
+
+    /// Byte offset of the ciphertexts of batch `batch_index` for `Argument::Account`,
+    /// including the account discriminator.
+    pub fn ciphertexts_offset(batch_index: u64) -> u32 {
+        let slot = batch_index as usize % GLUCOSE_RING_BATCHES;
+        (8 + offset_of!(GlucoseSeries, batches)
+            + slot * core::mem::size_of::<GlucoseBatch>()
+            + offset_of!(GlucoseBatch, ciphertexts)) as u32
+    }
+}
+
+/// One batch of glucose readings, encrypted together.
+#[zero_copy]
+#[repr(C)]
+pub struct GlucoseBatch {
+    /// x25519 public key the batch is encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the batch is encrypted with
+    pub nonce: [u8; 16],
+    /// Unix timestamp of the first reading
+    pub first_timestamp: i64,
+    /// Unix timestamp of the last reading
+    pub last_timestamp: i64,
+    /// Encrypted `GlucoseReadings`, in field order
+    pub ciphertexts: [[u8; 32]; GLUCOSE_READINGS_PER_BATCH],
+}
+
+/// Encrypted mental health section of a patient, seeded by the patient record.
+///
+/// Kept apart from `PatientData` so general-purpose shares never include it. The encryption
+/// key, nonce and ciphertexts come first so that the ciphertexts sit at the fixed
+/// `MENTAL_HEALTH_CIPHERTEXTS_OFFSET` used by `share_mental_health`.
+#[account]
+#[derive(InitSpace)]
+pub struct MentalHealthData {
+    /// Patient record the section belongs to
+    pub patient_data: Pubkey,
+    /// x25519 public key the ciphertexts were encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the ciphertexts were encrypted with
+    pub nonce: u128,
+    /// Encrypted diagnoses, therapy note references and medication flags
+    pub ciphertexts: [[u8; 32]; MENTAL_HEALTH_FIELDS],
+    /// Unix timestamp of the latest update
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+/// Patient consent to disclose the mental health section to one receiver, seeded by
+/// (patient record, receiver).
+#[account]
+#[derive(InitSpace)]
+pub struct MentalHealthGrant {
+    /// Patient record the grant applies to
+    pub patient_data: Pubkey,
+    /// x25519 public key of the recipient
+    pub receiver: [u8; 32],
+    /// Unix timestamp after which the grant is no longer valid
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+/// Encrypted reproductive and obstetric record of a patient, seeded by the patient record.
+///
+/// Kept apart from `PatientData` so only OB/GYN credential holders can read it. The
+/// encryption key, nonce and ciphertexts come first so that the ciphertexts sit at the fixed
+/// `OBSTETRIC_CIPHERTEXTS_OFFSET` used by `share_obstetric_data`.
+#[account]
+#[derive(InitSpace)]
+pub struct ObstetricData {
+    /// Patient record the obstetric record belongs to
+    pub patient_data: Pubkey,
+    /// x25519 public key the ciphertexts were encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the ciphertexts were encrypted with
+    pub nonce: u128,
+    /// Encrypted pregnancy history, due date, gestational age, complications and delivery
+    /// method
+    pub ciphertexts: [[u8; 32]; OBSTETRIC_FIELDS],
+    /// Unix timestamp of the latest update
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+/// Off-chain imaging studies of a patient, seeded by the patient record.
+#[account]
+#[derive(InitSpace)]
+pub struct ImagingStudies {
+    /// Patient record the studies belong to
+    pub patient_data: Pubkey,
+    /// Studies by imaging slot; slots without an attached study are all zeroes
+    pub studies: [ImagingStudy; MAX_IMAGING_STUDIES],
+    pub bump: u8,
+}
+
+/// Pointer to an imaging study stored off-chain, with a commitment to its content.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct ImagingStudy {
+    /// x25519 public key the storage URI was encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the storage URI was encrypted with
+    pub nonce: u128,
+    /// Encrypted storage URI (IPFS CID or Arweave transaction id), 16 bytes per ciphertext
+    pub storage_uri: [[u8; 32]; STORAGE_URI_CIPHERTEXTS],
+    /// SHA-256 hash of the study content
+    pub content_hash: [u8; 32],
+    /// Unix timestamp the study was attached at
+    pub attached_at: i64,
+}
+
+/// Encrypted file attachment of a patient, seeded by (patient, attachment id).
+///
+/// The encryption key, nonce and ciphertexts come first so that the ciphertexts sit at the
+/// fixed `ATTACHMENT_CIPHERTEXTS_OFFSET` used by `share_attachment_key`.
+#[account]
+#[derive(InitSpace)]
+pub struct Attachment {
+    /// Patient record the attachment belongs to
+    pub patient_data: Pubkey,
+    /// x25519 public key the ciphertexts were encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the ciphertexts were encrypted with
+    pub nonce: u128,
+    /// Encrypted symmetric content key followed by the encrypted storage URI, 16 bytes per
+    /// ciphertext
+    pub ciphertexts: [[u8; 32]; ATTACHMENT_CIPHERTEXTS],
+    /// Caller-chosen identifier, unique per patient record
+    pub attachment_id: u64,
+    /// One of the `ATTACHMENT_TYPE_*` constants
+    pub attachment_type: u8,
+    /// SHA-256 hash of the encrypted file
+    pub content_hash: [u8; 32],
+    /// Unix timestamp the attachment was registered at
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// Compressed lab history of a patient, seeded by the patient record.
+///
+/// Lab results are leaves of an append-only Merkle tree of depth `LAB_HISTORY_DEPTH`; only
+/// the root and the number of leaves are stored, the leaves live in `LabLeafAppendedEvent`.
+#[account]
+#[derive(InitSpace)]
+pub struct LabHistory {
+    /// Patient record the history belongs to
+    pub patient_data: Pubkey,
+    /// Current Merkle root
+    pub root: [u8; 32],
+    /// Number of leaves appended so far; also the index of the next leaf
+    pub leaf_count: u64,
+    pub bump: u8,
+}
+
+/// A lab-history leaf and its Merkle proof, as supplied to `share_lab_leaf`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LabLeaf {
+    /// Index of the leaf in the tree
+    pub index: u64,
+    /// x25519 public key the result was encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the result was encrypted with
+    pub nonce: u128,
+    /// Encrypted test type, date, value and range flag
+    pub ciphertexts: [[u8; 32]; LAB_LEAF_FIELDS],
+    /// Sibling hashes from the leaf up to the root
+    pub proof: [[u8; 32]; LAB_HISTORY_DEPTH],
+}
+
+/// Index of a split patient record, seeded by the `patient_data` PDA address it is keyed by.
+///
+/// Points at the per-section accounts replacing the monolithic `PatientData` account; a section
+/// that has not been stored yet is `Pubkey::default()`.
+#[account]
+#[derive(InitSpace)]
+pub struct RecordIndex {
+    /// `patient_data` PDA address of the patient, used as the record's identity
+    pub patient_data: Pubkey,
+    /// `DemographicsAccount` of the record
+    pub demographics: Pubkey,
+    /// `HealthcareAccount` of the record
+    pub healthcare: Pubkey,
+    /// `GenomicAccount` of the record
+    pub genomics: Pubkey,
+    /// `LabAccount` of the record
+    pub lab_tests: Pubkey,
+    /// `SECTION_*` bits stored so far
+    pub sections: u8,
+    /// Unix timestamp of the latest section write
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+/// Basic demographics and allergies section of a split record, seeded by its `RecordIndex`.
+///
+/// The ciphertexts come first so that they sit right after the discriminator.
+#[account]
+#[derive(InitSpace)]
+pub struct DemographicsAccount {
+    /// Encrypted fields, laid out as `patient_id` through `allergies` of `PatientData`
+    pub ciphertexts: [[u8; 32]; DEMOGRAPHICS_FIELDS],
+    pub bump: u8,
+}
+
+/// Advanced healthcare (history, medications, procedures, family history) section of a split
+/// record, seeded by its `RecordIndex`.
+///
+/// The ciphertexts come first so that they sit right after the discriminator.
+#[account]
+#[derive(InitSpace)]
+pub struct HealthcareAccount {
+    /// Encrypted fields, laid out as `medical_history` through `family_history` of `PatientData`
+    pub ciphertexts: [[u8; 32]; HEALTHCARE_FIELDS],
+    pub bump: u8,
+}
+
+/// Genomic analysis section of a split record, seeded by its `RecordIndex`.
+///
+/// The ciphertexts come first so that they sit right after the discriminator.
+#[account]
+#[derive(InitSpace)]
+pub struct GenomicAccount {
+    /// Encrypted fields, laid out as `variant_count` through `ancestry_components` of `PatientData`
+    pub ciphertexts: [[u8; 32]; GENOMIC_FIELDS],
+    pub bump: u8,
+}
+
+/// Lab test and imaging section of a split record, seeded by its `RecordIndex`.
+///
+/// The ciphertexts come first so that they sit right after the discriminator.
+#[account]
+#[derive(InitSpace)]
+pub struct LabAccount {
+    /// Encrypted fields, laid out as `lab_test_count` through `imaging_dates` of `PatientData`
+    pub ciphertexts: [[u8; 32]; LAB_FIELDS],
+    pub bump: u8,
+}
+
+/// Immutable record of a single disclosure, seeded by (patient, computation_offset).
+#[account]
+#[derive(InitSpace)]
+pub struct AccessLogEntry {
+    /// Patient record that was disclosed
+    pub patient_data: Pubkey,
+    /// Public key the data was re-encrypted for
+    pub receiver: [u8; 32],
+    /// Signer that requested the disclosure
+    pub accessor: Pubkey,
+    /// Role the accessor acted under (`ROLE_*`)
+    pub role: u8,
+    /// Purpose of use (`PURPOSE_*`)
+    pub purpose: u8,
+    /// Offset of the computation that performed the disclosure
+    pub computation_offset: u64,
+    /// Unix timestamp of the disclosure
+    pub timestamp: i64,
+    pub bump: u8,
+    /// Whether the disclosed record was pseudonymized for research
+    pub de_identified: bool,
+}
+
+/// Patient annotation on a record entry, seeded by (patient, entry_id).
+///
+/// The encryption key, nonce and ciphertexts come first so that the ciphertexts sit at the
+/// fixed `ENTRY_FLAG_CIPHERTEXTS_OFFSET` used by `share_flagged_entry`.
+#[account]
+#[derive(InitSpace)]
+pub struct EntryFlag {
+    /// Patient record the flagged entry belongs to
+    pub patient_data: Pubkey,
+    /// x25519 public key the annotation was encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the annotation was encrypted with
+    pub nonce: u128,
+    /// Encrypted annotation fields
+    pub ciphertexts: [[u8; 32]; ANNOTATION_FIELDS],
+    /// Id of the flagged entry
+    pub entry_id: u64,
+    /// Whether the patient disputes the entry
+    pub disputed: bool,
+    /// Unix timestamp of the latest flag
+    pub flagged_at: i64,
+    pub bump: u8,
+}
+
+/// Honeytoken monitoring state of a patient record, seeded by the patient record.
+#[account]
+#[derive(InitSpace)]
+pub struct CanaryStatus {
+    /// Patient record the canary belongs to
+    pub patient_data: Pubkey,
+    /// Monitoring service allowed to report hits
+    pub monitor: Pubkey,
+    /// Number of reported hits
+    pub hit_count: u64,
+    /// Unix timestamp of the latest hit
+    pub last_hit_at: i64,
+    /// Evidence hash of the latest hit
+    pub last_evidence: [u8; 32],
+    pub bump: u8,
+}
+
+/// Per-category role restrictions set by a patient, seeded by the patient record.
+#[account]
+#[derive(InitSpace)]
+pub struct PatientPolicy {
+    /// Patient record the policy applies to
+    pub patient_data: Pubkey,
+    /// For each `CATEGORY_*`, the bit set of roles allowed to receive it
+    pub allowed_roles: [u16; DATA_CATEGORIES],
+    pub bump: u8,
+}
+
+/// Record sections the patient has locked with `lock_section`, seeded by the patient record.
+#[account]
+#[derive(InitSpace)]
+pub struct SectionLocks {
+    /// Patient record the locks apply to
+    pub patient_data: Pubkey,
+    /// Bit set of locked `SECTION_*`
+    pub locked: u8,
+    pub bump: u8,
+}
+
+/// Settings of a credentialed role, seeded by the role id.
+#[account]
+#[derive(InitSpace)]
+pub struct RoleConfig {
+    /// Role id, as recorded in access logs and patient policies
+    pub role: u8,
+    /// Verified Metaplex collection the role's credential NFTs must belong to;
+    /// `Pubkey::default()` leaves it unchecked
+    pub collection: Pubkey,
+    /// Single credential mint accepted for the role; `Pubkey::default()` accepts any
+    pub credential_mint: Pubkey,
+    /// `SECTION_*` bits of the record the role receives from role-gated shares
+    pub section_mask: u8,
+    pub bump: u8,
+}
+
+/// Program-wide settings, seeded by `program_config`.
+#[account]
+#[derive(InitSpace)]
+pub struct ProgramConfig {
+    /// Key allowed to change the config
+    pub authority: Pubkey,
+    /// Whether the public-good subsidy mode is on
+    pub subsidy_enabled: bool,
+    /// Mint of the financial hardship attestation credential
+    pub hardship_credential_mint: Pubkey,
+    /// Maximum lamports a single wallet can ever claim from the subsidy pool
+    pub subsidy_cap_per_wallet: u64,
+    /// Whether role-gated shares only accept non-transferable Token-2022 credentials
+    pub require_soulbound_credentials: bool,
+    /// Share of subscription payments taken as protocol fee, in basis points
+    pub protocol_fee_bps: u16,
+    /// Wallet receiving protocol fees
+    pub fee_destination: Pubkey,
+    /// Whether shares are currently rejected
+    pub paused: bool,
+    /// Maximum entries that can be appended to a record; 0 is unlimited
+    pub max_records_per_patient: u64,
+    /// Role-gated shares allowed per (patient, receiver) pair and window; 0 disables the limit
+    pub share_rate_limit: u32,
+    /// Length of a share rate limit window in seconds
+    pub share_rate_window: i64,
+    /// How long shared records are kept for receivers
+    pub retention_policy: RetentionPolicy,
+    /// Cohort members that must match an `aggregate_cohort` query to release its statistics
+    pub min_cohort_size: u8,
+    pub bump: u8,
+}
+
+/// Retention of shared records, applied by `expire_shared_record`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct RetentionPolicy {
+    /// Seconds a shared record without its own `retain_until` is kept; 0 keeps it until
+    /// the receiver closes it
+    pub default_retention: i64,
+}
+
+impl ProgramConfig {
+    /// Protocol fee taken from a payment of `amount`.
+    pub fn protocol_fee(&self, amount: u64) -> u64 {
+        (amount as u128 * self.protocol_fee_bps as u128 / MAX_BPS as u128) as u64
+    }
+}
+
+/// M-of-N governance holding the config authority, seeded by `governance`.
+#[account]
+#[derive(InitSpace)]
+pub struct GovernanceAccount {
+    /// Keys allowed to propose and approve admin actions
+    #[max_len(MAX_GOVERNANCE_MEMBERS)]
+    pub members: Vec<Pubkey>,
+    /// Approvals needed to execute a proposal
+    pub threshold: u8,
+    /// Number of proposals ever made, used as the next proposal id
+    pub proposal_count: u64,
+    pub bump: u8,
+}
+
+/// An admin action awaiting governance approval, seeded by its id.
+#[account]
+#[derive(InitSpace)]
+pub struct AdminProposal {
+    pub proposal_id: u64,
+    /// Member who proposed the action
+    pub proposer: Pubkey,
+    /// Action applied on execution
+    pub action: AdminAction,
+    /// Members who approved the action, including the proposer
+    #[max_len(MAX_GOVERNANCE_MEMBERS)]
+    pub approvals: Vec<Pubkey>,
+    /// Whether the action has been applied
+    pub executed: bool,
+    /// Unix timestamp of the proposal
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// Config authority action applied by governance through `execute_action`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum AdminAction {
+    /// Same as `update_config`
+    UpdateConfig {
+        protocol_fee_bps: u16,
+        fee_destination: Pubkey,
+        paused: bool,
+        max_records_per_patient: u64,
+    },
+    /// Same as `pause` / `unpause`
+    SetPaused { paused: bool },
+    /// Same as `set_role_config`
+    SetRoleConfig {
+        role: u8,
+        collection: Pubkey,
+        credential_mint: Pubkey,
+        section_mask: u8,
+    },
+    /// Same as `set_registrar`, for an existing provider registry
+    SetRegistrar { registrar: Pubkey },
+    /// Same as `set_share_rate_limit`
+    SetShareRateLimit {
+        share_rate_limit: u32,
+        share_rate_window: i64,
+    },
+}
+
+impl AdminAction {
+    /// Role whose `RoleConfig` the action writes; 0 for actions not touching roles.
+    pub fn role(&self) -> u8 {
+        match self {
+            AdminAction::SetRoleConfig { role, .. } => *role,
+            _ => 0,
+        }
+    }
+}
+
+/// Lamports set aside to cover costs of patients in financial hardship.
+#[account]
+#[derive(InitSpace)]
+pub struct SubsidyPool {
+    /// Lamports ever added with `fund_subsidy_pool`
+    pub total_funded: u64,
+    /// Lamports ever paid out with `claim_subsidy`
+    pub total_disbursed: u64,
+    pub bump: u8,
+}
+
+/// Subsidies claimed by one wallet, seeded by the wallet.
+#[account]
+#[derive(InitSpace)]
+pub struct SubsidyUsage {
+    /// Wallet the subsidies were paid to
+    pub wallet: Pubkey,
+    /// Lamports claimed so far
+    pub claimed: u64,
+    pub bump: u8,
+}
+
+/// Capabilities a receiver publishes for clients to check before sharing, seeded by the
+/// receiver's wallet.
+#[account]
+#[derive(InitSpace)]
+pub struct ReceiverProfile {
+    /// Wallet that publishes and may close the profile
+    pub authority: Pubkey,
+    /// x25519 public key shares should be encrypted for
+    pub arcis_key: [u8; 32],
+    /// Computation definition offsets whose outputs the receiver can consume
+    #[max_len(MAX_PROFILE_CIRCUITS)]
+    pub supported_circuits: Vec<u32>,
+    /// Largest number of ciphertexts accepted in one share
+    pub max_payload_fields: u16,
+    /// Bit set of `CATEGORY_*` the receiver wants to receive
+    pub preferred_categories: u8,
+    /// Hash of the receiver's data retention policy
+    pub retention_policy_hash: [u8; 32],
+    /// Unix timestamp of the latest update
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl ReceiverProfile {
+    /// Whether the receiver accepts the output of the circuit at `comp_def_offset`.
+    pub fn supports(&self, comp_def_offset: u32) -> bool {
+        self.supported_circuits.contains(&comp_def_offset)
+    }
 }
 
-#[queue_computation_accounts("share_patient_data", payer)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct SharePatientDataWithRole<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = payer,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, SignerAccount>,
-    #[account(
-        address = derive_mxe_pda!()
-    )]
-    pub mxe_account: Account<'info, MXEAccount>,
-    #[account(
-        mut,
-        address = derive_mempool_pda!()
-    )]
-    /// CHECK: mempool_account, checked by the arcium program.
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(
-        mut,
-        address = derive_execpool_pda!()
-    )]
-    /// CHECK: executing_pool, checked by the arcium program.
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(
-        mut,
-        address = derive_comp_pda!(computation_offset)
-    )]
-    /// CHECK: computation_account, checked by the arcium program.
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(
-        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SHARE_PATIENT_DATA)
-    )]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(
-        mut,
-        address = derive_cluster_pda!(mxe_account)
-    )]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(
-        mut,
-        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
-    )]
-    pub pool_account: Account<'info, FeePool>,
-    #[account(
-        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
-    )]
-    pub clock_account: Account<'info, ClockAccount>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub patient_data: AccountLoader<'info, PatientData>,
+/// A patient's consent to be included in aggregate research cohorts, seeded by the patient
+/// record.
+#[account]
+#[derive(InitSpace)]
+pub struct ResearchConsent {
+    /// Patient record the consent applies to
+    pub patient_data: Pubkey,
+    /// Whether the record may currently be used for research
+    pub opted_in: bool,
+    /// `RESEARCH_SCOPE_*` bits the consent covers
+    pub scopes: u8,
+    /// Unix timestamp the consent lapses at
+    pub expires_at: i64,
+    /// x25519 public key the stored record is encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the stored record is encrypted with
+    pub encryption_nonce: u128,
+    pub bump: u8,
+}
 
-    // Credential NFT accounts
-    pub credential_mint: Account<'info, anchor_spl::token::Mint>,
-    #[account(
-        constraint = credential_token_account.owner == payer.key() @ ErrorCode::Unauthorized,
-        constraint = credential_token_account.mint == credential_mint.key() @ ErrorCode::Unauthorized,
-    )]
-    pub credential_token_account: Account<'info, anchor_spl::token::TokenAccount>,
-    pub token_program: Program<'info, anchor_spl::token::Token>,
+impl ResearchConsent {
+    /// Fails with `ResearchConsentMissing` unless the consent is opted in, unexpired and
+    /// covers every bit of `scope`.
+    pub fn check_active(&self, scope: u8) -> Result<()> {
+        require!(
+            self.opted_in
+                && self.scopes & scope == scope
+                && Clock::get()?.unix_timestamp < self.expires_at,
+            ErrorCode::ResearchConsentMissing
+        );
+        Ok(())
+    }
 }
 
-// SharePatientDataCallback accounts removed
+/// Extension programs approved to be called on share hooks, seeded by `extension_registry`.
+#[account]
+#[derive(InitSpace)]
+pub struct ExtensionRegistry {
+    /// Registered extensions, called in this order
+    #[max_len(MAX_EXTENSIONS)]
+    pub extensions: Vec<ExtensionEntry>,
+    pub bump: u8,
+}
 
-#[init_computation_definition_accounts("share_patient_data", payer)]
-#[derive(Accounts)]
-pub struct InitSharePatientDataCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(
-        mut,
-        address = derive_mxe_pda!()
-    )]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account, checked by arcium program.
-    /// Can't check it here as it's not initialized yet.
-    pub comp_def_account: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+/// Programs approved to invoke `share_patient_data` via CPI, seeded by `cpi_allowlist`.
+#[account]
+#[derive(InitSpace)]
+pub struct CpiAllowlist {
+    #[max_len(MAX_CPI_CALLERS)]
+    pub programs: Vec<Pubkey>,
+    pub bump: u8,
 }
 
-/// Basic patient demographics data event
-#[event]
-pub struct ReceivedBasicPatientDataEvent {
-    pub nonce: [u8; 16],
-    pub patient_id: [u8; 32],
-    pub age: [u8; 32],
-    pub gender: [u8; 32],
-    pub blood_type: [u8; 32],
-    pub weight: [u8; 32],
-    pub height: [u8; 32],
-    pub allergies: [[u8; 32]; 5],
+/// Offsets of the Arcium clusters computations may be pinned to, seeded by
+/// `approved_clusters`.
+#[account]
+#[derive(InitSpace)]
+pub struct ApprovedClusters {
+    #[max_len(MAX_APPROVED_CLUSTERS)]
+    pub clusters: Vec<u32>,
+    pub bump: u8,
 }
 
-/// Advanced healthcare data event (medical history, medications, procedures, family history)
-#[event]
-pub struct ReceivedHealthcareDataEvent {
-    pub nonce: [u8; 16],
-    pub medical_history: [[u8; 32]; 10],
-    pub medication_count: [u8; 32],
-    pub medications: [[u8; 32]; 8],
-    pub procedure_count: [u8; 32],
-    pub procedure_dates: [[u8; 32]; 8],
-    pub family_history: [[u8; 32]; 5],
+/// Active circuit version of each upgraded instruction, seeded by `comp_def_registry`.
+#[account]
+#[derive(InitSpace)]
+pub struct CompDefRegistry {
+    #[max_len(MAX_VERSIONED_CIRCUITS)]
+    pub circuits: Vec<VersionedCircuit>,
+    pub bump: u8,
 }
 
-/// Genomic analysis data event (genetic variants, markers, carrier status, ancestry)
-#[event]
-pub struct ReceivedGenomicDataEvent {
-    pub nonce: [u8; 16],
-    pub variant_count: [u8; 32],
-    pub genetic_markers: [[u8; 32]; 15],
-    pub variant_significance: [[u8; 32]; 15],
-    pub carrier_status: [[u8; 32]; 5],
-    pub pharmacogenomic_markers: [[u8; 32]; 3],
-    pub ancestry_components: [[u8; 32]; 7],
+/// Versions of the circuit behind one instruction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct VersionedCircuit {
+    /// Instruction name, e.g. `share_patient_data`
+    #[max_len(MAX_CIRCUIT_NAME_LEN)]
+    pub name: String,
+    /// Active version; 1 is the built-in circuit
+    pub version: u32,
+    /// Computation definition offset of the active version
+    pub comp_def_offset: u32,
+    /// Version staged by `upgrade_comp_def`; 0 if none
+    pub pending_version: u32,
+    /// Computation definition offset of the staged version
+    pub pending_comp_def_offset: u32,
 }
 
-/// Lab test results and imaging data event
-#[event]
-pub struct ReceivedLabTestDataEvent {
-    pub nonce: [u8; 16],
-    pub lab_test_count: [u8; 32],
-    pub lab_test_types: [[u8; 32]; 10],
-    pub lab_test_dates: [[u8; 32]; 10],
-    pub lab_test_values: [[u8; 32]; 10],
-    pub lab_test_flags: [[u8; 32]; 10],
-    pub imaging_count: [u8; 32],
-    pub imaging_types: [[u8; 32]; 10],
-    pub imaging_dates: [[u8; 32]; 10],
+/// A registered extension program.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ExtensionEntry {
+    /// Extension program
+    pub program_id: Pubkey,
+    /// Bit set of `HOOK_*` the extension is called on
+    pub hooks: u8,
+    /// Number of extension accounts passed to each hook
+    pub extra_accounts: u8,
 }
 
-/// Stores encrypted patient medical information including advanced healthcare,
-/// genomic analysis, and lab test results.
-#[account(zero_copy)]
-#[repr(C)]
-pub struct PatientData {
-    // Basic demographics
-    /// Encrypted unique patient identifier
-    pub patient_id: [u8; 32],
-    /// Encrypted patient age
-    pub age: [u8; 32],
-    /// Encrypted gender information
-    pub gender: [u8; 32],
-    /// Encrypted blood type
-    pub blood_type: [u8; 32],
-    /// Encrypted weight measurement
-    pub weight: [u8; 32],
-    /// Encrypted height measurement
-    pub height: [u8; 32],
-    /// Array of encrypted allergy information (up to 5 allergies)
-    pub allergies: [[u8; 32]; 5],
-    // Advanced healthcare
-    /// Encrypted medical history flags (diabetes, hypertension, heart_disease, cancer, stroke, asthma, copd, arthritis, osteoporosis, depression)
-    pub medical_history: [[u8; 32]; 10],
-    /// Encrypted medication count
-    pub medication_count: [u8; 32],
-    /// Array of encrypted medication IDs (up to 8 medications)
-    pub medications: [[u8; 32]; 8],
-    /// Encrypted procedure count
-    pub procedure_count: [u8; 32],
-    /// Array of encrypted procedure dates (days since epoch, up to 8 procedures)
-    pub procedure_dates: [[u8; 32]; 8],
-    /// Encrypted family history flags (diabetes, heart_disease, cancer, stroke, hypertension)
-    pub family_history: [[u8; 32]; 5],
-    // Genomic analysis
-    /// Encrypted genetic variant count
-    pub variant_count: [u8; 32],
-    /// Array of encrypted genetic marker identifiers (up to 15 variants)
-    pub genetic_markers: [[u8; 32]; 15],
-    /// Array of encrypted variant significance scores (up to 15)
-    pub variant_significance: [[u8; 32]; 15],
-    /// Encrypted carrier status flags (cystic_fibrosis, sickle_cell, tay_sachs, hemophilia, huntington)
-    pub carrier_status: [[u8; 32]; 5],
-    /// Encrypted pharmacogenomic markers (warfarin_sensitivity, clopidogrel_resistance, statin_response)
-    pub pharmacogenomic_markers: [[u8; 32]; 3],
-    /// Array of encrypted ancestry component percentages (up to 7 populations)
-    pub ancestry_components: [[u8; 32]; 7],
-    // Lab test results
-    /// Encrypted lab test count
-    pub lab_test_count: [u8; 32],
-    /// Array of encrypted lab test type identifiers (up to 10 tests)
-    pub lab_test_types: [[u8; 32]; 10],
-    /// Array of encrypted lab test dates (days since epoch, up to 10 tests)
-    pub lab_test_dates: [[u8; 32]; 10],
-    /// Array of encrypted lab test values (normalized, up to 10 tests)
-    pub lab_test_values: [[u8; 32]; 10],
-    /// Array of encrypted lab test normal range flags (0=low, 1=normal, 2=high, up to 10 tests)
-    pub lab_test_flags: [[u8; 32]; 10],
-    /// Encrypted imaging count
-    pub imaging_count: [u8; 32],
-    /// Array of encrypted imaging type identifiers (up to 10 imaging studies)
-    pub imaging_types: [[u8; 32]; 10],
-    /// Array of encrypted imaging dates (days since epoch, up to 10)
-    pub imaging_dates: [[u8; 32]; 10],
+/// Arguments passed to extension share hooks.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ShareHookArgs {
+    pub patient_data: Pubkey,
+    pub receiver: [u8; 32],
+    pub accessor: Pubkey,
+    pub purpose: u8,
+    pub computation_offset: u64,
+}
+
+/// Differential privacy loss a patient record has contributed to research queries, seeded
+/// by the patient record.
+#[account]
+#[derive(InitSpace)]
+pub struct PrivacyBudget {
+    /// Patient record the budget applies to
+    pub patient_data: Pubkey,
+    /// Privacy loss spent so far, epsilon * 100
+    pub consumed: u64,
+    /// Number of differentially private queries the record took part in
+    pub query_count: u64,
+    pub bump: u8,
+}
+
+/// Lamports a researcher pays the patients of an aggregated cohort, seeded by
+/// (researcher, computation_offset).
+#[account]
+#[derive(InitSpace)]
+pub struct RoyaltyPool {
+    /// Researcher who funded the pool
+    pub researcher: Pubkey,
+    /// Offset of the aggregation the pool pays for
+    pub computation_offset: u64,
+    /// Lamports to share between the members
+    pub amount: u64,
+    /// Patient records in the aggregated cohort, empty until the aggregation is queued
+    #[max_len(COHORT_SIZE)]
+    pub members: Vec<Pubkey>,
+    /// Whether the aggregation completed
+    pub completed: bool,
+    pub bump: u8,
+}
+
+/// Research royalties credited to a patient record, seeded by the patient record. The
+/// claimable lamports are held by the account itself.
+#[account]
+#[derive(InitSpace)]
+pub struct RoyaltyBalance {
+    /// Patient record the royalties are owed to
+    pub patient_data: Pubkey,
+    /// Lamports that can be claimed with `claim_royalty`
+    pub claimable: u64,
+    /// Lamports ever credited
+    pub total_earned: u64,
+    pub bump: u8,
+}
+
+/// Anonymized-cohort query an organization sells to researchers, seeded by
+/// (organization, listing_id).
+#[account]
+#[derive(InitSpace)]
+pub struct DatasetListing {
+    /// Organization selling the query
+    pub organization: Pubkey,
+    /// Organization-chosen identifier of the listing
+    pub listing_id: u64,
+    /// `cohort_criteria_hash` of the query being sold
+    pub criteria_hash: [u8; 32],
+    /// Lamports per query, paid to the contributing patients
+    pub price: u64,
+    /// Number of queries that can be sold
+    pub max_queries: u32,
+    /// Number of queries sold so far
+    pub queries_sold: u32,
+    /// Whether queries can currently be bought
+    pub active: bool,
+    pub bump: u8,
+}
+
+/// A researcher's paid right to run one listed query, seeded by
+/// (researcher, computation_offset).
+#[account]
+#[derive(InitSpace)]
+pub struct QueryPurchase {
+    /// Listing the query was bought from
+    pub listing: Pubkey,
+    /// Researcher who bought the query
+    pub researcher: Pubkey,
+    /// Offset the aggregation is queued at
+    pub computation_offset: u64,
+    /// Criteria the aggregation must use, copied from the listing
+    pub criteria_hash: [u8; 32],
+    /// Whether the aggregation has been queued
+    pub used: bool,
+    pub bump: u8,
+}
+
+/// A telemedicine visit between a patient and a provider, seeded by (patient, session_id).
+#[account]
+#[derive(InitSpace)]
+pub struct TeleSession {
+    /// Patient record the visit documents
+    pub patient_data: Pubkey,
+    /// Provider holding the visit
+    pub provider: Pubkey,
+    /// Patient-chosen identifier of the session
+    pub session_id: u64,
+    /// Unix timestamp the visit is scheduled for
+    pub scheduled_at: i64,
+    /// Unix timestamp the session was closed, 0 while open
+    pub closed_at: i64,
+    /// `SESSION_OPEN` or `SESSION_CLOSED`
+    pub status: u8,
+    /// Ids of the visit note entries written during the session
+    #[max_len(MAX_SESSION_NOTES)]
+    pub note_entry_ids: Vec<u64>,
+    pub bump: u8,
+}
+
+/// A visit booked between a patient and a provider, seeded by (patient, appointment_id).
+#[account]
+#[derive(InitSpace)]
+pub struct Appointment {
+    /// Patient record the visit is for
+    pub patient_data: Pubkey,
+    /// Provider holding the visit, whose `VisitGrant` activates once they confirm
+    pub provider: Pubkey,
+    /// Patient-chosen identifier of the appointment
+    pub appointment_id: u64,
+    /// Unix timestamp the visit window opens
+    pub starts_at: i64,
+    /// Unix timestamp the visit window closes, when the visit grant expires
+    pub ends_at: i64,
+    /// `APPOINTMENT_BOOKED`, `APPOINTMENT_CONFIRMED` or `APPOINTMENT_CANCELLED`
+    pub status: u8,
+    pub bump: u8,
+}
+
+/// Visit-scoped consent of an appointment's provider, seeded by the appointment.
+///
+/// Kept apart from `DelegationAccount` so that confirming a visit never grants, or
+/// overwrites, share authority over the record.
+#[account]
+#[derive(InitSpace)]
+pub struct VisitGrant {
+    /// Patient record the grant applies to
+    pub patient_data: Pubkey,
+    /// Appointment the grant belongs to
+    pub appointment: Pubkey,
+    /// Provider the grant is issued to
+    pub provider: Pubkey,
+    /// Wallet that booked the appointment, refunded when the grant is closed
+    pub rent_payer: Pubkey,
+    /// Unix timestamp after which the grant is no longer valid
+    pub expires_at: i64,
+    /// Whether the provider has confirmed the appointment
+    pub active: bool,
+    pub bump: u8,
+}
+
+/// Payment locked for a telemedicine session, seeded by the session. Released to the
+/// provider and the protocol fee destination by `settle_session`.
+#[account]
+#[derive(InitSpace)]
+pub struct SessionEscrow {
+    /// Session paid for
+    pub tele_session: Pubkey,
+    /// Wallet that paid and receives the rent back on settlement
+    pub payer: Pubkey,
+    /// Mint of the payment token
+    pub mint: Pubkey,
+    /// Token account holding the payment, owned by this escrow
+    pub vault: Pubkey,
+    /// Tokens paid for the session
+    pub amount: u64,
+    pub bump: u8,
+}
+
+/// Registry of verified provider credentials, seeded by `provider_registry`.
+#[account]
+#[derive(InitSpace)]
+pub struct ProviderRegistry {
+    /// Key allowed to register and deactivate providers
+    pub registrar: Pubkey,
+    /// Number of credential mints ever registered
+    pub provider_count: u64,
+    pub bump: u8,
+}
+
+/// A verified provider credential, seeded by its mint.
+#[account]
+#[derive(InitSpace)]
+pub struct ProviderRecord {
+    /// Credential NFT mint
+    pub mint: Pubkey,
+    /// Role the credential grants
+    pub role: u8,
+    /// Hash of the provider's license document
+    pub license_hash: [u8; 32],
+    /// Whether the credential is currently accepted
+    pub active: bool,
+    /// Unix timestamp of the latest registration
+    pub registered_at: i64,
+    pub bump: u8,
+}
+
+/// A laboratory signing key registered by the registrar, seeded by the key.
+#[account]
+#[derive(InitSpace)]
+pub struct LabSigner {
+    /// Ed25519 key the laboratory signs lab results with
+    pub lab: Pubkey,
+    /// Whether the key may currently attest lab results
+    pub active: bool,
+    /// Unix timestamp of the latest registration
+    pub registered_at: i64,
+    pub bump: u8,
+}
+
+/// A laboratory's signature over a record's lab section, seeded by (patient_data, lab).
+#[account]
+#[derive(InitSpace)]
+pub struct LabAttestation {
+    pub patient_data: Pubkey,
+    /// Registered laboratory signing key
+    pub lab: Pubkey,
+    /// Slot at which the attestation was recorded
+    pub slot: u64,
+    /// SHA-256 of the lab section ciphertexts the lab signed
+    pub digest: [u8; 32],
+    pub bump: u8,
+}
+
+/// A credentialed provider's attestation of one record section, seeded by (patient_data,
+/// provider, section).
+#[account]
+#[derive(InitSpace)]
+pub struct Attestation {
+    pub patient_data: Pubkey,
+    /// Wallet of the attesting provider
+    pub provider: Pubkey,
+    /// Credential NFT mint the provider attested under
+    pub credential_mint: Pubkey,
+    /// Role of the provider's registered credential
+    pub role: u8,
+    /// The attested `SECTION_*` bit
+    pub section: u8,
+    /// Provider-supplied hash of the section contents
+    pub digest: [u8; 32],
+    /// Unix timestamp of the latest attestation
+    pub attested_at: i64,
+    pub bump: u8,
+}
+
+/// An insurance claim submitted by a provider, seeded by (patient, provider, claim_id). The
+/// claim fields stay encrypted for the MPC network; the insurer only learns the encrypted
+/// consistency score.
+#[account]
+#[derive(InitSpace)]
+pub struct Claim {
+    pub patient_data: Pubkey,
+    /// Wallet of the provider who submitted the claim
+    pub provider: Pubkey,
+    /// Wallet of the insurer adjudicating the claim
+    pub insurer: Pubkey,
+    /// Provider-chosen identifier of the claim
+    pub claim_id: u64,
+    /// x25519 public key the claim fields are encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the claim fields are encrypted with
+    pub nonce: u128,
+    /// Encrypted CPT codes of the billed lines
+    pub cpt_codes: [[u8; 32]; CLAIM_LINES],
+    /// Encrypted billed amount in cents
+    pub amount: [u8; 32],
+    /// Encrypted ICD-10 code of the primary diagnosis
+    pub diagnosis: [u8; 32],
+    /// `CLAIM_STATUS_*` of the claim
+    pub status: u8,
+    /// Nonce of the consistency score, once computed
+    pub consistency_nonce: u128,
+    /// Consistency score encrypted for the insurer, once computed
+    pub consistency_score: [u8; 32],
+    /// Unix timestamp of the submission
+    pub submitted_at: i64,
+    /// Unix timestamp of the adjudication, 0 until adjudicated
+    pub adjudicated_at: i64,
+    pub bump: u8,
+}
+
+/// Pre-authorization rules of an insurer evaluated by `check_coverage`, seeded by
+/// (insurer, policy_id).
+#[account]
+#[derive(InitSpace)]
+pub struct CoveragePolicy {
+    /// Wallet of the insurer publishing the policy
+    pub insurer: Pubkey,
+    /// Insurer-chosen identifier of the policy
+    pub policy_id: u64,
+    /// x25519 public key decisions are encrypted for
+    pub insurer_key: [u8; 32],
+    /// Medical history flag the patient must have, or `ANY_CONDITION`
+    pub required_condition: u8,
+    /// Medical history flags denying coverage, one bit each
+    pub excluded_conditions: u16,
+    /// Medical history flags sending the request to review, one bit each
+    pub review_conditions: u16,
+    /// Recorded procedures above which the request goes to review
+    pub max_procedures: u8,
+    pub bump: u8,
+}
+
+/// A patient's request for independent opinions from several specialists, seeded by
+/// (patient record, request_id).
+#[account]
+#[derive(InitSpace)]
+pub struct SecondOpinion {
+    /// Patient record the opinions are about
+    pub patient_data: Pubkey,
+    /// Patient-chosen identifier of the request
+    pub request_id: u64,
+    /// Wallets of the specialists asked
+    #[max_len(MAX_OPINION_SPECIALISTS)]
+    pub specialists: Vec<Pubkey>,
+    /// Number of opinions filed so far
+    pub opinions: u8,
+    pub bump: u8,
+}
+
+/// A specialist's encrypted verdict on a second-opinion request, seeded by (request,
+/// specialist).
+#[account]
+#[derive(InitSpace)]
+pub struct Opinion {
+    /// Request the opinion answers
+    pub second_opinion: Pubkey,
+    /// Wallet of the specialist
+    pub specialist: Pubkey,
+    /// x25519 public key the verdict is encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the verdict is encrypted with
+    pub nonce: u128,
+    /// Encrypted verdict code
+    pub verdict: [u8; 32],
+    /// Unix timestamp the opinion was filed
+    pub submitted_at: i64,
+    pub bump: u8,
+}
+
+/// Providers jointly caring for a patient, seeded by (patient record, team_id). Records
+/// shared to the team are released to a member only with `threshold` member approvals.
+#[account]
+#[derive(InitSpace)]
+pub struct CareTeam {
+    /// Patient record the team cares for
+    pub patient_data: Pubkey,
+    /// Patient-chosen identifier of the team
+    pub team_id: u64,
+    /// Wallets of the team members
+    #[max_len(MAX_CARE_TEAM_MEMBERS)]
+    pub members: Vec<Pubkey>,
+    /// Member approvals needed to release a record
+    pub threshold: u8,
+    pub bump: u8,
+}
+
+/// A member's pending access to a care team's record, seeded by (team record, member).
+/// Closed when the record is released.
+#[account]
+#[derive(InitSpace)]
+pub struct TeamAccessRequest {
+    /// Care team the member belongs to
+    pub care_team: Pubkey,
+    /// Team record requested
+    pub team_record: Pubkey,
+    /// Wallet of the requesting member
+    pub member: Pubkey,
+    /// Members that approved, one bit per index in `CareTeam::members`
+    pub approvals: u8,
+    pub bump: u8,
+}
+
+/// A hospital or clinic whose member providers act under access granted to it, seeded by
+/// (authority, org_id).
+#[account]
+#[derive(InitSpace)]
+pub struct Organization {
+    /// Key managing the organization and its members
+    pub authority: Pubkey,
+    /// Authority-chosen identifier of the organization
+    pub org_id: u64,
+    /// Organization-level credential NFT mint issued to staff
+    pub credential_mint: Pubkey,
+    /// Wallets of the member providers
+    #[max_len(MAX_ORGANIZATION_MEMBERS)]
+    pub members: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+/// Validity of a credential NFT, seeded by its mint. Role-gated shares reject credentials
+/// that are revoked or past `expires_at`.
+#[account]
+#[derive(InitSpace)]
+pub struct CredentialRecord {
+    /// Credential NFT mint
+    pub mint: Pubkey,
+    /// Unix timestamp at which the underlying license lapses
+    pub expires_at: i64,
+    /// Whether the registrar revoked the credential
+    pub revoked: bool,
+    /// Unix timestamp of the latest issuance or renewal
+    pub issued_at: i64,
+    pub bump: u8,
+}
+
+/// Receiver keys a patient allows shares to, seeded by the patient record. While it exists,
+/// shares to any other key are denied.
+#[account]
+#[derive(InitSpace)]
+pub struct ReceiverAllowlist {
+    /// Patient record the allowlist applies to
+    pub patient_data: Pubkey,
+    /// Allowed x25519 receiver public keys
+    #[max_len(MAX_ALLOWLISTED_RECEIVERS)]
+    pub receivers: Vec<[u8; 32]>,
+    pub bump: u8,
+}
+
+/// Record fields a patient lets delegates share with one receiver through
+/// `share_selected_fields`, seeded by the patient record and the receiver key.
+#[account]
+#[derive(InitSpace)]
+pub struct FieldConsent {
+    /// Patient record the consent applies to
+    pub patient_data: Pubkey,
+    /// x25519 public key of the consented receiver
+    pub receiver: [u8; 32],
+    /// Consented fields, in the bit order of `share_selected_fields`
+    pub field_mask: u128,
+    pub bump: u8,
+}
+
+/// Data-residency rules set by the config authority, one per record jurisdiction.
+#[account]
+#[derive(InitSpace)]
+pub struct JurisdictionRules {
+    #[max_len(MAX_JURISDICTION_RULES)]
+    pub rules: Vec<JurisdictionRule>,
+    pub bump: u8,
+}
+
+/// Receiver jurisdictions records tagged with `jurisdiction` may be shared to.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct JurisdictionRule {
+    /// Record jurisdiction the rule applies to
+    pub jurisdiction: u16,
+    /// Allowed receiver jurisdictions
+    #[max_len(MAX_ALLOWED_JURISDICTIONS)]
+    pub allowed: Vec<u16>,
+}
+
+/// Jurisdiction a receiver key is registered in by the config authority, seeded by the key.
+#[account]
+#[derive(InitSpace)]
+pub struct ReceiverJurisdiction {
+    /// x25519 public key of the receiver
+    pub receiver: [u8; 32],
+    /// Jurisdiction code of the receiver
+    pub jurisdiction: u16,
+    pub bump: u8,
+}
+
+/// A pending request for a share of a patient's record, seeded by (patient record,
+/// requester).
+#[account]
+#[derive(InitSpace)]
+pub struct ShareRequest {
+    /// Patient record requested
+    pub patient_data: Pubkey,
+    /// Wallet that asked for the share; becomes the receiver authority on approval
+    pub requester: Pubkey,
+    /// x25519 public key the record is re-encrypted for
+    pub receiver: [u8; 32],
+    /// `PURPOSE_*` of the request
+    pub purpose: u8,
+    /// `SECTION_*` bits requested
+    pub sections_mask: u8,
+    /// Unix timestamp of the request
+    pub requested_at: i64,
+    pub bump: u8,
+}
+
+/// A share waiting in a receiver's inbox, seeded by (receiver, computation_offset).
+/// Receivers discover their shares by filtering these accounts on `receiver`.
+#[account]
+#[derive(InitSpace)]
+pub struct InboxEntry {
+    /// Public key the share is encrypted for
+    pub receiver: [u8; 32],
+    /// Wallet allowed to acknowledge the entry
+    pub receiver_authority: Pubkey,
+    /// Account that paid the rent and receives it back on acknowledgement
+    pub rent_payer: Pubkey,
+    /// Patient record that was shared
+    pub patient_data: Pubkey,
+    /// Offset of the share computation, also locating its `SharedRecord`
+    pub computation_offset: u64,
+    /// `SECTION_*` bits included in the share
+    pub sections: u8,
+    /// Unix timestamp at which the share was queued
+    pub queued_at: i64,
+    /// Unix timestamp at which the share callback delivered the ciphertexts; 0 while pending
+    pub delivered_at: i64,
+    pub bump: u8,
+}
+
+/// Payment locked for a paid share request, seeded by (patient record, requester). The
+/// share callback releases it to the patient; otherwise it is refunded by `cancel_request`.
+#[account]
+#[derive(InitSpace)]
+pub struct AccessEscrow {
+    /// Patient record requested
+    pub patient_data: Pubkey,
+    /// Wallet that paid and receives refunds
+    pub requester: Pubkey,
+    /// Mint of the payment token
+    pub mint: Pubkey,
+    /// Token account holding the payment, owned by this escrow
+    pub vault: Pubkey,
+    /// Tokens paid for the share
+    pub amount: u64,
+    /// Patient token account the payment is released to, set on approval
+    pub payee: Pubkey,
+    /// Offset of the approved share computation
+    pub computation_offset: u64,
+    /// Unix timestamp of the request
+    pub created_at: i64,
+    /// Unix timestamp of the approval; 0 while pending
+    pub approved_at: i64,
+    /// Whether the payment was released to the patient
+    pub released: bool,
+    pub bump: u8,
+}
+
+/// A patient's offer of recurring access to parts of their record, seeded by the patient
+/// record.
+#[account]
+#[derive(InitSpace)]
+pub struct AccessPlan {
+    /// Patient record the plan covers
+    pub patient_data: Pubkey,
+    /// Patient wallet receiving subscription payments
+    pub patient: Pubkey,
+    /// Lamports paid per period
+    pub price: u64,
+    /// Length of a period in seconds
+    pub period: i64,
+    /// `SECTION_*` bits subscribers may share
+    pub sections_mask: u8,
+    /// x25519 public key the stored record is encrypted with
+    pub encryption_key: [u8; 32],
+    /// Nonce the stored record is encrypted with
+    pub encryption_nonce: u128,
+    pub bump: u8,
+}
+
+/// A provider's or insurer's paid subscription to an access plan, seeded by (plan,
+/// subscriber).
+#[account]
+#[derive(InitSpace)]
+pub struct ActiveSubscription {
+    /// Plan subscribed to
+    pub access_plan: Pubkey,
+    /// Wallet that pays for and triggers shares
+    pub subscriber: Pubkey,
+    /// x25519 public key shares are re-encrypted for
+    pub receiver: [u8; 32],
+    /// `SECTION_*` bits covered, copied from the plan at the latest payment
+    pub sections_mask: u8,
+    /// Unix timestamp the subscription lapses at
+    pub expires_at: i64,
+    pub bump: u8,
 }
 
 #[error_code]
@@ -530,4 +25468,240 @@ pub enum ErrorCode {
     InvalidCredentialMint,
     #[msg("Unauthorized or mismatched credential account")]
     Unauthorized,
+    #[msg("Receiver has been revoked by the patient")]
+    ReceiverRevoked,
+    #[msg("Invalid emergency policy")]
+    InvalidEmergencyPolicy,
+    #[msg("Signer is not a guardian of this patient")]
+    NotAGuardian,
+    #[msg("Guardian has already approved this request")]
+    GuardianAlreadyApproved,
+    #[msg("Break-glass request is not yet executable")]
+    BreakGlassNotReady,
+    #[msg("Invalid audit index shard")]
+    InvalidAuditShard,
+    #[msg("Audit index shard belongs to a previous epoch")]
+    StaleAuditShard,
+    #[msg("Invalid address lookup table")]
+    InvalidLookupTable,
+    #[msg("Invalid delegation parameters")]
+    InvalidDelegation,
+    #[msg("Delegation has expired")]
+    DelegationExpired,
+    #[msg("Action is outside the delegated scope")]
+    DelegationScopeExceeded,
+    #[msg("Receiver nonce counter exhausted")]
+    NonceExhausted,
+    #[msg("Invalid batch share")]
+    InvalidBatch,
+    #[msg("Grant expiry index is full")]
+    GrantIndexFull,
+    #[msg("Invalid record entry")]
+    InvalidEntry,
+    #[msg("Invalid role")]
+    InvalidRole,
+    #[msg("Entry has been superseded by an amendment")]
+    EntrySuperseded,
+    #[msg("Invalid purpose of use")]
+    InvalidPurpose,
+    #[msg("Entry is flagged by the patient and must be shared with its annotation")]
+    EntryFlagged,
+    #[msg("Not every tracked grant was revoked")]
+    GrantsRemaining,
+    #[msg("Invalid clinical trial criteria")]
+    InvalidTrialCriteria,
+    #[msg("The patient's policy does not allow this role to receive the data")]
+    RoleNotAllowed,
+    #[msg("Subsidy mode is disabled")]
+    SubsidyDisabled,
+    #[msg("Subsidy cap exceeded for this wallet")]
+    SubsidyCapExceeded,
+    #[msg("Subsidy pool has insufficient funds")]
+    InsufficientSubsidyPool,
+    #[msg("Record rotation is not due yet")]
+    RotationNotDue,
+    #[msg("A record rotation is already in progress")]
+    RotationPending,
+    #[msg("Unknown attribute selector")]
+    InvalidAttributeSelector,
+    #[msg("Too many circuits in the receiver profile")]
+    TooManyCircuits,
+    #[msg("Unknown data category")]
+    InvalidCategories,
+    #[msg("A cohort needs exactly 5 distinct patient records with their consent accounts")]
+    InvalidCohort,
+    #[msg("A cohort member has not consented to research use")]
+    ConsentMissing,
+    #[msg("Invalid extension registration")]
+    InvalidExtension,
+    #[msg("A registered extension or its accounts are missing")]
+    MissingExtension,
+    #[msg("Epsilon must be at least the noise floor and within the privacy budget")]
+    InvalidEpsilon,
+    #[msg("A cohort member's privacy budget is exhausted")]
+    PrivacyBudgetExhausted,
+    #[msg("The telemedicine session is closed")]
+    SessionClosed,
+    #[msg("The telemedicine session has no room for more notes")]
+    TooManySessionNotes,
+    #[msg("The credential mint is not a registered, active provider credential")]
+    ProviderNotRegistered,
+    #[msg("The credential is registered for a different role")]
+    CredentialRoleMismatch,
+    #[msg("The signer is not a member of the organization")]
+    NotOrganizationMember,
+    #[msg("The provider is already a member of the organization")]
+    AlreadyOrganizationMember,
+    #[msg("The organization has no room for more members")]
+    TooManyOrganizationMembers,
+    #[msg("The credential has expired")]
+    CredentialExpired,
+    #[msg("The credential has been revoked")]
+    CredentialRevoked,
+    #[msg("The credential NFT is not in the verified collection for its role")]
+    UnverifiedCredentialCollection,
+    #[msg("Soulbound credentials are required but the credential mint is transferable")]
+    TransferableCredential,
+    #[msg("The receiver is not on the patient's allowlist")]
+    ReceiverNotAllowlisted,
+    #[msg("The receiver allowlist is full")]
+    AllowlistFull,
+    #[msg("At least one record section must be requested")]
+    InvalidSections,
+    #[msg("The amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("The payee token account does not match the escrow")]
+    InvalidPayee,
+    #[msg("The escrow is locked until the approved share completes or times out")]
+    EscrowLocked,
+    #[msg("The royalty pool is already attached to a cohort")]
+    RoyaltyPoolInUse,
+    #[msg("The aggregation paid for by the royalty pool has not completed")]
+    RoyaltiesNotReady,
+    #[msg("The period must be greater than zero")]
+    InvalidPeriod,
+    #[msg("The subscription has expired")]
+    SubscriptionExpired,
+    #[msg("The protocol fee cannot exceed 10000 basis points")]
+    InvalidFee,
+    #[msg("The program is paused")]
+    ProgramPaused,
+    #[msg("The record has reached the maximum number of entries")]
+    TooManyRecords,
+    #[msg("Governance needs 1 to 10 distinct members and a threshold between 1 and the member count")]
+    InvalidGovernance,
+    #[msg("The signer is not a governance member")]
+    NotGovernanceMember,
+    #[msg("The member has already approved the proposal")]
+    AlreadyApproved,
+    #[msg("The proposal has already been executed")]
+    ProposalExecuted,
+    #[msg("The proposal does not have enough approvals")]
+    ThresholdNotReached,
+    #[msg("Too many shares to this receiver in the current window")]
+    RateLimited,
+    #[msg("The input nonce has already been used for this patient and receiver")]
+    NonceReplayed,
+    #[msg("Invalid recovery policy: need 1-8 guardians, 1 <= threshold <= guardians and a positive delay")]
+    InvalidRecoveryPolicy,
+    #[msg("Not enough guardians signed the recovery")]
+    NotEnoughGuardians,
+    #[msg("A recovery is already pending")]
+    RecoveryPending,
+    #[msg("No recovery is pending")]
+    NoPendingRecovery,
+    #[msg("The recovery delay has not elapsed yet")]
+    RecoveryDelayNotElapsed,
+    #[msg("Invalid jurisdiction rule: the jurisdiction must be tagged, with at most 8 allowed receiver jurisdictions and 16 rules")]
+    InvalidJurisdictionRule,
+    #[msg("The receiver is not registered in a jurisdiction allowed for this record")]
+    JurisdictionNotAllowed,
+    #[msg("The shared record's retention window has not elapsed")]
+    RetentionNotElapsed,
+    #[msg("Vitals batches must hold their samples in order and follow the previous batch")]
+    InvalidVitalsBatch,
+    #[msg("The vitals batch is no longer retained or does not overlap the window")]
+    InvalidVitalsWindow,
+    #[msg("Glucose batches must hold their readings in order and follow the previous batch")]
+    InvalidGlucoseBatch,
+    #[msg("The glucose batches are not all retained in the ring buffer")]
+    GlucoseBatchNotRetained,
+    #[msg("The mental health grant has expired")]
+    MentalHealthGrantExpired,
+    #[msg("The patient has not come of age yet")]
+    PatientNotOfAge,
+    #[msg("Invalid imaging study: the slot must be below 10 and the content hash set")]
+    InvalidImagingStudy,
+    #[msg("Invalid attachment: unknown attachment type or missing content hash")]
+    InvalidAttachment,
+    #[msg("The lab history is full")]
+    LabHistoryFull,
+    #[msg("Merkle proof does not match the lab history root")]
+    InvalidMerkleProof,
+    #[msg("Invalid capacity expansion: section must be medications or lab tests, in whole chunks")]
+    InvalidCapacityExpansion,
+    #[msg("The record has no such expanded capacity chunk")]
+    InvalidCapacityChunk,
+    #[msg("The calling program is not approved to invoke this instruction via CPI")]
+    CpiCallerNotAllowed,
+    #[msg("The laboratory key is not registered or has been deactivated")]
+    UnregisteredLab,
+    #[msg("The preceding instruction does not carry the lab's signature over the lab section")]
+    InvalidLabSignature,
+    #[msg("The share is no longer awaiting its computation callback")]
+    ShareNotPending,
+    #[msg("The share has not been queued long enough to time out")]
+    ShareTimeoutNotReached,
+    #[msg("Only failed, timed-out or cancelled shares can be retried")]
+    ShareNotRetryable,
+    #[msg("The cluster account is not the MXE cluster or an approved cluster offset")]
+    ClusterNotApproved,
+    #[msg("The circuit version or its computation definition is invalid")]
+    InvalidCompDefVersion,
+    #[msg("This circuit version has been superseded in the computation definition registry")]
+    CompDefDeprecated,
+    #[msg("No circuit version is staged for this instruction")]
+    NoPendingCompDef,
+    #[msg("Field mask is empty or selects fields the record does not have")]
+    InvalidFieldMask,
+    #[msg("The patient has not consented to sharing these fields with this receiver")]
+    FieldsNotConsented,
+    #[msg("Cohort query condition or age range is invalid")]
+    InvalidCohortQuery,
+    #[msg("Minimum cohort size must be between 1 and the cohort size")]
+    InvalidMinCohortSize,
+    #[msg("Research consent scopes are empty or unknown, or its expiry has passed")]
+    InvalidResearchConsent,
+    #[msg("A contributing patient has no active research consent for this scope")]
+    ResearchConsentMissing,
+    #[msg("The dataset listing is inactive or sold out")]
+    ListingUnavailable,
+    #[msg("The query purchase is used, unfunded or does not match the query criteria")]
+    InvalidQueryPurchase,
+    #[msg("The claim is not in the status this instruction requires")]
+    InvalidClaimStatus,
+    #[msg("Coverage policy conditions must be medical history flags")]
+    InvalidCoveragePolicy,
+    #[msg("The telemedicine session must be closed first")]
+    SessionNotClosed,
+    #[msg("A second opinion needs distinct specialists and opinions from the same request")]
+    InvalidSecondOpinion,
+    #[msg("A care team needs distinct members and a threshold of at least two of them")]
+    InvalidCareTeam,
+    #[msg("The care team record has not been written yet")]
+    TeamRecordNotReady,
+    #[msg("Not enough care team members approved the access")]
+    TeamAccessNotApproved,
+    #[msg("The witness must be someone other than the patient")]
+    InvalidWitness,
+    #[msg("The appointment window or status does not allow this")]
+    InvalidAppointment,
+    #[msg("The encounter does not belong to this patient record")]
+    InvalidEncounter,
+    #[msg("A co-signed update must be approved by the other party")]
+    CoSignerRequired,
+    #[msg("The ciphertexts do not match the proposed update")]
+    UpdateHashMismatch,
+    #[msg("The patient has locked a section this instruction reads or writes")]
+    SectionLocked,
 }
@@ -58,6 +58,243 @@ describe("ShareMedicalRecords", () => {
 
   const arciumEnv = getArciumEnv();
 
+  // Shares record themselves in an audit index shard for the current epoch.
+  const ensureAuditIndexShard = async (patientData: PublicKey) => {
+    const { epoch } = await provider.connection.getEpochInfo("confirmed");
+    const epochBn = new anchor.BN(epoch);
+    const shard = 0;
+    const [auditIndex] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("audit_index"),
+        patientData.toBuffer(),
+        epochBn.toArrayLike(Buffer, "le", 8),
+        Buffer.from([shard]),
+      ],
+      program.programId
+    );
+    if ((await provider.connection.getAccountInfo(auditIndex)) === null) {
+      await program.methods
+        .initAuditIndexShard(epochBn, shard)
+        .accountsPartial({ patientData })
+        .rpc({ commitment: "confirmed" });
+    }
+    return auditIndex;
+  };
+
+  // Resolves once `action` fails with the program error `code`.
+  const expectProgramError = async (action: Promise<unknown>, code: string) => {
+    let error: unknown;
+    try {
+      await action;
+    } catch (e) {
+      error = e;
+    }
+    expect(error, `expected ${code}`).to.be.instanceOf(anchor.AnchorError);
+    expect((error as anchor.AnchorError).error.errorCode.code).to.equal(code);
+  };
+
+  // Role-gated shares only accept configured roles and credential mints registered for their
+  // role and issued a valid credential record. The test wallet (`owner`) acts as config
+  // authority and registrar.
+  const registerProviderCredential = async (
+    owner: PublicKey,
+    mint: PublicKey,
+    role: number,
+    sections: number
+  ) => {
+    const [programConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("program_config")],
+      program.programId
+    );
+    if ((await provider.connection.getAccountInfo(programConfig)) === null) {
+      await program.methods.initProgramConfig().rpc({ commitment: "confirmed" });
+    }
+    const [providerRegistry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("provider_registry")],
+      program.programId
+    );
+    if ((await provider.connection.getAccountInfo(providerRegistry)) === null) {
+      await program.methods
+        .setRegistrar(owner)
+        .rpc({ commitment: "confirmed" });
+    }
+    const [roleConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("role_config"), Buffer.from([role])],
+      program.programId
+    );
+    if ((await provider.connection.getAccountInfo(roleConfig)) === null) {
+      await program.methods
+        .setRoleConfig(role, PublicKey.default, PublicKey.default, sections)
+        .rpc({ commitment: "confirmed" });
+    }
+    await program.methods
+      .registerProvider(mint, role, Array.from(randomBytes(32)))
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .issueCredential(
+        mint,
+        new anchor.BN(Math.floor(Date.now() / 1000) + 86400)
+      )
+      .rpc({ commitment: "confirmed" });
+  };
+
+  // Creates a 0-decimal credential mint, mints its NFT to `owner` and registers it for
+  // `role`.
+  const createProviderCredential = async (owner: Keypair, role: number) => {
+    const mint = await createMint(
+      provider.connection,
+      owner,
+      owner.publicKey,
+      null,
+      0
+    );
+    const tokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      owner,
+      mint,
+      owner.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      owner,
+      mint,
+      tokenAccount.address,
+      owner,
+      1
+    );
+    await registerProviderCredential(owner.publicKey, mint, role, 0xff);
+    return { mint, tokenAccount: tokenAccount.address };
+  };
+
+  const airdrop = async (...wallets: PublicKey[]) => {
+    for (const wallet of wallets) {
+      const airdropSig = await provider.connection.requestAirdrop(
+        wallet,
+        10 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig, "confirmed");
+    }
+  };
+
+  // Stores a record for a fresh, funded patient wallet. Returns the key and nonce it
+  // was encrypted with and the queue accounts of a computation on it.
+  const storeFreshRecord = async () => {
+    const patient = Keypair.generate();
+    await airdrop(patient.publicKey);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+    const senderPrivateKey = x25519.utils.randomSecretKey();
+    const cipher = new RescueCipher(
+      x25519.getSharedSecret(senderPrivateKey, mxePublicKey)
+    );
+    const nonce = randomBytes(16);
+    await program.methods
+      .storePatientData(
+        cipher
+          .encrypt(Array(152).fill(BigInt(1)), nonce)
+          .map((ct) => Array.from(ct))
+      )
+      .accounts({ payer: patient.publicKey })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+    const patientData = PublicKey.findProgramAddressSync(
+      [Buffer.from("patient_data"), patient.publicKey.toBuffer()],
+      program.programId
+    )[0];
+    const auditIndex = await ensureAuditIndexShard(patientData);
+    const queueAccounts = (
+      computationOffset: anchor.BN,
+      circuit = "share_patient_data"
+    ) => ({
+      computationAccount: getComputationAccAddress(
+        program.programId,
+        computationOffset
+      ),
+      clusterAccount: arciumEnv.arciumClusterPubkey,
+      mxeAccount: getMXEAccAddress(program.programId),
+      mempoolAccount: getMempoolAccAddress(program.programId),
+      executingPool: getExecutingPoolAccAddress(program.programId),
+      compDefAccount: getCompDefAccAddress(
+        program.programId,
+        Buffer.from(getCompDefAccOffset(circuit)).readUInt32LE()
+      ),
+      patientData,
+      auditIndex,
+    });
+
+    const senderPubKey = Array.from(x25519.getPublicKey(senderPrivateKey));
+    const nonceBn = new anchor.BN(deserializeLE(nonce).toString());
+    // Shares the record with `receiver`, signed by `payer` without a delegation.
+    const share = (receiver: number[], payer: Keypair = patient) => {
+      const computationOffset = new anchor.BN(randomBytes(8), "hex");
+      return program.methods
+        .sharePatientData(
+          computationOffset,
+          receiver,
+          senderPubKey,
+          nonceBn,
+          0,
+          null
+        )
+        .accountsPartial({
+          ...queueAccounts(computationOffset),
+          payer: payer.publicKey,
+          delegation: null,
+          receiverAuthority: payer.publicKey,
+        })
+        .signers([payer])
+        .rpc({ commitment: "confirmed" });
+    };
+
+    return {
+      patient,
+      patientData,
+      senderPubKey,
+      nonce: nonceBn,
+      queueAccounts,
+      share,
+    };
+  };
+
+  const randomReceiver = () =>
+    Array.from(x25519.getPublicKey(x25519.utils.randomSecretKey()));
+
+  // Books an hour-long appointment, starting now, on `patient`'s record.
+  const bookAppointment = async (patient: Keypair, providerKey: PublicKey) => {
+    const appointmentId = new anchor.BN(randomBytes(8), "hex");
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .bookAppointment(
+        appointmentId,
+        providerKey,
+        new anchor.BN(now),
+        new anchor.BN(now + 3600)
+      )
+      .accounts({ payer: patient.publicKey })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+    const patientData = PublicKey.findProgramAddressSync(
+      [Buffer.from("patient_data"), patient.publicKey.toBuffer()],
+      program.programId
+    )[0];
+    const appointment = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("appointment"),
+        patientData.toBuffer(),
+        appointmentId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+    const visitGrant = PublicKey.findProgramAddressSync(
+      [Buffer.from("visit_grant"), appointment.toBuffer()],
+      program.programId
+    )[0];
+    return { appointment, visitGrant };
+  };
+
   it("can store and share patient data confidentially!", async () => {
     const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
 
@@ -129,17 +366,25 @@ describe("ShareMedicalRecords", () => {
 
     const receiverSecretKey = x25519.utils.randomSecretKey();
     const receiverPubKey = x25519.getPublicKey(receiverSecretKey);
-    const receiverNonce = randomBytes(16);
 
     const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    const patientData = PublicKey.findProgramAddressSync(
+      [Buffer.from("patient_data"), owner.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+    const receivedBasicEventPromise = awaitEvent("receivedBasicPatientDataEvent");
+    const shareQueuedEventPromise = awaitEvent("shareQueuedEvent");
+    const shareCompletedEventPromise = awaitEvent("shareCompletedEvent");
 
     const queueSig = await program.methods
       .sharePatientData(
         computationOffset,
         Array.from(receiverPubKey),
-        new anchor.BN(deserializeLE(receiverNonce).toString()),
         Array.from(senderPublicKey),
-        new anchor.BN(deserializeLE(nonce).toString())
+        new anchor.BN(deserializeLE(nonce).toString()),
+        0,
+        null // default MXE cluster
       )
       .accountsPartial({
         computationAccount: getComputationAccAddress(
@@ -154,10 +399,9 @@ describe("ShareMedicalRecords", () => {
           program.programId,
           Buffer.from(getCompDefAccOffset("share_patient_data")).readUInt32LE()
         ),
-        patientData: PublicKey.findProgramAddressSync(
-          [Buffer.from("patient_data"), owner.publicKey.toBuffer()],
-          program.programId
-        )[0],
+        patientData,
+        auditIndex: await ensureAuditIndexShard(patientData),
+        receiverAuthority: owner.publicKey,
       })
       .rpc({ commitment: "confirmed" });
     console.log("Queue sig is ", queueSig);
@@ -176,10 +420,53 @@ describe("ShareMedicalRecords", () => {
     );
     const receiverCipher = new RescueCipher(receiverSharedSecret);
 
-    console.log("Computation finalized successfully");
-    // Note: Callback was removed to minimize stack usage, so events are no longer emitted
-    // In production, clients would fetch the re-encrypted data from the computation output
-    console.log("Data sharing completed - verify decryption via computation output");
+    const receivedBasicEvent = await receivedBasicEventPromise;
+    const decrypted = receiverCipher.decrypt(
+      [
+        receivedBasicEvent.patientId,
+        receivedBasicEvent.age,
+        receivedBasicEvent.gender,
+        receivedBasicEvent.bloodType,
+        receivedBasicEvent.weight,
+        receivedBasicEvent.height,
+        ...receivedBasicEvent.allergies,
+      ],
+      new Uint8Array(receivedBasicEvent.nonce)
+    );
+
+    expect(decrypted[0]).to.equal(patientId);
+    expect(decrypted[1]).to.equal(age);
+    expect(decrypted[2]).to.equal(gender);
+    expect(decrypted[3]).to.equal(bloodType);
+    expect(decrypted[4]).to.equal(weight);
+    expect(decrypted[5]).to.equal(height);
+    for (let i = 0; i < allergies.length; i++) {
+      expect(decrypted[6 + i]).to.equal(allergies[i]);
+    }
+
+    // Lifecycle events let indexers follow a share from queue to completion
+    const shareQueuedEvent = await shareQueuedEventPromise;
+    const shareCompletedEvent = await shareCompletedEventPromise;
+    expect(shareQueuedEvent.schemaVersion).to.equal(2);
+    expect(shareQueuedEvent.computationOffset.eq(computationOffset)).to.be.true;
+    expect(shareQueuedEvent.purpose).to.equal(0);
+    expect(shareCompletedEvent.schemaVersion).to.equal(2);
+    expect(shareCompletedEvent.patientData.equals(patientData)).to.be.true;
+    const completedOffset = shareCompletedEvent.computationOffset;
+    expect(completedOffset.eq(computationOffset)).to.be.true;
+
+    const shareStatus = await program.account.shareStatus.fetch(
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("share_status"),
+          patientData.toBuffer(),
+          computationOffset.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0]
+    );
+    expect(shareStatus.status).to.equal(1);
+    expect(shareStatus.resolvedSlot.gte(shareStatus.queuedSlot)).to.be.true;
   });
 
   it("can share patient data with doctor role credential NFT", async () => {
@@ -222,6 +509,17 @@ describe("ShareMedicalRecords", () => {
       owner,
       1 // Mint 1 credential NFT
     );
+    await registerProviderCredential(
+      owner.publicKey,
+      credentialMintKeypair.publicKey,
+      1, // ROLE_DOCTOR
+      0xff // ALL_SECTIONS
+    );
+    await initCompDef(
+      owner,
+      "share_patient_data_scoped",
+      program.methods.initSharePatientDataScopedCompDef()
+    );
 
     console.log(
       `Created doctor credential NFT: ${credentialMintKeypair.publicKey}`
@@ -231,7 +529,6 @@ describe("ShareMedicalRecords", () => {
     // Use the role-gated share function
     const receiverSecretKey = x25519.utils.randomSecretKey();
     const receiverPubKey = x25519.getPublicKey(receiverSecretKey);
-    const receiverNonce = randomBytes(16);
     const computationOffset = new anchor.BN(randomBytes(8), "hex");
     const senderPrivateKey = x25519.utils.randomSecretKey();
     const senderPublicKey = x25519.getPublicKey(senderPrivateKey);
@@ -244,12 +541,14 @@ describe("ShareMedicalRecords", () => {
 
     try {
       const shareSig = await program.methods
-        .sharePatientDataDoctor(
+        .sharePatientDataWithRole(
+          1, // ROLE_DOCTOR
           computationOffset,
           Array.from(receiverPubKey),
-          new anchor.BN(deserializeLE(receiverNonce).toString()),
           Array.from(senderPublicKey),
-          new anchor.BN(deserializeLE(nonce).toString())
+          new anchor.BN(deserializeLE(nonce).toString()),
+          0,
+          null
         )
         .accountsPartial({
           computationAccount: getComputationAccAddress(
@@ -262,11 +561,16 @@ describe("ShareMedicalRecords", () => {
           executingPool: getExecutingPoolAccAddress(program.programId),
           compDefAccount: getCompDefAccAddress(
             program.programId,
-            Buffer.from(getCompDefAccOffset("share_patient_data")).readUInt32LE()
+            Buffer.from(
+              getCompDefAccOffset("share_patient_data_scoped")
+            ).readUInt32LE()
           ),
           patientData: patientDataPDA,
+          auditIndex: await ensureAuditIndexShard(patientDataPDA),
+          receiverAuthority: owner.publicKey,
           credentialMint: credentialMintKeypair.publicKey,
           credentialTokenAccount: tokenAccount.address,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .rpc({ commitment: "confirmed" });
 
@@ -317,10 +621,15 @@ describe("ShareMedicalRecords", () => {
       owner,
       1
     );
+    await registerProviderCredential(
+      owner.publicKey,
+      credentialMintKeypair.publicKey,
+      2, // ROLE_NURSE
+      0b11 // SECTION_DEMOGRAPHICS | SECTION_ALLERGIES
+    );
 
     const receiverSecretKey = x25519.utils.randomSecretKey();
     const receiverPubKey = x25519.getPublicKey(receiverSecretKey);
-    const receiverNonce = randomBytes(16);
     const computationOffset = new anchor.BN(randomBytes(8), "hex");
     const senderPrivateKey = x25519.utils.randomSecretKey();
     const senderPublicKey = x25519.getPublicKey(senderPrivateKey);
@@ -332,12 +641,14 @@ describe("ShareMedicalRecords", () => {
     )[0];
 
     const shareSig = await program.methods
-      .sharePatientDataNurse(
+      .sharePatientDataWithRole(
+        2, // ROLE_NURSE
         computationOffset,
         Array.from(receiverPubKey),
-        new anchor.BN(deserializeLE(receiverNonce).toString()),
         Array.from(senderPublicKey),
-        new anchor.BN(deserializeLE(nonce).toString())
+        new anchor.BN(deserializeLE(nonce).toString()),
+        0,
+        null
       )
       .accountsPartial({
         computationAccount: getComputationAccAddress(
@@ -350,11 +661,16 @@ describe("ShareMedicalRecords", () => {
         executingPool: getExecutingPoolAccAddress(program.programId),
         compDefAccount: getCompDefAccAddress(
           program.programId,
-          Buffer.from(getCompDefAccOffset("share_patient_data")).readUInt32LE()
+          Buffer.from(
+            getCompDefAccOffset("share_patient_data_scoped")
+          ).readUInt32LE()
         ),
         patientData: patientDataPDA,
+        auditIndex: await ensureAuditIndexShard(patientDataPDA),
+        receiverAuthority: owner.publicKey,
         credentialMint: credentialMintKeypair.publicKey,
         credentialTokenAccount: tokenAccount.address,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .rpc({ commitment: "confirmed" });
 
@@ -401,10 +717,15 @@ describe("ShareMedicalRecords", () => {
       owner,
       1
     );
+    await registerProviderCredential(
+      owner.publicKey,
+      credentialMintKeypair.publicKey,
+      3, // ROLE_PHARMACIST
+      0b110 // SECTION_ALLERGIES | SECTION_MEDICATIONS
+    );
 
     const receiverSecretKey = x25519.utils.randomSecretKey();
     const receiverPubKey = x25519.getPublicKey(receiverSecretKey);
-    const receiverNonce = randomBytes(16);
     const computationOffset = new anchor.BN(randomBytes(8), "hex");
     const senderPrivateKey = x25519.utils.randomSecretKey();
     const senderPublicKey = x25519.getPublicKey(senderPrivateKey);
@@ -416,12 +737,14 @@ describe("ShareMedicalRecords", () => {
     )[0];
 
     const shareSig = await program.methods
-      .sharePatientDataPharmacist(
+      .sharePatientDataWithRole(
+        3, // ROLE_PHARMACIST
         computationOffset,
         Array.from(receiverPubKey),
-        new anchor.BN(deserializeLE(receiverNonce).toString()),
         Array.from(senderPublicKey),
-        new anchor.BN(deserializeLE(nonce).toString())
+        new anchor.BN(deserializeLE(nonce).toString()),
+        0,
+        null
       )
       .accountsPartial({
         computationAccount: getComputationAccAddress(
@@ -434,11 +757,16 @@ describe("ShareMedicalRecords", () => {
         executingPool: getExecutingPoolAccAddress(program.programId),
         compDefAccount: getCompDefAccAddress(
           program.programId,
-          Buffer.from(getCompDefAccOffset("share_patient_data")).readUInt32LE()
+          Buffer.from(
+            getCompDefAccOffset("share_patient_data_scoped")
+          ).readUInt32LE()
         ),
         patientData: patientDataPDA,
+        auditIndex: await ensureAuditIndexShard(patientDataPDA),
+        receiverAuthority: owner.publicKey,
         credentialMint: credentialMintKeypair.publicKey,
         credentialTokenAccount: tokenAccount.address,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .rpc({ commitment: "confirmed" });
 
@@ -446,6 +774,784 @@ describe("ShareMedicalRecords", () => {
     expect(shareSig).to.be.a("string");
   });
 
+  it("stores a record within its compute budget", async () => {
+    // Ceilings for `store_patient_data`, which copies the ciphertexts into the account in
+    // one go
+    const budgets: [number, number][] = [
+      [152, 40_000],
+      [178, 45_000],
+    ];
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+    for (const [fields, budget] of budgets) {
+      const patient = Keypair.generate();
+      const airdropSig = await provider.connection.requestAirdrop(
+        patient.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig, "confirmed");
+
+      const cipher = new RescueCipher(
+        x25519.getSharedSecret(x25519.utils.randomSecretKey(), mxePublicKey)
+      );
+      const record = Array.from({ length: fields }, (_, i) => BigInt(i));
+      const storeSig = await program.methods
+        .storePatientData(
+          cipher.encrypt(record, randomBytes(16)).map((ct) => Array.from(ct))
+        )
+        .accounts({ payer: patient.publicKey })
+        .signers([patient])
+        .rpc({ commitment: "confirmed" });
+
+      const tx = await provider.connection.getTransaction(storeSig, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const unitsConsumed = tx.meta.computeUnitsConsumed;
+      console.log(
+        `store_patient_data with ${fields} fields used`,
+        unitsConsumed,
+        "CU"
+      );
+      expect(unitsConsumed).to.be.below(budget);
+    }
+  });
+
+  it("runs the full patient flow end to end", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const patient = Keypair.generate();
+    const caregiver = Keypair.generate();
+    for (const wallet of [patient, caregiver]) {
+      const airdropSig = await provider.connection.requestAirdrop(
+        wallet.publicKey,
+        10 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig, "confirmed");
+    }
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+    await initCompDef(
+      owner,
+      "compute_cardio_risk",
+      program.methods.initComputeCardioRiskCompDef()
+    );
+
+    // 1. The patient stores their record
+    const senderPrivateKey = x25519.utils.randomSecretKey();
+    const senderPublicKey = x25519.getPublicKey(senderPrivateKey);
+    const cipher = new RescueCipher(
+      x25519.getSharedSecret(senderPrivateKey, mxePublicKey)
+    );
+    const age = BigInt(69);
+    const gender = BigInt(true);
+    const record = [
+      BigInt(7),
+      age,
+      gender,
+      ...Array(149).fill(BigInt(0)),
+    ];
+    const nonce = randomBytes(16);
+    const nonceBn = new anchor.BN(deserializeLE(nonce).toString());
+    await program.methods
+      .storePatientData(
+        cipher.encrypt(record, nonce).map((ct) => Array.from(ct))
+      )
+      .accounts({ payer: patient.publicKey })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+
+    const patientData = PublicKey.findProgramAddressSync(
+      [Buffer.from("patient_data"), patient.publicKey.toBuffer()],
+      program.programId
+    )[0];
+    const auditIndex = await ensureAuditIndexShard(patientData);
+    const queueAccounts = (computationOffset: anchor.BN, circuit: string) => ({
+      computationAccount: getComputationAccAddress(
+        program.programId,
+        computationOffset
+      ),
+      clusterAccount: arciumEnv.arciumClusterPubkey,
+      mxeAccount: getMXEAccAddress(program.programId),
+      mempoolAccount: getMempoolAccAddress(program.programId),
+      executingPool: getExecutingPoolAccAddress(program.programId),
+      compDefAccount: getCompDefAccAddress(
+        program.programId,
+        Buffer.from(getCompDefAccOffset(circuit)).readUInt32LE()
+      ),
+      patientData,
+      auditIndex,
+    });
+
+    // 2. The patient grants a caregiver consent to share on their behalf
+    const expiresAt = new anchor.BN(Math.floor(Date.now() / 1000) + 86400);
+    await program.methods
+      .delegateAuthority(caregiver.publicKey, 1, expiresAt)
+      .accountsPartial({ payer: patient.publicKey, patientData })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+    const delegation = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("delegation"),
+        patientData.toBuffer(),
+        caregiver.publicKey.toBuffer(),
+      ],
+      program.programId
+    )[0];
+
+    // 3. The caregiver shares the record with a doctor
+    const doctorSecretKey = x25519.utils.randomSecretKey();
+    const doctorPubKey = x25519.getPublicKey(doctorSecretKey);
+    const doctorCipher = new RescueCipher(
+      x25519.getSharedSecret(doctorSecretKey, mxePublicKey)
+    );
+    const shareOffset = new anchor.BN(randomBytes(8), "hex");
+    const receivedBasicEventPromise = awaitEvent("receivedBasicPatientDataEvent");
+    await program.methods
+      .sharePatientData(
+        shareOffset,
+        Array.from(doctorPubKey),
+        Array.from(senderPublicKey),
+        nonceBn,
+        0,
+        null
+      )
+      .accountsPartial({
+        ...queueAccounts(shareOffset, "share_patient_data"),
+        payer: caregiver.publicKey,
+        delegation,
+        receiverAuthority: caregiver.publicKey,
+      })
+      .signers([caregiver])
+      .rpc({ commitment: "confirmed" });
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      shareOffset,
+      program.programId,
+      "confirmed"
+    );
+    const receivedBasicEvent = await receivedBasicEventPromise;
+    const [sharedAge] = doctorCipher.decrypt(
+      [receivedBasicEvent.age],
+      new Uint8Array(receivedBasicEvent.nonce)
+    );
+    expect(sharedAge).to.equal(age);
+
+    // 4. The doctor gets a cardiovascular risk band instead of the raw record
+    const riskOffset = new anchor.BN(randomBytes(8), "hex");
+    const riskEventPromise = awaitEvent("cardioRiskComputedEvent");
+    await program.methods
+      .computeCardioRisk(
+        riskOffset,
+        Array.from(doctorPubKey),
+        Array.from(senderPublicKey),
+        nonceBn,
+        0,
+        null
+      )
+      .accountsPartial({
+        ...queueAccounts(riskOffset, "compute_cardio_risk"),
+        payer: patient.publicKey,
+      })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      riskOffset,
+      program.programId,
+      "confirmed"
+    );
+    const riskEvent = await riskEventPromise;
+    const [riskBand] = doctorCipher.decrypt(
+      [riskEvent.riskBand],
+      new Uint8Array(riskEvent.nonce)
+    );
+    // Age 69 (3) and male (1) score 4: moderate
+    expect(riskBand).to.equal(BigInt(1));
+
+    // 5. The patient revokes every grant
+    const grantExpiryIndex = PublicKey.findProgramAddressSync(
+      [Buffer.from("grant_expiry_index"), patientData.toBuffer()],
+      program.programId
+    )[0];
+    await program.methods
+      .revokeAllAccess()
+      .accountsPartial({ payer: patient.publicKey, patientData, grantExpiryIndex })
+      .remainingAccounts([
+        { pubkey: delegation, isSigner: false, isWritable: true },
+      ])
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+    expect(await provider.connection.getAccountInfo(delegation)).to.be.null;
+
+    // 6. The audit trail holds one entry per disclosure
+    const auditTrail = await program.account.accessLogEntry.all([
+      { memcmp: { offset: 8, bytes: patientData.toBase58() } },
+    ]);
+    for (const { account } of auditTrail) {
+      console.log(
+        `Access by ${account.accessor} as role ${account.role} for purpose ${account.purpose} at ${account.timestamp}`
+      );
+    }
+    expect(auditTrail).to.have.lengthOf(2);
+  });
+
+  it("re-authorizes the signer when a share is retried", async () => {
+    const patient = Keypair.generate();
+    const caregiver = Keypair.generate();
+    for (const wallet of [patient, caregiver]) {
+      const airdropSig = await provider.connection.requestAirdrop(
+        wallet.publicKey,
+        10 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig, "confirmed");
+    }
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+    const senderPrivateKey = x25519.utils.randomSecretKey();
+    const cipher = new RescueCipher(
+      x25519.getSharedSecret(senderPrivateKey, mxePublicKey)
+    );
+    const nonce = randomBytes(16);
+    const nonceBn = new anchor.BN(deserializeLE(nonce).toString());
+    await program.methods
+      .storePatientData(
+        cipher
+          .encrypt(Array(152).fill(BigInt(1)), nonce)
+          .map((ct) => Array.from(ct))
+      )
+      .accounts({ payer: patient.publicKey })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+    const patientData = PublicKey.findProgramAddressSync(
+      [Buffer.from("patient_data"), patient.publicKey.toBuffer()],
+      program.programId
+    )[0];
+    const auditIndex = await ensureAuditIndexShard(patientData);
+    const queueAccounts = (computationOffset: anchor.BN) => ({
+      computationAccount: getComputationAccAddress(
+        program.programId,
+        computationOffset
+      ),
+      clusterAccount: arciumEnv.arciumClusterPubkey,
+      mxeAccount: getMXEAccAddress(program.programId),
+      mempoolAccount: getMempoolAccAddress(program.programId),
+      executingPool: getExecutingPoolAccAddress(program.programId),
+      compDefAccount: getCompDefAccAddress(
+        program.programId,
+        Buffer.from(getCompDefAccOffset("share_patient_data")).readUInt32LE()
+      ),
+      patientData,
+      auditIndex,
+    });
+
+    await program.methods
+      .delegateAuthority(
+        caregiver.publicKey,
+        1,
+        new anchor.BN(Math.floor(Date.now() / 1000) + 86400)
+      )
+      .accountsPartial({ payer: patient.publicKey, patientData })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+    const delegation = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("delegation"),
+        patientData.toBuffer(),
+        caregiver.publicKey.toBuffer(),
+      ],
+      program.programId
+    )[0];
+
+    // The caregiver queues a share which the patient cancels in the same transaction, so
+    // it is retryable before its callback can run
+    const receiver = Array.from(
+      x25519.getPublicKey(x25519.utils.randomSecretKey())
+    );
+    const shareOffset = new anchor.BN(randomBytes(8), "hex");
+    const shareOffsetBytes = shareOffset.toArrayLike(Buffer, "le", 8);
+    const shareIx = await program.methods
+      .sharePatientData(
+        shareOffset,
+        receiver,
+        Array.from(x25519.getPublicKey(senderPrivateKey)),
+        nonceBn,
+        0,
+        null
+      )
+      .accountsPartial({
+        ...queueAccounts(shareOffset),
+        payer: caregiver.publicKey,
+        delegation,
+        receiverAuthority: caregiver.publicKey,
+      })
+      .instruction();
+    const cancelIx = await program.methods
+      .cancelShare(shareOffset)
+      .accountsPartial({
+        payer: patient.publicKey,
+        rentPayer: caregiver.publicKey,
+        sharedRecord: PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("shared_record"),
+            patientData.toBuffer(),
+            Buffer.from(receiver),
+            shareOffsetBytes,
+          ],
+          program.programId
+        )[0],
+        inboxEntry: PublicKey.findProgramAddressSync(
+          [Buffer.from("inbox_entry"), Buffer.from(receiver), shareOffsetBytes],
+          program.programId
+        )[0],
+      })
+      .instruction();
+    await (provider as anchor.AnchorProvider).sendAndConfirm(
+      new anchor.web3.Transaction().add(shareIx, cancelIx),
+      [caregiver, patient],
+      { commitment: "confirmed" }
+    );
+
+    // Once the delegation is revoked, having queued the original share is not enough
+    await program.methods
+      .revokeDelegation(caregiver.publicKey)
+      .accountsPartial({ payer: patient.publicKey, patientData })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+    const retryOffset = new anchor.BN(randomBytes(8), "hex");
+    await expectProgramError(
+      program.methods
+        .retryShare(
+          retryOffset,
+          shareOffset,
+          Array.from(x25519.getPublicKey(senderPrivateKey)),
+          nonceBn,
+          null
+        )
+        .accountsPartial({
+          ...queueAccounts(retryOffset),
+          payer: caregiver.publicKey,
+          delegation: null,
+          organization: null,
+          receiverAuthority: caregiver.publicKey,
+        })
+        .signers([caregiver])
+        .rpc({ commitment: "confirmed" }),
+      "Unauthorized"
+    );
+  });
+
+  it("only executes break-glass requests approved by enough guardians", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const record = await storeFreshRecord();
+    const { patient, patientData } = record;
+    const guardians = [Keypair.generate(), Keypair.generate()];
+    await program.methods
+      .setEmergencyPolicy(
+        guardians.map((guardian) => guardian.publicKey),
+        2,
+        new anchor.BN(0)
+      )
+      .accounts({ payer: patient.publicKey })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+
+    const { mint, tokenAccount } = await createProviderCredential(owner, 1);
+    await program.methods
+      .openBreakGlass(randomReceiver())
+      .accountsPartial({
+        patientData,
+        credentialMint: mint,
+        credentialTokenAccount: tokenAccount,
+      })
+      .rpc({ commitment: "confirmed" });
+    const breakGlassRequest = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("break_glass"),
+        patientData.toBuffer(),
+        owner.publicKey.toBuffer(),
+      ],
+      program.programId
+    )[0];
+    const approve = (guardian: Keypair) =>
+      program.methods
+        .approveBreakGlass()
+        .accountsPartial({ guardian: guardian.publicKey, breakGlassRequest })
+        .signers([guardian])
+        .rpc({ commitment: "confirmed" });
+
+    await expectProgramError(approve(Keypair.generate()), "NotAGuardian");
+    await approve(guardians[0]);
+    await expectProgramError(approve(guardians[0]), "GuardianAlreadyApproved");
+
+    // One of two approvals, and no time-based fallback
+    const compDef = getCompDefAccAddress(
+      program.programId,
+      Buffer.from(getCompDefAccOffset("share_emergency_data")).readUInt32LE()
+    );
+    if ((await provider.connection.getAccountInfo(compDef)) === null) {
+      await initCompDef(
+        owner,
+        "share_emergency_data",
+        program.methods.initShareEmergencyDataCompDef()
+      );
+    }
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await expectProgramError(
+      program.methods
+        .executeBreakGlass(
+          computationOffset,
+          record.senderPubKey,
+          record.nonce,
+          null
+        )
+        .accountsPartial(
+          record.queueAccounts(computationOffset, "share_emergency_data")
+        )
+        .rpc({ commitment: "confirmed" }),
+      "BreakGlassNotReady"
+    );
+  });
+
+  it("rejects shares to a revoked receiver", async () => {
+    const record = await storeFreshRecord();
+    const receiver = randomReceiver();
+    await program.methods
+      .revokeReceiver(receiver)
+      .accounts({ payer: record.patient.publicKey })
+      .signers([record.patient])
+      .rpc({ commitment: "confirmed" });
+
+    await expectProgramError(record.share(receiver), "ReceiverRevoked");
+  });
+
+  it("rejects shares by wallets that are neither the patient nor a delegate", async () => {
+    const record = await storeFreshRecord();
+    const stranger = Keypair.generate();
+    await airdrop(stranger.publicKey);
+
+    await expectProgramError(
+      record.share(randomReceiver(), stranger),
+      "Unauthorized"
+    );
+  });
+
+  it("checks every receiver of a batch share", async () => {
+    const record = await storeFreshRecord();
+    const { patient, patientData } = record;
+    const receiver = randomReceiver();
+    await program.methods
+      .revokeReceiver(receiver)
+      .accounts({ payer: patient.publicKey })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    const offsetBytes = computationOffset.toArrayLike(Buffer, "le", 8);
+    const pda = (...seeds: Buffer[]) =>
+      PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+    const receiverAccounts = [
+      getComputationAccAddress(program.programId, computationOffset),
+      pda(
+        Buffer.from("receiver_revocation"),
+        patientData.toBuffer(),
+        Buffer.from(receiver)
+      ),
+      pda(
+        Buffer.from("shared_record"),
+        patientData.toBuffer(),
+        Buffer.from(receiver),
+        offsetBytes
+      ),
+      pda(Buffer.from("access_log"), patientData.toBuffer(), offsetBytes),
+      pda(
+        Buffer.from("share_nonce"),
+        patientData.toBuffer(),
+        Buffer.from(receiver)
+      ),
+      pda(Buffer.from("receiver_jurisdiction"), Buffer.from(receiver)),
+      pda(Buffer.from("inbox_entry"), Buffer.from(receiver), offsetBytes),
+      pda(Buffer.from("share_status"), patientData.toBuffer(), offsetBytes),
+    ];
+    const shareBatch = (accounts: PublicKey[]) =>
+      program.methods
+        .sharePatientDataMulti(
+          new anchor.BN(randomBytes(8), "hex"),
+          [
+            {
+              receiver,
+              computationOffset,
+              receiverAuthority: patient.publicKey,
+            },
+          ],
+          record.senderPubKey,
+          record.nonce,
+          0,
+          null
+        )
+        .accountsPartial({
+          ...record.queueAccounts(computationOffset),
+          payer: patient.publicKey,
+          delegation: null,
+        })
+        .remainingAccounts(
+          accounts.map((pubkey) => ({
+            pubkey,
+            isSigner: false,
+            isWritable: true,
+          }))
+        )
+        .signers([patient])
+        .rpc({ commitment: "confirmed" });
+
+    // Every receiver needs its full set of accounts
+    await expectProgramError(
+      shareBatch(receiverAccounts.slice(0, -1)),
+      "InvalidBatch"
+    );
+    await expectProgramError(shareBatch(receiverAccounts), "ReceiverRevoked");
+  });
+
+  it("rejects break-glass requests from revoked credentials", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const { patient, patientData } = await storeFreshRecord();
+    await program.methods
+      .setEmergencyPolicy([Keypair.generate().publicKey], 1, new anchor.BN(0))
+      .accounts({ payer: patient.publicKey })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+
+    const { mint, tokenAccount } = await createProviderCredential(owner, 1);
+    await program.methods
+      .revokeCredential(mint)
+      .rpc({ commitment: "confirmed" });
+
+    await expectProgramError(
+      program.methods
+        .openBreakGlass(randomReceiver())
+        .accountsPartial({
+          patientData,
+          credentialMint: mint,
+          credentialTokenAccount: tokenAccount,
+        })
+        .rpc({ commitment: "confirmed" }),
+      "CredentialRevoked"
+    );
+  });
+
+  it("rejects shares to receivers outside the allowlist", async () => {
+    const record = await storeFreshRecord();
+    await program.methods
+      .addReceiver(randomReceiver())
+      .accounts({ payer: record.patient.publicKey })
+      .signers([record.patient])
+      .rpc({ commitment: "confirmed" });
+
+    await expectProgramError(
+      record.share(randomReceiver()),
+      "ReceiverNotAllowlisted"
+    );
+  });
+
+  it("rejects shares while the program is paused", async () => {
+    const record = await storeFreshRecord();
+    await program.methods.pause().rpc({ commitment: "confirmed" });
+    try {
+      await expectProgramError(record.share(randomReceiver()), "ProgramPaused");
+    } finally {
+      await program.methods.unpause().rpc({ commitment: "confirmed" });
+    }
+  });
+
+  it("only lets the appointment's provider confirm it", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const { patient } = await storeFreshRecord();
+    const { appointment } = await bookAppointment(patient, owner.publicKey);
+
+    const stranger = Keypair.generate();
+    await airdrop(stranger.publicKey);
+    await expectProgramError(
+      program.methods
+        .confirmAppointment()
+        .accountsPartial({ payer: stranger.publicKey, appointment })
+        .signers([stranger])
+        .rpc({ commitment: "confirmed" }),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects visit summaries before the appointment is confirmed", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const { patient, patientData } = await storeFreshRecord();
+    const { mint, tokenAccount } = await createProviderCredential(owner, 1);
+    const { visitGrant } = await bookAppointment(patient, owner.publicKey);
+
+    // The visit grant stays inactive until the provider confirms
+    await expectProgramError(
+      program.methods
+        .appendVisitSummary(new anchor.BN(randomBytes(8), "hex"), {
+          encryptionKey: randomReceiver(),
+          nonce: new anchor.BN(0),
+          ciphertexts: Array.from({ length: 16 }, () => Array(32).fill(0)),
+        })
+        .accountsPartial({
+          patientData,
+          visitGrant,
+          credentialMint: mint,
+          credentialTokenAccount: tokenAccount,
+        })
+        .rpc({ commitment: "confirmed" }),
+      "InvalidAppointment"
+    );
+  });
+
+  it("rejects full-record shares while a section is locked", async () => {
+    const record = await storeFreshRecord();
+    await program.methods
+      .lockSection(16) // SECTION_GENOMICS
+      .accounts({ payer: record.patient.publicKey })
+      .signers([record.patient])
+      .rpc({ commitment: "confirmed" });
+
+    await expectProgramError(record.share(randomReceiver()), "SectionLocked");
+  });
+
+  it("rejects section shares to receivers outside the record's jurisdiction", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const record = await storeFreshRecord();
+    const { patient } = record;
+    // Records tagged 276 may only go to receivers registered in 276
+    await program.methods
+      .setJurisdiction(276)
+      .accounts({ payer: patient.publicKey })
+      .signers([patient])
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .setJurisdictionRule(276, [276])
+      .rpc({ commitment: "confirmed" });
+
+    const compDef = getCompDefAccAddress(
+      program.programId,
+      Buffer.from(getCompDefAccOffset("share_demographics")).readUInt32LE()
+    );
+    if ((await provider.connection.getAccountInfo(compDef)) === null) {
+      await initCompDef(
+        owner,
+        "share_demographics",
+        program.methods.initShareDemographicsCompDef()
+      );
+    }
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await expectProgramError(
+      program.methods
+        .shareDemographics(
+          computationOffset,
+          randomReceiver(),
+          record.senderPubKey,
+          record.nonce,
+          0,
+          null
+        )
+        .accountsPartial({
+          ...record.queueAccounts(computationOffset, "share_demographics"),
+          payer: patient.publicKey,
+          delegation: null,
+        })
+        .signers([patient])
+        .rpc({ commitment: "confirmed" }),
+      "JurisdictionNotAllowed"
+    );
+  });
+
+  it("rejects differentially private queries below the noise floor", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const compDef = getCompDefAccAddress(
+      program.programId,
+      Buffer.from(getCompDefAccOffset("aggregate_cohort_dp")).readUInt32LE()
+    );
+    if ((await provider.connection.getAccountInfo(compDef)) === null) {
+      await initCompDef(
+        owner,
+        "aggregate_cohort_dp",
+        program.methods.initAggregateCohortDpCompDef()
+      );
+    }
+
+    // Below epsilon 0.25 the noise is capped, so averaging many cheap queries would reveal
+    // the counts. The epsilon is checked before the cohort members are read.
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    await expectProgramError(
+      program.methods
+        .aggregateCohortDp(
+          computationOffset,
+          randomReceiver(),
+          new anchor.BN(deserializeLE(randomBytes(16)).toString()),
+          new anchor.BN(1),
+          null
+        )
+        .accountsPartial({
+          computationAccount: getComputationAccAddress(
+            program.programId,
+            computationOffset
+          ),
+          clusterAccount: arciumEnv.arciumClusterPubkey,
+          mxeAccount: getMXEAccAddress(program.programId),
+          mempoolAccount: getMempoolAccAddress(program.programId),
+          executingPool: getExecutingPoolAccAddress(program.programId),
+          compDefAccount: compDef,
+          royaltyPool: null,
+        })
+        .remainingAccounts(
+          Array.from({ length: 20 }, () => ({
+            pubkey: Keypair.generate().publicKey,
+            isSigner: false,
+            isWritable: false,
+          }))
+        )
+        .rpc({ commitment: "confirmed" }),
+      "InvalidEpsilon"
+    );
+  });
+
+  async function initCompDef(
+    owner: anchor.web3.Keypair,
+    circuit: string,
+    method: { accounts: Function }
+  ): Promise<string> {
+    const offset = getCompDefAccOffset(circuit);
+    const sig = await method
+      .accounts({
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(offset).readUInt32LE()
+        ),
+        payer: owner.publicKey,
+        mxeAccount: getMXEAccAddress(program.programId),
+      })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+
+    const finalizeTx = await buildFinalizeCompDefTx(
+      provider as anchor.AnchorProvider,
+      Buffer.from(offset).readUInt32LE(),
+      program.programId
+    );
+    const latestBlockhash = await provider.connection.getLatestBlockhash();
+    finalizeTx.recentBlockhash = latestBlockhash.blockhash;
+    finalizeTx.lastValidBlockHeight = latestBlockhash.lastValidBlockHeight;
+    finalizeTx.sign(owner);
+    await provider.sendAndConfirm(finalizeTx);
+    return sig;
+  }
+
   async function initSharePatientDataCompDef(
     program: Program<ShareMedicalRecords>,
     owner: anchor.web3.Keypair,